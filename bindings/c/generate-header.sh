@@ -0,0 +1,8 @@
+#!/bin/sh
+# Regenerate include/fingerprint.h from the exported functions of src/lib.rs with cbindgen
+# (`cargo install cbindgen`). test_header_matches_exports in tests/capi.rs fails when the header is out of date.
+
+set -eu
+
+cd "$(dirname "$0")"
+cbindgen --config cbindgen.toml --output include/fingerprint.h
@@ -0,0 +1,95 @@
+/*
+ * C API of the fingerprint library, built as the fingerprint_c shared library.
+ *
+ * Ownership: every FpHandle returned by fp_finger or fp_decode is owned by the caller, and must be released with
+ * fp_free exactly once. Pointers returned by fp_bytes and fp_last_error_message are owned by the library.
+ *
+ * Thread safety: handles are immutable, so they may be shared between threads and used from several threads at once,
+ * as long as no thread frees a handle another is still using. The last error message is kept per thread.
+ *
+ * Errors: no function unwinds into the caller. Functions returning a pointer return NULL on error, and functions
+ * returning int return -1 on error; fp_last_error_message then describes the error.
+ */
+
+/* Generated from src/lib.rs by generate-header.sh; do not edit. */
+
+#ifndef FINGERPRINT_H
+#define FINGERPRINT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Opaque fingerprint owned by a C caller.
+ */
+typedef struct FpHandle FpHandle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif  // __cplusplus
+
+/**
+ * Fingerprint the file at a null-terminated path, with the default fingerprinter for its type.
+ *
+ * Returns null on error.
+ *
+ * # Safety
+ *
+ * `path` must be null or a valid null-terminated string.
+ */
+FpHandle *fp_finger(const char *path);
+
+/**
+ * Borrow the wire encoding of a fingerprint, valid until the handle is freed.
+ *
+ * Returns 0 on success, or -1 on error.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a live handle, and `out_ptr` and `out_len` must be null or valid for writes.
+ */
+int fp_bytes(const FpHandle *handle, const uint8_t **out_ptr, size_t *out_len);
+
+/**
+ * Compare two fingerprints, writing their similarity in [0, 1].
+ *
+ * Returns 0 on success, or -1 on error, including fingerprints made with incompatible options.
+ *
+ * # Safety
+ *
+ * `a` and `b` must be null or live handles, and `out_score` must be null or valid for writes.
+ */
+int fp_compare(const FpHandle *a, const FpHandle *b, double *out_score);
+
+/**
+ * Decode a fingerprint from its wire encoding, as returned by [fp_bytes].
+ *
+ * Returns null on error.
+ *
+ * # Safety
+ *
+ * `bytes` must be valid for reads of `len` bytes, or may be null when `len` is 0.
+ */
+FpHandle *fp_decode(const uint8_t *bytes, size_t len);
+
+/**
+ * Return the message of the last error on this thread, or null if there has been none.
+ *
+ * The message is owned by the library, and valid until the next failing call on the same thread.
+ */
+const char *fp_last_error_message(void);
+
+/**
+ * Free a handle. Null is ignored.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a live handle, which is not used again.
+ */
+void fp_free(FpHandle *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FINGERPRINT_H */
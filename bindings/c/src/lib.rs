@@ -0,0 +1,190 @@
+//! C API for linking the library from C and C++, built into the `fingerprint_c` shared library and declared in
+//! `include/fingerprint.h`.
+//!
+//! Handles are owned by the caller until passed to [fp_free], and may be shared between threads. No function unwinds
+//! into the caller; failures return null or -1, with a message from [fp_last_error_message].
+
+use std::{
+	cell::RefCell,
+	ffi::{c_char, c_double, c_int, CStr, CString},
+	panic::{self, AssertUnwindSafe},
+	ptr, slice,
+};
+
+use fingerprint::{paths, Fingerprint, FingerprintError};
+
+thread_local! {
+	/// Message of the last error on this thread.
+	static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Opaque fingerprint owned by a C caller.
+pub struct FpHandle {
+	fingerprint: Fingerprint,
+	encoded: Vec<u8>,
+}
+
+impl FpHandle {
+	/// Box a fingerprint into a handle for the caller to own.
+	fn new(fingerprint: Fingerprint) -> *mut Self {
+		let encoded = fingerprint.encode();
+
+		Box::into_raw(Box::new(Self {
+			fingerprint,
+			encoded,
+		}))
+	}
+}
+
+/// Record an error as the last error on this thread.
+fn set_last_error(message: impl ToString) {
+	let message = message.to_string().replace('\0', "\\0");
+
+	LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+/// Run `f`, recording any error or panic as the last error and returning `failed` instead.
+fn guard<T>(failed: T, f: impl FnOnce() -> Result<T, FingerprintError>) -> T {
+	match panic::catch_unwind(AssertUnwindSafe(f)) {
+		Ok(Ok(value)) => value,
+		Ok(Err(e)) => {
+			set_last_error(e);
+
+			failed
+		}
+		Err(payload) => {
+			let message = payload
+				.downcast_ref::<&str>()
+				.map(|message| message.to_string())
+				.or_else(|| payload.downcast_ref::<String>().cloned())
+				.unwrap_or_else(|| "unknown panic".to_string());
+
+			set_last_error(format!("panic: {}", message));
+
+			failed
+		}
+	}
+}
+
+/// Fingerprint the file at a null-terminated path, with the default fingerprinter for its type.
+///
+/// Returns null on error.
+///
+/// # Safety
+///
+/// `path` must be null or a valid null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fp_finger(path: *const c_char) -> *mut FpHandle {
+	guard(ptr::null_mut(), || {
+		if path.is_null() {
+			return Err(FingerprintError::InvalidData("path is null".into()));
+		}
+
+		let path = paths::from_bytes(unsafe { CStr::from_ptr(path) }.to_bytes());
+
+		Ok(FpHandle::new(Fingerprint::finger(path)?))
+	})
+}
+
+/// Borrow the wire encoding of a fingerprint, valid until the handle is freed.
+///
+/// Returns 0 on success, or -1 on error.
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle, and `out_ptr` and `out_len` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fp_bytes(
+	handle: *const FpHandle,
+	out_ptr: *mut *const u8,
+	out_len: *mut usize,
+) -> c_int {
+	guard(-1, || {
+		if handle.is_null() || out_ptr.is_null() || out_len.is_null() {
+			return Err(FingerprintError::InvalidData("null argument".into()));
+		}
+
+		let encoded = unsafe { &(*handle).encoded };
+
+		unsafe {
+			*out_ptr = encoded.as_ptr();
+			*out_len = encoded.len();
+		}
+
+		Ok(0)
+	})
+}
+
+/// Compare two fingerprints, writing their similarity in [0, 1].
+///
+/// Returns 0 on success, or -1 on error, including fingerprints made with incompatible options.
+///
+/// # Safety
+///
+/// `a` and `b` must be null or live handles, and `out_score` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fp_compare(
+	a: *const FpHandle,
+	b: *const FpHandle,
+	out_score: *mut c_double,
+) -> c_int {
+	guard(-1, || {
+		if a.is_null() || b.is_null() || out_score.is_null() {
+			return Err(FingerprintError::InvalidData("null argument".into()));
+		}
+
+		let score = unsafe { (*a).fingerprint.try_compare(&(*b).fingerprint)? };
+
+		unsafe { *out_score = score };
+
+		Ok(0)
+	})
+}
+
+/// Decode a fingerprint from its wire encoding, as returned by [fp_bytes].
+///
+/// Returns null on error.
+///
+/// # Safety
+///
+/// `bytes` must be valid for reads of `len` bytes, or may be null when `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn fp_decode(bytes: *const u8, len: usize) -> *mut FpHandle {
+	guard(ptr::null_mut(), || {
+		let data = match bytes.is_null() {
+			true if len == 0 => &[][..],
+			true => return Err(FingerprintError::InvalidData("bytes is null".into())),
+			false => unsafe { slice::from_raw_parts(bytes, len) },
+		};
+
+		Ok(FpHandle::new(Fingerprint::decode(data)?))
+	})
+}
+
+/// Return the message of the last error on this thread, or null if there has been none.
+///
+/// The message is owned by the library, and valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn fp_last_error_message() -> *const c_char {
+	LAST_ERROR.with(|last| {
+		last.borrow()
+			.as_ref()
+			.map_or(ptr::null(), |message| message.as_ptr())
+	})
+}
+
+/// Free a handle. Null is ignored.
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle, which is not used again.
+#[no_mangle]
+pub unsafe extern "C" fn fp_free(handle: *mut FpHandle) {
+	guard((), || {
+		if !handle.is_null() {
+			drop(unsafe { Box::from_raw(handle) });
+		}
+
+		Ok(())
+	})
+}
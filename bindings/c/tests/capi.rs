@@ -0,0 +1,181 @@
+use std::{
+	ffi::{c_char, c_double, c_int, CStr},
+	ptr,
+};
+
+use libloading::{Library, Symbol};
+
+/// Opaque handle, as declared in `include/fingerprint.h`.
+#[repr(C)]
+struct FpHandle {
+	_private: [u8; 0],
+}
+
+/// Open the shared library built for this test run, next to the test binary.
+fn library() -> Library {
+	let exe = std::env::current_exe().unwrap();
+	let path = exe
+		.parent()
+		.unwrap()
+		.join(libloading::library_filename("fingerprint_c"));
+
+	unsafe { Library::new(path) }.unwrap()
+}
+
+#[test]
+fn test_capi_round_trip() {
+	let library = library();
+
+	unsafe {
+		let finger: Symbol<unsafe extern "C" fn(*const c_char) -> *mut FpHandle> =
+			library.get(b"fp_finger").unwrap();
+		let bytes: Symbol<
+			unsafe extern "C" fn(*const FpHandle, *mut *const u8, *mut usize) -> c_int,
+		> = library.get(b"fp_bytes").unwrap();
+		let compare: Symbol<
+			unsafe extern "C" fn(*const FpHandle, *const FpHandle, *mut c_double) -> c_int,
+		> = library.get(b"fp_compare").unwrap();
+		let decode: Symbol<unsafe extern "C" fn(*const u8, usize) -> *mut FpHandle> =
+			library.get(b"fp_decode").unwrap();
+		let last_error: Symbol<extern "C" fn() -> *const c_char> =
+			library.get(b"fp_last_error_message").unwrap();
+		let free: Symbol<unsafe extern "C" fn(*mut FpHandle)> = library.get(b"fp_free").unwrap();
+
+		let a = finger(c"../../samples/ascii.txt".as_ptr());
+		let b = finger(c"../../samples/ascii_similar.txt".as_ptr());
+
+		assert!(!a.is_null() && !b.is_null());
+
+		let (mut data, mut len) = (ptr::null(), 0);
+
+		assert_eq!(bytes(a, &mut data, &mut len), 0);
+
+		let decoded = decode(data, len);
+		let mut score = 0.0;
+
+		assert!(!decoded.is_null());
+		assert_eq!(compare(a, decoded, &mut score), 0);
+		assert_eq!(score, 1.0);
+		assert_eq!(compare(a, b, &mut score), 0);
+		assert!(score > 0.8 && score < 1.0);
+
+		assert!(finger(c"../../samples/missing".as_ptr()).is_null());
+		assert!(CStr::from_ptr(last_error())
+			.to_str()
+			.unwrap()
+			.contains("samples/missing"));
+		assert!(decode(b"garbage".as_ptr(), 7).is_null());
+		assert_eq!(compare(a, ptr::null(), &mut score), -1);
+
+		free(a);
+		free(b);
+		free(decoded);
+		free(ptr::null_mut());
+	}
+}
+
+/// Translate a Rust FFI type from `src/lib.rs` into its C spelling in `include/fingerprint.h`.
+fn c_type(rust: &str) -> String {
+	if let Some(inner) = rust.strip_prefix("*mut ") {
+		return pointer_to(&c_type(inner));
+	}
+
+	if let Some(inner) = rust.strip_prefix("*const ") {
+		return match inner.starts_with('*') {
+			true => pointer_to(&c_type(inner)),
+			false => pointer_to(&format!("const {}", c_type(inner))),
+		};
+	}
+
+	match rust {
+		"c_char" => "char",
+		"c_int" => "int",
+		"c_double" => "double",
+		"u8" => "uint8_t",
+		"usize" => "size_t",
+		"FpHandle" => "FpHandle",
+		_ => panic!("no C spelling for {rust}"),
+	}
+	.to_string()
+}
+
+/// Spell a pointer to a C type.
+fn pointer_to(pointee: &str) -> String {
+	match pointee.ends_with('*') {
+		true => format!("{pointee}*"),
+		false => format!("{pointee} *"),
+	}
+}
+
+/// Join a C type and a name the way the header does, with pointer stars against the name.
+fn declare(ty: &str, name: &str) -> String {
+	match ty.ends_with('*') {
+		true => format!("{ty}{name}"),
+		false => format!("{ty} {name}"),
+	}
+}
+
+/// Return the C prototype of every exported function in `src/lib.rs`, in source order.
+fn exported_prototypes() -> Vec<(String, String)> {
+	let source = std::fs::read_to_string("src/lib.rs").unwrap();
+
+	source
+		.split("#[no_mangle]")
+		.skip(1)
+		.map(|item| {
+			let signature = item[item.find("fn ").unwrap() + 3..item.find('{').unwrap()]
+				.split_whitespace()
+				.collect::<Vec<_>>()
+				.join(" ");
+			let (name, rest) = signature.split_once('(').unwrap();
+			let (params, ret) = rest.split_once(')').unwrap();
+			let params = params
+				.split(',')
+				.map(str::trim)
+				.filter(|param| !param.is_empty())
+				.map(|param| {
+					let (name, ty) = param.split_once(": ").unwrap();
+
+					declare(&c_type(ty), name)
+				})
+				.collect::<Vec<_>>();
+			let ret = match ret.trim().strip_prefix("-> ") {
+				Some(ty) => c_type(ty),
+				None => "void".to_string(),
+			};
+			let params = match params.is_empty() {
+				true => "void".to_string(),
+				false => params.join(", "),
+			};
+
+			(
+				name.to_string(),
+				format!("{}({});", declare(&ret, name), params),
+			)
+		})
+		.collect()
+}
+
+#[test]
+fn test_header_matches_exports() {
+	let header = std::fs::read_to_string("include/fingerprint.h").unwrap();
+	let prototypes = header
+		.lines()
+		.filter(|line| line.ends_with(");") && !line.starts_with(' ') && !line.starts_with('#'))
+		.collect::<Vec<_>>();
+	let exported = exported_prototypes();
+
+	assert_eq!(
+		prototypes,
+		exported
+			.iter()
+			.map(|(_, prototype)| prototype.as_str())
+			.collect::<Vec<_>>()
+	);
+
+	let library = library();
+
+	for (name, _) in &exported {
+		unsafe { library.get::<*const ()>(name.as_bytes()) }.unwrap();
+	}
+}
@@ -0,0 +1,4 @@
+fn main() {
+	// Node-API symbols are resolved by the Node.js process loading the addon.
+	napi_build::setup();
+}
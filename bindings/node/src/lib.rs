@@ -0,0 +1,145 @@
+//! Node.js addon, loaded from JavaScript as a `.node` module.
+
+use std::path::PathBuf;
+
+use napi::{bindgen_prelude::*, Env, JsError};
+use napi_derive::napi;
+
+use fingerprint::FingerprintError;
+
+/// Return the `code` property of the JavaScript error for an error.
+fn code(e: &FingerprintError) -> &'static str {
+	match e.inner() {
+		FingerprintError::Compare(_) => "ERR_COMPARE",
+		FingerprintError::Parse(_) | FingerprintError::Corrupt(_) => "ERR_DECODE",
+		FingerprintError::Store(_) => "ERR_FINGERPRINT",
+		_ => "ERR_FINGER",
+	}
+}
+
+/// Fingerprint metadata passed to JavaScript alongside its wire encoding.
+#[napi(object)]
+pub struct Metadata {
+	/// Path of the fingerprinted file.
+	pub path: String,
+
+	/// Name of the fingerprinter type, such as `"raw"`.
+	pub r#type: String,
+
+	/// Options tag as 16 hexadecimal digits.
+	pub options_tag: String,
+
+	/// Fingerprint bits as a hexadecimal string.
+	pub hex: String,
+}
+
+/// Fingerprint as passed to and from JavaScript.
+#[napi(object)]
+pub struct JsFingerprint {
+	/// Wire encoding of the fingerprint.
+	pub buffer: Buffer,
+
+	/// Metadata describing the fingerprint.
+	pub metadata: Metadata,
+}
+
+impl From<fingerprint::Fingerprint> for JsFingerprint {
+	fn from(fingerprint: fingerprint::Fingerprint) -> Self {
+		Self {
+			buffer: fingerprint.encode().into(),
+			metadata: Metadata {
+				path: fingerprint.path().to_string_lossy().into_owned(),
+				r#type: fingerprint.r#type().to_string(),
+				options_tag: fingerprint.options_tag().to_string(),
+				hex: fingerprint.to_string(),
+			},
+		}
+	}
+}
+
+/// Convert an error into a rejection whose `code` describes it, keeping the code for [reject].
+fn fail(code_slot: &mut &'static str, e: FingerprintError) -> napi::Error {
+	*code_slot = code(&e);
+
+	napi::Error::from_reason(e.to_string())
+}
+
+/// Reject a promise with a JavaScript error carrying `code`.
+fn reject<T>(env: Env, code: &'static str, e: napi::Error) -> Result<T> {
+	Err(JsError::from(napi::Error::new(code, e.reason))
+		.into_unknown(env)
+		.into())
+}
+
+/// Fingerprinting of a file, run on the libuv threadpool.
+pub struct FingerTask {
+	path: PathBuf,
+	code: &'static str,
+}
+
+impl Task for FingerTask {
+	type Output = fingerprint::Fingerprint;
+	type JsValue = JsFingerprint;
+
+	fn compute(&mut self) -> Result<Self::Output> {
+		fingerprint::Fingerprint::finger(&self.path).map_err(|e| fail(&mut self.code, e))
+	}
+
+	fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+		Ok(output.into())
+	}
+
+	fn reject(&mut self, env: Env, e: napi::Error) -> Result<Self::JsValue> {
+		reject(env, self.code, e)
+	}
+}
+
+/// Comparison of two encoded fingerprints, run on the libuv threadpool.
+pub struct CompareTask {
+	a: Vec<u8>,
+	b: Vec<u8>,
+	code: &'static str,
+}
+
+impl Task for CompareTask {
+	type Output = f64;
+	type JsValue = f64;
+
+	fn compute(&mut self) -> Result<Self::Output> {
+		let compare = || -> std::result::Result<f64, FingerprintError> {
+			let a = fingerprint::Fingerprint::decode(&self.a)?;
+			let b = fingerprint::Fingerprint::decode(&self.b)?;
+
+			Ok(a.try_compare(&b)?)
+		};
+
+		compare().map_err(|e| fail(&mut self.code, e))
+	}
+
+	fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+		Ok(output)
+	}
+
+	fn reject(&mut self, env: Env, e: napi::Error) -> Result<Self::JsValue> {
+		reject(env, self.code, e)
+	}
+}
+
+/// Fingerprint a file with the default fingerprinter for its type, resolving to its encoding and metadata.
+#[napi]
+pub fn finger(path: String) -> AsyncTask<FingerTask> {
+	AsyncTask::new(FingerTask {
+		path: path.into(),
+		code: "ERR_FINGERPRINT",
+	})
+}
+
+/// Compare two fingerprints made with the same options, resolving to their similarity in [0, 1].
+#[napi]
+pub fn compare(a: JsFingerprint, b: JsFingerprint) -> AsyncTask<CompareTask> {
+	AsyncTask::new(CompareTask {
+		a: a.buffer.to_vec(),
+		b: b.buffer.to_vec(),
+		code: "ERR_FINGERPRINT",
+	})
+}
@@ -0,0 +1,165 @@
+//! Python bindings, built into the `fingerprint` Python module.
+
+use std::path::PathBuf;
+
+use pyo3::{
+	prelude::*,
+	types::{PyBytes, PyDict},
+};
+
+use fingerprint::{paths::files, store::group_duplicates};
+
+/// Python exception hierarchy, rooted at `FingerprintError`.
+mod exceptions {
+	use pyo3::{create_exception, exceptions::PyException};
+
+	create_exception!(
+		fingerprint,
+		FingerprintError,
+		PyException,
+		"Base class of fingerprint errors."
+	);
+	create_exception!(
+		fingerprint,
+		FingerError,
+		FingerprintError,
+		"A file could not be fingerprinted."
+	);
+	create_exception!(
+		fingerprint,
+		CompareError,
+		FingerprintError,
+		"Fingerprints could not be compared."
+	);
+	create_exception!(
+		fingerprint,
+		DecodeError,
+		FingerprintError,
+		"Encoded fingerprint could not be decoded."
+	);
+}
+
+use exceptions::{CompareError, DecodeError, FingerError, FingerprintError};
+
+/// Convert an error into the matching Python exception.
+fn to_py_err(e: fingerprint::FingerprintError) -> PyErr {
+	use fingerprint::FingerprintError as Error;
+
+	let message = e.to_string();
+
+	match e.inner() {
+		Error::Compare(_) => CompareError::new_err(message),
+		Error::Parse(_) | Error::Corrupt(_) => DecodeError::new_err(message),
+		Error::Store(_) => FingerprintError::new_err(message),
+		_ => FingerError::new_err(message),
+	}
+}
+
+/// Fingerprint of a file, as seen from Python.
+#[pyclass(name = "Fingerprint", module = "fingerprint", frozen)]
+struct PyFingerprint(fingerprint::Fingerprint);
+
+#[pymethods]
+impl PyFingerprint {
+	/// Fingerprint a file with the default fingerprinter for its type, releasing the GIL while it runs.
+	#[staticmethod]
+	fn finger(py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+		py.allow_threads(|| fingerprint::Fingerprint::finger(path))
+			.map(Self)
+			.map_err(to_py_err)
+	}
+
+	/// Decode a fingerprint from its wire encoding.
+	#[staticmethod]
+	fn decode(data: &[u8]) -> PyResult<Self> {
+		fingerprint::Fingerprint::decode(data)
+			.map(Self)
+			.map_err(to_py_err)
+	}
+
+	/// Return the similarity in [0, 1] of this fingerprint and another made with the same options.
+	fn compare(&self, other: &Self) -> PyResult<f64> {
+		self.0
+			.try_compare(&other.0)
+			.map_err(|e| to_py_err(e.into()))
+	}
+
+	/// Return the wire encoding of the fingerprint.
+	fn encode<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+		PyBytes::new(py, &self.0.encode())
+	}
+
+	/// Fingerprint bits, packed into bytes.
+	#[getter]
+	fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+		PyBytes::new(py, self.0.bytes())
+	}
+
+	/// Fingerprint bits as a hexadecimal string.
+	#[getter]
+	fn hex(&self) -> String {
+		self.0.to_string()
+	}
+
+	/// Path of the fingerprinted file.
+	#[getter]
+	fn path(&self) -> PathBuf {
+		self.0.path()
+	}
+
+	/// Name of the fingerprinter type, such as `"raw"`.
+	#[getter]
+	fn r#type(&self) -> String {
+		self.0.r#type().to_string()
+	}
+
+	fn __repr__(&self) -> String {
+		format!("Fingerprint('{}')", self.0.to_uri())
+	}
+}
+
+/// Fingerprint every file under a directory, returning groups of similar files as dicts of `paths` and `similarity`.
+///
+/// Files that cannot be fingerprinted are skipped. The GIL is released while fingerprinting.
+#[pyfunction]
+#[pyo3(signature = (dir, threshold = 0.9))]
+fn find_duplicates(py: Python<'_>, dir: PathBuf, threshold: f64) -> PyResult<Vec<Py<PyDict>>> {
+	let groups = py
+		.allow_threads(|| -> Result<_, fingerprint::FingerprintError> {
+			let fingerprints: Vec<_> = files(&dir)?
+				.into_iter()
+				.filter_map(|path| fingerprint::Fingerprint::finger(path).ok())
+				.collect();
+
+			Ok(group_duplicates(&fingerprints, threshold))
+		})
+		.map_err(to_py_err)?;
+
+	groups
+		.into_iter()
+		.map(|group| {
+			let dict = PyDict::new(py);
+
+			dict.set_item("paths", group.paths)?;
+			dict.set_item("similarity", group.similarity)?;
+
+			Ok(dict.unbind())
+		})
+		.collect()
+}
+
+/// Python module `fingerprint`.
+#[pymodule]
+#[pyo3(name = "fingerprint")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+	let py = m.py();
+
+	m.add_class::<PyFingerprint>()?;
+	m.add_function(wrap_pyfunction!(find_duplicates, m)?)?;
+	m.add("FingerprintError", py.get_type::<FingerprintError>())?;
+	m.add("FingerError", py.get_type::<FingerError>())?;
+	m.add("CompareError", py.get_type::<CompareError>())?;
+	m.add("DecodeError", py.get_type::<DecodeError>())?;
+
+	Ok(())
+}
@@ -0,0 +1,12 @@
+fn main() {
+	// The protobuf types are generated from the schema with a vendored protoc, so they cannot drift from it.
+	#[cfg(feature = "proto")]
+	{
+		println!("cargo:rerun-if-changed=proto/fingerprint.proto");
+
+		prost_build::Config::new()
+			.protoc_executable(protoc_bin_vendored::protoc_bin_path().expect("vendored protoc"))
+			.compile_protos(&["proto/fingerprint.proto"], &["proto"])
+			.expect("compile proto/fingerprint.proto");
+	}
+}
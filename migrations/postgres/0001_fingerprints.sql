@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS fingerprints (
+	path TEXT PRIMARY KEY,
+	kind TEXT NOT NULL,
+	bits BYTEA NOT NULL,
+	inserted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS fingerprints_kind ON fingerprints (kind);
@@ -0,0 +1,82 @@
+// Tests of the Node.js bindings, run from the repository root with `npm run build && npm test`.
+
+import assert from 'node:assert/strict';
+import { mkdtempSync, writeFileSync } from 'node:fs';
+import { createRequire } from 'node:module';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { test } from 'node:test';
+
+const { finger, compare } = createRequire(import.meta.url)('../../fingerprint.node');
+
+/** Write a mono 16-bit WAV of `seconds` of a 440Hz tone. */
+function writeWav(path, seconds) {
+  const rate = 44100;
+  const samples = rate * seconds;
+  const data = Buffer.alloc(44 + samples * 2);
+
+  data.write('RIFF', 0);
+  data.writeUInt32LE(36 + samples * 2, 4);
+  data.write('WAVEfmt ', 8);
+  data.writeUInt32LE(16, 16);
+  data.writeUInt16LE(1, 20);
+  data.writeUInt16LE(1, 22);
+  data.writeUInt32LE(rate, 24);
+  data.writeUInt32LE(rate * 2, 28);
+  data.writeUInt16LE(2, 32);
+  data.writeUInt16LE(16, 34);
+  data.write('data', 36);
+  data.writeUInt32LE(samples * 2, 40);
+
+  for (let n = 0; n < samples; n++) {
+    data.writeInt16LE(Math.round(16000 * Math.sin((2 * Math.PI * 440 * n) / rate)), 44 + n * 2);
+  }
+
+  writeFileSync(path, data);
+}
+
+test('finger resolves to a buffer and metadata', async () => {
+  const fp = await finger('samples/ascii.txt');
+
+  assert.ok(Buffer.isBuffer(fp.buffer));
+  assert.equal(fp.metadata.path, 'samples/ascii.txt');
+  assert.equal(fp.metadata.type, 'raw');
+  assert.equal(fp.metadata.hex, '6964d14b3a2bf3264db15649d5de4ad5');
+  assert.equal(fp.metadata.optionsTag.length, 16);
+});
+
+test('compare round trips', async () => {
+  const a = await finger('samples/ascii.txt');
+  const b = await finger('samples/ascii_similar.txt');
+
+  assert.equal(await compare(a, a), 1);
+
+  const score = await compare(a, b);
+
+  assert.ok(score > 0.8 && score < 1);
+});
+
+test('errors carry a code', async () => {
+  await assert.rejects(finger('samples/missing'), { code: 'ERR_FINGER' });
+
+  const text = await finger('samples/ascii.txt');
+  const audio = await finger('samples/audio/tone.flac');
+
+  await assert.rejects(compare(text, audio), { code: 'ERR_COMPARE' });
+  await assert.rejects(compare({ ...text, buffer: Buffer.from('garbage') }, text), { code: 'ERR_DECODE' });
+});
+
+test('fingerprinting does not block the event loop', async () => {
+  const path = join(mkdtempSync(join(tmpdir(), 'fingerprint-')), 'long.wav');
+  let ticks = 0;
+
+  writeWav(path, 120);
+
+  const timer = setInterval(() => ticks++, 1);
+  const fp = await finger(path);
+
+  clearInterval(timer);
+
+  assert.equal(fp.metadata.type, 'audio');
+  assert.ok(ticks > 0, 'no timer ran while fingerprinting');
+});
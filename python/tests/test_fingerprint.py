@@ -0,0 +1,117 @@
+"""Tests of the Python bindings, run from the repository root after `maturin develop`."""
+
+import os
+import shutil
+import threading
+import time
+
+import pytest
+
+import fingerprint
+from fingerprint import Fingerprint
+
+
+def test_finger():
+    fp = Fingerprint.finger("samples/ascii.txt")
+
+    assert fp.hex == "6964d14b3a2bf3264db15649d5de4ad5"
+    assert fp.bytes == bytes.fromhex(fp.hex)
+    assert fp.type == "raw"
+    assert str(fp.path) == "samples/ascii.txt"
+
+
+def test_compare():
+    fp = Fingerprint.finger("samples/ascii.txt")
+
+    assert fp.compare(fp) == 1.0
+    assert 0.8 < fp.compare(Fingerprint.finger("samples/ascii_similar.txt")) < 1.0
+
+
+def test_encode_round_trip():
+    fp = Fingerprint.finger("samples/ascii.txt")
+
+    assert Fingerprint.decode(fp.encode()).hex == fp.hex
+
+
+def test_errors():
+    with pytest.raises(fingerprint.FingerError):
+        Fingerprint.finger("samples/missing")
+
+    with pytest.raises(fingerprint.DecodeError):
+        Fingerprint.decode(b"garbage")
+
+    audio = Fingerprint.finger("samples/audio/tone.flac")
+
+    with pytest.raises(fingerprint.CompareError):
+        Fingerprint.finger("samples/ascii.txt").compare(audio)
+
+    assert issubclass(fingerprint.CompareError, fingerprint.FingerprintError)
+
+
+def test_find_duplicates(tmp_path):
+    shutil.copy("samples/ascii.txt", tmp_path / "a.txt")
+    shutil.copy("samples/ascii.txt", tmp_path / "b.txt")
+    shutil.copy("samples/ascii_different.txt", tmp_path / "c.txt")
+
+    groups = fingerprint.find_duplicates(str(tmp_path), threshold=0.9)
+
+    assert len(groups) == 1
+    assert [os.path.basename(p) for p in groups[0]["paths"]] == ["a.txt", "b.txt"]
+    assert groups[0]["similarity"] == 1.0
+
+
+def test_releases_gil(tmp_path):
+    page = open("samples/text/page.html").read()
+    path = tmp_path / "large.html"
+
+    # Large enough that fingerprinting takes far longer than the interpreter's thread switch interval.
+    path.write_text(page * (16_000_000 // len(page)))
+
+    running = threading.Event()
+    stop = threading.Event()
+    progress = {"count": 0, "longest_stall": 0.0}
+
+    def count():
+        last = time.perf_counter()
+
+        running.set()
+
+        while not stop.is_set():
+            now = time.perf_counter()
+            progress["count"] += 1
+            progress["longest_stall"] = max(progress["longest_stall"], now - last)
+            last = now
+
+    counter = threading.Thread(target=count)
+    counter.start()
+    running.wait()
+
+    before = progress["count"]
+    start = time.perf_counter()
+    fp = Fingerprint.finger(path)
+    elapsed = time.perf_counter() - start
+    during = progress["count"] - before
+
+    stop.set()
+    counter.join()
+
+    assert fp.type == "text"
+    # Holding the GIL would stall the counter for the whole call.
+    assert during > 0
+    assert progress["longest_stall"] < elapsed / 4, (progress, elapsed)
+
+
+def test_finger_from_threads():
+    results = []
+    threads = [
+        threading.Thread(target=lambda: results.append(Fingerprint.finger("samples/audio/tone.flac")))
+        for _ in range(4)
+    ]
+
+    for thread in threads:
+        thread.start()
+
+    for thread in threads:
+        thread.join()
+
+    assert len({fp.hex for fp in results}) == 1
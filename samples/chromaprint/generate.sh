@@ -0,0 +1,16 @@
+#!/bin/sh
+# Capture the fpcalc output of a track and of a second, lossy encode of it, checked by the tests of
+# src/chromaprint.rs. Run from the repository root, with Chromaprint's fpcalc and ffmpeg installed.
+
+set -eu
+
+out=samples/chromaprint
+
+ffmpeg -y -loglevel error -i samples/audio/tone.flac -c:a libvorbis -q:a 3 "$out/tone.ogg"
+
+for file in samples/audio/tone.flac "$out/tone.ogg"; do
+	name=$(basename "$file")
+
+	fpcalc "$file" > "$out/$name.fpcalc"
+	fpcalc -raw "$file" > "$out/$name.fpcalc-raw"
+done
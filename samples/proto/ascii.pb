@@ -0,0 +1,2 @@
+ *idK:+&MVIJ2samples/ascii.txt:
+sourcepython
\ No newline at end of file
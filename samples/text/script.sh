@@ -0,0 +1,9 @@
+#!/bin/sh
+# Fingerprint every file below a directory and print the results as CSV.
+set -eu
+
+dir="${1:-.}"
+
+find "$dir" -type f | while read -r path; do
+	fingerprint --format csv "$path"
+done
@@ -0,0 +1,31 @@
+#!/usr/bin/env python3
+"""Write the digests and distances of sample files computed by the reference TLSH implementation.
+
+The tests of src/fuzzy.rs check the crate's digests and distances against the files written here. Run from the
+repository root, with the reference implementation's Python bindings installed (`pip install py-tlsh`).
+"""
+
+import itertools
+import pathlib
+
+import tlsh
+
+SAMPLES = [
+	"samples/ascii.txt",
+	"samples/ascii_similar.txt",
+	"samples/ascii_different.txt",
+	"samples/binary/table",
+	"samples/font/sample.ttf",
+]
+
+OUT = pathlib.Path("samples/tlsh")
+
+digests = {path: tlsh.hash(pathlib.Path(path).read_bytes()) for path in SAMPLES}
+
+with open(OUT / "digests.tsv", "w") as out:
+	for path, digest in digests.items():
+		out.write(f"{path}\t{digest}\n")
+
+with open(OUT / "distances.tsv", "w") as out:
+	for a, b in itertools.combinations(SAMPLES, 2):
+		out.write(f"{a}\t{b}\t{tlsh.diff(digests[a], digests[b])}\n")
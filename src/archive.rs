@@ -0,0 +1,624 @@
+use std::{
+	fs::File,
+	io::{self, BufReader, BufWriter, Read, Write},
+	path::Path,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+	paths,
+	store::Store,
+	wire::{self, FORMAT_VERSION, MAX_ENCODED_SIZE},
+	Fingerprint, FingerprintError, ALGORITHM_VERSION,
+};
+
+/// Magic bytes identifying an archive.
+const MAGIC: [u8; 4] = *b"FPAR";
+
+/// Current version of the archive format.
+const VERSION: u8 = 1;
+
+/// Tag preceding every fingerprint record.
+const RECORD_TAG: u8 = 1;
+
+/// Tag preceding the trailer that ends the archive.
+const END_TAG: u8 = 0;
+
+/// Largest size (bytes) of a path in a record, that of `PATH_MAX` on Linux.
+const MAX_PATH_SIZE: usize = 4096;
+
+/// Errors specific to archives.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ArchiveError {
+	/// Input does not start with the archive magic bytes, or contains an unknown tag.
+	#[error("malformed fingerprint archive")]
+	Malformed,
+
+	/// Input was written with an archive format version this crate cannot read.
+	#[error("unsupported archive version {found}, newest supported is {max_supported}")]
+	UnsupportedVersion {
+		/// Archive format version of the input.
+		found: u8,
+
+		/// Newest archive format version this crate can read.
+		max_supported: u8,
+	},
+
+	/// Fingerprints in the archive were produced by different fingerprinting algorithms.
+	#[error("archive holds fingerprints of algorithm version {found}, expected {expected}")]
+	IncompatibleAlgorithm {
+		/// Algorithm version recorded in the archive.
+		found: u16,
+
+		/// Algorithm version of this crate.
+		expected: u16,
+	},
+
+	/// Input ended before the trailer was read.
+	#[error("fingerprint archive is truncated")]
+	Truncated,
+
+	/// The number of records read does not match the count in the trailer.
+	#[error("archive trailer records {expected} fingerprints but {found} were read")]
+	CountMismatch {
+		/// Record count stored in the trailer.
+		expected: u64,
+
+		/// Number of records read.
+		found: u64,
+	},
+
+	/// Stored checksum does not match the checksum of the archive.
+	#[error("archive checksum mismatch")]
+	ChecksumMismatch,
+
+	/// A path or fingerprint of a record is larger than any the archive format holds.
+	#[error("archive record field of {size} bytes exceeds the maximum of {max_size}")]
+	FieldTooLarge {
+		/// Size of the field.
+		size: usize,
+
+		/// Largest size of the field.
+		max_size: usize,
+	},
+}
+
+/// Metadata recorded at the start of an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+	/// Version of this crate that wrote the archive.
+	pub crate_version: String,
+
+	/// Version of the fingerprinting algorithms of the archived fingerprints.
+	pub algorithm_version: u16,
+
+	/// Version of the [wire](crate::wire) format of the archived fingerprints.
+	pub wire_version: u8,
+
+	/// Time the archive was created (seconds since the Unix epoch).
+	pub created_at: i64,
+}
+
+/// A fingerprint in the archive that differs from the one already stored for the same path.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+	/// Fingerprint already in the store, which is kept.
+	pub existing: Fingerprint,
+
+	/// Fingerprint in the archive, which is not imported.
+	pub incoming: Fingerprint,
+}
+
+/// Outcome of an [import].
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+	/// Number of fingerprints added to the store.
+	pub imported: u64,
+
+	/// Number of fingerprints already stored identically.
+	pub unchanged: u64,
+
+	/// Fingerprints not imported because a different fingerprint is stored for the same path.
+	pub conflicts: Vec<Conflict>,
+}
+
+/// Writer computing the CRC32 of everything written through it.
+struct ChecksumWriter<W: Write> {
+	inner: W,
+	hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let written = self.inner.write(buf)?;
+
+		self.hasher.update(&buf[..written]);
+
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+/// Reader computing the CRC32 of everything read through it, failing with [ArchiveError::Truncated] on short reads.
+struct ChecksumReader<R: Read> {
+	inner: R,
+	hasher: crc32fast::Hasher,
+}
+
+impl<R: Read> ChecksumReader<R> {
+	fn take(&mut self, len: usize) -> Result<Vec<u8>, FingerprintError> {
+		let mut buf = Vec::new();
+
+		(&mut self.inner).take(len as u64).read_to_end(&mut buf)?;
+
+		if buf.len() < len {
+			return Err(FingerprintError::from(ArchiveError::Truncated));
+		}
+
+		self.hasher.update(&buf);
+
+		Ok(buf)
+	}
+
+	fn array<const N: usize>(&mut self) -> Result<[u8; N], FingerprintError> {
+		Ok(self.take(N)?.try_into().unwrap())
+	}
+
+	/// Read a field preceded by its size, failing before reading it when it is larger than `max_size`.
+	fn field(&mut self, max_size: usize) -> Result<Vec<u8>, FingerprintError> {
+		let size = u32::from_be_bytes(self.array()?) as usize;
+
+		check_size(size, max_size)?;
+		self.take(size)
+	}
+}
+
+/// Fail with [ArchiveError::FieldTooLarge] when a field is larger than `max_size`.
+fn check_size(size: usize, max_size: usize) -> Result<(), FingerprintError> {
+	match size > max_size {
+		true => Err(FingerprintError::from(ArchiveError::FieldTooLarge {
+			size,
+			max_size,
+		})),
+		false => Ok(()),
+	}
+}
+
+/// Map an unexpected end of input to [ArchiveError::Truncated].
+fn truncated(e: io::Error) -> FingerprintError {
+	match e.kind() {
+		io::ErrorKind::UnexpectedEof => ArchiveError::Truncated.into(),
+		_ => e.into(),
+	}
+}
+
+/// Export every fingerprint in a store to a single archive file, returning the number of fingerprints exported.
+///
+/// Layout (multi-byte integers are big-endian):
+///
+/// | Field                     | Size               |
+/// |---------------------------|--------------------|
+/// | magic `FPAR`              | 4                  |
+/// | archive version           | 1                  |
+/// | crate version size        | 1                  |
+/// | crate version             | crate version size |
+/// | algorithm version         | 2                  |
+/// | wire format version       | 1                  |
+/// | creation time             | 8                  |
+/// | *per record:* tag `1`     | 1                  |
+/// | *per record:* path size   | 4                  |
+/// | *per record:* path        | path size          |
+/// | *per record:* fingerprint size | 4             |
+/// | *per record:* fingerprint | fingerprint size   |
+/// | tag `0`                   | 1                  |
+/// | record count              | 8                  |
+/// | CRC32                     | 4                  |
+///
+/// Fingerprints are in the [wire](crate::wire) encoding, and the CRC32 covers every preceding byte. Paths are at most
+/// 4096 bytes and fingerprints at most [MAX_ENCODED_SIZE] bytes. Records are streamed from the store, so memory use
+/// does not grow with the size of the store.
+pub fn export<S: Store, P: AsRef<Path>>(store: &S, out: P) -> Result<u64, FingerprintError> {
+	let mut writer = ChecksumWriter {
+		inner: BufWriter::new(File::create(out)?),
+		hasher: crc32fast::Hasher::new(),
+	};
+	let crate_version = env!("CARGO_PKG_VERSION").as_bytes();
+	let created_at = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_secs() as i64)
+		.unwrap_or_default();
+	let mut count = 0u64;
+
+	writer.write_all(&MAGIC)?;
+	writer.write_all(&[VERSION, crate_version.len() as u8])?;
+	writer.write_all(crate_version)?;
+	writer.write_all(&ALGORITHM_VERSION.to_be_bytes())?;
+	writer.write_all(&[FORMAT_VERSION])?;
+	writer.write_all(&created_at.to_be_bytes())?;
+
+	store.for_each(&mut |fingerprint| {
+		let path = paths::to_bytes(&fingerprint.path);
+		let encoded = fingerprint.encode();
+
+		check_size(path.len(), MAX_PATH_SIZE)?;
+		check_size(encoded.len(), MAX_ENCODED_SIZE)?;
+		writer.write_all(&[RECORD_TAG])?;
+		writer.write_all(&(path.len() as u32).to_be_bytes())?;
+		writer.write_all(&path)?;
+		writer.write_all(&(encoded.len() as u32).to_be_bytes())?;
+		writer.write_all(&encoded)?;
+		count += 1;
+
+		Ok(())
+	})?;
+
+	writer.write_all(&[END_TAG])?;
+	writer.write_all(&count.to_be_bytes())?;
+
+	let checksum = writer.hasher.clone().finalize();
+	let mut inner = writer.inner;
+
+	inner.write_all(&checksum.to_be_bytes())?;
+	inner.flush()?;
+
+	Ok(count)
+}
+
+/// Read the header of an archive.
+pub fn header<P: AsRef<Path>>(path: P) -> Result<Header, FingerprintError> {
+	read_header(&mut ChecksumReader {
+		inner: BufReader::new(File::open(path)?),
+		hasher: crc32fast::Hasher::new(),
+	})
+}
+
+/// Read and validate the header at the start of an archive.
+fn read_header<R: Read>(reader: &mut ChecksumReader<R>) -> Result<Header, FingerprintError> {
+	if reader.array::<4>()? != MAGIC {
+		return Err(FingerprintError::from(ArchiveError::Malformed));
+	}
+
+	let [version, crate_version_size] = reader.array()?;
+
+	if version != VERSION {
+		return Err(FingerprintError::from(ArchiveError::UnsupportedVersion {
+			found: version,
+			max_supported: VERSION,
+		}));
+	}
+
+	let crate_version = String::from_utf8(reader.take(crate_version_size as usize)?)
+		.map_err(|_| ArchiveError::Malformed)?;
+	let header = Header {
+		crate_version,
+		algorithm_version: u16::from_be_bytes(reader.array()?),
+		wire_version: reader.array::<1>()?[0],
+		created_at: i64::from_be_bytes(reader.array()?),
+	};
+
+	if header.algorithm_version != ALGORITHM_VERSION {
+		return Err(FingerprintError::from(
+			ArchiveError::IncompatibleAlgorithm {
+				found: header.algorithm_version,
+				expected: ALGORITHM_VERSION,
+			},
+		));
+	}
+
+	if header.wire_version > FORMAT_VERSION {
+		return Err(FingerprintError::from(
+			wire::WireError::UnsupportedVersion {
+				found: header.wire_version,
+				max_supported: FORMAT_VERSION,
+			},
+		));
+	}
+
+	Ok(header)
+}
+
+/// Import an archive produced by [export] into a store.
+///
+/// Fingerprints for paths not yet in the store are added. When the store already holds a different fingerprint for
+/// a path, or one produced with different options, the stored fingerprint is kept and the pair is reported as a
+/// [Conflict].
+///
+/// The archive is read twice: once to validate it, and again to import its records
+/// [in a transaction](Store::in_transaction), so a truncated or corrupt archive leaves the store unchanged. Records
+/// are streamed from the archive, so memory use does not grow with its size.
+pub fn import<S: Store, P: AsRef<Path>>(
+	path: P,
+	store: &S,
+) -> Result<ImportReport, FingerprintError> {
+	read_archive(path.as_ref(), |_| Ok(()))?;
+
+	store.in_transaction(|store| {
+		let mut report = ImportReport::default();
+
+		read_archive(path.as_ref(), |incoming| {
+			match store.get(&incoming.path)? {
+				Some(existing)
+					if existing.r#type == incoming.r#type
+						&& existing.options_tag == incoming.options_tag
+						&& existing.bits() == incoming.bits() =>
+				{
+					report.unchanged += 1
+				}
+				Some(existing) => report.conflicts.push(Conflict { existing, incoming }),
+				None => {
+					store.upsert(&incoming)?;
+					report.imported += 1;
+				}
+			}
+
+			Ok(())
+		})?;
+
+		Ok(report)
+	})
+}
+
+/// Read an archive, calling a function with every fingerprint in it, and validate its trailer.
+fn read_archive<F: FnMut(Fingerprint) -> Result<(), FingerprintError>>(
+	path: &Path,
+	mut f: F,
+) -> Result<(), FingerprintError> {
+	let mut reader = ChecksumReader {
+		inner: BufReader::new(File::open(path)?),
+		hasher: crc32fast::Hasher::new(),
+	};
+	let mut count = 0u64;
+
+	read_header(&mut reader)?;
+
+	loop {
+		match reader.array::<1>()?[0] {
+			RECORD_TAG => {}
+			END_TAG => break,
+			_ => return Err(FingerprintError::from(ArchiveError::Malformed)),
+		}
+
+		let path = paths::from_bytes(&reader.field(MAX_PATH_SIZE)?);
+		let mut fingerprint = wire::decode_stored(
+			&reader.field(MAX_ENCODED_SIZE)?,
+			format_args!("archive record {} ({})", count, path.display()),
+		)?;
+
+		fingerprint.path = path;
+		count += 1;
+		f(fingerprint)?;
+	}
+
+	let expected = u64::from_be_bytes(reader.array()?);
+	let checksum = reader.hasher.clone().finalize();
+	let mut stored = [0; 4];
+
+	reader.inner.read_exact(&mut stored).map_err(truncated)?;
+
+	if u32::from_be_bytes(stored) != checksum {
+		return Err(FingerprintError::from(ArchiveError::ChecksumMismatch));
+	}
+
+	if expected != count {
+		return Err(FingerprintError::from(ArchiveError::CountMismatch {
+			expected,
+			found: count,
+		}));
+	}
+
+	Ok(())
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+	use std::{fs, path::PathBuf};
+
+	use super::{export, header, import, ArchiveError, MAX_PATH_SIZE};
+	use crate::{
+		options::Options,
+		store::{SqliteStore, Store},
+		Fingerprint, ALGORITHM_VERSION,
+	};
+
+	/// Return the `(path, encoded fingerprint)` pairs held by a store.
+	fn contents<S: Store>(store: &S) -> Vec<(PathBuf, Vec<u8>)> {
+		let mut contents = Vec::new();
+
+		store
+			.for_each(&mut |fingerprint| {
+				contents.push((fingerprint.path(), fingerprint.encode()));
+
+				Ok(())
+			})
+			.unwrap();
+
+		contents
+	}
+
+	/// Populate a store with copies of the samples under many paths.
+	fn populate<S: Store>(store: &S) {
+		let samples = [
+			Fingerprint::finger("samples/ascii.txt").unwrap(),
+			Fingerprint::finger("samples/ascii_different.txt").unwrap(),
+			Fingerprint::finger("samples/empty").unwrap(),
+		];
+
+		for index in 0..300 {
+			let mut fingerprint = samples[index % samples.len()].clone();
+
+			fingerprint.path = PathBuf::from(format!("library/{}", index));
+			store.upsert(&fingerprint).unwrap();
+		}
+	}
+
+	/// Export a populated `source` store and import it into an empty `target` store, twice.
+	fn round_trip<S: Store, T: Store>(source: &S, target: &T) {
+		let dir = tempfile::tempdir().unwrap();
+		let archive = dir.path().join("library.fpa");
+
+		populate(source);
+
+		assert_eq!(export(source, &archive).unwrap(), 300);
+
+		let header = header(&archive).unwrap();
+
+		assert_eq!(header.crate_version, env!("CARGO_PKG_VERSION"));
+		assert_eq!(header.algorithm_version, ALGORITHM_VERSION);
+
+		let report = import(&archive, target).unwrap();
+
+		assert_eq!(report.imported, 300);
+		assert!(report.conflicts.is_empty());
+		assert_eq!(contents(target), contents(source));
+
+		let report = import(&archive, target).unwrap();
+
+		assert_eq!(report.imported, 0);
+		assert_eq!(report.unchanged, 300);
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let dir = tempfile::tempdir().unwrap();
+		let source = SqliteStore::open(dir.path().join("source.db")).unwrap();
+		let target = SqliteStore::open(dir.path().join("target.db")).unwrap();
+
+		round_trip(&source, &target);
+	}
+
+	#[cfg(feature = "redb")]
+	#[test]
+	fn test_redb_round_trip() {
+		use crate::store::RedbStore;
+
+		let dir = tempfile::tempdir().unwrap();
+		let source = RedbStore::open(dir.path().join("source.redb")).unwrap();
+		let target = RedbStore::open_in_memory().unwrap();
+
+		round_trip(&source, &target);
+		round_trip(&source, &SqliteStore::open_in_memory().unwrap());
+	}
+
+	#[test]
+	fn test_conflicts() {
+		let dir = tempfile::tempdir().unwrap();
+		let archive = dir.path().join("library.fpa");
+		let source = SqliteStore::open_in_memory().unwrap();
+		let target = SqliteStore::open_in_memory().unwrap();
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let mut different = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+		let mut retagged = ascii.clone();
+
+		source.upsert(&ascii).unwrap();
+		different.path = ascii.path();
+		target.upsert(&different).unwrap();
+		export(&source, &archive).unwrap();
+
+		let report = import(&archive, &target).unwrap();
+
+		assert_eq!(report.imported, 0);
+		assert_eq!(report.conflicts.len(), 1);
+		assert_eq!(report.conflicts[0].existing.compare(&different), 1.0);
+		assert_eq!(
+			target
+				.get_by_path("samples/ascii.txt")
+				.unwrap()
+				.unwrap()
+				.compare(&different),
+			1.0
+		);
+
+		retagged.options_tag = Options::text().tag();
+		target.delete(&retagged.path).unwrap();
+		target.upsert(&retagged).unwrap();
+
+		let report = import(&archive, &target).unwrap();
+
+		assert_eq!(report.unchanged, 0);
+		assert_eq!(report.conflicts.len(), 1);
+		assert_eq!(
+			report.conflicts[0].existing.options_tag,
+			Options::text().tag()
+		);
+	}
+
+	#[test]
+	fn test_damaged() {
+		let dir = tempfile::tempdir().unwrap();
+		let archive = dir.path().join("library.fpa");
+		let source = SqliteStore::open_in_memory().unwrap();
+
+		populate(&source);
+		export(&source, &archive).unwrap();
+
+		let data = fs::read(&archive).unwrap();
+		let target = SqliteStore::open_in_memory().unwrap();
+
+		fs::write(&archive, &data[..data.len() - 10]).unwrap();
+
+		let error = import(&archive, &target).unwrap_err();
+
+		assert_eq!(error.downcast_ref(), Some(&ArchiveError::Truncated));
+		assert!(contents(&target).is_empty());
+
+		let mut corrupted = data.clone();
+
+		corrupted[4] = 2;
+		fs::write(&archive, &corrupted).unwrap();
+
+		let error = import(&archive, &target).unwrap_err();
+
+		assert_eq!(
+			error.downcast_ref(),
+			Some(&ArchiveError::UnsupportedVersion {
+				found: 2,
+				max_supported: 1
+			})
+		);
+
+		let mut corrupted = data;
+		let count = corrupted.len() - 5;
+
+		corrupted[count] ^= 0x01;
+		fs::write(&archive, &corrupted).unwrap();
+
+		let error = import(&archive, &target).unwrap_err();
+
+		assert_eq!(error.downcast_ref(), Some(&ArchiveError::ChecksumMismatch));
+		assert!(contents(&target).is_empty());
+	}
+
+	#[test]
+	fn test_oversized_field() {
+		let dir = tempfile::tempdir().unwrap();
+		let archive = dir.path().join("library.fpa");
+		let source = SqliteStore::open_in_memory().unwrap();
+
+		source
+			.upsert(&Fingerprint::finger("samples/ascii.txt").unwrap())
+			.unwrap();
+		export(&source, &archive).unwrap();
+
+		// The path size follows the header and the tag of the first record.
+		let mut data = fs::read(&archive).unwrap();
+		let offset = 4 + 2 + env!("CARGO_PKG_VERSION").len() + 2 + 1 + 8 + 1;
+
+		data[offset..offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+		fs::write(&archive, &data).unwrap();
+
+		let target = SqliteStore::open_in_memory().unwrap();
+		let error = import(&archive, &target).unwrap_err();
+
+		assert_eq!(
+			error.downcast_ref(),
+			Some(&ArchiveError::FieldTooLarge {
+				size: u32::MAX as usize,
+				max_size: MAX_PATH_SIZE,
+			})
+		);
+		assert!(contents(&target).is_empty());
+	}
+}
@@ -0,0 +1,341 @@
+//! HTTP service fingerprinting and comparing files, for running the library as a sidecar.
+//!
+//! Endpoints:
+//!
+//! - `POST /fingerprint`: fingerprint a multipart upload (field `file`), or a JSON `{"path": ...}` under `--root`.
+//! - `POST /compare`: compare JSON `{"a": ..., "b": ...}` canonical fingerprints, or multipart uploads `a` and `b`.
+//! - `GET /healthz`: report the environment diagnostics, and the health of the service derived from them.
+//!
+//! Uploads are streamed to temporary files, and fingerprinting runs on blocking threads.
+
+use std::{
+	error,
+	net::SocketAddr,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use axum::{
+	extract::{DefaultBodyLimit, FromRequest, Multipart, Request, State},
+	http::{header, StatusCode},
+	response::{IntoResponse, Response},
+	routing::{get, post},
+	Json, Router,
+};
+use clap::Parser;
+use fingerprint::{
+	diagnostics::{self, Component, EnvironmentReport},
+	runtime::Config,
+	Fingerprint,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tempfile::NamedTempFile;
+use tokio::{fs::File, io::AsyncWriteExt, net::TcpListener};
+use tower::limit::ConcurrencyLimitLayer;
+
+/// Generic [error::Error] type.
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Serve fingerprinting over HTTP.
+#[derive(Debug, Parser)]
+#[clap(name = "fingerprint-server", version)]
+struct Cli {
+	/// Address to listen on; port 0 picks a free port, printed on startup.
+	#[clap(long, default_value = "127.0.0.1:8080")]
+	listen: SocketAddr,
+
+	/// Most requests handled at once.
+	#[clap(long, default_value_t = 16)]
+	max_concurrency: usize,
+
+	/// Largest request body (bytes), including uploads.
+	#[clap(long, default_value_t = 64 * 1024 * 1024)]
+	max_body_bytes: usize,
+
+	/// Directory paths sent to `/fingerprint` must lie under; paths are refused when not set.
+	#[clap(long)]
+	root: Option<PathBuf>,
+}
+
+/// State shared by the handlers.
+struct AppState {
+	root: Option<PathBuf>,
+}
+
+/// Error response, rendered as JSON `{"error": ...}`.
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+	fn bad_request(message: impl ToString) -> Self {
+		Self(StatusCode::BAD_REQUEST, message.to_string())
+	}
+
+	fn unprocessable(message: impl ToString) -> Self {
+		Self(StatusCode::UNPROCESSABLE_ENTITY, message.to_string())
+	}
+
+	/// Keep the status of a rejected request body, such as 413 for one over the size limit.
+	fn rejected(status: StatusCode, message: impl ToString) -> Self {
+		Self(status, message.to_string())
+	}
+
+	fn internal(message: impl ToString) -> Self {
+		Self(StatusCode::INTERNAL_SERVER_ERROR, message.to_string())
+	}
+}
+
+impl IntoResponse for ApiError {
+	fn into_response(self) -> Response {
+		(self.0, Json(json!({ "error": self.1 }))).into_response()
+	}
+}
+
+/// Fingerprint of a file, as returned by `/fingerprint`.
+#[derive(Serialize)]
+struct Fingerprinted {
+	uri: String,
+	r#type: String,
+	options_tag: String,
+	hex: String,
+	name: String,
+}
+
+/// Body of a `/fingerprint` request for a path.
+#[derive(Deserialize)]
+struct PathRequest {
+	path: PathBuf,
+}
+
+/// Body of a `/compare` request for canonical fingerprints.
+#[derive(Deserialize)]
+struct CompareRequest {
+	a: String,
+	b: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+	let cli = Cli::parse();
+
+	tracing_subscriber::fmt()
+		.with_writer(std::io::stderr)
+		.init();
+
+	let state = Arc::new(AppState {
+		root: cli.root.map(|root| root.canonicalize()).transpose()?,
+	});
+	let app = Router::new()
+		.route("/fingerprint", post(fingerprint))
+		.route("/compare", post(compare))
+		.route("/healthz", get(healthz))
+		.layer(DefaultBodyLimit::max(cli.max_body_bytes))
+		.layer(ConcurrencyLimitLayer::new(cli.max_concurrency))
+		.with_state(state);
+	let listener = TcpListener::bind(cli.listen).await?;
+
+	println!("listening on {}", listener.local_addr()?);
+	axum::serve(listener, app).await?;
+
+	Ok(())
+}
+
+/// Return whether a request carries a multipart body.
+fn is_multipart(request: &Request) -> bool {
+	request
+		.headers()
+		.get(header::CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| value.starts_with("multipart/form-data"))
+}
+
+/// Stream a multipart field to a temporary file, keeping the extension of its file name for format detection.
+async fn save_field(
+	field: &mut axum::extract::multipart::Field<'_>,
+) -> Result<NamedTempFile, ApiError> {
+	let extension = field
+		.file_name()
+		.and_then(|name| Path::new(name).extension())
+		.map(|extension| format!(".{}", extension.to_string_lossy()))
+		.unwrap_or_default();
+	let temporary = Config::global()
+		.temp_file_with_suffix(&extension)
+		.map_err(ApiError::internal)?;
+	let mut file = File::from_std(temporary.reopen().map_err(ApiError::internal)?);
+
+	while let Some(chunk) = field
+		.chunk()
+		.await
+		.map_err(|e| ApiError::rejected(e.status(), e.body_text()))?
+	{
+		file.write_all(&chunk).await.map_err(ApiError::internal)?;
+	}
+
+	file.flush().await.map_err(ApiError::internal)?;
+
+	Ok(temporary)
+}
+
+/// Fingerprint each named field of a multipart upload, in the order of `names`.
+async fn finger_uploads(
+	mut multipart: Multipart,
+	names: &[&str],
+) -> Result<Vec<(String, Fingerprint)>, ApiError> {
+	let mut uploads: Vec<(String, NamedTempFile, String)> = Vec::new();
+
+	while let Some(mut field) = multipart
+		.next_field()
+		.await
+		.map_err(|e| ApiError::rejected(e.status(), e.body_text()))?
+	{
+		let Some(name) = field
+			.name()
+			.filter(|name| names.contains(name))
+			.map(str::to_string)
+		else {
+			continue;
+		};
+		let file_name = field.file_name().unwrap_or(&name).to_string();
+
+		uploads.push((name, save_field(&mut field).await?, file_name));
+	}
+
+	let mut fingerprints = Vec::new();
+
+	for name in names {
+		let (_, temporary, file_name) = uploads
+			.iter()
+			.find(|(field, ..)| field == name)
+			.ok_or_else(|| ApiError::bad_request(format!("missing multipart field {:?}", name)))?;
+
+		fingerprints.push((
+			file_name.clone(),
+			Fingerprint::finger_async(temporary.path())
+				.await
+				.map_err(ApiError::unprocessable)?,
+		));
+	}
+
+	Ok(fingerprints)
+}
+
+/// `POST /fingerprint`
+async fn fingerprint(
+	State(state): State<Arc<AppState>>,
+	request: Request,
+) -> Result<Json<Fingerprinted>, ApiError> {
+	let (name, fingerprint) = match is_multipart(&request) {
+		true => {
+			let multipart = Multipart::from_request(request, &())
+				.await
+				.map_err(|e| ApiError::rejected(e.status(), e.body_text()))?;
+
+			finger_uploads(multipart, &["file"]).await?.remove(0)
+		}
+		false => {
+			let Json(body) = Json::<PathRequest>::from_request(request, &())
+				.await
+				.map_err(|e| ApiError::rejected(e.status(), e.body_text()))?;
+			let root = state.root.as_ref().ok_or_else(|| {
+				ApiError(
+					StatusCode::FORBIDDEN,
+					"paths are not accepted without --root".into(),
+				)
+			})?;
+			let path = root
+				.join(&body.path)
+				.canonicalize()
+				.map_err(|e| ApiError(StatusCode::NOT_FOUND, e.to_string()))?;
+
+			if !path.starts_with(root) {
+				return Err(ApiError(
+					StatusCode::FORBIDDEN,
+					"path is outside the root".into(),
+				));
+			}
+
+			(
+				body.path.to_string_lossy().into_owned(),
+				Fingerprint::finger_async(path)
+					.await
+					.map_err(ApiError::unprocessable)?,
+			)
+		}
+	};
+
+	Ok(Json(Fingerprinted {
+		uri: fingerprint.to_uri(),
+		r#type: fingerprint.r#type().to_string(),
+		options_tag: fingerprint.options_tag().to_string(),
+		hex: fingerprint.to_string(),
+		name,
+	}))
+}
+
+/// `POST /compare`
+async fn compare(request: Request) -> Result<Json<serde_json::Value>, ApiError> {
+	let (a, b) = match is_multipart(&request) {
+		true => {
+			let multipart = Multipart::from_request(request, &())
+				.await
+				.map_err(|e| ApiError::rejected(e.status(), e.body_text()))?;
+			let mut fingerprints = finger_uploads(multipart, &["a", "b"]).await?;
+			let (_, b) = fingerprints.remove(1);
+			let (_, a) = fingerprints.remove(0);
+
+			(a, b)
+		}
+		false => {
+			let Json(body) = Json::<CompareRequest>::from_request(request, &())
+				.await
+				.map_err(|e| ApiError::rejected(e.status(), e.body_text()))?;
+
+			(
+				Fingerprint::from_uri(&body.a).map_err(ApiError::bad_request)?,
+				Fingerprint::from_uri(&body.b).map_err(ApiError::bad_request)?,
+			)
+		}
+	};
+	let similarity = a.try_compare(&b).map_err(ApiError::unprocessable)?;
+
+	Ok(Json(json!({ "similarity": similarity })))
+}
+
+/// Derive the health of the service from the environment diagnostics.
+///
+/// The service is `unavailable` without a writable temporary directory, since uploads are saved there.
+fn health(report: &EnvironmentReport) -> (StatusCode, &'static str) {
+	match report.require(Component::TempDir) {
+		Ok(()) => (StatusCode::OK, "ok"),
+		Err(_) => (StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+	}
+}
+
+/// `GET /healthz`
+async fn healthz() -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+	let report = tokio::task::spawn_blocking(diagnostics::check_environment)
+		.await
+		.map_err(ApiError::internal)?;
+	let findings: Vec<_> = report
+		.findings
+		.iter()
+		.map(|finding| {
+			json!({
+				"component": finding.component.to_string(),
+				"available": finding.available,
+				"detail": finding.detail,
+				"hint": finding.hint,
+			})
+		})
+		.collect();
+
+	let (code, status) = health(&report);
+
+	Ok((
+		code,
+		Json(json!({
+			"status": status,
+			"findings": findings,
+		})),
+	))
+}
@@ -0,0 +1,143 @@
+//! Compare every file in one list of paths with every file in another, writing the similarities as CSV.
+//!
+//! Each list is a file of newline-delimited paths, or `-` to read it from standard input. Files are fingerprinted in
+//! parallel with the default fingerprinter for their type, and `path1,path2,similarity` rows are written to standard
+//! output, skipping pairs whose fingerprints cannot be compared. Progress is reported to standard error.
+//!
+//! Exit codes:
+//!
+//! - 0: success.
+//! - 1: a file failed to fingerprint, and its rows were left out.
+//! - 2: another error occurred, including invalid arguments.
+
+use std::{
+	error, fs,
+	io::{self, Read},
+	path::{Path, PathBuf},
+	process::ExitCode,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use clap::Parser;
+use fingerprint::{runtime::Config, Fingerprint};
+use rayon::prelude::*;
+
+/// Generic [error::Error] type.
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Exit code when a file failed to fingerprint.
+const EXIT_FINGER_FAILED: u8 = 1;
+
+/// Exit code when another error occurred.
+const EXIT_ERROR: u8 = 2;
+
+/// Compare every file in one list of paths with every file in another, writing the similarities as CSV.
+#[derive(Debug, Parser)]
+#[clap(
+	name = "fp_compare",
+	version,
+	after_help = "EXIT CODES:\n    0    Success\n    1    A file failed to fingerprint\n    2    Another error occurred"
+)]
+struct Cli {
+	/// File of newline-delimited paths, or `-` for standard input.
+	first: PathBuf,
+
+	/// File of newline-delimited paths, or `-` for standard input.
+	second: PathBuf,
+}
+
+fn main() -> ExitCode {
+	match run(&Cli::parse()) {
+		Ok(code) => ExitCode::from(code),
+		Err(e) => {
+			eprintln!("error: {}", e);
+
+			ExitCode::from(EXIT_ERROR)
+		}
+	}
+}
+
+/// Run the comparison, returning the exit code.
+fn run(cli: &Cli) -> Result<u8, Error> {
+	if cli.first == Path::new("-") && cli.second == Path::new("-") {
+		return Err("only one list can be read from standard input".into());
+	}
+
+	let first = read_paths(&cli.first)?;
+	let second = read_paths(&cli.second)?;
+	let (first, first_failed) = finger_all(&first);
+	let (second, second_failed) = finger_all(&second);
+	let mut writer = csv::Writer::from_writer(io::stdout().lock());
+
+	writer.write_record(["path1", "path2", "similarity"])?;
+
+	for a in &first {
+		for b in &second {
+			match a.try_compare(b) {
+				Ok(similarity) => writer.write_record([
+					a.path().to_string_lossy().as_ref(),
+					b.path().to_string_lossy().as_ref(),
+					&similarity.to_string(),
+				])?,
+				Err(e) => eprintln!(
+					"warning: {}, {}: {}",
+					a.path().display(),
+					b.path().display(),
+					e
+				),
+			}
+		}
+	}
+
+	writer.flush()?;
+
+	Ok(match first_failed || second_failed {
+		true => EXIT_FINGER_FAILED,
+		false => 0,
+	})
+}
+
+/// Read newline-delimited paths from a file, or from standard input for `-`, skipping blank lines.
+fn read_paths(path: &Path) -> io::Result<Vec<PathBuf>> {
+	let contents = match path == Path::new("-") {
+		true => {
+			let mut contents = String::new();
+
+			io::stdin().read_to_string(&mut contents)?;
+
+			contents
+		}
+		false => fs::read_to_string(path)?,
+	};
+
+	Ok(contents
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.map(PathBuf::from)
+		.collect())
+}
+
+/// Fingerprint files in parallel, returning the fingerprints in order and whether any file failed.
+fn finger_all(paths: &[PathBuf]) -> (Vec<Fingerprint>, bool) {
+	let done = AtomicUsize::new(0);
+	let results: Vec<_> = Config::global().install(|| {
+		paths
+			.par_iter()
+			.map(|path| {
+				let result = Fingerprint::finger(path);
+
+				eprintln!(
+					"[{}/{}] {}",
+					done.fetch_add(1, Ordering::Relaxed) + 1,
+					paths.len(),
+					path.display()
+				);
+
+				result.map_err(|e| eprintln!("error: {}: {}", path.display(), e))
+			})
+			.collect()
+	});
+	let failed = results.iter().any(Result::is_err);
+
+	(results.into_iter().flatten().collect(), failed)
+}
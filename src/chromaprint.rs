@@ -0,0 +1,500 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::Fingerprint;
+
+/// Largest value packed in 3 bits; larger bit deltas store the excess in 5 bits.
+const MAX_NORMAL_VALUE: u8 = 7;
+
+/// Most sub-fingerprints (about 0.124 s each) one fingerprint is shifted against the other when aligning them.
+pub const MAX_OFFSET: usize = 120;
+
+/// Fewest overlapping sub-fingerprints an alignment is scored on, unless a fingerprint is shorter.
+pub const MIN_OVERLAP: usize = 16;
+
+/// Errors produced while reading Chromaprint fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChromaprintError {
+	/// The compressed fingerprint is not valid base64url.
+	#[error("invalid base64 in Chromaprint fingerprint: {0}")]
+	InvalidBase64(String),
+
+	/// The compressed fingerprint ends before all of its sub-fingerprints.
+	#[error("truncated Chromaprint fingerprint")]
+	Truncated,
+
+	/// A value of a raw fingerprint is not a 32-bit integer.
+	#[error("invalid value in raw Chromaprint fingerprint: {0:?}")]
+	InvalidValue(String),
+
+	/// `fpcalc` output has no `FINGERPRINT=` line.
+	#[error("no FINGERPRINT line in fpcalc output")]
+	MissingFingerprint,
+
+	/// Fingerprints were computed with different Chromaprint algorithms.
+	#[error("Chromaprint fingerprints use different algorithms ({left} and {right})")]
+	AlgorithmMismatch {
+		/// Algorithm of the left-hand fingerprint.
+		left: u8,
+
+		/// Algorithm of the right-hand fingerprint.
+		right: u8,
+	},
+
+	/// A fingerprint has no sub-fingerprints.
+	#[error("cannot compare an empty Chromaprint fingerprint")]
+	Empty,
+
+	/// A Chromaprint fingerprint was compared with a fingerprint of this crate.
+	#[error("Chromaprint fingerprints can only be compared with Chromaprint fingerprints")]
+	Incomparable,
+}
+
+/// Fingerprint computed by Chromaprint, as produced by `fpcalc` and stored by AcoustID.
+///
+/// Each 32-bit sub-fingerprint summarises about 0.124 s of audio, so the length follows the duration of the track.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chromaprint {
+	algorithm: u8,
+	values: Vec<u32>,
+}
+
+impl Chromaprint {
+	/// Create a fingerprint from the algorithm that computed it and its sub-fingerprints.
+	pub fn new(algorithm: u8, values: Vec<u32>) -> Self {
+		Self { algorithm, values }
+	}
+
+	/// Create a fingerprint from the signed sub-fingerprints of `fpcalc -raw` and `chromaprint_get_raw_fingerprint`.
+	pub fn from_raw(algorithm: u8, values: &[i32]) -> Self {
+		Self::new(
+			algorithm,
+			values.iter().map(|value| *value as u32).collect(),
+		)
+	}
+
+	/// Parse the comma-separated sub-fingerprints of `fpcalc -raw`, written signed or unsigned.
+	pub fn parse_raw(algorithm: u8, raw: &str) -> Result<Self, ChromaprintError> {
+		let values = raw
+			.split(',')
+			.map(str::trim)
+			.filter(|value| !value.is_empty())
+			.map(|value| {
+				value
+					.parse::<i64>()
+					.ok()
+					.filter(|value| (i32::MIN as i64..=u32::MAX as i64).contains(value))
+					.map(|value| value as u32)
+					.ok_or_else(|| ChromaprintError::InvalidValue(value.to_string()))
+			})
+			.collect::<Result<_, _>>()?;
+
+		Ok(Self::new(algorithm, values))
+	}
+
+	/// Parse a compressed fingerprint in the base64url form printed by `fpcalc`.
+	pub fn from_base64(encoded: &str) -> Result<Self, ChromaprintError> {
+		let bytes = URL_SAFE_NO_PAD
+			.decode(encoded.trim().trim_end_matches('='))
+			.map_err(|e| ChromaprintError::InvalidBase64(e.to_string()))?;
+
+		decompress(&bytes)
+	}
+
+	/// Parse the output of `fpcalc`, in either its default compressed form or its `-raw` form.
+	///
+	/// The algorithm of a raw fingerprint is not printed, so `raw_algorithm` is used for it; `fpcalc` defaults to 1.
+	pub fn parse_fpcalc(output: &str, raw_algorithm: u8) -> Result<Self, ChromaprintError> {
+		let fingerprint = output
+			.lines()
+			.find_map(|line| line.trim().strip_prefix("FINGERPRINT="))
+			.ok_or(ChromaprintError::MissingFingerprint)?;
+
+		match fingerprint.contains(',') || fingerprint.parse::<i64>().is_ok() {
+			true => Self::parse_raw(raw_algorithm, fingerprint),
+			false => Self::from_base64(fingerprint),
+		}
+	}
+
+	/// Return the compressed fingerprint in the base64url form printed by `fpcalc`.
+	pub fn to_base64(&self) -> String {
+		URL_SAFE_NO_PAD.encode(compress(self))
+	}
+
+	/// Return the Chromaprint algorithm that computed the fingerprint.
+	pub fn algorithm(&self) -> u8 {
+		self.algorithm
+	}
+
+	/// Return the sub-fingerprints.
+	pub fn values(&self) -> &[u32] {
+		&self.values
+	}
+
+	/// Return the similarity in [0, 1] of this fingerprint and another, allowing one to start later than the other.
+	///
+	/// Fingerprints are shifted against each other by up to [MAX_OFFSET] sub-fingerprints, and each alignment is
+	/// scored by its bit agreement, one minus the bit error rate, over the overlapping sub-fingerprints. The best
+	/// score is returned.
+	pub fn compare(&self, other: &Chromaprint) -> Result<f64, ChromaprintError> {
+		if self.algorithm != other.algorithm {
+			return Err(ChromaprintError::AlgorithmMismatch {
+				left: self.algorithm,
+				right: other.algorithm,
+			});
+		}
+
+		if self.values.is_empty() || other.values.is_empty() {
+			return Err(ChromaprintError::Empty);
+		}
+
+		let min_overlap = MIN_OVERLAP.min(self.values.len()).min(other.values.len());
+		let offsets = -(MAX_OFFSET.min(other.values.len() - 1) as isize)
+			..=MAX_OFFSET.min(self.values.len() - 1) as isize;
+
+		Ok(offsets
+			.filter_map(|offset| {
+				let (left, right) = match offset >= 0 {
+					true => (&self.values[offset as usize..], &other.values[..]),
+					false => (&self.values[..], &other.values[(-offset) as usize..]),
+				};
+				let overlap = left.len().min(right.len());
+
+				(overlap >= min_overlap).then(|| {
+					let errors: u32 = left
+						.iter()
+						.zip(right)
+						.map(|(a, b)| (a ^ b).count_ones())
+						.sum();
+
+					1f64 - errors as f64 / (32 * overlap) as f64
+				})
+			})
+			.fold(0f64, f64::max))
+	}
+}
+
+/// Audio fingerprint computed by this crate or read from Chromaprint.
+#[derive(Debug, Clone)]
+pub enum AudioFingerprint {
+	/// Fingerprint computed by this crate.
+	Native(Fingerprint),
+
+	/// Fingerprint computed by Chromaprint.
+	Chromaprint(Chromaprint),
+}
+
+impl AudioFingerprint {
+	/// Return the similarity in [0, 1] of this fingerprint and another of the same kind.
+	///
+	/// Chromaprint and native fingerprints summarise different features and are never comparable with each other.
+	pub fn try_compare(&self, other: &AudioFingerprint) -> Result<f64, crate::FingerprintError> {
+		match (self, other) {
+			(Self::Native(a), Self::Native(b)) => Ok(a.try_compare(b)?),
+			(Self::Chromaprint(a), Self::Chromaprint(b)) => Ok(a.compare(b)?),
+			_ => Err(ChromaprintError::Incomparable.into()),
+		}
+	}
+}
+
+impl From<Fingerprint> for AudioFingerprint {
+	fn from(fingerprint: Fingerprint) -> Self {
+		Self::Native(fingerprint)
+	}
+}
+
+impl From<Chromaprint> for AudioFingerprint {
+	fn from(fingerprint: Chromaprint) -> Self {
+		Self::Chromaprint(fingerprint)
+	}
+}
+
+/// Append the low `width` bits of each value to a little-endian bit stream.
+fn pack(values: impl IntoIterator<Item = u8>, width: usize, output: &mut Vec<u8>) {
+	let start = output.len() * 8;
+
+	for (index, value) in values.into_iter().enumerate() {
+		for bit in 0..width {
+			let position = start + index * width + bit;
+
+			if position / 8 == output.len() {
+				output.push(0);
+			}
+
+			output[position / 8] |= (value >> bit & 1) << (position % 8);
+		}
+	}
+}
+
+/// Read `width`-bit values from a little-endian bit stream.
+fn unpack(bytes: &[u8], width: usize) -> impl Iterator<Item = u8> + '_ {
+	(0..bytes.len() * 8 / width).map(move |index| {
+		(0..width).fold(0, |value, bit| {
+			let position = index * width + bit;
+
+			value | (bytes[position / 8] >> (position % 8) & 1) << bit
+		})
+	})
+}
+
+/// Compress a fingerprint as Chromaprint does.
+///
+/// Each sub-fingerprint is XORed with the previous one, and the positions of its set bits are stored as deltas
+/// ending in 0. Deltas are packed in 3 bits, with deltas of [MAX_NORMAL_VALUE] or more storing the excess in 5 bits
+/// after all of them, behind a header of the algorithm and the 24-bit number of sub-fingerprints.
+fn compress(fingerprint: &Chromaprint) -> Vec<u8> {
+	let mut deltas = Vec::new();
+	let mut previous = 0;
+
+	for value in &fingerprint.values {
+		let mut changed = value ^ previous;
+		let mut last_bit = 0;
+
+		while changed != 0 {
+			let bit = changed.trailing_zeros() as u8 + 1;
+
+			deltas.push(bit - last_bit);
+			last_bit = bit;
+			changed &= changed - 1;
+		}
+
+		deltas.push(0);
+		previous = *value;
+	}
+
+	let length = fingerprint.values.len() as u32;
+	let mut output = vec![fingerprint.algorithm];
+
+	output.extend_from_slice(&length.to_be_bytes()[1..]);
+	pack(
+		deltas.iter().map(|delta| (*delta).min(MAX_NORMAL_VALUE)),
+		3,
+		&mut output,
+	);
+	pack(
+		deltas
+			.iter()
+			.filter(|delta| **delta >= MAX_NORMAL_VALUE)
+			.map(|delta| delta - MAX_NORMAL_VALUE),
+		5,
+		&mut output,
+	);
+
+	output
+}
+
+/// Decompress a fingerprint compressed by [compress].
+fn decompress(bytes: &[u8]) -> Result<Chromaprint, ChromaprintError> {
+	let [algorithm, a, b, c, packed @ ..] = bytes else {
+		return Err(ChromaprintError::Truncated);
+	};
+	let length = u32::from_be_bytes([0, *a, *b, *c]) as usize;
+	let mut deltas = Vec::new();
+	let mut ends = 0;
+
+	for delta in unpack(packed, 3) {
+		if ends == length {
+			break;
+		}
+
+		ends += (delta == 0) as usize;
+		deltas.push(delta);
+	}
+
+	if ends != length {
+		return Err(ChromaprintError::Truncated);
+	}
+
+	let exceptions = deltas
+		.iter()
+		.filter(|delta| **delta == MAX_NORMAL_VALUE)
+		.count();
+	let mut excess = unpack(&packed[(deltas.len() * 3).div_ceil(8)..], 5).take(exceptions);
+	let mut values = Vec::with_capacity(length);
+	let mut value = 0u32;
+	let mut last_bit = 0u8;
+
+	for delta in deltas {
+		let delta = match delta {
+			MAX_NORMAL_VALUE => {
+				MAX_NORMAL_VALUE + excess.next().ok_or(ChromaprintError::Truncated)?
+			}
+			delta => delta,
+		};
+
+		if delta == 0 {
+			values.push(value ^ values.last().copied().unwrap_or(0));
+			value = 0;
+			last_bit = 0;
+
+			continue;
+		}
+
+		last_bit += delta;
+
+		if last_bit > 32 {
+			return Err(ChromaprintError::InvalidValue(format!("bit {}", last_bit)));
+		}
+
+		value |= 1 << (last_bit - 1);
+	}
+
+	Ok(Chromaprint::new(*algorithm, values))
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::{Rng, SeedableRng};
+	use rand_chacha::ChaCha8Rng;
+
+	use super::{compress, decompress, AudioFingerprint, Chromaprint, ChromaprintError};
+	use crate::Fingerprint;
+
+	/// Random sub-fingerprints, changing a few bits at a time as those of real audio do.
+	fn track(rng: &mut ChaCha8Rng, length: usize) -> Vec<u32> {
+		let mut value = rng.gen::<u32>();
+
+		(0..length)
+			.map(|_| {
+				for _ in 0..rng.gen_range(0..6) {
+					value ^= 1 << rng.gen_range(0..32);
+				}
+
+				value
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_parse_fpcalc() {
+		// One sub-fingerprint with bit 1 set: header, then deltas [1, 0].
+		assert_eq!(
+			Chromaprint::parse_fpcalc("DURATION=1\nFINGERPRINT=AQAAAQE\n", 1),
+			Ok(Chromaprint::new(1, vec![1]))
+		);
+		// Bit 10 set: a delta of 10 is stored as 7 with an excess of 3.
+		assert_eq!(
+			Chromaprint::from_base64("AQAAAQcD"),
+			Ok(Chromaprint::new(1, vec![1 << 9]))
+		);
+		assert_eq!(Chromaprint::new(1, vec![1 << 9]).to_base64(), "AQAAAQcD");
+		assert_eq!(
+			Chromaprint::parse_fpcalc("FILE=a.flac\nDURATION=1\nFINGERPRINT=-1,2,4294967295\n", 2),
+			Ok(Chromaprint::from_raw(2, &[-1, 2, -1]))
+		);
+		assert_eq!(
+			Chromaprint::parse_fpcalc("DURATION=1\n", 1),
+			Err(ChromaprintError::MissingFingerprint)
+		);
+		assert_eq!(
+			Chromaprint::from_base64("AQAAAg"),
+			Err(ChromaprintError::Truncated)
+		);
+		assert!(matches!(
+			Chromaprint::parse_raw(1, "1,x"),
+			Err(ChromaprintError::InvalidValue(_))
+		));
+		assert!(matches!(
+			Chromaprint::from_base64("AQ!"),
+			Err(ChromaprintError::InvalidBase64(_))
+		));
+	}
+
+	#[test]
+	fn test_compress() {
+		// Vectors of the fingerprint compressor tests of Chromaprint.
+		for (values, expected) in [
+			(vec![1], vec![0, 0, 0, 1, 1]),
+			(vec![7], vec![0, 0, 0, 1, 73, 0]),
+			(vec![1 << 6], vec![0, 0, 0, 1, 7, 0]),
+			(vec![1 << 8], vec![0, 0, 0, 1, 7, 2]),
+			(vec![1, 0], vec![0, 0, 0, 2, 65, 0]),
+			(vec![1, 1], vec![0, 0, 0, 2, 1, 0]),
+		] {
+			let fingerprint = Chromaprint::new(0, values);
+
+			assert_eq!(compress(&fingerprint), expected);
+			assert_eq!(decompress(&expected), Ok(fingerprint));
+		}
+	}
+
+	#[test]
+	#[ignore = "needs fpcalc output, written by samples/chromaprint/generate.sh"]
+	fn test_fpcalc() {
+		let read =
+			|name: &str| std::fs::read_to_string(format!("samples/chromaprint/{}", name)).unwrap();
+		let tracks: Vec<Chromaprint> = ["tone.flac", "tone.ogg"]
+			.into_iter()
+			.map(|name| {
+				let output = read(&format!("{}.fpcalc", name));
+				let compressed = Chromaprint::parse_fpcalc(&output, 1).unwrap();
+				let raw =
+					Chromaprint::parse_fpcalc(&read(&format!("{}.fpcalc-raw", name)), 1).unwrap();
+
+				assert_eq!(compressed, raw, "{}", name);
+				assert!(output.contains(&format!("FINGERPRINT={}\n", raw.to_base64())));
+
+				compressed
+			})
+			.collect();
+
+		assert!(tracks[0].compare(&tracks[1]).unwrap() > 0.9);
+	}
+
+	#[test]
+	fn test_base64_round_trip() {
+		let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+		for length in [0, 1, 7, 100, 1000] {
+			let fingerprint = Chromaprint::new(1, track(&mut rng, length));
+
+			assert_eq!(
+				Chromaprint::from_base64(&fingerprint.to_base64()),
+				Ok(fingerprint)
+			);
+		}
+
+		let extremes = Chromaprint::new(4, vec![0, u32::MAX, 1 << 31, 0, u32::MAX]);
+
+		assert_eq!(
+			Chromaprint::from_base64(&extremes.to_base64()),
+			Ok(extremes)
+		);
+	}
+
+	#[test]
+	fn test_compare() {
+		let mut rng = ChaCha8Rng::seed_from_u64(2);
+		let original = track(&mut rng, 600);
+		// A second encode of the same track: starting 40 sub-fingerprints (about 5 s) later, with noisy bits.
+		let reencoded: Vec<u32> = original[40..]
+			.iter()
+			.map(|value| value ^ (1 << rng.gen_range(0..32)) & rng.gen::<u32>())
+			.collect();
+		let a = Chromaprint::new(1, original);
+		let b = Chromaprint::new(1, reencoded);
+		let other = Chromaprint::new(1, track(&mut rng, 600));
+
+		assert_eq!(a.compare(&a), Ok(1.0));
+		assert!(a.compare(&b).unwrap() > 0.95);
+		assert!(b.compare(&a).unwrap() > 0.95);
+		assert!(a.compare(&other).unwrap() < 0.7);
+		assert_eq!(
+			a.compare(&Chromaprint::new(2, b.values().to_vec())),
+			Err(ChromaprintError::AlgorithmMismatch { left: 1, right: 2 })
+		);
+		assert_eq!(
+			a.compare(&Chromaprint::new(1, Vec::new())),
+			Err(ChromaprintError::Empty)
+		);
+	}
+
+	#[test]
+	fn test_audio_fingerprint() {
+		let native = AudioFingerprint::from(Fingerprint::finger("samples/ascii.txt").unwrap());
+		let chromaprint = AudioFingerprint::from(Chromaprint::new(1, vec![1, 2, 3]));
+
+		assert_eq!(native.try_compare(&native).unwrap(), 1.0);
+		assert_eq!(chromaprint.try_compare(&chromaprint).unwrap(), 1.0);
+		assert!(native.try_compare(&chromaprint).is_err());
+	}
+}
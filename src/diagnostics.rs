@@ -0,0 +1,142 @@
+use std::{
+	fmt::Display,
+	fs::{self, File},
+	io,
+	path::Path,
+	process,
+};
+
+use crate::runtime::Config;
+
+/// External component that some fingerprinting paths depend on.
+///
+/// Files are decoded in process, so no external program such as `ffmpeg` is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+	/// A writable temporary directory.
+	TempDir,
+}
+
+impl Display for Component {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Self::TempDir => "temporary directory",
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+/// A required component is unavailable.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{component} is unavailable: {hint}")]
+pub struct MissingDependency {
+	/// Component that is unavailable.
+	pub component: Component,
+
+	/// How to make the component available.
+	pub hint: String,
+}
+
+/// Result of checking one component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+	/// Component checked.
+	pub component: Component,
+
+	/// Whether the component is usable.
+	pub available: bool,
+
+	/// What was found, such as a path or an error message.
+	pub detail: String,
+
+	/// How to make the component available, when it is not.
+	pub hint: Option<String>,
+}
+
+/// Findings of [check_environment].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvironmentReport {
+	/// Result of checking each component.
+	pub findings: Vec<Finding>,
+}
+
+impl EnvironmentReport {
+	/// Return the finding for a component.
+	pub fn finding(&self, component: Component) -> Option<&Finding> {
+		self.findings
+			.iter()
+			.find(|finding| finding.component == component)
+	}
+
+	/// Fail with [MissingDependency] when a component is unavailable.
+	pub fn require(&self, component: Component) -> Result<(), MissingDependency> {
+		match self.finding(component) {
+			Some(finding) if finding.available => Ok(()),
+			finding => Err(MissingDependency {
+				component,
+				hint: finding
+					.and_then(|finding| finding.hint.clone())
+					.unwrap_or_else(|| "component was not checked".to_string()),
+			}),
+		}
+	}
+}
+
+/// Check the components fingerprinting depends on.
+pub fn check_environment() -> EnvironmentReport {
+	let temp_dir = &Config::global().temp_dir;
+	let finding = match check_writable(temp_dir) {
+		Ok(()) => Finding {
+			component: Component::TempDir,
+			available: true,
+			detail: temp_dir.display().to_string(),
+			hint: None,
+		},
+		Err(e) => Finding {
+			component: Component::TempDir,
+			available: false,
+			detail: format!("{} is not writable: {}", temp_dir.display(), e),
+			hint: Some(
+				"set TMPDIR (TEMP on Windows), or the runtime temp_dir, to a writable directory"
+					.to_string(),
+			),
+		},
+	};
+
+	EnvironmentReport {
+		findings: vec![finding],
+	}
+}
+
+/// Check a directory is writable by creating and removing a file in it.
+fn check_writable(dir: &Path) -> io::Result<()> {
+	let path = dir.join(format!(".fingerprint-check-{}", process::id()));
+
+	File::create(&path)?;
+	fs::remove_file(&path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{check_environment, Component, EnvironmentReport};
+
+	#[test]
+	fn test_check_environment() {
+		let report = check_environment();
+
+		assert!(report.require(Component::TempDir).is_ok());
+		assert!(report
+			.finding(Component::TempDir)
+			.is_some_and(|finding| finding.hint.is_none()));
+
+		let error = EnvironmentReport {
+			findings: Vec::new(),
+		}
+		.require(Component::TempDir)
+		.unwrap_err();
+
+		assert_eq!(error.component, Component::TempDir);
+		assert_eq!(error.hint, "component was not checked");
+	}
+}
@@ -0,0 +1,239 @@
+use std::{
+	io,
+	path::{Path, PathBuf},
+};
+
+use crate::{fingerprinters::raw::RawError, wire::CorruptFingerprint, CompareError, Stage, Type};
+
+/// Error of a library this crate decodes files or stores fingerprints with, carried as the source of a
+/// [FingerprintError].
+pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors produced by every fallible operation of the crate, from [Fingerprint::finger](crate::Fingerprint::finger)
+/// and the [fingerprinters](crate::fingerprinters::Fingerprinter) to the [stores](crate::store).
+///
+/// The [EmptyInput](Self::EmptyInput) variant, for fingerprinters that produce no segments, is distinct from
+/// [EmptyFile](Self::EmptyFile), for files with no bytes to decode.
+#[derive(Debug, thiserror::Error)]
+pub enum FingerprintError {
+	/// Opening or reading the file failed.
+	#[error(transparent)]
+	Io(#[from] io::Error),
+
+	/// Reading a byte of the file failed while fingerprinting it raw.
+	#[error(transparent)]
+	Raw(#[from] RawError),
+
+	/// The file could not be decoded as its detected type.
+	#[error("cannot decode file: {0}")]
+	Decode(#[source] BoxError),
+
+	/// An encoded fingerprint, such as a [wire](crate::wire) encoding or a [uri](crate::uri), could not be parsed.
+	#[error("cannot parse fingerprint: {0}")]
+	Parse(#[source] BoxError),
+
+	/// A stored fingerprint failed its integrity check.
+	#[error(transparent)]
+	Corrupt(#[from] CorruptFingerprint),
+
+	/// Fingerprints could not be compared.
+	#[error(transparent)]
+	Compare(#[from] CompareError),
+
+	/// A [store](crate::store), [archive](crate::archive), [sidecar](crate::sidecar) or extended attribute of
+	/// fingerprints failed.
+	#[error("fingerprint store failed: {0}")]
+	Store(#[source] BoxError),
+
+	/// The file was detected as an image, audio or video whose format has no dedicated fingerprinter in this build,
+	/// returned by [Fingerprint::finger_strict](crate::Fingerprint::finger_strict).
+	#[error("no {} fingerprinter for {}", .r#type, .mime_type)]
+	UnsupportedType {
+		/// Type the file was detected as.
+		r#type: Type,
+
+		/// MIME type the file was detected as.
+		mime_type: String,
+	},
+
+	/// The fingerprinter produced no segments to compare.
+	#[error("no segments to fingerprint")]
+	EmptyInput,
+
+	/// The file has no bytes, so there is nothing to decode as its format.
+	#[error("file is empty")]
+	EmptyFile,
+
+	/// Data given to build a fingerprint or configuration is invalid.
+	#[error("invalid data: {0}")]
+	InvalidData(String),
+
+	/// Processing a file failed at a stage.
+	#[error("{stage} failed for {}: {source}", .path.display())]
+	Stage {
+		/// Path of the file being processed.
+		path: PathBuf,
+
+		/// Stage at which processing failed.
+		stage: Stage,
+
+		/// Error produced at the stage.
+		#[source]
+		source: Box<FingerprintError>,
+	},
+}
+
+impl FingerprintError {
+	/// Return the path of the file being processed, if known.
+	pub fn path(&self) -> Option<&Path> {
+		match self {
+			Self::Stage { path, .. } => Some(path),
+			_ => None,
+		}
+	}
+
+	/// Return the stage at which processing failed, if known.
+	pub fn stage(&self) -> Option<Stage> {
+		match self {
+			Self::Stage { stage, .. } => Some(*stage),
+			_ => None,
+		}
+	}
+
+	/// Return the error without its path and stage, to match on what went wrong.
+	pub fn inner(&self) -> &FingerprintError {
+		match self {
+			Self::Stage { source, .. } => source.inner(),
+			_ => self,
+		}
+	}
+
+	/// Return the error of a library or of this crate that the error wraps, such as the
+	/// [WireError](crate::wire::WireError) behind a [Parse](Self::Parse) failure, if it is an `E`.
+	pub fn downcast_ref<E: std::error::Error + 'static>(&self) -> Option<&E> {
+		match self.inner() {
+			Self::Decode(source) | Self::Parse(source) | Self::Store(source) => {
+				source.downcast_ref()
+			}
+			Self::Corrupt(corrupt) => (corrupt as &dyn std::error::Error).downcast_ref(),
+			Self::Compare(compare) => (compare as &dyn std::error::Error).downcast_ref(),
+			_ => None,
+		}
+	}
+}
+
+/// Implement conversions of errors into a [FingerprintError] variant holding them as a boxed source.
+macro_rules! boxed_from {
+	($variant:ident: $($(#[$meta:meta])* $error:ty),* $(,)?) => {
+		$(
+			$(#[$meta])*
+			impl From<$error> for FingerprintError {
+				fn from(e: $error) -> Self {
+					Self::$variant(Box::new(e))
+				}
+			}
+		)*
+	};
+}
+
+boxed_from!(Decode:
+	#[cfg(feature = "audio")]
+	crate::fingerprinters::audio::AudioError,
+	#[cfg(feature = "audio")]
+	symphonia::core::errors::Error,
+	#[cfg(feature = "image")]
+	crate::fingerprinters::image_text::ImageTextError,
+	#[cfg(feature = "image")]
+	crate::fingerprinters::sprite::SpriteError,
+	#[cfg(feature = "image")]
+	image::ImageError,
+	#[cfg(feature = "text")]
+	crate::fingerprinters::subtitle::SubtitleError,
+	#[cfg(feature = "pdf")]
+	crate::fingerprinters::pdf::PdfError,
+	#[cfg(feature = "office")]
+	crate::fingerprinters::office::OfficeError,
+	#[cfg(feature = "office")]
+	quick_xml::Error,
+	#[cfg(feature = "office")]
+	quick_xml::encoding::EncodingError,
+	#[cfg(feature = "office")]
+	quick_xml::events::attributes::AttrError,
+	#[cfg(any(feature = "office", feature = "archive"))]
+	zip::result::ZipError,
+	#[cfg(feature = "epub")]
+	crate::fingerprinters::epub::EpubError,
+	#[cfg(feature = "archive")]
+	crate::fingerprinters::archive::ArchiveError,
+	#[cfg(feature = "binary")]
+	crate::fingerprinters::binary::BinaryError,
+	#[cfg(feature = "binary")]
+	object::Error,
+	#[cfg(feature = "font")]
+	crate::fingerprinters::font::FontError,
+	#[cfg(feature = "mesh")]
+	crate::fingerprinters::mesh::MeshError,
+	#[cfg(feature = "email")]
+	crate::fingerprinters::email::EmailError,
+);
+
+boxed_from!(Parse:
+	crate::wire::WireError,
+	crate::uri::UriError,
+	crate::chromaprint::ChromaprintError,
+	crate::ParseTypeError,
+	crate::LengthError,
+	hex::FromHexError,
+	#[cfg(feature = "proto")]
+	crate::proto::ProtoError,
+	#[cfg(feature = "postcard")]
+	postcard::Error,
+);
+
+boxed_from!(Store:
+	#[cfg(feature = "sqlite")]
+	rusqlite::Error,
+	crate::archive::ArchiveError,
+	#[cfg(feature = "redb")]
+	redb::DatabaseError,
+	#[cfg(feature = "redb")]
+	redb::TransactionError,
+	#[cfg(feature = "redb")]
+	redb::TableError,
+	#[cfg(feature = "redb")]
+	redb::StorageError,
+	#[cfg(feature = "redb")]
+	redb::CommitError,
+	crate::sidecar::SidecarError,
+	#[cfg(feature = "sqlx-postgres")]
+	crate::store::PostgresError,
+	#[cfg(feature = "sqlx-postgres")]
+	sqlx::Error,
+	#[cfg(all(unix, feature = "xattr"))]
+	crate::xattr_store::XattrError,
+);
+
+impl From<crate::fuzzy::FuzzyError> for FingerprintError {
+	fn from(e: crate::fuzzy::FuzzyError) -> Self {
+		match e {
+			crate::fuzzy::FuzzyError::InvalidDigest(_) => Self::Parse(Box::new(e)),
+			_ => Self::Decode(Box::new(e)),
+		}
+	}
+}
+
+/// Attach the [Stage] and path of processing a file to fingerprinting errors.
+pub(crate) trait FingerprintContext<T> {
+	/// Wrap an error in [FingerprintError::Stage] for a stage of processing a path.
+	fn at_stage<P: AsRef<Path>>(self, stage: Stage, path: P) -> Result<T, FingerprintError>;
+}
+
+impl<T, E: Into<FingerprintError>> FingerprintContext<T> for Result<T, E> {
+	fn at_stage<P: AsRef<Path>>(self, stage: Stage, path: P) -> Result<T, FingerprintError> {
+		self.map_err(|source| FingerprintError::Stage {
+			path: path.as_ref().into(),
+			stage,
+			source: Box::new(source.into()),
+		})
+	}
+}
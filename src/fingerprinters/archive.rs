@@ -0,0 +1,375 @@
+use std::{
+	fs::File,
+	io::{BufReader, Read, Seek},
+	path::{Component, Path, PathBuf},
+};
+
+use bitvec::prelude::*;
+
+use crate::{options::Options, Fingerprint, FingerprintError, Type};
+
+/// Largest member (bytes) read; larger members fail the archive rather than exhaust memory.
+pub const MAX_MEMBER_BYTES: u64 = 1 << 30;
+
+/// Errors specific to archive fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ArchiveError {
+	/// The file is not a zip, tar or gzipped tar archive.
+	#[error("not a zip, tar or gzipped tar archive")]
+	Unsupported,
+
+	/// A member is larger than [MAX_MEMBER_BYTES].
+	#[error(
+		"archive member {name} is {size} bytes, larger than the maximum of {MAX_MEMBER_BYTES}"
+	)]
+	MemberTooLarge {
+		/// Name of the member.
+		name: String,
+
+		/// Size (bytes) of the member.
+		size: u64,
+	},
+}
+
+/// Fingerprint of one file in an archive.
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+	/// Path of the member within the archive, with root, `.` and `..` components removed.
+	pub name: PathBuf,
+
+	/// Size (bytes) of the member.
+	pub size: u64,
+
+	/// Fingerprint of the member with the default fingerprinter for its type, whose path is [name](Self::name).
+	pub fingerprint: Fingerprint,
+}
+
+/// Fingerprint of the files in a zip or tar archive, independent of compression, member order and archive format.
+#[derive(Debug, Clone)]
+pub struct ArchiveFingerprint {
+	/// Fingerprints of the files in the archive, in order of name.
+	pub members: Vec<ArchiveMember>,
+
+	/// Order-insensitive summary of the member fingerprints as a 128-bit fingerprint.
+	///
+	/// Each bit is the low bit of the minimum of one hash function over the member fingerprints (1-bit MinHash), so
+	/// archives sharing a fraction `j` of their members agree on about `(1 + j) / 2` of the bits.
+	pub summary: Fingerprint,
+}
+
+/// Comparison of two archive fingerprints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveComparison {
+	/// Similarity of the summaries, in [0, 1].
+	pub similarity: f64,
+
+	/// Jaccard similarity of the sets of member fingerprints.
+	pub jaccard: f64,
+
+	/// Names of the members with identical fingerprints, left then right.
+	pub shared: Vec<(PathBuf, PathBuf)>,
+}
+
+impl ArchiveFingerprint {
+	/// Compare the members of this archive with those of another.
+	pub fn compare(&self, other: &ArchiveFingerprint) -> ArchiveComparison {
+		let shared: Vec<_> = super::pair_identical(
+			self.members.iter().map(|member| &member.fingerprint),
+			other.members.iter().map(|member| &member.fingerprint),
+		)
+		.into_iter()
+		.map(|(left, right)| {
+			(
+				self.members[left].name.clone(),
+				other.members[right].name.clone(),
+			)
+		})
+		.collect();
+		let union = self.members.len() + other.members.len() - shared.len();
+
+		ArchiveComparison {
+			similarity: self.summary.compare(&other.summary),
+			jaccard: match union {
+				0 => 1f64,
+				union => shared.len() as f64 / union as f64,
+			},
+			shared,
+		}
+	}
+}
+
+/// Fingerprint every file in a zip, tar or gzipped tar archive, detected by its magic bytes.
+///
+/// Nothing is extracted at member paths. Members are read into memory and fingerprinted by type, raw from memory when
+/// unrecognised and otherwise from a temporary file of the [runtime configuration](crate::runtime::Config).
+pub fn fingerprint<P: AsRef<Path>>(path: P) -> Result<ArchiveFingerprint, FingerprintError> {
+	let mut file = BufReader::new(File::open(&path)?);
+	let mut magic = [0u8; 262];
+	let read = file.read(&mut magic)?;
+
+	file.rewind()?;
+
+	let mut members = match infer::get(&magic[..read]).map(|kind| kind.mime_type()) {
+		Some("application/zip") => zip_members(file)?,
+		Some("application/gzip") => tar_members(flate2::read::GzDecoder::new(file))?,
+		Some("application/x-tar") => tar_members(file)?,
+		_ => return Err(FingerprintError::from(ArchiveError::Unsupported)),
+	};
+
+	members.sort_by(|a, b| a.name.cmp(&b.name));
+
+	let summary = Fingerprint {
+		path: path.as_ref().into(),
+		fingerprint: summarise(&members),
+		r#type: Type::Raw,
+		options_tag: Options::archive_members().tag(),
+		original_version: None,
+	};
+
+	Ok(ArchiveFingerprint { members, summary })
+}
+
+/// Fingerprint the files of a zip archive.
+fn zip_members<R: Read + Seek>(reader: R) -> Result<Vec<ArchiveMember>, FingerprintError> {
+	let mut archive = zip::ZipArchive::new(reader)?;
+	let mut members = Vec::new();
+
+	for index in 0..archive.len() {
+		let file = archive.by_index(index)?;
+
+		if file.is_file() {
+			let name = file.name().to_string();
+			let size = file.size();
+
+			members.push(member(&name, size, file)?);
+		}
+	}
+
+	Ok(members)
+}
+
+/// Fingerprint the files of a tar archive.
+fn tar_members<R: Read>(reader: R) -> Result<Vec<ArchiveMember>, FingerprintError> {
+	let mut archive = tar::Archive::new(reader);
+	let mut members = Vec::new();
+
+	for entry in archive.entries()? {
+		let entry = entry?;
+
+		if entry.header().entry_type().is_file() {
+			let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+			let size = entry.size();
+
+			members.push(member(&name, size, entry)?);
+		}
+	}
+
+	Ok(members)
+}
+
+/// Fingerprint one member from its reader.
+fn member<R: Read>(name: &str, size: u64, reader: R) -> Result<ArchiveMember, FingerprintError> {
+	let too_large = || ArchiveError::MemberTooLarge {
+		name: name.to_string(),
+		size,
+	};
+
+	if size > MAX_MEMBER_BYTES {
+		return Err(FingerprintError::from(too_large()));
+	}
+
+	let mut bytes = Vec::with_capacity(size as usize);
+
+	reader.take(MAX_MEMBER_BYTES + 1).read_to_end(&mut bytes)?;
+
+	if bytes.len() as u64 > MAX_MEMBER_BYTES {
+		return Err(FingerprintError::from(too_large()));
+	}
+
+	let name = sanitise(name);
+	let mut fingerprint = Fingerprint::finger_from_bytes(&bytes, None)?;
+
+	fingerprint.path = name.clone();
+
+	Ok(ArchiveMember {
+		name,
+		size: bytes.len() as u64,
+		fingerprint,
+	})
+}
+
+/// Return a member path with root, prefix, `.` and `..` components removed, so it cannot name a path outside the
+/// archive.
+fn sanitise(name: &str) -> PathBuf {
+	Path::new(name)
+		.components()
+		.filter_map(|component| match component {
+			Component::Normal(part) => Some(part),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Fold member fingerprints into a 128-bit summary (1-bit MinHash).
+fn summarise(members: &[ArchiveMember]) -> BitBox<u8> {
+	let hashes: Vec<u64> = members
+		.iter()
+		.map(|member| {
+			let fingerprint = &member.fingerprint;
+			let mut canonical = fingerprint.r#type.to_string().into_bytes();
+
+			canonical.extend_from_slice(&fingerprint.options_tag.to_bytes());
+			canonical.extend_from_slice(fingerprint.fingerprint.as_raw_slice());
+
+			// 64-bit FNV-1a, as for options tags.
+			canonical.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+				(hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+			})
+		})
+		.collect();
+
+	super::min_hash(&hashes)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, io::Write};
+
+	use rand::{Rng, SeedableRng};
+	use rand_chacha::ChaCha8Rng;
+
+	use super::{fingerprint, ArchiveError};
+	use crate::fingerprinters::test_util::write_zip;
+
+	/// Files of the test tree: text, a FLAC recording and a PNG image.
+	fn tree() -> Vec<(String, Vec<u8>)> {
+		let mut rng = ChaCha8Rng::seed_from_u64(1);
+		let mut files: Vec<(String, Vec<u8>)> = (0..5)
+			.map(|index| {
+				(
+					format!("docs/file{}.txt", index),
+					(0..2000).map(|_| rng.gen_range(b'a'..=b'z')).collect(),
+				)
+			})
+			.collect();
+		files.push((
+			"media/tone.flac".into(),
+			fs::read("samples/audio/tone.flac").unwrap(),
+		));
+
+		#[cfg(feature = "image")]
+		{
+			let mut png = Vec::new();
+
+			image::RgbImage::from_fn(64, 64, |x, y| image::Rgb([x as u8 * 4, y as u8 * 4, 128]))
+				.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+				.unwrap();
+			files.push(("media/gradient.png".into(), png));
+		}
+
+		files
+	}
+
+	fn write_tar<W: Write>(writer: W, files: &[(String, Vec<u8>)]) {
+		let mut tar = tar::Builder::new(writer);
+
+		for (name, contents) in files {
+			let mut header = tar::Header::new_gnu();
+
+			header.set_size(contents.len() as u64);
+			header.set_mode(0o644);
+			header.set_cksum();
+			tar.append_data(&mut header, name, contents.as_slice())
+				.unwrap();
+		}
+
+		tar.into_inner().unwrap().flush().unwrap();
+	}
+
+	#[test]
+	fn test_archive_formats() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = |name: &str| dir.path().join(name);
+		let files = tree();
+		let mut reversed = files.clone();
+		let mut changed = files.clone();
+
+		reversed.reverse();
+		changed[0].1[0] = b'!';
+
+		write_zip(&path("tree.zip"), &files, 1);
+		write_zip(&path("rezipped.zip"), &reversed, 9);
+		write_tar(fs::File::create(path("tree.tar")).unwrap(), &files);
+		write_tar(
+			flate2::write::GzEncoder::new(
+				fs::File::create(path("tree.tar.gz")).unwrap(),
+				flate2::Compression::default(),
+			),
+			&reversed,
+		);
+		write_zip(&path("changed.zip"), &changed, 6);
+
+		let original = fingerprint(path("tree.zip")).unwrap();
+
+		assert_eq!(original.members.len(), files.len());
+		assert_eq!(original.members[0].fingerprint.r#type(), crate::Type::Raw);
+
+		for other in ["rezipped.zip", "tree.tar", "tree.tar.gz"] {
+			let comparison = original.compare(&fingerprint(path(other)).unwrap());
+
+			assert_eq!(comparison.similarity, 1.0, "{}", other);
+			assert_eq!(comparison.shared.len(), files.len());
+		}
+
+		let comparison = original.compare(&fingerprint(path("changed.zip")).unwrap());
+
+		assert_eq!(comparison.shared.len(), files.len() - 1);
+		assert!(!comparison
+			.shared
+			.iter()
+			.any(|(left, _)| left.ends_with("file0.txt")));
+		assert_eq!(
+			comparison.jaccard,
+			(files.len() - 1) as f64 / (files.len() + 1) as f64
+		);
+		assert!(comparison.similarity < 1.0);
+	}
+
+	#[test]
+	fn test_archive_malicious_paths() {
+		let dir = tempfile::tempdir().unwrap();
+		let archive = dir.path().join("inner").join("evil.zip");
+
+		fs::create_dir(dir.path().join("inner")).unwrap();
+		write_zip(
+			&archive,
+			&[
+				("../escaped.txt", vec![b'x'; 100]),
+				("/etc/absolute.txt", vec![b'y'; 100]),
+			],
+			6,
+		);
+
+		let fingerprint = fingerprint(&archive).unwrap();
+		let names: Vec<_> = fingerprint
+			.members
+			.iter()
+			.map(|member| member.name.clone())
+			.collect();
+
+		assert_eq!(
+			names,
+			["escaped.txt", "etc/absolute.txt"].map(std::path::PathBuf::from)
+		);
+		assert!(!dir.path().join("escaped.txt").exists());
+
+		fs::write(dir.path().join("plain"), b"not an archive").unwrap();
+
+		assert_eq!(
+			super::fingerprint(dir.path().join("plain"))
+				.unwrap_err()
+				.downcast_ref::<ArchiveError>(),
+			Some(&ArchiveError::Unsupported)
+		);
+	}
+}
@@ -0,0 +1,789 @@
+use std::{
+	fmt,
+	fs::File,
+	io,
+	ops::RangeInclusive,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use bitvec::prelude::*;
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use symphonia::core::{
+	audio::SampleBuffer,
+	codecs::{DecoderOptions, CODEC_TYPE_NULL},
+	errors::Error as DecodeError,
+	formats::FormatOptions,
+	io::MediaSourceStream,
+	meta::MetadataOptions,
+	probe::Hint,
+};
+
+use super::{FingerElement, FingerSegment, Fingerprinter};
+use crate::{FingerprintError, NUM_FINGERPRINT_SEGMENTS};
+
+/// Sample rate (Hz) decoded audio is resampled to before fingerprinting.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Size (samples) of each window of the [spectral fingerprinter](AudioFingerprinter), about 0.37s at [SAMPLE_RATE].
+pub const WINDOW_SAMPLES: usize = 16_384;
+
+/// Number of frequency sub-bands the spectrum of each window is mapped to.
+pub const BANDS: usize = 32;
+
+/// Lowest and highest frequencies (Hz) of the sub-bands.
+const BAND_RANGE: (f64, f64) = (300.0, 2000.0);
+
+/// Slowest and fastest tempos (beats per minute) detected by [extract_bpm].
+pub const BPM_RANGE: (f64, f64) = (40.0, 300.0);
+
+/// Size (samples) of the frames whose energy is tracked to detect onsets, about 5.8ms at [SAMPLE_RATE].
+const ONSET_FRAME: usize = 256;
+
+/// Errors specific to audio fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AudioError {
+	/// The file contains no decodable audio track.
+	#[error("no audio track found")]
+	NoTrack,
+
+	/// The audio track does not declare its sample rate.
+	#[error("audio track has no sample rate")]
+	UnknownSampleRate,
+
+	/// The audio has too few samples to divide into the segments of the fingerprint.
+	#[error("{0} samples is too short to fingerprint")]
+	TooShort(usize),
+
+	/// The audio has no onsets, such as silence or a steady tone, from which to detect its tempo.
+	#[error("no beats to detect the tempo from")]
+	NoBeats,
+}
+
+/// Fingerprinter for audio that is independent of the file format the audio is stored in.
+///
+/// Any format supported by `symphonia` (MP3, FLAC, Ogg Vorbis, WAV) is decoded, downmixed to mono, resampled to
+/// 44.1kHz and quantised to 16 bits. The PCM is divided into equal segments, and each fingerprint bit compares the RMS
+/// energy of consecutive segments, so the same recording fingerprints alike in every format.
+#[derive(Debug)]
+pub struct AudioFormatInvariantFingerprinter {
+	path: PathBuf,
+	samples: Vec<i16>,
+}
+
+impl AudioFormatInvariantFingerprinter {
+	/// Create new fingerprinter, decoding the whole file.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let (samples, rate) = decode(path.as_ref())?;
+
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			samples: resample(&samples, rate, SAMPLE_RATE)
+				.into_iter()
+				.map(|sample| (sample * i16::MAX as f32).round() as i16)
+				.collect(),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate the fingerprint from the RMS energy of consecutive PCM segments.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let len = self.samples.len();
+
+		if len < NUM_FINGERPRINT_SEGMENTS {
+			return Err(FingerprintError::from(AudioError::TooShort(len)));
+		}
+
+		let energies: Vec<f64> = (0..NUM_FINGERPRINT_SEGMENTS)
+			.map(|index| {
+				let segment = &self.samples[index * len / NUM_FINGERPRINT_SEGMENTS
+					..(index + 1) * len / NUM_FINGERPRINT_SEGMENTS];
+				let sum: f64 = segment.iter().map(|sample| (*sample as f64).powi(2)).sum();
+
+				(sum / segment.len() as f64).sqrt()
+			})
+			.collect();
+		let mut fingerprint = bitbox![u8, Lsb0; 0; NUM_FINGERPRINT_SEGMENTS];
+
+		for index in 1..NUM_FINGERPRINT_SEGMENTS {
+			fingerprint.set(index - 1, energies[index] >= energies[index - 1]);
+		}
+
+		fingerprint.set(
+			NUM_FINGERPRINT_SEGMENTS - 1,
+			energies[0] >= energies[NUM_FINGERPRINT_SEGMENTS - 1],
+		);
+
+		tracing::trace!(samples = len, "hashed segments");
+
+		Ok(fingerprint)
+	}
+}
+
+/// Fingerprinter for audio by the spectral energy of overlapping windows.
+///
+/// The audio is decoded and resampled as for [AudioFormatInvariantFingerprinter] and split into 128 Hamming-weighted
+/// windows of [WINDOW_SAMPLES] overlapping by half, so about the first 24s are fingerprinted; shorter audio is split
+/// into proportionally shorter windows. The power spectrum of each window between 300Hz and 2kHz is mapped to [BANDS]
+/// logarithmically spaced sub-bands, and each fingerprint bit compares the total sub-band energy of consecutive
+/// windows. Energy in that range is little affected by lossy encoding, so encodes of a recording at different bit
+/// rates fingerprint alike.
+pub struct AudioFingerprinter {
+	path: PathBuf,
+	samples: Vec<f32>,
+	window: usize,
+	fft: Arc<dyn Fft<f32>>,
+}
+
+impl fmt::Debug for AudioFingerprinter {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AudioFingerprinter")
+			.field("path", &self.path)
+			.field("samples", &self.samples.len())
+			.field("window", &self.window)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<'fp> Fingerprinter<'fp> for AudioFingerprinter {
+	fn new<P: AsRef<Path>>(path: P) -> Result<AudioFingerprinter, FingerprintError> {
+		let (samples, rate) = decode(path.as_ref())?;
+		let samples = resample(&samples, rate, SAMPLE_RATE);
+		let window = WINDOW_SAMPLES.min(2 * samples.len() / (NUM_FINGERPRINT_SEGMENTS + 1));
+
+		// Below this, the sub-bands would hold fewer FFT bins than there are bands.
+		if window < 2 * BANDS {
+			return Err(AudioError::TooShort(samples.len()).into());
+		}
+
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			samples,
+			window,
+			fft: FftPlanner::new().plan_fft_forward(window),
+		})
+	}
+
+	fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+}
+
+impl AudioFingerprinter {
+	/// Return the FFT bin nearest to a frequency (Hz).
+	pub fn bin(&self, frequency: f64) -> usize {
+		(frequency * self.window as f64 / SAMPLE_RATE as f64).round() as usize
+	}
+
+	/// Return the frequency (Hz) of an FFT bin.
+	pub fn frequency(&self, bin: usize) -> f64 {
+		bin as f64 * SAMPLE_RATE as f64 / self.window as f64
+	}
+
+	/// Return the tempo (beats per minute) of the audio, as detected by [extract_bpm].
+	pub fn bpm(&self) -> Result<f64, FingerprintError> {
+		let frame_rate = SAMPLE_RATE as f64 / ONSET_FRAME as f64;
+		let energies: Vec<f64> = self
+			.samples
+			.chunks_exact(ONSET_FRAME)
+			.map(|frame| {
+				let sum: f64 = frame.iter().map(|sample| (*sample as f64).powi(2)).sum();
+
+				(sum / ONSET_FRAME as f64 + 1e-10).ln()
+			})
+			.collect();
+		let rises: Vec<f64> = energies
+			.windows(2)
+			.map(|pair| (pair[1] - pair[0]).max(0.0))
+			.collect();
+		// Smoothing spreads each onset over neighbouring frames, so beats falling between frames still line up.
+		let onsets: Vec<f64> = (0..rises.len())
+			.map(|index| {
+				rises[index.saturating_sub(2)..(index + 3).min(rises.len())]
+					.iter()
+					.sum()
+			})
+			.collect();
+		let shortest = (60.0 * frame_rate / BPM_RANGE.1).floor() as usize;
+		let longest = (60.0 * frame_rate / BPM_RANGE.0).ceil() as usize;
+
+		if onsets.len() <= longest + 1 {
+			return Err(AudioError::TooShort(self.samples.len()).into());
+		}
+
+		let correlation =
+			|lag: usize| -> f64 { onsets.iter().zip(&onsets[lag..]).map(|(a, b)| a * b).sum() };
+		let strongest = |lags: RangeInclusive<usize>| {
+			lags.map(|lag| (lag, correlation(lag)))
+				.max_by(|a, b| a.1.total_cmp(&b.1))
+				.unwrap()
+		};
+		let (mut lag, mut peak) = strongest(shortest..=longest);
+
+		if peak <= 0.0 {
+			return Err(AudioError::NoBeats.into());
+		}
+
+		// Beats also correlate at every multiple of their period, as strongly when the period falls between frames, so
+		// the shortest period correlating nearly as well is taken.
+		while lag / 2 >= shortest {
+			let (half, correlated) = strongest((lag / 2 - 1).max(shortest)..=lag / 2 + 1);
+
+			if correlated < 0.8 * peak {
+				break;
+			}
+
+			(lag, peak) = (half, correlated);
+		}
+
+		// Fit a parabola through the peak and its neighbours to find the lag between frames.
+		let (before, after) = (correlation(lag - 1), correlation(lag + 1));
+		let curvature = before - 2.0 * peak + after;
+		let offset = match curvature < 0.0 {
+			true => 0.5 * (before - after) / curvature,
+			false => 0.0,
+		};
+
+		Ok((60.0 * frame_rate / (lag as f64 + offset)).clamp(BPM_RANGE.0, BPM_RANGE.1))
+	}
+}
+
+/// Detect the tempo (beats per minute) of the audio of a file.
+///
+/// The audio is decoded and resampled as for [AudioFingerprinter]. Rises in the log energy of consecutive frames of
+/// about 5.8ms form an onset envelope, and the tempo is that of the lag, within [BPM_RANGE], at which the envelope
+/// best correlates with itself, or of a fraction of that lag correlating nearly as well, so that the tempo of the beat
+/// is preferred over slower tempos whose beats fall on every other one. Audio without onsets fails with
+/// [AudioError::NoBeats].
+pub fn extract_bpm<P: AsRef<Path>>(path: P) -> Result<f64, FingerprintError> {
+	AudioFingerprinter::new(path)?.bpm()
+}
+
+impl<'fp> IntoIterator for &'fp AudioFingerprinter {
+	type Item = AudioSegment<'fp>;
+	type IntoIter = AudioSegmentIterator<'fp>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		Self::IntoIter { fp: self, index: 0 }
+	}
+}
+
+/// Structure for one window of an audio fingerprint.
+#[derive(Clone, Debug)]
+pub struct AudioSegment<'fp> {
+	fp: &'fp AudioFingerprinter,
+	index: usize,
+	pos: usize,
+	spectrum: Vec<f32>,
+	value: Option<f64>,
+}
+
+impl AudioSegment<'_> {
+	/// Return the energy of each sub-band of the window.
+	pub fn bands(&self) -> Result<[f64; BANDS], FingerprintError> {
+		let (low, high) = BAND_RANGE;
+		let mut bands = [0f64; BANDS];
+
+		for element in self {
+			let frequency = self.fp.frequency(element.pos());
+			let band = (BANDS as f64 * (frequency / low).ln() / (high / low).ln()) as usize;
+
+			bands[band.min(BANDS - 1)] += element.data()? as f64;
+		}
+
+		Ok(bands)
+	}
+}
+
+impl<'fp> FingerSegment<'fp> for AudioSegment<'fp> {
+	type Fingerprinter = &'fp AudioFingerprinter;
+	type Value = f64;
+
+	fn fingerprinter(&self) -> Self::Fingerprinter {
+		self.fp
+	}
+
+	fn index(&self) -> usize {
+		self.index
+	}
+
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	fn size(&self) -> usize {
+		self.fp.window
+	}
+
+	fn value(&mut self) -> Result<Self::Value, FingerprintError> {
+		match self.value {
+			Some(value) => Ok(value),
+			None => {
+				let value = self.bands()?.iter().sum();
+
+				self.value = Some(value);
+
+				Ok(value)
+			}
+		}
+	}
+}
+
+impl<'fp> IntoIterator for &'fp AudioSegment<'fp> {
+	type Item = AudioElement<'fp>;
+	type IntoIter = AudioElementIterator<'fp>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		let (low, high) = BAND_RANGE;
+		let first = self.fp.bin(low).max(1);
+
+		Self::IntoIter {
+			fp: self.fp,
+			segment: self,
+			first,
+			index: 0,
+			len: (self.fp.bin(high).min(self.spectrum.len() - 1) + 1).saturating_sub(first),
+		}
+	}
+}
+
+/// Iterator for windows in an audio fingerprint.
+#[derive(Clone, Debug)]
+pub struct AudioSegmentIterator<'fp> {
+	fp: &'fp AudioFingerprinter,
+	index: usize,
+}
+
+impl<'fp> Iterator for AudioSegmentIterator<'fp> {
+	type Item = AudioSegment<'fp>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index >= NUM_FINGERPRINT_SEGMENTS {
+			return None;
+		}
+
+		let (index, window) = (self.index, self.fp.window);
+		let pos = index * window / 2;
+		let mut buffer: Vec<Complex<f32>> = self.fp.samples[pos..pos + window]
+			.iter()
+			.enumerate()
+			.map(|(n, sample)| {
+				let hamming = 0.54
+					- 0.46 * (2.0 * std::f32::consts::PI * n as f32 / (window - 1) as f32).cos();
+
+				Complex::new(sample * hamming, 0.0)
+			})
+			.collect();
+
+		self.fp.fft.process(&mut buffer);
+		self.index += 1;
+
+		Some(AudioSegment {
+			fp: self.fp,
+			index,
+			pos,
+			spectrum: buffer[..=window / 2]
+				.iter()
+				.map(|bin| bin.norm_sqr())
+				.collect(),
+			value: None,
+		})
+	}
+}
+
+/// Structure for one FFT bin of a window.
+#[derive(Clone, Debug)]
+pub struct AudioElement<'fp> {
+	fp: &'fp AudioFingerprinter,
+	segment: &'fp AudioSegment<'fp>,
+	index: usize,
+	pos: usize,
+}
+
+impl<'fp> FingerElement for AudioElement<'fp> {
+	type Fingerprinter = &'fp AudioFingerprinter;
+	type Segment = &'fp AudioSegment<'fp>;
+	type Data = f32;
+
+	fn fingerprinter(&self) -> Self::Fingerprinter {
+		self.fp
+	}
+
+	fn segment(&self) -> Self::Segment {
+		self.segment
+	}
+
+	fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns the FFT bin of the element.
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	fn size(&self) -> usize {
+		1
+	}
+
+	/// Returns the power of the FFT bin.
+	fn data(&self) -> Result<Self::Data, FingerprintError> {
+		Ok(self.segment.spectrum[self.pos])
+	}
+}
+
+/// Iterator for the FFT bins of a window within the sub-band range.
+#[derive(Clone, Debug)]
+pub struct AudioElementIterator<'fp> {
+	fp: &'fp AudioFingerprinter,
+	segment: &'fp AudioSegment<'fp>,
+	first: usize,
+	index: usize,
+	len: usize,
+}
+
+impl<'fp> Iterator for AudioElementIterator<'fp> {
+	type Item = AudioElement<'fp>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index >= self.len {
+			return None;
+		}
+
+		let index = self.index;
+
+		self.index += 1;
+
+		Some(AudioElement {
+			fp: self.fp,
+			segment: self.segment,
+			index,
+			pos: self.first + index,
+		})
+	}
+}
+
+/// Return whether audio of a MIME type, as detected by `infer`, can be decoded by the audio fingerprinter.
+pub fn is_decodable(mime_type: &str) -> bool {
+	DECODABLE_MIME_TYPES.contains(&mime_type)
+}
+
+/// MIME types `infer` detects for the formats enabled in `symphonia`.
+const DECODABLE_MIME_TYPES: [&str; 4] = ["audio/mpeg", "audio/ogg", "audio/x-flac", "audio/x-wav"];
+
+/// Decode the first audio track of a file to mono samples in [-1, 1], returning them with their sample rate.
+fn decode(path: &Path) -> Result<(Vec<f32>, u32), FingerprintError> {
+	let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+	let mut hint = Hint::new();
+
+	if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+		hint.with_extension(extension);
+	}
+
+	let mut format = symphonia::default::get_probe()
+		.format(
+			&hint,
+			source,
+			&FormatOptions::default(),
+			&MetadataOptions::default(),
+		)?
+		.format;
+	let track = format
+		.tracks()
+		.iter()
+		.find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+		.ok_or(AudioError::NoTrack)?;
+	let track_id = track.id;
+	let rate = track
+		.codec_params
+		.sample_rate
+		.ok_or(AudioError::UnknownSampleRate)?;
+	let mut decoder =
+		symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+	let mut samples = Vec::new();
+
+	loop {
+		let packet = match format.next_packet() {
+			Ok(packet) => packet,
+			Err(DecodeError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+			Err(e) => return Err(FingerprintError::from(e)),
+		};
+
+		if packet.track_id() != track_id {
+			continue;
+		}
+
+		let decoded = decoder.decode(&packet)?;
+		let spec = *decoded.spec();
+		let channels = spec.channels.count();
+		let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+
+		buffer.copy_interleaved_ref(decoded);
+		samples.extend(
+			buffer
+				.samples()
+				.chunks(channels)
+				.map(|frame| frame.iter().sum::<f32>() / channels as f32),
+		);
+	}
+
+	Ok((samples, rate))
+}
+
+/// Resample by linear interpolation.
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+	if from == to || samples.is_empty() {
+		return samples.to_vec();
+	}
+
+	let len = (samples.len() as u64 * to as u64 / from as u64) as usize;
+
+	(0..len)
+		.map(|index| {
+			let position = index as f64 * from as f64 / to as f64;
+			let before = position.floor() as usize;
+			let after = (before + 1).min(samples.len() - 1);
+			let weight = (position - before as f64) as f32;
+
+			samples[before] * (1.0 - weight) + samples[after] * weight
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{f64::consts::PI, path::Path};
+
+	use super::{extract_bpm, resample, AudioError, AudioFingerprinter};
+	use crate::{
+		fingerprinters::{FingerElement, FingerSegment, Fingerprinter},
+		options::Options,
+		Fingerprint, FingerprintError, Type,
+	};
+
+	/// Return the test recording at a sample rate, the signal stored in `samples/audio/tone.flac` (0.5s at 44.1kHz).
+	fn tone(rate: u32) -> Vec<f64> {
+		(0..rate / 2)
+			.map(|n| {
+				let t = n as f64 / rate as f64;
+				let envelope = 0.35
+					+ 0.25 * (2.0 * PI * 3.1 * t).sin()
+					+ 0.2 * (2.0 * PI * 7.7 * t + 1.0).sin();
+
+				envelope * (2.0 * PI * 1000.0 * t).sin()
+			})
+			.collect()
+	}
+
+	/// Write the test recording as a 16-bit WAV with the given sample rate, (identical) channels and gain.
+	fn write_wav(path: &Path, rate: u32, channels: u16, gain: f64) {
+		let spec = hound::WavSpec {
+			channels,
+			sample_rate: rate,
+			bits_per_sample: 16,
+			sample_format: hound::SampleFormat::Int,
+		};
+		let mut writer = hound::WavWriter::create(path, spec).unwrap();
+
+		for sample in tone(rate) {
+			for _ in 0..channels {
+				writer
+					.write_sample((sample * gain * i16::MAX as f64).round() as i16)
+					.unwrap();
+			}
+		}
+
+		writer.finalize().unwrap();
+	}
+
+	#[test]
+	fn test_format_invariant() {
+		let dir = tempfile::tempdir().unwrap();
+		let stereo = dir.path().join("stereo.wav");
+		let resampled = dir.path().join("resampled.wav");
+
+		write_wav(&stereo, 44_100, 2, 1.0);
+		write_wav(&resampled, 48_000, 1, 1.0);
+
+		let flac = Fingerprint::finger_audio_format_invariant("samples/audio/tone.flac").unwrap();
+		let stereo = Fingerprint::finger_audio_format_invariant(stereo).unwrap();
+		let resampled = Fingerprint::finger_audio_format_invariant(resampled).unwrap();
+
+		assert!(flac.compare(&stereo) >= 0.99);
+		assert!(flac.compare(&resampled) >= 0.9);
+	}
+
+	#[test]
+	fn test_detected_audio() {
+		let dir = tempfile::tempdir().unwrap();
+		let quieter = dir.path().join("quieter.wav");
+
+		write_wav(&quieter, 44_100, 1, 0.7);
+
+		let flac = Fingerprint::finger("samples/audio/tone.flac").unwrap();
+		let quieter = Fingerprint::finger(quieter).unwrap();
+
+		assert_eq!(flac.r#type(), Type::Audio);
+		assert_eq!(
+			flac.options_tag(),
+			crate::options::Options::audio_spectral().tag()
+		);
+		assert_eq!(
+			flac.options_tag(),
+			crate::options::Options::default_for(&Type::Audio).tag()
+		);
+		assert!(flac.compare(&quieter) >= 0.9, "{}", flac.compare(&quieter));
+	}
+
+	#[test]
+	fn test_spectral_sine() {
+		let dir = tempfile::tempdir().unwrap();
+		let (original, resampled) = (
+			dir.path().join("original.wav"),
+			dir.path().join("resampled.wav"),
+		);
+
+		write_wav(&original, 44_100, 1, 1.0);
+		write_wav(&resampled, 48_000, 2, 0.5);
+
+		let fingerprinter = AudioFingerprinter::new(&original).unwrap();
+
+		// The 1kHz tone peaks in its FFT bin or, where the envelope dips, a neighbour in every window.
+		for segment in &fingerprinter {
+			let peak = segment
+				.into_iter()
+				.max_by(|a, b| a.data().unwrap().total_cmp(&b.data().unwrap()))
+				.unwrap();
+
+			assert!(peak.pos().abs_diff(fingerprinter.bin(1000.0)) <= 1);
+			assert_eq!(segment.size(), 2 * 22_050 / 129);
+		}
+
+		let first = Fingerprint::finger_audio(&original).unwrap();
+		let resampled = Fingerprint::finger_audio(&resampled).unwrap();
+
+		assert_eq!(
+			first.to_string(),
+			Fingerprint::finger_audio(&original).unwrap().to_string()
+		);
+		assert!(
+			first.compare(&resampled) > 0.82,
+			"{}",
+			first.compare(&resampled)
+		);
+	}
+
+	#[test]
+	fn test_too_short() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("short.wav");
+		let spec = hound::WavSpec {
+			channels: 1,
+			sample_rate: 44_100,
+			bits_per_sample: 16,
+			sample_format: hound::SampleFormat::Int,
+		};
+		let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+
+		for sample in 0..64 {
+			writer.write_sample(sample as i16).unwrap();
+		}
+
+		writer.finalize().unwrap();
+
+		assert_eq!(
+			Fingerprint::finger_audio_format_invariant(&path)
+				.unwrap_err()
+				.downcast_ref(),
+			Some(&AudioError::TooShort(64))
+		);
+		assert!(matches!(
+			AudioFingerprinter::new(&path),
+			Err(FingerprintError::Decode(e)) if e.downcast_ref() == Some(&AudioError::TooShort(64))
+		));
+		assert!(Fingerprint::finger(&path).is_err());
+		assert!(Fingerprint::finger_audio_format_invariant("samples/ascii.txt").is_err());
+	}
+
+	/// Write a click track: `beats` decaying 2kHz clicks at a tempo, then the same track played at double speed.
+	fn write_clicks(original: &Path, double_speed: &Path, bpm: f64, beats: usize) {
+		let spec = hound::WavSpec {
+			channels: 1,
+			sample_rate: 44_100,
+			bits_per_sample: 16,
+			sample_format: hound::SampleFormat::Int,
+		};
+		let period = (60.0 / bpm * 44_100.0) as usize;
+		let samples: Vec<i16> = (0..period * beats)
+			.map(|n| {
+				let t = (n % period) as f64 / 44_100.0;
+
+				((-t * 60.0).exp() * (2.0 * PI * 2000.0 * t).sin() * 0.8 * i16::MAX as f64) as i16
+			})
+			.collect();
+
+		for (path, step) in [(original, 1), (double_speed, 2)] {
+			let mut writer = hound::WavWriter::create(path, spec).unwrap();
+
+			for sample in samples.iter().step_by(step) {
+				writer.write_sample(*sample).unwrap();
+			}
+
+			writer.finalize().unwrap();
+		}
+	}
+
+	#[test]
+	fn test_bpm() {
+		let dir = tempfile::tempdir().unwrap();
+		let (original, double_speed) = (
+			dir.path().join("original.wav"),
+			dir.path().join("double_speed.wav"),
+		);
+
+		write_clicks(&original, &double_speed, 120.0, 24);
+
+		let bpm = extract_bpm(&original).unwrap();
+		let double_bpm = extract_bpm(&double_speed).unwrap();
+
+		assert!((bpm - 120.0).abs() < 2.0, "{}", bpm);
+		assert!((double_bpm - 240.0).abs() < 4.0, "{}", double_bpm);
+
+		let first = Fingerprint::finger_audio_with_bpm(&original).unwrap();
+		let double = Fingerprint::finger_audio_with_bpm(&double_speed).unwrap();
+
+		assert_eq!(first.bits().len(), 128);
+		assert_eq!(first.bytes()[0], 102);
+		assert_eq!(double.bytes()[0], 204);
+		assert_eq!(
+			first.bits()[8..],
+			Fingerprint::finger_audio(&original).unwrap().bits()[..120]
+		);
+		assert_eq!(first.options_tag(), Options::audio_bpm().tag());
+		assert_eq!(
+			first.compare(&Fingerprint::finger_audio_with_bpm(&original).unwrap()),
+			1.0
+		);
+		assert!(first.compare(&double) < 1.0);
+		assert!(first
+			.try_compare(&Fingerprint::finger_audio(&original).unwrap())
+			.is_err());
+		assert_eq!(
+			extract_bpm("samples/audio/tone.flac")
+				.map_err(|e| e.downcast_ref::<AudioError>().cloned()),
+			Err(Some(AudioError::TooShort(22_050)))
+		);
+	}
+
+	#[test]
+	fn test_resample() {
+		assert_eq!(resample(&[0.0, 1.0], 1, 1), [0.0, 1.0]);
+		assert_eq!(resample(&[0.0, 1.0], 1, 2), [0.0, 0.5, 1.0, 1.0]);
+		assert_eq!(resample(&[0.0, 0.5, 1.0, 1.0], 2, 1), [0.0, 1.0]);
+	}
+}
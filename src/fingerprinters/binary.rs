@@ -0,0 +1,240 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+use object::{BinaryFormat, Object, ObjectSection, SectionKind};
+
+use super::{raw::RawFingerprinter, Fingerprinter};
+use crate::{options::Options, Fingerprint, FingerprintError, Type, NUM_FINGERPRINT_SEGMENTS};
+
+/// Errors specific to executable fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BinaryError {
+	/// The file is not an ELF or PE executable or object.
+	#[error("not an ELF or PE file")]
+	Unsupported,
+
+	/// No section with content was selected for fingerprinting.
+	#[error("no sections to fingerprint")]
+	NoSections,
+}
+
+/// Fingerprint of one section of an executable.
+#[derive(Debug, Clone)]
+pub struct BinarySection {
+	/// Name of the section, such as `.text`.
+	pub name: String,
+
+	/// Size (bytes) of the section content.
+	pub size: u64,
+
+	/// Raw fingerprint of the section content.
+	pub fingerprint: Fingerprint,
+}
+
+/// Fingerprint of the sections of an ELF or PE file, independent of headers, timestamps and sections that do not
+/// hold code or data.
+#[derive(Debug, Clone)]
+pub struct BinaryFingerprint {
+	/// Fingerprints of the sections, in file order.
+	pub sections: Vec<BinarySection>,
+
+	/// Summary of the section fingerprints as a 128-bit fingerprint, each bit the majority of that bit over the
+	/// sections weighted by their size.
+	pub summary: Fingerprint,
+}
+
+/// Similarity of one section in a comparison of two executables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionComparison {
+	/// Name of the section.
+	pub name: String,
+
+	/// Similarity of the section in both files, in [0, 1], or `None` when only one of them has it.
+	pub similarity: Option<f64>,
+}
+
+/// Comparison of two executable fingerprints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryComparison {
+	/// Similarity of the summaries, in [0, 1].
+	pub similarity: f64,
+
+	/// Similarity of each section by name, in the order of the left-hand file then the sections only in the right-hand
+	/// file.
+	pub sections: Vec<SectionComparison>,
+}
+
+impl BinaryFingerprint {
+	/// Compare this executable with another, overall and section by section.
+	pub fn compare(&self, other: &BinaryFingerprint) -> BinaryComparison {
+		let similarity = |section: &BinarySection, others: &[BinarySection]| {
+			others
+				.iter()
+				.find(|candidate| candidate.name == section.name)
+				.map(|candidate| section.fingerprint.compare(&candidate.fingerprint))
+		};
+		let mut sections: Vec<SectionComparison> = self
+			.sections
+			.iter()
+			.map(|section| SectionComparison {
+				name: section.name.clone(),
+				similarity: similarity(section, &other.sections),
+			})
+			.collect();
+
+		sections.extend(
+			other
+				.sections
+				.iter()
+				.filter(|section| similarity(section, &self.sections).is_none())
+				.map(|section| SectionComparison {
+					name: section.name.clone(),
+					similarity: None,
+				}),
+		);
+
+		BinaryComparison {
+			similarity: self.summary.compare(&other.summary),
+			sections,
+		}
+	}
+}
+
+/// Fingerprint the sections of an ELF or PE file.
+///
+/// By default only sections of code, data and read-only data are fingerprinted, skipping debug information, notes
+/// (such as build IDs), symbol tables and other metadata. PE signatures are held outside the sections and never
+/// fingerprinted. With `all_sections`, every section with content in the file is fingerprinted.
+pub fn fingerprint<P: AsRef<Path>>(
+	path: P,
+	all_sections: bool,
+) -> Result<BinaryFingerprint, FingerprintError> {
+	let bytes = std::fs::read(&path)?;
+	let file = object::File::parse(bytes.as_slice()).map_err(|_| BinaryError::Unsupported)?;
+
+	if !matches!(file.format(), BinaryFormat::Elf | BinaryFormat::Pe) {
+		return Err(FingerprintError::from(BinaryError::Unsupported));
+	}
+
+	let mut sections = Vec::new();
+
+	for section in file.sections() {
+		if section.kind().is_bss() || !(all_sections || code_or_data(section.kind())) {
+			continue;
+		}
+
+		let data = section.data()?;
+
+		if data.is_empty() {
+			continue;
+		}
+
+		let name = section.name()?.to_string();
+
+		sections.push(BinarySection {
+			fingerprint: Fingerprint {
+				path: PathBuf::from(&name),
+				fingerprint: RawFingerprinter::from_slice(data).finger()?,
+				r#type: Type::Raw,
+				options_tag: Options::raw().tag(),
+				original_version: None,
+			},
+			size: data.len() as u64,
+			name,
+		});
+	}
+
+	if sections.is_empty() {
+		return Err(FingerprintError::from(BinaryError::NoSections));
+	}
+
+	let summary = Fingerprint {
+		path: path.as_ref().into(),
+		fingerprint: summarise(&sections),
+		r#type: Type::Raw,
+		options_tag: Options::binary_sections().tag(),
+		original_version: None,
+	};
+
+	Ok(BinaryFingerprint { sections, summary })
+}
+
+/// Return whether a section kind holds code or data, rather than debug information or metadata.
+fn code_or_data(kind: SectionKind) -> bool {
+	matches!(
+		kind,
+		SectionKind::Text
+			| SectionKind::Data
+			| SectionKind::ReadOnlyData
+			| SectionKind::ReadOnlyDataWithRel
+			| SectionKind::ReadOnlyString
+			| SectionKind::Tls
+	)
+}
+
+/// Fold section fingerprints into a 128-bit summary, each bit the majority vote of the sections weighted by size.
+fn summarise(sections: &[BinarySection]) -> BitBox<u8> {
+	(0..NUM_FINGERPRINT_SEGMENTS)
+		.map(|index| {
+			sections
+				.iter()
+				.map(|section| match section.fingerprint.fingerprint[index] {
+					true => section.size as i128,
+					false => -(section.size as i128),
+				})
+				.sum::<i128>()
+				> 0
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{fingerprint, BinaryError};
+
+	#[test]
+	fn test_binary_stripped() {
+		let original = fingerprint("samples/binary/table", false).unwrap();
+		let stripped = fingerprint("samples/binary/table_stripped", false).unwrap();
+		let unrelated = fingerprint("samples/binary/strings", false).unwrap();
+		let comparison = original.compare(&stripped);
+
+		assert!(original
+			.sections
+			.iter()
+			.any(|section| section.name == ".text"));
+		assert!(!original
+			.sections
+			.iter()
+			.any(|section| section.name.starts_with(".debug") || section.name.starts_with(".note")));
+		assert_eq!(comparison.similarity, 1.0);
+		assert!(comparison
+			.sections
+			.iter()
+			.all(|section| section.similarity == Some(1.0)));
+
+		let comparison = original.compare(&unrelated);
+		let rodata = comparison
+			.sections
+			.iter()
+			.find(|section| section.name == ".rodata")
+			.unwrap();
+
+		assert!(comparison.similarity < 0.8, "{}", comparison.similarity);
+		assert!(rodata.similarity.unwrap() < 0.8);
+
+		let all = fingerprint("samples/binary/table", true).unwrap();
+
+		assert!(all.sections.len() > original.sections.len());
+	}
+
+	#[test]
+	fn test_binary_unsupported() {
+		assert_eq!(
+			fingerprint("samples/ascii.txt", false)
+				.unwrap_err()
+				.downcast_ref::<BinaryError>(),
+			Some(&BinaryError::Unsupported)
+		);
+	}
+}
@@ -0,0 +1,135 @@
+use std::{
+	fs::File,
+	path::{Path, PathBuf},
+};
+
+use bitvec::prelude::*;
+use zip::ZipArchive;
+
+use super::{
+	office::{paragraphs, part},
+	text::TextFingerprinter,
+	Fingerprinter,
+};
+use crate::FingerprintError;
+
+/// Fingerprinter for Word documents by the text of their paragraphs.
+///
+/// Word rewrites the XML of a DOCX on every save, with new revision IDs, authors and timestamps, even when the
+/// displayed content is unchanged. Only the text of the paragraphs of `word/document.xml` is extracted, in document
+/// order and one paragraph per line, and fingerprinted with the [TextFingerprinter]. Text deleted by a tracked change
+/// is dropped and inserted text kept, so a document with tracked changes fingerprints as its copy with the changes
+/// accepted.
+#[derive(Debug)]
+pub struct DocxFingerprinter {
+	text: TextFingerprinter,
+}
+
+impl DocxFingerprinter {
+	/// Create new fingerprinter, extracting the paragraph text of the document.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let mut archive = ZipArchive::new(File::open(&path)?)?;
+		let text = paragraphs(&part(&mut archive, "word/document.xml")?, Some(b"t"))?.join("\n");
+
+		Ok(Self {
+			text: TextFingerprinter::from_extracted(path, &text),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.text.path()
+	}
+
+	/// Generate the fingerprint of the extracted text.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		self.text.finger()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use crate::{
+		fingerprinters::{office::OfficeError, test_util::write_zip},
+		Fingerprint, Type,
+	};
+
+	/// Paragraphs of the test document.
+	const PARAGRAPHS: [&str; 3] = [
+		"The quarterly report covers revenue growth in every region, with the sales team ahead of its target.",
+		"Customer reviews of the new product were positive, and the launch plan stays on budget for the year.",
+		"The main risk remains the market in the northern region, which the board will review next quarter.",
+	];
+
+	/// Write a DOCX whose `word/document.xml` has the given body.
+	fn write_docx(path: &Path, body: &str) {
+		write_zip(
+			path,
+			&[
+				("[Content_Types].xml", "<Types/>".to_string()),
+				(
+					"word/document.xml",
+					format!(
+						r#"<w:document xmlns:w="w"><w:body>{}<w:sectPr/></w:body></w:document>"#,
+						body
+					),
+				),
+			],
+			6,
+		);
+	}
+
+	#[test]
+	fn test_tracked_changes() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = |name: &str| dir.path().join(name);
+		let tracked = format!(
+			r#"<w:p w:rsidR="00A1B2C3"><w:r><w:t>{}</w:t></w:r></w:p><w:p w:rsidR="00D4E5F6"><w:r w:rsidRPr="00112233"><w:t xml:space="preserve">Customer reviews of the </w:t></w:r><w:del w:id="1" w:author="Alice" w:date="2024-03-01T10:00:00Z"><w:r><w:delText>old </w:delText></w:r></w:del><w:ins w:id="2" w:author="Alice" w:date="2024-03-01T10:00:00Z"><w:r><w:t>new </w:t></w:r></w:ins><w:r><w:t>product were positive, and the launch plan stays on budget for the year.</w:t></w:r></w:p><w:p><w:pPr><w:rPr><w:ins w:id="3" w:author="Bob" w:date="2024-03-02T09:30:00Z"/></w:rPr></w:pPr><w:r><w:t>{}</w:t></w:r></w:p>"#,
+			PARAGRAPHS[0], PARAGRAPHS[2]
+		);
+		let stripped: String = PARAGRAPHS
+			.iter()
+			.map(|paragraph| format!("<w:p><w:r><w:t>{}</w:t></w:r></w:p>", paragraph))
+			.collect();
+
+		write_docx(&path("tracked.docx"), &tracked);
+		write_docx(&path("stripped.docx"), &stripped);
+		write_docx(
+			&path("other.docx"),
+			&stripped
+				.replace("positive", "negative")
+				.replace("review", "revisit"),
+		);
+
+		let finger = |name: &str| Fingerprint::finger_docx_content(path(name)).unwrap();
+		let original = finger("tracked.docx");
+
+		assert_eq!(original.compare(&finger("stripped.docx")), 1.0);
+
+		assert_eq!(
+			original.compare(
+				&Fingerprint::finger_from_bytes(PARAGRAPHS.join("\n").as_bytes(), Some(Type::Text))
+					.unwrap()
+			),
+			1.0
+		);
+		assert!(original.compare(&finger("other.docx")) < 1.0);
+		assert!(
+			Fingerprint::finger_raw(path("tracked.docx"))
+				.unwrap()
+				.compare(&Fingerprint::finger_raw(path("stripped.docx")).unwrap())
+				< 1.0
+		);
+
+		write_zip(&path("empty.docx"), &[("readme.txt", "hello")], 6);
+
+		let e = Fingerprint::finger_docx_content(path("empty.docx")).unwrap_err();
+
+		assert_eq!(
+			e.downcast_ref::<OfficeError>(),
+			Some(&OfficeError::MissingPart("word/document.xml".to_string()))
+		);
+	}
+}
@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+
+use mail_parser::{Address, MessageParser, MimeHeaders};
+
+use super::text_simhash::SimHashTextFingerprinter;
+use crate::{options::Options, Fingerprint, FingerprintError, Type};
+
+/// Magic bytes of compound files, the container of Outlook `.msg` messages.
+const COMPOUND_FILE_MAGIC: [u8; 8] = [0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+
+/// Errors specific to email fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EmailError {
+	/// The file is not an RFC 5322 message.
+	#[error("not an RFC 5322 email message")]
+	Invalid,
+
+	/// The file is an Outlook `.msg` message, whose compound file format is not parsed.
+	#[error("Outlook .msg messages are not supported")]
+	Unsupported,
+}
+
+/// Fingerprint of one attachment of an email.
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+	/// File name of the attachment, empty when it has none.
+	pub name: String,
+
+	/// Size (bytes) of the decoded attachment.
+	pub size: u64,
+
+	/// Fingerprint of the attachment with the default fingerprinter for its type, whose path is [name](Self::name).
+	pub fingerprint: Fingerprint,
+}
+
+/// Fingerprint of an email, independent of the routing headers, MIME boundaries and transfer encodings that differ
+/// between copies of the same message.
+#[derive(Debug, Clone)]
+pub struct EmailFingerprint {
+	/// Text fingerprint of the body, comparable with [Fingerprint::finger_text_simhash] fingerprints of the same text.
+	pub body: Fingerprint,
+
+	/// BLAKE3 digest of the canonical From, To, Subject and Date headers.
+	pub headers: [u8; 32],
+
+	/// Fingerprints of the attachments, in message order.
+	pub attachments: Vec<EmailAttachment>,
+}
+
+/// Comparison of two email fingerprints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailComparison {
+	/// Similarity of the body texts, in [0, 1].
+	pub body: f64,
+
+	/// Jaccard similarity of the sets of attachment fingerprints, 1 when neither email has attachments.
+	pub attachments: f64,
+
+	/// Whether the From, To, Subject and Date headers are the same, telling a copy of the message from the same text
+	/// sent in another thread.
+	pub same_headers: bool,
+}
+
+impl EmailComparison {
+	/// Return whether the emails are copies of the same message: the same headers, with body and attachments at least
+	/// `threshold` similar.
+	pub fn is_duplicate(&self, threshold: f64) -> bool {
+		self.same_headers && self.body >= threshold && self.attachments >= threshold
+	}
+}
+
+impl EmailFingerprint {
+	/// Compare the body, attachments and headers of this email with those of another.
+	pub fn compare(&self, other: &EmailFingerprint) -> EmailComparison {
+		let shared = super::pair_identical(
+			self.attachments
+				.iter()
+				.map(|attachment| &attachment.fingerprint),
+			other
+				.attachments
+				.iter()
+				.map(|attachment| &attachment.fingerprint),
+		)
+		.len();
+		let union = self.attachments.len() + other.attachments.len() - shared;
+
+		EmailComparison {
+			body: self.body.compare(&other.body),
+			attachments: match union {
+				0 => 1f64,
+				union => shared as f64 / union as f64,
+			},
+			same_headers: self.headers == other.headers,
+		}
+	}
+}
+
+/// Fingerprint an RFC 5322 (`.eml`) email message.
+///
+/// The body is the decoded text of the message, with HTML-only messages converted to text, fingerprinted with the
+/// [SimHashTextFingerprinter]. Attachments are decoded and fingerprinted by type, raw from memory when unrecognised
+/// and otherwise from a temporary file of the [runtime configuration](crate::runtime::Config).
+pub fn fingerprint<P: AsRef<Path>>(path: P) -> Result<EmailFingerprint, FingerprintError> {
+	let bytes = std::fs::read(&path)?;
+
+	if bytes.starts_with(&COMPOUND_FILE_MAGIC) {
+		return Err(FingerprintError::from(EmailError::Unsupported));
+	}
+
+	let message = MessageParser::default()
+		.parse(&bytes)
+		.filter(|message| !message.headers().is_empty())
+		.ok_or(EmailError::Invalid)?;
+	let text = (0..message.text_body_count())
+		.filter_map(|index| message.body_text(index))
+		.collect::<Vec<_>>()
+		.join("\n");
+	let body = Fingerprint {
+		path: path.as_ref().into(),
+		fingerprint: SimHashTextFingerprinter::from_text(&path, text).finger()?,
+		r#type: Type::Text,
+		options_tag: Options::text_simhash().tag(),
+		original_version: None,
+	};
+
+	let canonical = [
+		format!("from:{}", addresses(message.from())),
+		format!("to:{}", addresses(message.to())),
+		format!(
+			"subject:{}",
+			message
+				.subject()
+				.unwrap_or_default()
+				.split_whitespace()
+				.collect::<Vec<_>>()
+				.join(" ")
+		),
+		format!(
+			"date:{}",
+			message
+				.date()
+				.map(|date| date.to_timestamp().to_string())
+				.unwrap_or_default()
+		),
+	]
+	.join("\n");
+
+	let attachments = message
+		.attachments()
+		.map(|part| {
+			let name = part.attachment_name().unwrap_or_default().to_string();
+			let contents = part.contents();
+
+			let mut fingerprint = Fingerprint::finger_from_bytes(contents, None)?;
+
+			fingerprint.path = PathBuf::from(&name);
+
+			Ok(EmailAttachment {
+				fingerprint,
+				size: contents.len() as u64,
+				name,
+			})
+		})
+		.collect::<Result<_, FingerprintError>>()?;
+
+	Ok(EmailFingerprint {
+		body,
+		headers: *blake3::hash(canonical.as_bytes()).as_bytes(),
+		attachments,
+	})
+}
+
+/// Return the lowercased addresses of an address header, without display names, in header order.
+fn addresses(address: Option<&Address>) -> String {
+	address
+		.into_iter()
+		.flat_map(|address| address.iter())
+		.filter_map(|addr| addr.address())
+		.map(|addr| addr.to_lowercase())
+		.collect::<Vec<_>>()
+		.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use super::{fingerprint, EmailError};
+
+	#[test]
+	fn test_email_relayed() {
+		let original = fingerprint("samples/email/message.eml").unwrap();
+		let relayed = fingerprint("samples/email/message_relayed.eml").unwrap();
+		let comparison = original.compare(&relayed);
+
+		assert_eq!(original.attachments.len(), 1);
+		assert_eq!(original.attachments[0].name, "notes.txt");
+		assert_eq!(original.body.r#type(), crate::Type::Text);
+		assert_eq!(comparison.body, 1.0);
+		assert_eq!(comparison.attachments, 1.0);
+		assert!(comparison.same_headers);
+		assert!(comparison.is_duplicate(0.95));
+
+		let forwarded = fingerprint("samples/email/message_forwarded.eml").unwrap();
+		let comparison = original.compare(&forwarded);
+
+		assert_eq!(comparison.body, 1.0);
+		assert_eq!(comparison.attachments, 0.0);
+		assert!(!comparison.same_headers);
+		assert!(!comparison.is_duplicate(0.95));
+	}
+
+	#[test]
+	fn test_email_errors() {
+		let dir = tempfile::tempdir().unwrap();
+		let msg = dir.path().join("message.msg");
+
+		fs::write(
+			&msg,
+			[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1, 0, 0, 0, 0],
+		)
+		.unwrap();
+
+		assert_eq!(
+			fingerprint(&msg).unwrap_err().downcast_ref::<EmailError>(),
+			Some(&EmailError::Unsupported)
+		);
+		assert_eq!(
+			fingerprint("samples/empty")
+				.unwrap_err()
+				.downcast_ref::<EmailError>(),
+			Some(&EmailError::Invalid)
+		);
+	}
+}
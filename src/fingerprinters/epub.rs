@@ -0,0 +1,469 @@
+use std::{
+	fs::File,
+	io::{Read, Seek},
+	path::{Path, PathBuf},
+};
+
+use bitvec::prelude::*;
+use quick_xml::{
+	escape::{resolve_html5_entity, resolve_predefined_entity},
+	events::{BytesStart, Event},
+	Reader,
+};
+use zip::ZipArchive;
+
+use super::{
+	office::{paragraphs, part},
+	text_simhash::SimHashTextFingerprinter,
+};
+use crate::FingerprintError;
+
+/// Errors specific to EPUB fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EpubError {
+	/// The container has no package document, or the package has no spine.
+	#[error("invalid EPUB: {0}")]
+	Invalid(String),
+
+	/// A spine document is encrypted, as by DRM.
+	#[error("EPUB content {0} is encrypted")]
+	Encrypted(String),
+}
+
+/// Fingerprinter for EPUB ebooks by the text of their chapters.
+///
+/// The package document named by `META-INF/container.xml` lists the chapters in reading order (its spine). Their
+/// XHTML is stripped of markup and the text is fingerprinted with the [SimHashTextFingerprinter], so copies differing
+/// in packaging, compression, images and metadata fingerprint alike. Cover pages and the package metadata (title,
+/// authors, dates) are left out unless requested.
+#[derive(Debug)]
+pub struct EpubFingerprinter {
+	text: SimHashTextFingerprinter,
+}
+
+impl EpubFingerprinter {
+	/// Create new fingerprinter, extracting the text of the spine, and of the cover and metadata when
+	/// `include_cover_and_metadata` is set.
+	pub fn new<P: AsRef<Path>>(
+		path: P,
+		include_cover_and_metadata: bool,
+	) -> Result<Self, FingerprintError> {
+		let archive = ZipArchive::new(File::open(&path)?)?;
+
+		Ok(Self {
+			text: SimHashTextFingerprinter::from_text(
+				path,
+				extract(archive, include_cover_and_metadata)?,
+			),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.text.path()
+	}
+
+	/// Generate the fingerprint of the extracted text.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		self.text.finger()
+	}
+}
+
+/// Document of the manifest of a package.
+struct Item {
+	id: String,
+	href: String,
+	cover: bool,
+}
+
+/// Extract the text of the spine of an EPUB in reading order.
+fn extract<R: Read + Seek>(
+	mut archive: ZipArchive<R>,
+	include_cover_and_metadata: bool,
+) -> Result<String, FingerprintError> {
+	let container = part(&mut archive, "META-INF/container.xml")?;
+	let package_path = attributes(&container, b"rootfile", "full-path")?
+		.into_iter()
+		.next()
+		.flatten()
+		.ok_or_else(|| EpubError::Invalid("container names no package document".into()))?;
+	let package = part(&mut archive, &package_path)?;
+	let directory = match package_path.rfind('/') {
+		Some(index) => &package_path[..=index],
+		None => "",
+	};
+	let encrypted = match archive.index_for_name("META-INF/encryption.xml") {
+		Some(_) => attributes(
+			&part(&mut archive, "META-INF/encryption.xml")?,
+			b"CipherReference",
+			"URI",
+		)?
+		.into_iter()
+		.flatten()
+		.map(|uri| percent_decode(&uri))
+		.collect(),
+		None => Vec::new(),
+	};
+	let (items, spine) = manifest_and_spine(&package)?;
+
+	if spine.is_empty() {
+		return Err(FingerprintError::from(EpubError::Invalid(
+			"package has an empty spine".into(),
+		)));
+	}
+
+	let mut text = match include_cover_and_metadata {
+		true => paragraphs(&package, None)?.join("\n"),
+		false => String::new(),
+	};
+
+	for idref in spine {
+		let item = items
+			.iter()
+			.find(|item| item.id == idref)
+			.ok_or_else(|| EpubError::Invalid(format!("spine names unknown item {:?}", idref)))?;
+
+		if item.cover && !include_cover_and_metadata {
+			continue;
+		}
+
+		let href = format!(
+			"{}{}",
+			directory,
+			percent_decode(item.href.split('#').next().unwrap_or_default())
+		);
+
+		if encrypted.contains(&href) {
+			return Err(FingerprintError::from(EpubError::Encrypted(href)));
+		}
+
+		text.push('\n');
+		text.push_str(&xhtml_text(&part(&mut archive, &href)?)?);
+	}
+
+	Ok(text)
+}
+
+/// Return the value of an attribute of every element of a local name, in document order.
+fn attributes(
+	xml: &str,
+	element: &[u8],
+	attribute: &str,
+) -> Result<Vec<Option<String>>, FingerprintError> {
+	let mut reader = Reader::from_str(xml);
+	let mut values = Vec::new();
+
+	loop {
+		match reader.read_event()? {
+			Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == element => {
+				values.push(attribute_value(&e, attribute)?)
+			}
+			Event::Eof => break,
+			_ => (),
+		}
+	}
+
+	Ok(values)
+}
+
+/// Return the unescaped value of an attribute of an element.
+fn attribute_value(element: &BytesStart, name: &str) -> Result<Option<String>, FingerprintError> {
+	Ok(match element.try_get_attribute(name)? {
+		Some(attribute) => Some(attribute.unescape_value()?.into_owned()),
+		None => None,
+	})
+}
+
+/// Return the documents of the manifest of a package, and the IDs of its spine in reading order.
+///
+/// Documents are cover pages when the guide or their ID names them as such, or their spine entry is not linear.
+fn manifest_and_spine(package: &str) -> Result<(Vec<Item>, Vec<String>), FingerprintError> {
+	let mut reader = Reader::from_str(package);
+	let mut items = Vec::new();
+	let mut spine = Vec::new();
+	let mut covers = Vec::new();
+
+	loop {
+		match reader.read_event()? {
+			Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+				b"item" => {
+					if let (Some(id), Some(href)) =
+						(attribute_value(&e, "id")?, attribute_value(&e, "href")?)
+					{
+						items.push(Item {
+							cover: id.to_ascii_lowercase().contains("cover"),
+							id,
+							href,
+						});
+					}
+				}
+				b"itemref" => {
+					if let Some(idref) = attribute_value(&e, "idref")? {
+						if attribute_value(&e, "linear")?.as_deref() == Some("no") {
+							covers.push(idref.clone());
+						}
+
+						spine.push(idref);
+					}
+				}
+				b"reference" if attribute_value(&e, "type")?.as_deref() == Some("cover") => {
+					if let Some(href) = attribute_value(&e, "href")? {
+						covers.extend(
+							items
+								.iter()
+								.filter(|item| item.href == href)
+								.map(|item| item.id.clone()),
+						);
+					}
+				}
+				_ => (),
+			},
+			Event::Eof => break,
+			_ => (),
+		}
+	}
+
+	for item in &mut items {
+		item.cover |= covers.contains(&item.id);
+	}
+
+	Ok((items, spine))
+}
+
+/// Extract the text of the body of an XHTML document, one line per block element.
+fn xhtml_text(xml: &str) -> Result<String, FingerprintError> {
+	let mut reader = Reader::from_str(xml);
+	let mut text = String::new();
+	let mut skipped = 0usize;
+
+	reader.config_mut().check_end_names = false;
+
+	loop {
+		match reader.read_event()? {
+			Event::Start(e)
+				if matches!(e.local_name().as_ref(), b"head" | b"script" | b"style") =>
+			{
+				skipped += 1
+			}
+			Event::End(e) if matches!(e.local_name().as_ref(), b"head" | b"script" | b"style") => {
+				skipped = skipped.saturating_sub(1)
+			}
+			Event::End(e)
+				if matches!(
+					e.local_name().as_ref(),
+					b"p" | b"div"
+						| b"li" | b"h1" | b"h2"
+						| b"h3" | b"h4" | b"h5"
+						| b"h6" | b"blockquote"
+						| b"tr"
+				) =>
+			{
+				text.push('\n')
+			}
+			Event::Empty(e) if e.local_name().as_ref() == b"br" => text.push('\n'),
+			Event::Text(e) if skipped == 0 => text.push_str(&e.decode()?),
+			Event::CData(e) if skipped == 0 => text.push_str(&e.decode()?),
+			Event::GeneralRef(e) if skipped == 0 => match e.resolve_char_ref()? {
+				Some(character) => text.push(character),
+				None => {
+					let name = e.decode()?;
+
+					text.push_str(
+						resolve_predefined_entity(&name)
+							.or_else(|| resolve_html5_entity(&name))
+							.unwrap_or(" "),
+					)
+				}
+			},
+			Event::Eof => break,
+			_ => (),
+		}
+	}
+
+	Ok(text)
+}
+
+/// Decode the percent-encoded bytes of a URI path.
+fn percent_decode(uri: &str) -> String {
+	let bytes = uri.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut index = 0;
+
+	while index < bytes.len() {
+		match (bytes[index], uri.get(index + 1..index + 3)) {
+			(b'%', Some(hex)) if u8::from_str_radix(hex, 16).is_ok() => {
+				decoded.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+				index += 3;
+			}
+			(byte, _) => {
+				decoded.push(byte);
+				index += 1;
+			}
+		}
+	}
+
+	String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{io::Write, path::Path};
+
+	use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+	use super::{percent_decode, EpubError};
+	use crate::{Fingerprint, FingerprintError, Stage};
+
+	/// Chapters of the test book.
+	const CHAPTERS: [&str; 3] = [
+		"It was a bright cold day in April, and the clocks were striking thirteen. The hallway smelt of boiled cabbage and old rag mats.",
+		"Outside, even through the shut window pane, the world looked cold. Down in the street little eddies of wind were whirling dust and torn paper into spirals.",
+		"The ministry of truth was startlingly different from any other object in sight. It was an enormous pyramidal structure of glittering white concrete.",
+	];
+
+	/// Write an EPUB of the test book, packaged under `directory` with a title and compression level.
+	fn write_epub(path: &Path, directory: &str, title: &str, level: i64, encrypted: bool) {
+		let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+		let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+		let deflated = SimpleFileOptions::default()
+			.compression_method(CompressionMethod::Deflated)
+			.compression_level(Some(level));
+		let mut write = |name: &str, contents: &[u8], options: SimpleFileOptions| {
+			zip.start_file(name, options).unwrap();
+			zip.write_all(contents).unwrap();
+		};
+
+		write("mimetype", b"application/epub+zip", stored);
+		write(
+			"META-INF/container.xml",
+			format!(r#"<container><rootfiles><rootfile full-path="{}package.opf" media-type="application/oebps-package+xml"/></rootfiles></container>"#, directory).as_bytes(),
+			deflated,
+		);
+
+		let items: String = (0..CHAPTERS.len())
+			.map(|index| {
+				format!(
+					r#"<item id="c{0}" href="text/chapter%20{0}.xhtml" media-type="application/xhtml+xml"/>"#,
+					index
+				)
+			})
+			.collect();
+		let spine: String = (0..CHAPTERS.len())
+			.map(|index| format!(r#"<itemref idref="c{}"/>"#, index))
+			.collect();
+
+		write(
+			&format!("{}package.opf", directory),
+			format!(
+				r#"<package><metadata><dc:title>{}</dc:title><meta property="dcterms:modified">2024-0{}-01</meta></metadata><manifest><item id="cover" href="text/cover.xhtml" media-type="application/xhtml+xml"/><item id="cover-image" href="images/cover.jpg" properties="cover-image"/>{}</manifest><spine><itemref idref="cover"/>{}</spine></package>"#,
+				title,
+				level,
+				items,
+				spine
+			)
+			.as_bytes(),
+			deflated,
+		);
+		write(
+			&format!("{}text/cover.xhtml", directory),
+			format!("<html><body><h1>{}</h1></body></html>", title).as_bytes(),
+			deflated,
+		);
+		write(
+			&format!("{}images/cover.jpg", directory),
+			&[level as u8; 512],
+			deflated,
+		);
+
+		for (index, chapter) in CHAPTERS.iter().enumerate() {
+			write(
+				&format!("{}text/chapter {}.xhtml", directory, index),
+				format!(
+					"<?xml version=\"1.0\"?><html><head><title>Chapter {0}</title><style>p {{ margin: 0 }}</style></head><body><h2>Chapter&nbsp;{0}</h2><p>{1}</p></body></html>",
+					index, chapter
+				)
+				.as_bytes(),
+				deflated,
+			);
+		}
+
+		if encrypted {
+			write(
+				"META-INF/encryption.xml",
+				format!(r#"<encryption><EncryptedData><CipherData><CipherReference URI="{}text/chapter%201.xhtml"/></CipherData></EncryptedData></encryption>"#, directory).as_bytes(),
+				deflated,
+			);
+		}
+
+		zip.finish().unwrap();
+	}
+
+	#[test]
+	fn test_epub_repackaged() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = |name: &str| dir.path().join(name);
+
+		write_epub(
+			&path("book.epub"),
+			"OEBPS/",
+			"Nineteen Eighty-Four",
+			1,
+			false,
+		);
+		write_epub(
+			&path("repackaged.epub"),
+			"",
+			"1984 (Annotated Edition)",
+			9,
+			false,
+		);
+
+		let original = Fingerprint::finger_epub(path("book.epub"), false).unwrap();
+		let repackaged = Fingerprint::finger_epub(path("repackaged.epub"), false).unwrap();
+
+		assert_eq!(original.compare(&repackaged), 1.0);
+		assert_eq!(
+			Fingerprint::finger(path("repackaged.epub"))
+				.unwrap()
+				.compare(&original),
+			1.0
+		);
+		assert!(
+			Fingerprint::finger_epub(path("book.epub"), true)
+				.unwrap()
+				.compare(&Fingerprint::finger_epub(path("repackaged.epub"), true).unwrap())
+				< 1.0
+		);
+	}
+
+	#[test]
+	fn test_epub_errors() {
+		let dir = tempfile::tempdir().unwrap();
+		let encrypted = dir.path().join("encrypted.epub");
+
+		write_epub(&encrypted, "OEBPS/", "Title", 6, true);
+
+		let e = Fingerprint::finger(&encrypted).unwrap_err();
+
+		assert_eq!(e.stage(), Some(Stage::Decode));
+		assert!(matches!(
+			e.inner(),
+			FingerprintError::Decode(e)
+				if e.downcast_ref() == Some(&EpubError::Encrypted("OEBPS/text/chapter 1.xhtml".into()))
+		));
+
+		let corrupt = dir.path().join("corrupt.epub");
+		let mut zip = ZipWriter::new(std::fs::File::create(&corrupt).unwrap());
+
+		zip.start_file(
+			"mimetype",
+			SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored),
+		)
+		.unwrap();
+		zip.write_all(b"application/epub+zip").unwrap();
+		zip.finish().unwrap();
+
+		assert!(Fingerprint::finger(&corrupt).is_err());
+		assert_eq!(percent_decode("a%20b%zz%2"), "a b%zz%2");
+	}
+}
@@ -0,0 +1,207 @@
+use std::path::Path;
+
+use ttf_parser::{Face, OutlineBuilder};
+
+use crate::{options::Options, Fingerprint, FingerprintError, Type};
+
+/// Size (cells per side) of the grid outline points are quantised to within each glyph's bounding box.
+pub const GRID: u32 = 32;
+
+/// Errors specific to font fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FontError {
+	/// The file is not a TrueType or OpenType font.
+	#[error("cannot parse font: {0}")]
+	Parse(String),
+
+	/// None of the canonical characters has an outline in the font.
+	#[error("font has no outlines for the canonical characters")]
+	NoGlyphs,
+}
+
+/// Hash of the outline of one character of a font.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+	/// Character the glyph is mapped to.
+	pub character: char,
+
+	/// Hash of the character and its outline points, quantised within the glyph's bounding box.
+	pub hash: u64,
+}
+
+/// Fingerprint of the design of a font from the outlines of a canonical character set, independent of font names,
+/// glyph order and other tables.
+#[derive(Debug, Clone)]
+pub struct FontFingerprint {
+	/// Glyphs of the canonical characters present in the font, in character order.
+	pub glyphs: Vec<Glyph>,
+
+	/// Order-insensitive summary of the glyph hashes as a 128-bit fingerprint (1-bit MinHash), so a subset of a font
+	/// partially matches the full font.
+	pub summary: Fingerprint,
+}
+
+/// Comparison of two font fingerprints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontComparison {
+	/// Similarity of the summaries, in [0, 1].
+	pub similarity: f64,
+
+	/// Fraction of the glyphs of the font with fewer glyphs that have the same shape in the other, 1 when a font is a
+	/// subset of the other.
+	pub shared: f64,
+
+	/// Jaccard similarity of the sets of glyph shapes.
+	pub jaccard: f64,
+}
+
+impl FontFingerprint {
+	/// Compare the glyph shapes of this font with those of another.
+	pub fn compare(&self, other: &FontFingerprint) -> FontComparison {
+		let shared = self
+			.glyphs
+			.iter()
+			.filter(|glyph| other.glyphs.contains(glyph))
+			.count();
+		let fewer = self.glyphs.len().min(other.glyphs.len());
+		let union = self.glyphs.len() + other.glyphs.len() - shared;
+
+		FontComparison {
+			similarity: self.summary.compare(&other.summary),
+			shared: match fewer {
+				0 => 0f64,
+				fewer => shared as f64 / fewer as f64,
+			},
+			jaccard: match union {
+				0 => 0f64,
+				union => shared as f64 / union as f64,
+			},
+		}
+	}
+}
+
+/// Points of a glyph outline, with contours separated.
+#[derive(Default)]
+struct Points {
+	contours: Vec<Vec<(f32, f32)>>,
+}
+
+impl OutlineBuilder for Points {
+	fn move_to(&mut self, x: f32, y: f32) {
+		self.contours.push(vec![(x, y)]);
+	}
+
+	fn line_to(&mut self, x: f32, y: f32) {
+		self.push(&[(x, y)]);
+	}
+
+	fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+		self.push(&[(x1, y1), (x, y)]);
+	}
+
+	fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+		self.push(&[(x1, y1), (x2, y2), (x, y)]);
+	}
+
+	fn close(&mut self) {}
+}
+
+impl Points {
+	/// Add points to the current contour.
+	fn push(&mut self, points: &[(f32, f32)]) {
+		if let Some(contour) = self.contours.last_mut() {
+			contour.extend_from_slice(points);
+		}
+	}
+}
+
+/// Fingerprint the glyph outlines (TrueType `glyf` or OpenType `CFF`) of the printable ASCII characters of a font.
+///
+/// Each outline is scaled to its bounding box and its on- and off-curve points quantised to a [GRID] by [GRID] grid
+/// before hashing, so the same design at another size or position, renamed or subset, fingerprints alike.
+pub fn fingerprint<P: AsRef<Path>>(path: P) -> Result<FontFingerprint, FingerprintError> {
+	let data = std::fs::read(&path)?;
+	let face = Face::parse(&data, 0).map_err(|e| FontError::Parse(e.to_string()))?;
+	let glyphs: Vec<Glyph> = ('!'..='~')
+		.filter_map(|character| {
+			let mut points = Points::default();
+			let bounds = face.outline_glyph(face.glyph_index(character)?, &mut points)?;
+			let (width, height) = (
+				(bounds.width() as f32).max(1f32),
+				(bounds.height() as f32).max(1f32),
+			);
+			let mut canonical = (character as u32).to_be_bytes().to_vec();
+
+			for contour in &points.contours {
+				canonical.extend_from_slice(&u32::MAX.to_be_bytes());
+
+				for (x, y) in contour {
+					let quantise = |value: f32, min: i16, size: f32| {
+						(((value - min as f32) / size * GRID as f32) as u32).min(GRID - 1) as u8
+					};
+
+					canonical.push(quantise(*x, bounds.x_min, width));
+					canonical.push(quantise(*y, bounds.y_min, height));
+				}
+			}
+
+			Some(Glyph {
+				character,
+				// 64-bit FNV-1a, as for options tags.
+				hash: canonical.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+					(hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+				}),
+			})
+		})
+		.collect();
+
+	if glyphs.is_empty() {
+		return Err(FingerprintError::from(FontError::NoGlyphs));
+	}
+
+	let hashes: Vec<u64> = glyphs.iter().map(|glyph| glyph.hash).collect();
+	let summary = Fingerprint {
+		path: path.as_ref().into(),
+		fingerprint: super::min_hash(&hashes),
+		r#type: Type::Raw,
+		options_tag: Options::font_glyphs().tag(),
+		original_version: None,
+	};
+
+	Ok(FontFingerprint { glyphs, summary })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{fingerprint, FontError};
+
+	#[test]
+	fn test_font_copies() {
+		let original = fingerprint("samples/font/sample.ttf").unwrap();
+		let renamed = original.compare(&fingerprint("samples/font/sample_renamed.ttf").unwrap());
+		let subset = original.compare(&fingerprint("samples/font/sample_subset.ttf").unwrap());
+		let other = original.compare(&fingerprint("samples/font/other.ttf").unwrap());
+
+		assert_eq!(original.glyphs.len(), 94);
+		assert_eq!((renamed.similarity, renamed.shared), (1.0, 1.0));
+		assert_eq!(subset.shared, 1.0);
+		assert_eq!(subset.jaccard, 62.0 / 94.0);
+		assert!(
+			subset.similarity >= 0.7 && subset.similarity < 1.0,
+			"{}",
+			subset.similarity
+		);
+		assert_eq!(other.shared, 0.0);
+		assert!(other.similarity < 0.7, "{}", other.similarity);
+	}
+
+	#[test]
+	fn test_font_errors() {
+		assert!(matches!(
+			fingerprint("samples/ascii.txt")
+				.unwrap_err()
+				.downcast_ref::<FontError>(),
+			Some(FontError::Parse(_))
+		));
+	}
+}
@@ -0,0 +1,501 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+use image::{
+	imageops::{self, FilterType},
+	GrayImage, ImageFormat, ImageReader,
+};
+
+use super::{FingerElement, FingerSegment, Fingerprinter};
+use crate::{options::Options, FingerprintError, NUM_FINGERPRINT_SEGMENTS};
+
+/// Width (pixels) of the image downsampled for [PHashAlgorithm::DHash], one more than the bits hashed per row.
+pub const DHASH_WIDTH: u32 = 9;
+
+/// Height (pixels) of the downsampled image, one row per segment.
+pub const HASH_HEIGHT: u32 = 16;
+
+/// Number of bits hashed per row.
+const ROW_BITS: usize = NUM_FINGERPRINT_SEGMENTS / HASH_HEIGHT as usize;
+
+/// Perceptual hash computed by the [image fingerprinter](ImageFingerprinter).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PHashAlgorithm {
+	/// Difference hash: each bit records whether a pixel is brighter than its right neighbour.
+	#[default]
+	DHash,
+
+	/// Average hash: each bit records whether a pixel is brighter than the mean of its row.
+	AHash,
+}
+
+impl PHashAlgorithm {
+	/// Return the options of fingerprints hashed with this algorithm.
+	pub fn options(self) -> Options {
+		match self {
+			Self::DHash => Options::image_dhash(),
+			Self::AHash => Options::image_ahash(),
+		}
+	}
+
+	/// Return the width (pixels) images are downsampled to.
+	fn width(self) -> u32 {
+		match self {
+			Self::DHash => DHASH_WIDTH,
+			Self::AHash => ROW_BITS as u32,
+		}
+	}
+}
+
+/// Fingerprinter for images by a perceptual hash of a downsampled greyscale copy.
+///
+/// The image (PNG, JPEG, the first frame of a GIF, WebP or TIFF) is converted to greyscale and downsampled to 16 rows,
+/// and each of the 8 bits of a row compares a pixel with its right neighbour ([PHashAlgorithm::DHash], over 9 columns)
+/// or with the mean of its row ([PHashAlgorithm::AHash], over 8 columns). Brightness relations survive resizing and
+/// lossy re-encoding, so copies of the same picture at other sizes or qualities fingerprint alike.
+///
+/// Segments are rows, and elements are the pixel comparisons of a row.
+#[derive(Debug)]
+pub struct ImageFingerprinter {
+	path: PathBuf,
+	algorithm: PHashAlgorithm,
+	pixels: GrayImage,
+}
+
+impl<'fp> Fingerprinter<'fp> for ImageFingerprinter {
+	fn new<P: AsRef<Path>>(path: P) -> Result<ImageFingerprinter, FingerprintError> {
+		Self::new_with_algorithm(path, PHashAlgorithm::default())
+	}
+
+	fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate the fingerprint from the sign of every pixel comparison, row by row.
+	fn finger(&'fp self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let mut fingerprint = bitbox![u8, Lsb0; 0; NUM_FINGERPRINT_SEGMENTS];
+
+		for segment in self {
+			for element in &segment {
+				fingerprint.set(
+					segment.index * ROW_BITS + element.index,
+					element.data()? > 0,
+				);
+			}
+		}
+
+		tracing::trace!(set_bits = fingerprint.count_ones(), "hashed rows");
+
+		Ok(fingerprint)
+	}
+}
+
+impl ImageFingerprinter {
+	/// Create new fingerprinter hashing with an algorithm, decoding and downsampling the image.
+	pub fn new_with_algorithm<P: AsRef<Path>>(
+		path: P,
+		algorithm: PHashAlgorithm,
+	) -> Result<Self, FingerprintError> {
+		let image = ImageReader::open(&path)?
+			.with_guessed_format()?
+			.decode()
+			.map_err(|e| FingerprintError::Decode(Box::new(e)))?
+			.to_luma8();
+
+		tracing::trace!(
+			width = image.width(),
+			height = image.height(),
+			"downsampling image"
+		);
+
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			algorithm,
+			pixels: imageops::resize(&image, algorithm.width(), HASH_HEIGHT, FilterType::Triangle),
+		})
+	}
+
+	/// Return the algorithm hashed with.
+	pub fn algorithm(&self) -> PHashAlgorithm {
+		self.algorithm
+	}
+}
+
+/// Return whether images of a MIME type can be decoded by the image fingerprinter.
+pub fn is_decodable(mime_type: &str) -> bool {
+	ImageFormat::from_mime_type(mime_type).is_some_and(|format| format.reading_enabled())
+}
+
+impl<'fp> IntoIterator for &'fp ImageFingerprinter {
+	type Item = ImageSegment<'fp>;
+	type IntoIter = ImageSegmentIterator<'fp>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		Self::IntoIter { fp: self, index: 0 }
+	}
+}
+
+/// Structure for one row of the downsampled image.
+#[derive(Clone, Debug)]
+pub struct ImageSegment<'fp> {
+	fp: &'fp ImageFingerprinter,
+	index: usize,
+	row: Vec<u8>,
+	value: Option<f64>,
+}
+
+impl<'fp> FingerSegment<'fp> for ImageSegment<'fp> {
+	type Fingerprinter = &'fp ImageFingerprinter;
+	type Value = f64;
+
+	fn fingerprinter(&self) -> Self::Fingerprinter {
+		self.fp
+	}
+
+	fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns the position of the first pixel of the row in the downsampled image.
+	fn pos(&self) -> usize {
+		self.index * self.row.len()
+	}
+
+	/// Returns the number of pixels in the row.
+	fn size(&self) -> usize {
+		self.row.len()
+	}
+
+	/// Returns the mean of the pixel comparisons (differences) of the row.
+	fn value(&mut self) -> Result<Self::Value, FingerprintError> {
+		match self.value {
+			Some(value) => Ok(value),
+			None => {
+				let total = self.into_iter().try_fold(0i64, |total, element| {
+					Ok::<i64, FingerprintError>(total + element.data()? as i64)
+				})?;
+				let value = total as f64 / ROW_BITS as f64;
+
+				self.value = Some(value);
+
+				Ok(value)
+			}
+		}
+	}
+}
+
+impl<'fp> IntoIterator for &'fp ImageSegment<'fp> {
+	type Item = ImageElement<'fp>;
+	type IntoIter = ImageElementIterator<'fp>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		Self::IntoIter {
+			fp: self.fp,
+			segment: self,
+			mean: self.row.iter().map(|pixel| *pixel as i16).sum::<i16>() / self.row.len() as i16,
+			index: 0,
+		}
+	}
+}
+
+/// Iterator for rows in an image fingerprint.
+#[derive(Clone, Debug)]
+pub struct ImageSegmentIterator<'fp> {
+	fp: &'fp ImageFingerprinter,
+	index: usize,
+}
+
+impl<'fp> Iterator for ImageSegmentIterator<'fp> {
+	type Item = ImageSegment<'fp>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index >= HASH_HEIGHT as usize {
+			return None;
+		}
+
+		let index = self.index;
+
+		self.index += 1;
+
+		Some(ImageSegment {
+			fp: self.fp,
+			index,
+			row: self
+				.fp
+				.pixels
+				.rows()
+				.nth(index)?
+				.map(|pixel| pixel[0])
+				.collect(),
+			value: None,
+		})
+	}
+}
+
+/// Structure for one pixel comparison of a row.
+#[derive(Clone, Debug)]
+pub struct ImageElement<'fp> {
+	fp: &'fp ImageFingerprinter,
+	segment: &'fp ImageSegment<'fp>,
+	index: usize,
+	reference: i16,
+}
+
+impl<'fp> FingerElement for ImageElement<'fp> {
+	type Fingerprinter = &'fp ImageFingerprinter;
+	type Segment = &'fp ImageSegment<'fp>;
+	type Data = i16;
+
+	fn fingerprinter(&self) -> Self::Fingerprinter {
+		self.fp
+	}
+
+	fn segment(&self) -> Self::Segment {
+		self.segment
+	}
+
+	fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns the position of the compared pixel in the downsampled image.
+	fn pos(&self) -> usize {
+		self.segment.pos() + self.index
+	}
+
+	/// Returns the number of pixels compared.
+	fn size(&self) -> usize {
+		1
+	}
+
+	/// Returns the pixel's brightness less that of its right neighbour or of the row mean, positive when the bit is set.
+	fn data(&self) -> Result<Self::Data, FingerprintError> {
+		Ok(self.segment.row[self.index] as i16 - self.reference)
+	}
+}
+
+/// Iterator for pixel comparisons in a row.
+#[derive(Clone, Debug)]
+pub struct ImageElementIterator<'fp> {
+	fp: &'fp ImageFingerprinter,
+	segment: &'fp ImageSegment<'fp>,
+	mean: i16,
+	index: usize,
+}
+
+impl<'fp> Iterator for ImageElementIterator<'fp> {
+	type Item = ImageElement<'fp>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index >= ROW_BITS {
+			return None;
+		}
+
+		let index = self.index;
+
+		self.index += 1;
+
+		Some(ImageElement {
+			fp: self.fp,
+			segment: self.segment,
+			index,
+			reference: match self.fp.algorithm {
+				PHashAlgorithm::DHash => self.segment.row[index + 1] as i16,
+				PHashAlgorithm::AHash => self.mean,
+			},
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs::File, path::Path};
+
+	use image::{codecs::jpeg::JpegEncoder, imageops, ImageFormat, Rgb, RgbImage};
+	use rand::{Rng, SeedableRng};
+
+	use super::{ImageFingerprinter, PHashAlgorithm, ROW_BITS};
+	use crate::{
+		fingerprinters::{FingerElement, FingerSegment, Fingerprinter},
+		options::Options,
+		Fingerprint, FingerprintError, Stage, Type,
+	};
+
+	/// Render a photo-like picture of soft coloured discs over a gradient sky.
+	fn photo(layout: u64) -> RgbImage {
+		let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(layout);
+		let discs: Vec<(f64, f64, f64, [f64; 3])> = (0..8)
+			.map(|_| {
+				(
+					rng.gen_range(0.0..640.0),
+					rng.gen_range(0.0..480.0),
+					rng.gen_range(30.0..140.0),
+					[rng.gen(), rng.gen(), rng.gen()],
+				)
+			})
+			.collect();
+
+		RgbImage::from_fn(640, 480, |x, y| {
+			let (x, y) = (x as f64, y as f64);
+			let mut colour = [0.3 + y / 960.0, 0.4 + y / 960.0, 0.9 - y / 960.0];
+
+			for (cx, cy, radius, disc) in &discs {
+				let distance = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt();
+				let weight = (1.0 - distance / radius).clamp(0.0, 1.0);
+
+				for (channel, value) in colour.iter_mut().enumerate() {
+					*value = *value * (1.0 - weight) + disc[channel] * weight;
+				}
+			}
+
+			Rgb(colour.map(|value| (value * 255.0) as u8))
+		})
+	}
+
+	#[test]
+	fn test_image_reencoded() {
+		let dir = tempfile::tempdir().unwrap();
+		let (jpeg, png, other) = (
+			dir.path().join("photo.jpg"),
+			dir.path().join("photo.png"),
+			dir.path().join("other.jpg"),
+		);
+		let source = photo(1);
+
+		source.save(&jpeg).unwrap();
+		imageops::resize(&source, 320, 240, imageops::FilterType::Lanczos3)
+			.save(&png)
+			.unwrap();
+		photo(2).save(&other).unwrap();
+
+		let jpeg = Fingerprint::finger(jpeg).unwrap();
+		let png = Fingerprint::finger(png).unwrap();
+		let other = Fingerprint::finger(other).unwrap();
+
+		assert_eq!(jpeg.r#type(), Type::Image);
+		assert_eq!(jpeg.options_tag(), Options::default_for(&Type::Image).tag());
+		assert!(jpeg.compare(&png) > 0.9, "{}", jpeg.compare(&png));
+		assert!(jpeg.compare(&other) < 0.75, "{}", jpeg.compare(&other));
+	}
+
+	/// Save a picture as a JPEG of a given quality.
+	fn save_jpeg(image: &RgbImage, path: &Path, quality: u8) {
+		image
+			.write_with_encoder(JpegEncoder::new_with_quality(
+				File::create(path).unwrap(),
+				quality,
+			))
+			.unwrap();
+	}
+
+	#[test]
+	fn test_image_jpeg_quality() {
+		let dir = tempfile::tempdir().unwrap();
+		let (high, low) = (dir.path().join("high.jpg"), dir.path().join("low.jpg"));
+		let source = photo(3);
+
+		save_jpeg(&source, &high, 95);
+		save_jpeg(&source, &low, 40);
+
+		for algorithm in [PHashAlgorithm::DHash, PHashAlgorithm::AHash] {
+			let high = Fingerprint::finger_image_with_algorithm(&high, algorithm).unwrap();
+			let low = Fingerprint::finger_image_with_algorithm(&low, algorithm).unwrap();
+
+			assert_eq!(high.options_tag(), algorithm.options().tag());
+			assert!(
+				high.compare(&low) >= 0.88,
+				"{:?} {}",
+				algorithm,
+				high.compare(&low)
+			);
+		}
+
+		assert_ne!(
+			Fingerprint::finger_image_with_algorithm(&high, PHashAlgorithm::AHash)
+				.unwrap()
+				.options_tag(),
+			Fingerprint::finger_image(&high).unwrap().options_tag()
+		);
+	}
+
+	#[test]
+	fn test_image_segments() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("photo.png");
+
+		photo(1).save(&path).unwrap();
+
+		let fingerprinter = ImageFingerprinter::new(&path).unwrap();
+		let fingerprint = fingerprinter.finger().unwrap();
+
+		for mut segment in &fingerprinter {
+			let deltas: Vec<i16> = segment
+				.into_iter()
+				.map(|element| element.data().unwrap())
+				.collect();
+
+			assert_eq!(deltas.len(), ROW_BITS);
+			assert_eq!(segment.size(), 9);
+			assert_eq!(
+				segment.value().unwrap(),
+				deltas.iter().sum::<i16>() as f64 / ROW_BITS as f64
+			);
+
+			for (index, delta) in deltas.iter().enumerate() {
+				assert_eq!(fingerprint[segment.index() * ROW_BITS + index], *delta > 0);
+			}
+		}
+	}
+
+	#[test]
+	fn test_image_formats() {
+		let dir = tempfile::tempdir().unwrap();
+		let source = photo(1);
+		let png = dir.path().join("photo.png");
+
+		source.save(&png).unwrap();
+
+		let png = Fingerprint::finger(png).unwrap();
+
+		for (name, format) in [
+			("photo.gif", ImageFormat::Gif),
+			("photo.webp", ImageFormat::WebP),
+		] {
+			let path = dir.path().join(name);
+
+			source.save_with_format(&path, format).unwrap();
+
+			let fingerprint = Fingerprint::finger(&path).unwrap();
+
+			assert_eq!(fingerprint.r#type(), Type::Image, "{}", name);
+			assert!(
+				png.compare(&fingerprint) > 0.9,
+				"{} {}",
+				name,
+				png.compare(&fingerprint)
+			);
+		}
+	}
+
+	#[test]
+	fn test_image_corrupt() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("corrupt.png");
+		let mut data = Vec::new();
+
+		photo(1)
+			.write_to(&mut std::io::Cursor::new(&mut data), ImageFormat::Png)
+			.unwrap();
+		data.truncate(data.len() / 2);
+		std::fs::write(&path, data).unwrap();
+
+		let error = Fingerprint::finger(&path).unwrap_err();
+
+		assert_eq!(error.stage(), Some(Stage::Decode));
+		assert!(matches!(
+			error.inner(),
+			FingerprintError::Decode(e) if e.is::<image::ImageError>()
+		));
+	}
+}
@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+use image::{
+	imageops::{self, FilterType},
+	ImageBuffer, Luma,
+};
+
+use super::phash::{phash, PHASH_SIZE};
+use crate::FingerprintError;
+
+/// Fingerprinter for 16-bit depth maps, such as those from LiDAR or structured-light sensors.
+///
+/// The depth map (16-bit greyscale PNG or TIFF; 8-bit images are widened) is downsampled to 32×32 and fingerprinted
+/// with the perceptual hash of its 16-bit depths. Depths are never reduced to 8 bits, so scenes whose depth range is
+/// narrower than 256 levels still fingerprint by their shape.
+#[derive(Debug)]
+pub struct DepthMapFingerprinter {
+	path: PathBuf,
+	image: ImageBuffer<Luma<u16>, Vec<u16>>,
+}
+
+impl DepthMapFingerprinter {
+	/// Create new fingerprinter.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			image: image::open(&path)?.to_luma16(),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate the fingerprint from the perceptual hash of the downsampled depths.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let image = imageops::resize(
+			&self.image,
+			PHASH_SIZE as u32,
+			PHASH_SIZE as u32,
+			FilterType::Triangle,
+		);
+		let pixels: Vec<f64> = image.pixels().map(|pixel| pixel[0] as f64).collect();
+
+		tracing::trace!(
+			width = self.image.width(),
+			height = self.image.height(),
+			"downsampled depth map"
+		);
+
+		Ok(phash(&pixels))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use image::{ImageBuffer, Luma};
+	use rand::{Rng, SeedableRng};
+
+	use crate::Fingerprint;
+
+	/// Render a depth map of a sloped floor with boxes standing on it, offset by `base` and scaled by `range`
+	/// millimetres, with uniform noise of up to `noise` millimetres.
+	fn scene(layout: u64, base: u16, range: f64, noise: u16) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+		let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(layout);
+		let boxes: Vec<(u32, u32, u32, f64)> = (0..6)
+			.map(|_| {
+				(
+					rng.gen_range(0..200),
+					rng.gen_range(0..140),
+					rng.gen_range(20..60),
+					rng.gen_range(0.1..0.5),
+				)
+			})
+			.collect();
+		let mut noise_rng = rand_chacha::ChaCha8Rng::seed_from_u64(noise as u64);
+
+		ImageBuffer::from_fn(256, 192, |x, y| {
+			let floor = 1.0 - y as f64 / 192.0;
+			let depth = boxes
+				.iter()
+				.filter(|(left, top, size, _)| {
+					(*left..left + size).contains(&x) && (*top..top + size).contains(&y)
+				})
+				.fold(floor, |depth, (.., height)| depth.min(*height));
+			let jitter = match noise {
+				0 => 0,
+				noise => noise_rng.gen_range(0..=noise),
+			};
+
+			Luma([base + (depth * range) as u16 + jitter])
+		})
+	}
+
+	#[test]
+	fn test_depth_map_noise() {
+		let dir = tempfile::tempdir().unwrap();
+		let clean = dir.path().join("clean.png");
+		let noisy = dir.path().join("noisy.tiff");
+		let other = dir.path().join("other.png");
+
+		scene(1, 500, 8000.0, 40).save(&clean).unwrap();
+		scene(1, 500, 8000.0, 400).save(&noisy).unwrap();
+		scene(2, 500, 8000.0, 40).save(&other).unwrap();
+
+		let clean = Fingerprint::finger_image_depth_map(clean).unwrap();
+		let noisy = Fingerprint::finger_image_depth_map(noisy).unwrap();
+		let other = Fingerprint::finger_image_depth_map(other).unwrap();
+
+		assert!(clean.compare(&noisy) >= 0.85);
+		assert!(clean.compare(&other) < clean.compare(&noisy));
+	}
+
+	#[test]
+	fn test_depth_map_narrow_range() {
+		let dir = tempfile::tempdir().unwrap();
+		let first = dir.path().join("first.png");
+		let second = dir.path().join("second.png");
+
+		// Every depth lies between 1000 and 1200, which reduction to 8 bits would flatten to two levels.
+		scene(1, 1000, 200.0, 0).save(&first).unwrap();
+		scene(2, 1000, 200.0, 0).save(&second).unwrap();
+
+		let first = Fingerprint::finger_image_depth_map(first).unwrap();
+		let second = Fingerprint::finger_image_depth_map(second).unwrap();
+
+		assert!(first.compare(&second) < 0.85);
+	}
+}
@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+use image::{
+	imageops::{self, FilterType},
+	GrayImage, Luma,
+};
+
+use super::{
+	image_text::{text_blocks, Block},
+	phash::{phash, PHASH_SIZE},
+};
+use crate::FingerprintError;
+
+/// Grey level at or above which a pixel may be caption fill.
+const LIGHT: u8 = 224;
+
+/// Grey level at or below which a pixel may be caption outline.
+const DARK: u8 = 32;
+
+/// Distance (pixels) within which light fill and dark outline must meet to count as caption text.
+const OUTLINE_RADIUS: u32 = 2;
+
+/// Distance (pixels) caption pixels are grown by, so the glyphs of a word join into one block.
+const DILATE_RADIUS: u32 = 3;
+
+/// Height (pixels) of the bands above and below a block whose median fills each of its columns.
+const BAND_HEIGHT: u32 = 4;
+
+/// Fingerprinter for an image with its outlined caption text removed, such as a meme.
+///
+/// Caption text is found as light pixels meeting a dark outline, the usual rendering of meme captions, without OCR.
+/// Each column of a connected caption region is replaced by the median grey level of the pixels just above and below
+/// the region in that column, and the fingerprint is the perceptual hash of the remaining image, so it depends on the
+/// base image rather than the caption. Captions without an outline, such as dark text on a light banner, are not
+/// detected and remain part of the fingerprinted image.
+#[derive(Debug)]
+pub struct ImageMemeFingerprinter {
+	path: PathBuf,
+	image: GrayImage,
+}
+
+impl ImageMemeFingerprinter {
+	/// Create new fingerprinter.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			image: image::open(&path)?.to_luma8(),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate the fingerprint from the perceptual hash of the text-stripped image.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let blocks = text_blocks(&caption_mask(&self.image));
+		let mut image = self.image.clone();
+
+		tracing::debug!(blocks = blocks.len(), "detected caption blocks");
+
+		for block in &blocks {
+			median_fill(&mut image, block);
+		}
+
+		let image = imageops::resize(
+			&image,
+			PHASH_SIZE as u32,
+			PHASH_SIZE as u32,
+			FilterType::Triangle,
+		);
+		let pixels: Vec<f64> = image.pixels().map(|pixel| pixel[0] as f64).collect();
+
+		Ok(phash(&pixels))
+	}
+}
+
+/// Return whether any pixel within `radius` of (x, y) satisfies `predicate`.
+fn any_near(
+	image: &GrayImage,
+	x: u32,
+	y: u32,
+	radius: u32,
+	predicate: impl Fn(u8) -> bool,
+) -> bool {
+	let (width, height) = image.dimensions();
+
+	(y.saturating_sub(radius)..=(y + radius).min(height - 1)).any(|ny| {
+		(x.saturating_sub(radius)..=(x + radius).min(width - 1))
+			.any(|nx| predicate(image.get_pixel(nx, ny)[0]))
+	})
+}
+
+/// Mark (255) light and dark pixels that meet each other, grown by [DILATE_RADIUS].
+fn caption_mask(image: &GrayImage) -> GrayImage {
+	let edges = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+		let value = image.get_pixel(x, y)[0];
+		let caption = match value {
+			v if v >= LIGHT => any_near(image, x, y, OUTLINE_RADIUS, |v| v <= DARK),
+			v if v <= DARK => any_near(image, x, y, OUTLINE_RADIUS, |v| v >= LIGHT),
+			_ => false,
+		};
+
+		Luma([if caption { 255 } else { 0 }])
+	});
+
+	GrayImage::from_fn(image.width(), image.height(), |x, y| {
+		match any_near(&edges, x, y, DILATE_RADIUS, |v| v != 0) {
+			true => Luma([255]),
+			false => Luma([0]),
+		}
+	})
+}
+
+/// Replace each column of a block with the median grey level of the [BAND_HEIGHT] pixels above and below it.
+///
+/// Filling column by column keeps horizontal gradients of the base image that a single fill colour would flatten.
+fn median_fill(image: &mut GrayImage, block: &Block) {
+	let height = image.height();
+	let above = block.top.saturating_sub(BAND_HEIGHT)..block.top;
+	let below = (block.bottom + 1).min(height)..(block.bottom + 1 + BAND_HEIGHT).min(height);
+
+	for x in block.left..=block.right {
+		let mut bands: Vec<u8> = above
+			.clone()
+			.chain(below.clone())
+			.map(|y| image.get_pixel(x, y)[0])
+			.collect();
+
+		// A block spanning the whole height has no bands, and is left as is.
+		if bands.is_empty() {
+			continue;
+		}
+
+		let middle = bands.len() / 2;
+		let median = Luma([*bands.select_nth_unstable(middle).1]);
+
+		for y in block.top..=block.bottom {
+			image.put_pixel(x, y, median);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use image::{GrayImage, Luma};
+	use rand::{Rng, SeedableRng};
+
+	use super::{caption_mask, text_blocks};
+	use crate::{fingerprinters::test_util::glyph, Fingerprint};
+
+	/// Render a base image of overlapping mid-grey rectangles, with a different layout for each seed.
+	fn base(width: u32, height: u32, seed: u64) -> GrayImage {
+		let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+		let mut image = GrayImage::from_pixel(width, height, Luma([128]));
+
+		for _ in 0..40 {
+			let (left, top) = (rng.gen_range(0..width), rng.gen_range(0..height));
+			let (right, bottom) = (
+				rng.gen_range(left..width).min(left + width / 3),
+				rng.gen_range(top..height).min(top + height / 3),
+			);
+			let value = Luma([rng.gen_range(48..208)]);
+
+			for y in top..=bottom {
+				for x in left..=right {
+					image.put_pixel(x, y, value);
+				}
+			}
+		}
+
+		image
+	}
+
+	/// Draw a caption centred horizontally at `top` in white with a black outline, meme style.
+	fn caption(image: &mut GrayImage, text: &str, top: u32, scale: u32) {
+		let left = (image.width() - 6 * scale * text.len() as u32) / 2;
+		let mut fill = Vec::new();
+
+		for (column, c) in text.chars().enumerate() {
+			for (gy, bits) in glyph(c).iter().enumerate() {
+				for gx in 0..5 {
+					if bits & (0x10 >> gx) != 0 {
+						for dy in 0..scale {
+							for dx in 0..scale {
+								fill.push((
+									left + (column as u32 * 6 + gx) * scale + dx,
+									top + gy as u32 * scale + dy,
+								));
+							}
+						}
+					}
+				}
+			}
+		}
+
+		for (x, y) in &fill {
+			for oy in y - 2..=y + 2 {
+				for ox in x - 2..=x + 2 {
+					image.put_pixel(ox, oy, Luma([0]));
+				}
+			}
+		}
+
+		for (x, y) in fill {
+			image.put_pixel(x, y, Luma([255]));
+		}
+	}
+
+	#[test]
+	fn test_meme_caption_invariant() {
+		let dir = tempfile::tempdir().unwrap();
+		let first = dir.path().join("first.png");
+		let second = dir.path().join("second.png");
+		let other = dir.path().join("other.png");
+
+		let mut image = base(320, 240, 0);
+		caption(&mut image, "ONE TWO", 10, 4);
+		caption(&mut image, "LATE", 190, 4);
+		image.save(&first).unwrap();
+
+		let mut image = base(320, 240, 0);
+		caption(&mut image, "SEW", 10, 4);
+		caption(&mut image, "NO WALL TOO", 190, 4);
+		image.save(&second).unwrap();
+
+		let mut image = base(320, 240, 1);
+		caption(&mut image, "ONE TWO", 10, 4);
+		caption(&mut image, "LATE", 190, 4);
+		image.save(&other).unwrap();
+
+		let first = Fingerprint::finger_image_meme_invariant(first).unwrap();
+		let second = Fingerprint::finger_image_meme_invariant(second).unwrap();
+		let other = Fingerprint::finger_image_meme_invariant(other).unwrap();
+
+		assert!(first.compare(&second) >= 0.9);
+		assert!(first.compare(&other) < first.compare(&second));
+	}
+
+	#[test]
+	fn test_caption_detection() {
+		let plain = base(320, 240, 1);
+		let mut captioned = plain.clone();
+
+		caption(&mut captioned, "LOW", 190, 4);
+
+		assert!(text_blocks(&caption_mask(&plain)).is_empty());
+
+		let blocks = text_blocks(&caption_mask(&captioned));
+
+		assert_eq!(blocks.len(), 1);
+		assert!(blocks[0].left <= 124 && blocks[0].right >= 196);
+		assert!(blocks[0].top <= 190 && blocks[0].bottom >= 218);
+	}
+}
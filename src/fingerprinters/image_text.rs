@@ -0,0 +1,331 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+use image::{
+	imageops::{self, FilterType},
+	GrayImage, Luma,
+};
+
+use super::phash::{phash, PHASH_SIZE};
+use crate::FingerprintError;
+
+/// Minimum area (pixels) of a connected component for it to count as a text block.
+const MIN_BLOCK_AREA: usize = 50;
+
+/// Height (pixels) every text block is scaled to before being added to the strip.
+const STRIP_HEIGHT: u32 = 32;
+
+/// Horizontal gap (pixels) between text blocks in the strip.
+const STRIP_GAP: u32 = 4;
+
+/// Errors specific to text region fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ImageTextError {
+	/// The image contains no connected component large enough to be a text block.
+	#[error("no text regions detected")]
+	NoTextRegions,
+}
+
+/// Bounding box of a connected foreground component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Block {
+	pub(super) left: u32,
+	pub(super) top: u32,
+	pub(super) right: u32,
+	pub(super) bottom: u32,
+}
+
+impl Block {
+	pub(super) fn width(&self) -> u32 {
+		self.right - self.left + 1
+	}
+
+	pub(super) fn height(&self) -> u32 {
+		self.bottom - self.top + 1
+	}
+}
+
+/// Fingerprinter for the text regions of an image, such as a screenshot.
+///
+/// The image is binarised with Otsu's threshold, connected components larger than 50 pixels are taken as text
+/// blocks, and the blocks are scaled to a common height and laid out in reading order on a single strip. The
+/// fingerprint is the perceptual hash of that strip, so it is insensitive to display scale and to the layout of the
+/// surrounding UI.
+#[derive(Debug)]
+pub struct ImageTextRegionFingerprinter {
+	path: PathBuf,
+	image: GrayImage,
+}
+
+impl ImageTextRegionFingerprinter {
+	/// Create new fingerprinter.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			image: image::open(&path)?.to_luma8(),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate the fingerprint from the perceptual hash of the canonical text strip.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let mask = binarise(&self.image);
+		let blocks = reading_order(text_blocks(&mask));
+
+		tracing::debug!(blocks = blocks.len(), "detected text blocks");
+
+		if blocks.is_empty() {
+			return Err(FingerprintError::from(ImageTextError::NoTextRegions));
+		}
+
+		let strip = strip(&mask, &blocks);
+		let strip = imageops::resize(
+			&strip,
+			PHASH_SIZE as u32,
+			PHASH_SIZE as u32,
+			FilterType::Triangle,
+		);
+		let pixels: Vec<f64> = strip.pixels().map(|pixel| pixel[0] as f64).collect();
+
+		Ok(phash(&pixels))
+	}
+}
+
+/// Compute Otsu's threshold, the grey level that maximises the between-class variance.
+fn otsu_threshold(image: &GrayImage) -> u8 {
+	let mut histogram = [0usize; 256];
+
+	for pixel in image.pixels() {
+		histogram[pixel[0] as usize] += 1;
+	}
+
+	let total = image.pixels().len() as f64;
+	let sum: f64 = histogram
+		.iter()
+		.enumerate()
+		.map(|(level, count)| level as f64 * *count as f64)
+		.sum();
+	let mut background_sum = 0f64;
+	let mut background_count = 0f64;
+	let mut best = (0f64, 0u8);
+
+	for (level, count) in histogram.iter().enumerate() {
+		background_count += *count as f64;
+		background_sum += level as f64 * *count as f64;
+
+		let foreground_count = total - background_count;
+
+		if background_count == 0f64 || foreground_count == 0f64 {
+			continue;
+		}
+
+		let background_mean = background_sum / background_count;
+		let foreground_mean = (sum - background_sum) / foreground_count;
+		let variance =
+			background_count * foreground_count * (background_mean - foreground_mean).powi(2);
+
+		if variance > best.0 {
+			best = (variance, level as u8);
+		}
+	}
+
+	best.1
+}
+
+/// Binarise an image, marking the minority class (the text) as foreground (255).
+fn binarise(image: &GrayImage) -> GrayImage {
+	let threshold = otsu_threshold(image);
+	let dark = image.pixels().filter(|pixel| pixel[0] <= threshold).count();
+	let dark_is_foreground = dark * 2 <= image.pixels().len();
+
+	GrayImage::from_fn(image.width(), image.height(), |x, y| {
+		let dark = image.get_pixel(x, y)[0] <= threshold;
+
+		match dark == dark_is_foreground {
+			true => Luma([255]),
+			false => Luma([0]),
+		}
+	})
+}
+
+/// Find bounding boxes of 8-connected foreground components larger than [MIN_BLOCK_AREA].
+pub(super) fn text_blocks(mask: &GrayImage) -> Vec<Block> {
+	let (width, height) = mask.dimensions();
+	let mut visited = vec![false; (width * height) as usize];
+	let mut blocks = Vec::new();
+	let mut stack = Vec::new();
+
+	for start in 0..visited.len() {
+		if visited[start] || mask.as_raw()[start] == 0 {
+			continue;
+		}
+
+		let (x, y) = (start as u32 % width, start as u32 / width);
+		let mut block = Block {
+			left: x,
+			top: y,
+			right: x,
+			bottom: y,
+		};
+		let mut area = 0;
+
+		visited[start] = true;
+		stack.push((x, y));
+
+		while let Some((x, y)) = stack.pop() {
+			area += 1;
+			block.left = block.left.min(x);
+			block.top = block.top.min(y);
+			block.right = block.right.max(x);
+			block.bottom = block.bottom.max(y);
+
+			for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+				for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+					let index = (ny * width + nx) as usize;
+
+					if !visited[index] && mask.as_raw()[index] != 0 {
+						visited[index] = true;
+						stack.push((nx, ny));
+					}
+				}
+			}
+		}
+
+		if area > MIN_BLOCK_AREA {
+			blocks.push(block);
+		}
+	}
+
+	blocks
+}
+
+/// Sort blocks top-to-bottom by line, then left-to-right within each line.
+fn reading_order(mut blocks: Vec<Block>) -> Vec<Block> {
+	blocks.sort_by_key(|block| (block.top, block.left));
+
+	let mut lines: Vec<Vec<Block>> = Vec::new();
+
+	for block in blocks {
+		match lines.last_mut() {
+			Some(line) if block.top <= line.iter().map(|b| b.bottom).max().unwrap_or(0) => {
+				line.push(block)
+			}
+			_ => lines.push(vec![block]),
+		}
+	}
+
+	lines
+		.into_iter()
+		.flat_map(|mut line| {
+			line.sort_by_key(|block| (block.left, block.top));
+			line
+		})
+		.collect()
+}
+
+/// Lay out blocks on a single strip, each scaled to [STRIP_HEIGHT] while preserving aspect ratio.
+fn strip(mask: &GrayImage, blocks: &[Block]) -> GrayImage {
+	let crops: Vec<GrayImage> = blocks
+		.iter()
+		.map(|block| {
+			let crop =
+				imageops::crop_imm(mask, block.left, block.top, block.width(), block.height());
+			let width = (block.width() * STRIP_HEIGHT / block.height()).max(1);
+
+			imageops::resize(&crop.to_image(), width, STRIP_HEIGHT, FilterType::Triangle)
+		})
+		.collect();
+
+	let width = crops.iter().map(|crop| crop.width() + STRIP_GAP).sum();
+	let mut strip = GrayImage::new(width, STRIP_HEIGHT);
+	let mut x = 0;
+
+	for crop in crops {
+		imageops::replace(&mut strip, &crop, x as i64, 0);
+		x += crop.width() + STRIP_GAP;
+	}
+
+	strip
+}
+
+#[cfg(test)]
+mod tests {
+	use image::{GrayImage, Luma};
+
+	use super::ImageTextError;
+	use crate::{fingerprinters::test_util::glyph, Fingerprint};
+
+	/// Render lines of text in dark pixels on a light background at the given pixel scale and margin.
+	fn render(lines: &[&str], scale: u32, margin: u32) -> GrayImage {
+		let width = margin * 2 + 6 * scale * lines.iter().map(|l| l.len()).max().unwrap() as u32;
+		let height = margin * 2 + 10 * scale * lines.len() as u32;
+		let mut image = GrayImage::from_pixel(width, height, Luma([230]));
+
+		for (row, line) in lines.iter().enumerate() {
+			for (column, c) in line.chars().enumerate() {
+				for (gy, bits) in glyph(c).iter().enumerate() {
+					for gx in 0..5 {
+						if bits & (0x10 >> gx) == 0 {
+							continue;
+						}
+
+						for dy in 0..scale {
+							for dx in 0..scale {
+								image.put_pixel(
+									margin + (column as u32 * 6 + gx) * scale + dx,
+									margin + (row as u32 * 10 + gy as u32) * scale + dy,
+									Luma([20]),
+								);
+							}
+						}
+					}
+				}
+			}
+		}
+
+		image
+	}
+
+	#[test]
+	fn test_text_region_scale_invariant() {
+		let dir = tempfile::tempdir().unwrap();
+		let small = dir.path().join("small.png");
+		let large = dir.path().join("large.png");
+		let other = dir.path().join("other.png");
+
+		render(&["HELLO", "WORLD"], 3, 10).save(&small).unwrap();
+		render(&["HELLO", "WORLD"], 5, 40).save(&large).unwrap();
+		render(&["DOLL", "HERO", "WHEEL"], 3, 10)
+			.save(&other)
+			.unwrap();
+
+		let small = Fingerprint::finger_image_text_region(small).unwrap();
+		let large = Fingerprint::finger_image_text_region(large).unwrap();
+		let other = Fingerprint::finger_image_text_region(other).unwrap();
+
+		assert!(small.compare(&large) >= 0.85);
+		assert!(small.compare(&other) < small.compare(&large));
+	}
+
+	#[test]
+	fn test_text_region_blank() {
+		let dir = tempfile::tempdir().unwrap();
+		let blank = dir.path().join("blank.png");
+
+		GrayImage::from_pixel(64, 64, Luma([255]))
+			.save(&blank)
+			.unwrap();
+
+		assert_eq!(
+			Fingerprint::finger_image_text_region(blank)
+				.unwrap_err()
+				.downcast_ref(),
+			Some(&ImageTextError::NoTextRegions)
+		);
+	}
+}
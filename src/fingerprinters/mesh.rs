@@ -0,0 +1,319 @@
+use std::{
+	fs::File,
+	io::BufReader,
+	path::{Path, PathBuf},
+};
+
+use bitvec::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::RNG_SEED;
+use crate::{FingerprintError, NUM_FINGERPRINT_SEGMENTS};
+
+/// Extensions of the mesh formats parsed, STL (ASCII or binary) and Wavefront OBJ.
+pub const EXTENSIONS: [&str; 2] = ["stl", "obj"];
+
+/// Number of points sampled on the surface.
+const SAMPLE_POINTS: usize = 8192;
+
+/// Number of random point pairs whose distances form the shape distribution.
+const SAMPLE_PAIRS: usize = 1 << 16;
+
+/// Number of histogram bins over distances from 0 to 2 (the diameter of the unit bounding sphere).
+const BINS: usize = 32;
+
+/// Number of thermometer-coded bits per histogram bin.
+const LEVELS: usize = NUM_FINGERPRINT_SEGMENTS / BINS;
+
+/// Errors specific to mesh fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MeshError {
+	/// The file is not an STL or OBJ mesh.
+	#[error("cannot parse mesh: {0}")]
+	Parse(String),
+
+	/// The mesh has no triangles with area.
+	#[error("mesh has no surface")]
+	Empty,
+}
+
+/// Triangle of a mesh.
+type Triangle = [[f64; 3]; 3];
+
+/// Fingerprinter for STL and OBJ meshes by their D2 shape distribution.
+///
+/// Points are sampled uniformly over the surface, centred on the area-weighted centroid and scaled to the unit
+/// bounding sphere, and the distances between random pairs of them are histogrammed. The histogram is independent of
+/// vertex order, triangulation, units, position and orientation, so the same model exported by different programs
+/// fingerprints alike. Each bin's share of the pairs is thermometer-coded into four bits, so fingerprints differing
+/// by one level in one bin differ by one bit.
+#[derive(Debug)]
+pub struct MeshFingerprinter {
+	path: PathBuf,
+	triangles: Vec<Triangle>,
+}
+
+impl MeshFingerprinter {
+	/// Create new fingerprinter, reading the triangles of the mesh in the format of its extension.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let extension = path
+			.as_ref()
+			.extension()
+			.and_then(|extension| extension.to_str())
+			.unwrap_or_default()
+			.to_ascii_lowercase();
+		let triangles = match extension.as_str() {
+			"obj" => obj_triangles(path.as_ref())?,
+			_ => stl_triangles(path.as_ref())?,
+		};
+
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			triangles,
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate the fingerprint from the shape distribution of the surface.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let areas: Vec<f64> = self.triangles.iter().map(area).collect();
+		let total: f64 = areas.iter().sum();
+
+		if total <= 0f64 || !total.is_finite() {
+			return Err(FingerprintError::from(MeshError::Empty));
+		}
+
+		let mut centroid = [0f64; 3];
+
+		for (triangle, area) in self.triangles.iter().zip(&areas) {
+			for (axis, centre) in centroid.iter_mut().enumerate() {
+				*centre += area * (triangle[0][axis] + triangle[1][axis] + triangle[2][axis])
+					/ 3f64 / total;
+			}
+		}
+
+		let radius = self
+			.triangles
+			.iter()
+			.flatten()
+			.map(|vertex| distance(vertex, &centroid))
+			.fold(0f64, f64::max);
+		let cumulative: Vec<f64> = areas
+			.iter()
+			.scan(0f64, |sum, area| {
+				*sum += area;
+
+				Some(*sum)
+			})
+			.collect();
+		let mut rng = ChaCha8Rng::seed_from_u64(RNG_SEED);
+		let points: Vec<[f64; 3]> = (0..SAMPLE_POINTS)
+			.map(|_| {
+				let target = rng.gen_range(0f64..total);
+				let index = cumulative
+					.partition_point(|sum| *sum <= target)
+					.min(self.triangles.len() - 1);
+				let [a, b, c] = self.triangles[index];
+				let (r1, r2) = (rng.gen::<f64>().sqrt(), rng.gen::<f64>());
+
+				[0, 1, 2].map(|axis| {
+					let point =
+						(1f64 - r1) * a[axis] + r1 * (1f64 - r2) * b[axis] + r1 * r2 * c[axis];
+
+					(point - centroid[axis]) / radius
+				})
+			})
+			.collect();
+		let mut histogram = [0usize; BINS];
+
+		for _ in 0..SAMPLE_PAIRS {
+			let (a, b) = (
+				&points[rng.gen_range(0..SAMPLE_POINTS)],
+				&points[rng.gen_range(0..SAMPLE_POINTS)],
+			);
+			let bin = (distance(a, b) / 2f64 * BINS as f64) as usize;
+
+			histogram[bin.min(BINS - 1)] += 1;
+		}
+
+		tracing::trace!(?histogram, "shape distribution");
+
+		// A bin holding its uniform share of the pairs codes as half the levels.
+		Ok(histogram
+			.iter()
+			.flat_map(|count| {
+				let level = (*count as f64 / SAMPLE_PAIRS as f64 * (BINS * LEVELS / 2) as f64)
+					.round() as usize;
+
+				(0..LEVELS).map(move |bit| level > bit)
+			})
+			.collect())
+	}
+}
+
+/// Return whether a path has a mesh extension.
+pub fn is_mesh(path: &Path) -> bool {
+	path.extension()
+		.and_then(|extension| extension.to_str())
+		.is_some_and(|extension| {
+			EXTENSIONS
+				.iter()
+				.any(|candidate| extension.eq_ignore_ascii_case(candidate))
+		})
+}
+
+/// Read the triangles of an ASCII or binary STL file.
+fn stl_triangles(path: &Path) -> Result<Vec<Triangle>, FingerprintError> {
+	let mesh = stl_io::read_stl(&mut BufReader::new(File::open(path)?))
+		.map_err(|e| MeshError::Parse(e.to_string()))?;
+	let vertex = |index: usize| {
+		let vertex = mesh.vertices[index];
+
+		[vertex[0] as f64, vertex[1] as f64, vertex[2] as f64]
+	};
+
+	Ok(mesh
+		.faces
+		.iter()
+		.map(|face| face.vertices.map(vertex))
+		.collect())
+}
+
+/// Read the triangles of an OBJ file, triangulating polygons. Material libraries are not read.
+fn obj_triangles(path: &Path) -> Result<Vec<Triangle>, FingerprintError> {
+	let options = tobj::LoadOptions {
+		triangulate: true,
+		ignore_points: true,
+		ignore_lines: true,
+		..Default::default()
+	};
+	let (models, _) = tobj::load_obj_buf(&mut BufReader::new(File::open(path)?), &options, |_| {
+		Err(tobj::LoadError::OpenFileFailed)
+	})
+	.map_err(|e| MeshError::Parse(e.to_string()))?;
+	let mut triangles = Vec::new();
+
+	for model in models {
+		let mesh = model.mesh;
+		let vertex = |index: &u32| -> Option<[f64; 3]> {
+			let start = *index as usize * 3;
+
+			Some(
+				<[f32; 3]>::try_from(mesh.positions.get(start..start + 3)?)
+					.ok()?
+					.map(|coordinate| coordinate as f64),
+			)
+		};
+
+		for face in mesh.indices.chunks_exact(3) {
+			let [a, b, c] = [&face[0], &face[1], &face[2]].map(vertex);
+
+			triangles.push(
+				a.zip(b)
+					.zip(c)
+					.map(|((a, b), c)| [a, b, c])
+					.ok_or_else(|| MeshError::Parse("vertex index out of range".into()))?,
+			);
+		}
+	}
+
+	Ok(triangles)
+}
+
+/// Return the area of a triangle.
+fn area([a, b, c]: &Triangle) -> f64 {
+	let (u, v) = (
+		[0, 1, 2].map(|i| b[i] - a[i]),
+		[0, 1, 2].map(|i| c[i] - a[i]),
+	);
+	let cross = [
+		u[1] * v[2] - u[2] * v[1],
+		u[2] * v[0] - u[0] * v[2],
+		u[0] * v[1] - u[1] * v[0],
+	];
+
+	distance(&cross, &[0f64; 3]) / 2f64
+}
+
+/// Return the Euclidean distance between two points.
+fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+	a.iter()
+		.zip(b)
+		.map(|(a, b)| (a - b) * (a - b))
+		.sum::<f64>()
+		.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::MeshError;
+	use crate::{Fingerprint, Type};
+
+	#[test]
+	fn test_mesh_copies() {
+		let original = Fingerprint::finger_mesh("samples/mesh/bracket.stl").unwrap();
+
+		for copy in ["bracket_retriangulated.obj", "bracket_scaled.stl"] {
+			let similarity = Fingerprint::finger(format!("samples/mesh/{}", copy))
+				.unwrap()
+				.compare(&original);
+
+			assert!(similarity >= 0.9, "{} {}", copy, similarity);
+		}
+
+		for other in ["sphere.obj", "rod.stl"] {
+			let similarity = Fingerprint::finger_mesh(format!("samples/mesh/{}", other))
+				.unwrap()
+				.compare(&original);
+
+			assert!(similarity < 0.85, "{} {}", other, similarity);
+		}
+	}
+
+	#[test]
+	fn test_mesh_type() {
+		let original = Fingerprint::finger("samples/mesh/bracket.stl").unwrap();
+
+		assert_eq!(original.r#type(), Type::Mesh);
+		assert_eq!(
+			original.to_string(),
+			Fingerprint::finger_mesh("samples/mesh/bracket.stl")
+				.unwrap()
+				.to_string()
+		);
+		assert_eq!("mesh".parse::<Type>().unwrap(), Type::Mesh);
+		assert_eq!(
+			Fingerprint::decode(&original.encode()).unwrap().r#type(),
+			Type::Mesh
+		);
+		assert!(original.to_uri().starts_with("fp1:mesh:v1:"));
+		assert_eq!(
+			Fingerprint::from_uri(&original.to_uri()).unwrap().r#type(),
+			Type::Mesh
+		);
+
+		#[cfg(feature = "proto")]
+		assert_eq!(
+			Fingerprint::from_proto(original.to_proto())
+				.unwrap()
+				.r#type(),
+			Type::Mesh
+		);
+	}
+
+	#[test]
+	fn test_mesh_errors() {
+		let e = Fingerprint::finger_mesh("samples/ascii.txt").unwrap_err();
+
+		assert!(matches!(
+			e.downcast_ref::<MeshError>(),
+			Some(MeshError::Parse(_))
+		));
+	}
+}
@@ -1,18 +1,150 @@
-use std::{
-	io,
-	path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 use bitvec::prelude::*;
 use rand::prelude::*;
 
-use crate::{Error, NUM_FINGERPRINT_SEGMENTS};
+#[cfg(any(feature = "archive", feature = "email"))]
+use crate::Fingerprint;
+use crate::{FingerprintError, NUM_FINGERPRINT_SEGMENTS};
 
 /// Implementation of raw fingerprinter.
 pub mod raw;
 
+/// Implementation of text fingerprinter.
+pub mod text;
+
+/// Implementation of format invariant audio fingerprinter.
+#[cfg(feature = "audio")]
+pub mod audio;
+
+/// Implementation of sampled fingerprinter for very large files.
+pub mod sampled;
+
+/// Implementation of SimHash text fingerprinter.
+#[cfg(feature = "text")]
+pub mod text_simhash;
+
+/// Implementation of subtitle dialogue fingerprinter.
+#[cfg(feature = "text")]
+pub mod subtitle;
+
+/// Implementation of PDF text layer fingerprinter.
+#[cfg(feature = "pdf")]
+pub mod pdf;
+
+/// Implementation of office document fingerprinter.
+#[cfg(feature = "office")]
+pub mod office;
+
+/// Implementation of DOCX paragraph text fingerprinter.
+#[cfg(feature = "office")]
+pub mod docx;
+
+/// Implementation of EPUB fingerprinter.
+#[cfg(feature = "epub")]
+pub mod epub;
+
+/// Implementation of zip and tar archive fingerprinter.
+#[cfg(feature = "archive")]
+pub mod archive;
+
+/// Implementation of ELF and PE executable fingerprinter.
+#[cfg(feature = "binary")]
+pub mod binary;
+
+/// Implementation of font glyph outline fingerprinter.
+#[cfg(feature = "font")]
+pub mod font;
+
+/// Implementation of 3D mesh shape fingerprinter.
+#[cfg(feature = "mesh")]
+pub mod mesh;
+
+/// Implementation of email message fingerprinter.
+#[cfg(feature = "email")]
+pub mod email;
+
+/// Implementation of perceptual image fingerprinter.
+#[cfg(feature = "image")]
+pub mod image;
+
+/// Implementation of image text region fingerprinter.
+#[cfg(feature = "image")]
+pub mod image_text;
+
+/// Implementation of meme invariant image fingerprinter.
+#[cfg(feature = "image")]
+pub mod image_meme;
+
+/// Implementation of depth map fingerprinter.
+#[cfg(feature = "image")]
+pub mod image_depth;
+
+/// Implementation of sprite sheet fingerprinter.
+#[cfg(feature = "image")]
+pub mod sprite;
+
+/// Perceptual hashing shared by image fingerprinters.
+#[cfg(feature = "image")]
+mod phash;
+
+/// Helpers shared by the tests of fingerprinters.
+#[cfg(test)]
+mod test_util;
+
 /// Seed for deterministic RNG.
-const RNG_SEED: u64 = 939270607250626829;
+pub(crate) const RNG_SEED: u64 = 939270607250626829;
+
+/// Pair up identical fingerprints of two collections, returning the indices of each pair, left then right.
+///
+/// Fingerprints are identical when they agree in type, options and bits, and each is paired at most once.
+#[cfg(any(feature = "archive", feature = "email"))]
+pub(crate) fn pair_identical<'a>(
+	left: impl IntoIterator<Item = &'a Fingerprint>,
+	right: impl IntoIterator<Item = &'a Fingerprint>,
+) -> Vec<(usize, usize)> {
+	let same = |a: &Fingerprint, b: &Fingerprint| {
+		a.r#type == b.r#type && a.options_tag == b.options_tag && a.fingerprint == b.fingerprint
+	};
+	let mut unmatched: Vec<(usize, &Fingerprint)> = right.into_iter().enumerate().collect();
+	let mut pairs = Vec::new();
+
+	for (index, fingerprint) in left.into_iter().enumerate() {
+		if let Some(position) = unmatched
+			.iter()
+			.position(|(_, candidate)| same(fingerprint, candidate))
+		{
+			pairs.push((index, unmatched.remove(position).0));
+		}
+	}
+
+	pairs
+}
+
+/// Fold a set of hashes into a 128-bit summary with one hash function per bit (1-bit MinHash).
+///
+/// Each bit is the low bit of the minimum of its hash function over the set, so sets sharing a fraction `j` of their
+/// members agree on about `(1 + j) / 2` of the bits.
+#[cfg(any(feature = "archive", feature = "font"))]
+pub(crate) fn min_hash(hashes: &[u64]) -> BitBox<u8> {
+	(0..NUM_FINGERPRINT_SEGMENTS as u64)
+		.map(|index| {
+			hashes
+				.iter()
+				.map(|hash| mix(hash ^ mix(index + 1)))
+				.min()
+				.is_some_and(|minimum| minimum & 1 == 1)
+		})
+		.collect()
+}
+
+/// SplitMix64 finaliser, spreading every input bit over the output.
+pub(crate) fn mix(mut x: u64) -> u64 {
+	x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+	x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+	x ^ (x >> 31)
+}
 
 /// Provides RNG support methods.
 trait ChooseMultipleStable {
@@ -63,7 +195,7 @@ where
 	<&'fp <&'fp Self as IntoIterator>::Item as IntoIterator>::Item: FingerElement,
 {
 	/// Create new fingerprinter.
-	fn new<P: AsRef<Path>>(path: P) -> Result<Self, Error>
+	fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError>
 	where
 		Self: Sized;
 
@@ -71,8 +203,13 @@ where
 	fn path(&self) -> PathBuf;
 
 	/// Process through each segment of a file using a particular fingerprinter, generating the final fingerprint.
-	fn finger(&'fp self) -> Result<BitBox<u8>, Error> {
-		let mut fingerprint = bitbox![u8, Lsb0; 0; NUM_FINGERPRINT_SEGMENTS];
+	///
+	/// Each bit compares the value of a segment with the next, and the last compares the last segment with the
+	/// first, so the fingerprint has one bit per segment. Fails with [FingerprintError::EmptyInput] if the
+	/// fingerprinter has no segments.
+	fn finger(&'fp self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path().display()).entered();
+		let mut fingerprint = BitVec::<u8, Lsb0>::with_capacity(NUM_FINGERPRINT_SEGMENTS);
 		let mut first = None;
 		let mut last = None;
 
@@ -80,26 +217,21 @@ where
 			let value = segment.value()?;
 
 			match last {
-				Some(last) => {
-					if value >= last {
-						fingerprint.set(segment.index() - 1, true);
-					}
-				}
-				None => {
-					first = Some(segment.value()?);
-				}
+				Some(last) => fingerprint.push(value >= last),
+				None => first = Some(segment.value()?),
 			}
 
 			last = Some(value);
 		}
 
-		if first.ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?
-			>= last.ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?
-		{
-			fingerprint.set(NUM_FINGERPRINT_SEGMENTS - 1, true);
-		}
+		fingerprint.push(
+			first.ok_or(FingerprintError::EmptyInput)?
+				>= last.ok_or(FingerprintError::EmptyInput)?,
+		);
+
+		tracing::trace!(set_bits = fingerprint.count_ones(), "hashed segments");
 
-		Ok(fingerprint)
+		Ok(fingerprint.into_boxed_bitslice())
 	}
 }
 
@@ -128,7 +260,7 @@ where
 	fn size(&self) -> usize;
 
 	/// Returns the segment value.
-	fn value(&mut self) -> Result<Self::Value, Error>;
+	fn value(&mut self) -> Result<Self::Value, FingerprintError>;
 }
 
 /// Methods for an element contained in a fingerprint segment.
@@ -158,5 +290,5 @@ pub trait FingerElement {
 	fn size(&self) -> usize;
 
 	/// Returns the value of the element.
-	fn data(&self) -> Result<Self::Data, Error>;
+	fn data(&self) -> Result<Self::Data, FingerprintError>;
 }
@@ -0,0 +1,389 @@
+use std::{
+	fs::File,
+	io::{Read, Seek},
+	path::{Path, PathBuf},
+};
+
+use bitvec::prelude::*;
+use quick_xml::{escape::resolve_predefined_entity, events::Event, Reader};
+use zip::ZipArchive;
+
+use super::text_simhash::SimHashTextFingerprinter;
+use crate::FingerprintError;
+
+/// Extensions of the office documents fingerprinted by their content.
+pub const EXTENSIONS: [&str; 5] = ["docx", "xlsx", "odt", "ods", "odp"];
+
+/// Errors specific to office document fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OfficeError {
+	/// The zip container is not a Word, Excel or OpenDocument file.
+	#[error("not a DOCX, XLSX or OpenDocument file")]
+	Unsupported,
+
+	/// A part the document format requires is missing from the container.
+	#[error("document has no {0}")]
+	MissingPart(String),
+}
+
+/// Fingerprinter for office documents by their text content.
+///
+/// DOCX, XLSX and OpenDocument files are zip containers whose bytes change on every save. The text of the primary
+/// content parts (`word/document.xml`, the shared strings and sheets of `xl/`, or `content.xml`) is extracted without
+/// markup or attributes, so revision IDs, timestamps, run splitting and compression do not affect it, and fingerprinted
+/// with the [SimHashTextFingerprinter].
+#[derive(Debug)]
+pub struct OfficeFingerprinter {
+	text: SimHashTextFingerprinter,
+}
+
+impl OfficeFingerprinter {
+	/// Create new fingerprinter, extracting the text of the document.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let text = extract(ZipArchive::new(File::open(&path)?)?)?;
+
+		Ok(Self {
+			text: SimHashTextFingerprinter::from_text(path, text),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.text.path()
+	}
+
+	/// Generate the fingerprint of the extracted text.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		self.text.finger()
+	}
+}
+
+/// Return whether a file of a detected MIME type is an office document fingerprinted by its content.
+///
+/// Documents detected by their contents are accepted, as are zip files with one of the [EXTENSIONS].
+pub fn is_office(mime_type: &str, path: &Path) -> bool {
+	mime_type.starts_with("application/vnd.openxmlformats-officedocument.wordprocessingml")
+		|| mime_type.starts_with("application/vnd.openxmlformats-officedocument.spreadsheetml")
+		|| mime_type.starts_with("application/vnd.oasis.opendocument")
+		|| (mime_type == "application/zip"
+			&& path
+				.extension()
+				.and_then(|extension| extension.to_str())
+				.is_some_and(|extension| {
+					EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+				}))
+}
+
+/// Extract the text of a document from its container.
+fn extract<R: Read + Seek>(mut archive: ZipArchive<R>) -> Result<String, FingerprintError> {
+	if archive.index_for_name("word/document.xml").is_some() {
+		Ok(paragraphs(&part(&mut archive, "word/document.xml")?, Some(b"t"))?.join("\n"))
+	} else if archive.index_for_name("xl/workbook.xml").is_some() {
+		let shared = match archive.index_for_name("xl/sharedStrings.xml") {
+			Some(_) => paragraphs(&part(&mut archive, "xl/sharedStrings.xml")?, Some(b"t"))?,
+			None => Vec::new(),
+		};
+		let mut sheets: Vec<String> = archive
+			.file_names()
+			.filter(|name| name.starts_with("xl/worksheets/") && name.ends_with(".xml"))
+			.map(str::to_string)
+			.collect();
+
+		// Natural order, so sheet10 follows sheet9.
+		sheets.sort_by_key(|name| (name.len(), name.clone()));
+
+		sheets.iter().try_fold(String::new(), |text, name| {
+			Ok(text + &sheet_text(&part(&mut archive, name)?, &shared)?)
+		})
+	} else if archive.index_for_name("content.xml").is_some() {
+		Ok(paragraphs(&part(&mut archive, "content.xml")?, None)?.join("\n"))
+	} else {
+		Err(FingerprintError::from(OfficeError::Unsupported))
+	}
+}
+
+/// Read a part of the container as UTF-8.
+pub(super) fn part<R: Read + Seek>(
+	archive: &mut ZipArchive<R>,
+	name: &str,
+) -> Result<String, FingerprintError> {
+	let mut file = archive
+		.by_name(name)
+		.map_err(|_| OfficeError::MissingPart(name.to_string()))?;
+	let mut xml = String::new();
+
+	file.read_to_string(&mut xml)?;
+
+	Ok(xml)
+}
+
+/// Extract the text of the paragraphs (`p`, `h` or `si` elements) of XML.
+///
+/// With `text_element`, only text inside elements of that local name is kept, as Word stores field codes and other
+/// non-text in text nodes elsewhere. Tabs, line breaks and space elements become spaces.
+pub(super) fn paragraphs(
+	xml: &str,
+	text_element: Option<&[u8]>,
+) -> Result<Vec<String>, FingerprintError> {
+	let mut reader = Reader::from_str(xml);
+	let mut paragraphs = Vec::new();
+	let mut text = String::new();
+	let mut inside = text_element.is_none();
+
+	loop {
+		match reader.read_event()? {
+			Event::Start(e) if Some(e.local_name().as_ref()) == text_element => inside = true,
+			Event::End(e) if Some(e.local_name().as_ref()) == text_element => inside = false,
+			Event::End(e) if matches!(e.local_name().as_ref(), b"p" | b"h" | b"si") => {
+				paragraphs.push(std::mem::take(&mut text))
+			}
+			Event::Empty(e)
+				if matches!(
+					e.local_name().as_ref(),
+					b"tab" | b"br" | b"s" | b"line-break"
+				) =>
+			{
+				text.push(' ')
+			}
+			Event::Text(e) if inside => text.push_str(&e.decode()?),
+			Event::CData(e) if inside => text.push_str(&e.decode()?),
+			Event::GeneralRef(e) if inside => match e.resolve_char_ref()? {
+				Some(character) => text.push(character),
+				None => text.push_str(resolve_predefined_entity(&e.decode()?).unwrap_or_default()),
+			},
+			Event::Eof => break,
+			_ => (),
+		}
+	}
+
+	if !text.is_empty() {
+		paragraphs.push(text);
+	}
+
+	Ok(paragraphs)
+}
+
+/// Extract the cell values of a worksheet, resolving shared strings, one line per row.
+fn sheet_text(xml: &str, shared: &[String]) -> Result<String, FingerprintError> {
+	let mut reader = Reader::from_str(xml);
+	let mut text = String::new();
+	let mut cell_type = None;
+	let mut value = None::<String>;
+
+	loop {
+		match reader.read_event()? {
+			Event::Start(e) if e.local_name().as_ref() == b"c" => {
+				cell_type = e
+					.try_get_attribute("t")?
+					.map(|attribute| attribute.value.into_owned());
+			}
+			Event::Start(e) if matches!(e.local_name().as_ref(), b"v" | b"t") => {
+				value.get_or_insert_with(String::new);
+			}
+			Event::Text(e) => {
+				if let Some(value) = &mut value {
+					value.push_str(&e.decode()?);
+				}
+			}
+			Event::GeneralRef(e) => {
+				if let Some(value) = &mut value {
+					value.push_str(resolve_predefined_entity(&e.decode()?).unwrap_or_default());
+				}
+			}
+			Event::End(e) if e.local_name().as_ref() == b"c" => {
+				let value = value.take().unwrap_or_default();
+				let value = match cell_type.as_deref() {
+					Some(b"s") => value
+						.trim()
+						.parse::<usize>()
+						.ok()
+						.and_then(|index| shared.get(index))
+						.map_or("", String::as_str),
+					_ => &value,
+				};
+
+				text.push_str(value);
+				text.push(' ');
+			}
+			Event::End(e) if e.local_name().as_ref() == b"row" => text.push('\n'),
+			Event::Eof => break,
+			_ => (),
+		}
+	}
+
+	Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use rand::{seq::SliceRandom, Rng, SeedableRng};
+	use rand_chacha::ChaCha8Rng;
+
+	use super::{extract, OfficeError};
+	use crate::{fingerprinters::test_util::write_zip, Fingerprint};
+
+	/// Vocabulary the test paragraphs are drawn from.
+	const WORDS: [&str; 16] = [
+		"report", "quarter", "revenue", "growth", "team", "market", "product", "customer", "plan",
+		"budget", "review", "launch", "region", "target", "sales", "risk",
+	];
+
+	/// Return ten paragraphs of random words.
+	fn paragraphs(seed: u64) -> Vec<String> {
+		let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+		(0..10)
+			.map(|_| {
+				(0..40)
+					.map(|_| *WORDS.choose(&mut rng).unwrap())
+					.collect::<Vec<_>>()
+					.join(" ")
+			})
+			.collect()
+	}
+
+	/// Write a DOCX as Word does, with revision IDs and runs split inside words.
+	fn write_word(path: &Path, paragraphs: &[String], seed: u64) {
+		let mut rng = ChaCha8Rng::seed_from_u64(seed);
+		let body: String = paragraphs
+			.iter()
+			.map(|paragraph| {
+				let cut = rng.gen_range(1..paragraph.len() - 1);
+
+				format!(
+					r#"<w:p w:rsidR="00{:06X}" w:rsidRDefault="00{:06X}"><w:r w:rsidRPr="00{:06X}"><w:t xml:space="preserve">{}</w:t></w:r><w:r><w:instrText>PAGE</w:instrText><w:t>{}</w:t></w:r></w:p>"#,
+					rng.gen::<u32>() & 0xffffff,
+					rng.gen::<u32>() & 0xffffff,
+					rng.gen::<u32>() & 0xffffff,
+					&paragraph[..cut],
+					&paragraph[cut..]
+				)
+			})
+			.collect();
+
+		write_zip(
+			path,
+			&[
+				("[Content_Types].xml", "<Types/>".into()),
+				(
+					"docProps/core.xml",
+					format!(
+						"<dcterms:modified>2024-01-{:02}T09:00:00Z</dcterms:modified>",
+						seed % 28 + 1
+					),
+				),
+				(
+					"word/document.xml",
+					format!(
+						r#"<w:document xmlns:w="w"><w:body>{}</w:body></w:document>"#,
+						body
+					),
+				),
+			],
+			6,
+		);
+	}
+
+	/// Write an ODT as LibreOffice does.
+	fn write_open_document(path: &Path, paragraphs: &[String]) {
+		let body: String = paragraphs
+			.iter()
+			.map(|paragraph| format!(r#"<text:p text:style-name="P1">{}</text:p>"#, paragraph))
+			.collect();
+
+		write_zip(
+			path,
+			&[
+				("mimetype", "application/vnd.oasis.opendocument.text".into()),
+				(
+					"content.xml",
+					format!(
+						r#"<office:document-content xmlns:office="o" xmlns:text="t"><office:body><office:text>{}</office:text></office:body></office:document-content>"#,
+						body
+					),
+				),
+				(
+					"meta.xml",
+					"<meta:creation-date>2025-06-11T17:15:42</meta:creation-date>".into(),
+				),
+			],
+			9,
+		);
+	}
+
+	#[test]
+	fn test_office_resaved_and_edited() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = |name: &str| dir.path().join(name);
+		let text = paragraphs(0);
+		let mut edited = text.clone();
+
+		write_word(&path("word.docx"), &text, 1);
+		write_word(&path("resaved.docx"), &text, 2);
+		write_open_document(&path("resaved.odt"), &text);
+
+		edited[3] = paragraphs(1)[3].clone();
+		write_word(&path("edited.docx"), &edited, 3);
+		edited[7] = paragraphs(1)[7].clone();
+		write_word(&path("edited_twice.docx"), &edited, 4);
+		write_word(&path("other.docx"), &paragraphs(2), 5);
+
+		let finger = |name: &str| Fingerprint::finger_office(path(name)).unwrap();
+		let original = finger("word.docx");
+		let raw = Fingerprint::finger_raw(path("word.docx"))
+			.unwrap()
+			.compare(&Fingerprint::finger_raw(path("resaved.docx")).unwrap());
+		let edited = original.compare(&finger("edited.docx"));
+		let edited_twice = original.compare(&finger("edited_twice.docx"));
+
+		assert_eq!(original.compare(&finger("resaved.docx")), 1.0);
+		assert_eq!(original.compare(&finger("resaved.odt")), 1.0);
+		assert!(raw < 1.0);
+		assert!(edited > 0.8 && edited < 1.0, "edited {}", edited);
+		assert!(edited_twice < edited, "edited twice {}", edited_twice);
+		assert!(original.compare(&finger("other.docx")) < edited_twice);
+		assert_eq!(
+			Fingerprint::finger(path("resaved.odt"))
+				.unwrap()
+				.compare(&original),
+			1.0
+		);
+	}
+
+	#[test]
+	fn test_spreadsheet_text() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("book.xlsx");
+
+		write_zip(
+			&path,
+			&[
+				("xl/workbook.xml", "<workbook/>"),
+				(
+					"xl/sharedStrings.xml",
+					"<sst><si><t>Revenue</t></si><si><r><t>Q1 </t></r><r><t>&amp; Q2</t></r></si></sst>",
+				),
+				(
+					"xl/worksheets/sheet1.xml",
+					r#"<worksheet><sheetData><row><c r="A1" t="s"><v>0</v></c><c r="B1"><v>42.5</v></c></row><row><c t="s"><v>1</v></c><c t="inlineStr"><is><t>note</t></is></c></row></sheetData></worksheet>"#,
+				),
+			],
+			6,
+		);
+
+		let archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+
+		assert_eq!(extract(archive).unwrap(), "Revenue 42.5 \nQ1 & Q2 note \n");
+
+		write_zip(&path, &[("readme.txt", "hello")], 6);
+
+		let e = Fingerprint::finger_office(&path).unwrap_err();
+
+		assert_eq!(
+			e.downcast_ref::<OfficeError>(),
+			Some(&OfficeError::Unsupported)
+		);
+	}
+}
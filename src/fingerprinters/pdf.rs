@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+
+use super::text_simhash::SimHashTextFingerprinter;
+use crate::FingerprintError;
+
+/// Errors specific to PDF fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PdfError {
+	/// The PDF could not be parsed or its text could not be extracted.
+	#[error("cannot extract PDF text: {0}")]
+	Extract(String),
+
+	/// The PDF has no text layer, as in scanned documents.
+	#[error("PDF has no text layer")]
+	NoTextLayer,
+}
+
+/// Fingerprinter for PDF documents by their text layer.
+///
+/// The text is extracted and fingerprinted with the [SimHashTextFingerprinter], so the same document exported by
+/// different producers, with different compression and object layout, fingerprints alike, and alike with the same text
+/// in a plain text file. PDFs without a text layer fail with [PdfError::NoTextLayer].
+#[derive(Debug)]
+pub struct PdfFingerprinter {
+	text: SimHashTextFingerprinter,
+}
+
+impl PdfFingerprinter {
+	/// Create new fingerprinter, extracting the text of every page.
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let bytes = std::fs::read(&path)?;
+		let text = pdf_extract::extract_text_from_mem(&bytes)
+			.map_err(|e| PdfError::Extract(e.to_string()))?;
+
+		if text.trim().is_empty() {
+			return Err(FingerprintError::from(PdfError::NoTextLayer));
+		}
+
+		Ok(Self {
+			text: SimHashTextFingerprinter::from_text(path, text),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.text.path()
+	}
+
+	/// Generate the fingerprint of the extracted text.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		self.text.finger()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::PdfError;
+	use crate::Fingerprint;
+
+	#[test]
+	fn test_pdf_resaved() {
+		let original = Fingerprint::finger_pdf("samples/pdf/document.pdf").unwrap();
+		let resaved = Fingerprint::finger_pdf("samples/pdf/document_resaved.pdf").unwrap();
+		let raw = Fingerprint::finger_raw("samples/pdf/document.pdf")
+			.unwrap()
+			.compare(&Fingerprint::finger_raw("samples/pdf/document_resaved.pdf").unwrap());
+
+		assert!(original.compare(&resaved) >= 0.95);
+		assert!(raw < 0.8, "raw {}", raw);
+		assert!(
+			Fingerprint::finger("samples/pdf/document_resaved.pdf")
+				.unwrap()
+				.compare(&original)
+				>= 0.95
+		);
+	}
+
+	#[test]
+	fn test_pdf_no_text_layer() {
+		let e = Fingerprint::finger_pdf("samples/pdf/blank.pdf").unwrap_err();
+
+		assert_eq!(e.downcast_ref::<PdfError>(), Some(&PdfError::NoTextLayer));
+		assert_eq!(
+			Fingerprint::finger("samples/pdf/blank.pdf")
+				.unwrap()
+				.r#type(),
+			crate::Type::Raw
+		);
+	}
+}
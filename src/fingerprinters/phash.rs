@@ -0,0 +1,64 @@
+use std::f64::consts::PI;
+
+use bitvec::prelude::*;
+
+use crate::NUM_FINGERPRINT_SEGMENTS;
+
+/// Width and height (pixels) of the image the perceptual hash is computed over.
+pub(crate) const PHASH_SIZE: usize = 32;
+
+/// Compute a DCT-based perceptual hash of a `PHASH_SIZE`×`PHASH_SIZE` grid of pixel values (row-major).
+///
+/// The lowest-frequency DCT coefficients (excluding the DC term) are taken in diagonal order, and each bit is set
+/// when its coefficient is above the median of the selected coefficients.
+pub(crate) fn phash(pixels: &[f64]) -> BitBox<u8> {
+	debug_assert_eq!(pixels.len(), PHASH_SIZE * PHASH_SIZE);
+
+	let cosines: Vec<f64> = (0..PHASH_SIZE * PHASH_SIZE)
+		.map(|i| {
+			let (k, n) = (i / PHASH_SIZE, i % PHASH_SIZE);
+
+			((2 * n + 1) as f64 * k as f64 * PI / (2 * PHASH_SIZE) as f64).cos()
+		})
+		.collect();
+
+	// Separable 2D DCT-II: transform rows, then columns.
+	let mut rows = vec![0f64; PHASH_SIZE * PHASH_SIZE];
+
+	for y in 0..PHASH_SIZE {
+		for k in 0..PHASH_SIZE {
+			rows[y * PHASH_SIZE + k] = (0..PHASH_SIZE)
+				.map(|x| pixels[y * PHASH_SIZE + x] * cosines[k * PHASH_SIZE + x])
+				.sum();
+		}
+	}
+
+	let coefficient = |u: usize, v: usize| -> f64 {
+		(0..PHASH_SIZE)
+			.map(|y| rows[y * PHASH_SIZE + u] * cosines[v * PHASH_SIZE + y])
+			.sum()
+	};
+
+	let coefficients: Vec<f64> = (1..2 * PHASH_SIZE - 1)
+		.flat_map(|diagonal| {
+			(0..=diagonal)
+				.map(move |u| (u, diagonal - u))
+				.filter(|&(u, v)| u < PHASH_SIZE && v < PHASH_SIZE)
+		})
+		.take(NUM_FINGERPRINT_SEGMENTS)
+		.map(|(u, v)| coefficient(u, v))
+		.collect();
+
+	let mut sorted = coefficients.clone();
+
+	sorted.sort_by(|a, b| a.total_cmp(b));
+
+	let median = (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2f64;
+	let mut fingerprint = bitbox![u8, Lsb0; 0; NUM_FINGERPRINT_SEGMENTS];
+
+	for (index, value) in coefficients.iter().enumerate() {
+		fingerprint.set(index, *value > median);
+	}
+
+	fingerprint
+}
@@ -1,50 +1,178 @@
 use std::fs::File;
-use std::sync::Arc;
 use std::{
-	error,
+	fmt,
+	io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
 	mem::size_of,
-	os::unix::fs::{FileExt, MetadataExt},
 	path::PathBuf,
+	sync::Mutex,
 };
 
 use divrem::DivRem;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 
-use crate::NUM_FINGERPRINT_SEGMENTS;
+use crate::{options::FingerprintConfig, FingerprintError};
 
-use super::{ChooseMultipleStable, Error, FingerElement, FingerSegment, Fingerprinter, RNG_SEED};
+use super::{ChooseMultipleStable, FingerElement, FingerSegment, Fingerprinter};
 
-/// Fingerprinter for raw files.
+/// Errors produced while reading a file for a raw fingerprint.
+#[derive(Debug, thiserror::Error)]
+pub enum RawError {
+	/// Reading a byte of the file failed, such as when the file was truncated while being fingerprinted.
+	#[error("failed to read byte {pos} of {}", .path.display())]
+	Read {
+		/// Path of the file being fingerprinted.
+		path: PathBuf,
+
+		/// File position of the byte.
+		pos: usize,
+
+		/// I/O error produced by the read.
+		#[source]
+		source: io::Error,
+	},
+}
+
+/// Fingerprinter for raw files, or for bytes read from memory or any seekable reader.
+///
+/// Segments of one fingerprinter may be read from several threads on Unix and Windows, where bytes are read at a
+/// position without a shared file cursor. Elsewhere, and for readers, reads seek a shared cursor behind a lock, so read
+/// from one thread at a time.
 #[derive(Debug)]
 pub struct RawFingerprinter {
 	path: PathBuf,
-	handle: File,
+	source: Source,
 	rng: ChaCha8Rng,
 	segment_sizes: Vec<usize>,
 }
 
+/// Bytes read by a raw fingerprinter.
+#[derive(Debug)]
+enum Source {
+	/// Open file, read at a position.
+	File(File),
+
+	/// Seekable reader, read in order and seeked when a read skips.
+	Reader(Mutex<Reader>),
+}
+
+/// Reader that can seek, boxed as a single trait object.
+trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Buffered reader of a raw fingerprinter, with the reader position its bytes start at.
+struct Reader {
+	reader: BufReader<Box<dyn ReadSeek>>,
+	start: u64,
+	pos: u64,
+}
+
+impl fmt::Debug for Reader {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Reader")
+			.field("start", &self.start)
+			.field("pos", &self.pos)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Reader {
+	/// Read the byte at a position relative to the start, seeking only when it does not follow the last read.
+	fn read_at(&mut self, pos: u64) -> io::Result<u8> {
+		if pos != self.pos {
+			self.reader.seek(SeekFrom::Start(self.start + pos))?;
+		}
+
+		let mut data = [0u8; 1];
+
+		// Leave the position unknown until the read succeeds, so a failed read seeks again.
+		self.pos = u64::MAX;
+		self.reader.read_exact(&mut data)?;
+		self.pos = pos + 1;
+
+		Ok(data[0])
+	}
+}
+
 impl<'fp> Fingerprinter<'fp> for RawFingerprinter {
-	fn new<P: AsRef<std::path::Path>>(path: P) -> Result<RawFingerprinter, Error> {
-		let path = path.as_ref().to_path_buf();
-		let size = path.metadata()?.size() as usize;
-		let (segment_size, remainder) = size.div_rem(NUM_FINGERPRINT_SEGMENTS);
-		let mut rng = ChaCha8Rng::seed_from_u64(RNG_SEED);
-		let mut segment_sizes = vec![segment_size; NUM_FINGERPRINT_SEGMENTS];
+	fn new<P: AsRef<std::path::Path>>(path: P) -> Result<RawFingerprinter, FingerprintError> {
+		Self::with_config(path, &FingerprintConfig::default())
+	}
 
-		segment_sizes.choose_multiple_stable(&mut rng, segment_size, remainder);
+	fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+}
+
+impl RawFingerprinter {
+	/// Create new fingerprinter splitting the file into the segments of a configuration.
+	pub fn with_config<P: AsRef<std::path::Path>>(
+		path: P,
+		config: &FingerprintConfig,
+	) -> Result<Self, FingerprintError> {
+		let path = path.as_ref().to_path_buf();
+		let (segment_sizes, rng) = segment_sizes(path.metadata()?.len() as usize, config);
 
 		Ok(Self {
-			handle: File::open(&path)?,
+			source: Source::File(File::open(&path)?),
 			rng,
 			path,
 			segment_sizes,
 		})
 	}
 
-	fn path(&self) -> PathBuf {
-		self.path.clone()
+	/// Create new fingerprinter for a copy of bytes held in memory, with an empty path.
+	pub fn from_slice(data: &[u8]) -> Self {
+		Self::from_source(Cursor::new(data.to_vec()), 0, data.len() as u64)
 	}
+
+	/// Create new fingerprinter for the next `len` bytes of a seekable reader, with an empty path.
+	///
+	/// Bytes are read through a small buffer as segments are hashed, so the reader is never held in memory. Fails if
+	/// the reader position cannot be read; a reader that ends before `len` bytes fails when hashed.
+	pub fn from_reader<R: Read + Seek + Send + 'static>(
+		mut reader: R,
+		len: u64,
+	) -> Result<Self, FingerprintError> {
+		let start = reader.stream_position()?;
+
+		Ok(Self::from_source(reader, start, len))
+	}
+
+	/// Create new fingerprinter for `len` bytes of a reader from position `start`.
+	fn from_source<R: Read + Seek + Send + 'static>(reader: R, start: u64, len: u64) -> Self {
+		let (segment_sizes, rng) = segment_sizes(len as usize, &FingerprintConfig::default());
+
+		Self {
+			path: PathBuf::new(),
+			source: Source::Reader(Mutex::new(Reader {
+				reader: BufReader::new(Box::new(reader)),
+				start,
+				pos: 0,
+			})),
+			rng,
+			segment_sizes,
+		}
+	}
+
+	/// Return the value (mean byte value) of every segment, in order.
+	pub fn segment_values(&self) -> Result<Vec<u8>, FingerprintError> {
+		self.into_iter()
+			.map(|mut segment| segment.value())
+			.collect()
+	}
+}
+
+/// Return the sizes of the segments of `size` bytes, and the RNG continuing after them.
+fn segment_sizes(size: usize, config: &FingerprintConfig) -> (Vec<usize>, ChaCha8Rng) {
+	let (segment_size, remainder) = size.div_rem(config.segments.max(1));
+	let mut rng = ChaCha8Rng::seed_from_u64(config.rng_seed);
+	let mut segment_sizes = vec![segment_size; config.segments];
+
+	segment_sizes.choose_multiple_stable(&mut rng, segment_size, remainder);
+
+	(segment_sizes, rng)
 }
 
 impl<'fp> IntoIterator for &'fp RawFingerprinter {
@@ -68,7 +196,7 @@ pub struct RawSegment<'fp> {
 	index: usize,
 	pos: usize,
 	size: usize,
-	value: Option<Result<u8, Arc<dyn error::Error>>>,
+	value: Option<u8>,
 }
 
 impl<'fp> FingerSegment<'fp> for RawSegment<'fp> {
@@ -91,20 +219,17 @@ impl<'fp> FingerSegment<'fp> for RawSegment<'fp> {
 		self.size
 	}
 
-	fn value(&mut self) -> Result<Self::Value, Error> {
-		match &self.value {
-			Some(value) => match value.clone() {
-				Ok(data) => Ok(data),
-				Err(e) => Err(Box::new(e)),
-			},
+	fn value(&mut self) -> Result<Self::Value, FingerprintError> {
+		match self.value {
+			Some(value) => Ok(value),
 			None => {
 				let total = self.into_iter().try_fold(0u128, |total, element| {
-					Ok::<u128, Error>(total + element.data()? as u128)
+					Ok::<u128, FingerprintError>(total + element.data()? as u128)
 				})?;
 
 				let value = (total / self.size as u128) as u8;
 
-				self.value = Some(Ok(value));
+				self.value = Some(value);
 
 				Ok(value)
 			}
@@ -138,7 +263,7 @@ impl<'fp> Iterator for RawSegmentIterator<'fp> {
 	type Item = RawSegment<'fp>;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		if self.index >= NUM_FINGERPRINT_SEGMENTS {
+		if self.index >= self.fp.segment_sizes.len() {
 			return None;
 		}
 
@@ -156,7 +281,7 @@ impl<'fp> Iterator for RawSegmentIterator<'fp> {
 			pos: start_pos,
 			size,
 			value: match size {
-				0 => Some(Ok(self.rng.gen())),
+				0 => Some(self.rng.gen()),
 				_ => None,
 			},
 		})
@@ -171,7 +296,6 @@ pub struct RawElement<'fp> {
 	index: usize,
 	pos: usize,
 	size: usize,
-	data: Result<u8, Arc<dyn error::Error>>,
 }
 
 impl<'fp> FingerElement for RawElement<'fp> {
@@ -199,12 +323,63 @@ impl<'fp> FingerElement for RawElement<'fp> {
 		self.size
 	}
 
-	fn data(&self) -> Result<Self::Data, Error> {
-		match self.data.clone() {
-			Ok(data) => Ok(data),
-			Err(e) => Err(Box::new(e)),
+	fn data(&self) -> Result<Self::Data, FingerprintError> {
+		let read = match &self.fp.source {
+			Source::File(file) => {
+				let mut data = [0u8; 1];
+
+				read_exact_at(file, &mut data, self.pos as u64).map(|_| data[0])
+			}
+			Source::Reader(reader) => reader
+				.lock()
+				.unwrap_or_else(|poisoned| poisoned.into_inner())
+				.read_at(self.pos as u64),
+		};
+
+		read.map_err(|source| {
+			FingerprintError::Raw(RawError::Read {
+				path: self.fp.path.clone(),
+				pos: self.pos,
+				source,
+			})
+		})
+	}
+}
+
+/// Read exactly enough bytes to fill `buf` from a file position, without moving the file cursor on Unix.
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], pos: u64) -> io::Result<()> {
+	std::os::unix::fs::FileExt::read_exact_at(file, buf, pos)
+}
+
+/// Read exactly enough bytes to fill `buf` from a file position. Windows has no `read_exact_at`, so this loops over
+/// `seek_read`, which moves the file cursor.
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut pos: u64) -> io::Result<()> {
+	use std::os::windows::fs::FileExt;
+
+	while !buf.is_empty() {
+		match file.seek_read(buf, pos) {
+			Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+			Ok(read) => {
+				buf = &mut buf[read..];
+				pos += read as u64;
+			}
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+			Err(e) => return Err(e),
 		}
 	}
+
+	Ok(())
+}
+
+/// Read exactly enough bytes to fill `buf` from a file position, on platforms without positional reads.
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(file: &File, buf: &mut [u8], pos: u64) -> io::Result<()> {
+	let mut file = file;
+
+	file.seek(SeekFrom::Start(pos))?;
+	file.read_exact(buf)
 }
 
 /// Iterator for elements in a raw fingerprint segment.
@@ -225,13 +400,6 @@ impl<'fp> Iterator for RawElementIterator<'fp> {
 
 		let index = self.index;
 		let pos = self.segment.pos + index;
-		let mut data = [0u8; 1];
-
-		let data: Result<u8, Arc<dyn error::Error>> =
-			match self.fp.handle.read_exact_at(&mut data, pos as u64) {
-				Ok(_) => Ok(data[0]),
-				Err(e) => Err(Arc::new(e)),
-			};
 
 		self.index += 1;
 
@@ -241,7 +409,80 @@ impl<'fp> Iterator for RawElementIterator<'fp> {
 			index,
 			pos,
 			size: size_of::<u8>(),
-			data,
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, io};
+
+	use super::{RawError, RawFingerprinter};
+	use crate::{fingerprinters::Fingerprinter, FingerprintError};
+
+	#[test]
+	fn test_from_slice() {
+		for path in [
+			"samples/ascii.txt",
+			"samples/empty",
+			"samples/audio/tone.flac",
+		] {
+			assert_eq!(
+				RawFingerprinter::from_slice(&fs::read(path).unwrap())
+					.finger()
+					.unwrap(),
+				RawFingerprinter::new(path).unwrap().finger().unwrap(),
+				"{}",
+				path
+			);
+		}
+	}
+
+	#[test]
+	fn test_from_reader() {
+		use std::io::{Cursor, Seek, SeekFrom};
+
+		let data = fs::read("samples/audio/tone.flac").unwrap();
+		let mut file = fs::File::open("samples/audio/tone.flac").unwrap();
+
+		file.seek(SeekFrom::Start(100)).unwrap();
+
+		assert_eq!(
+			RawFingerprinter::from_reader(file, 1000)
+				.unwrap()
+				.finger()
+				.unwrap(),
+			RawFingerprinter::from_slice(&data[100..1100])
+				.finger()
+				.unwrap()
+		);
+		assert!(matches!(
+			RawFingerprinter::from_reader(Cursor::new(b"short".to_vec()), 100)
+				.unwrap()
+				.finger(),
+			Err(FingerprintError::Raw(RawError::Read { pos: 5, source, .. }))
+				if source.kind() == io::ErrorKind::UnexpectedEof
+		));
+	}
+
+	#[test]
+	fn test_truncated_while_reading() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("shrinking");
+
+		fs::write(&path, [7u8; 256]).unwrap();
+
+		let fingerprinter = RawFingerprinter::new(&path).unwrap();
+
+		fs::write(&path, [7u8; 16]).unwrap();
+
+		let FingerprintError::Raw(RawError::Read { pos, source, .. }) =
+			fingerprinter.finger().unwrap_err()
+		else {
+			panic!("expected a read error");
+		};
+
+		assert_eq!(pos, 16);
+		assert_eq!(source.kind(), io::ErrorKind::UnexpectedEof);
+	}
+}
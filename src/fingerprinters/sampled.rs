@@ -0,0 +1,211 @@
+use std::{
+	fs::File,
+	io::{self, Read, Seek, SeekFrom},
+	path::Path,
+};
+
+use bitvec::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::RNG_SEED;
+use crate::{options::Options, Fingerprint, FingerprintError, Type, NUM_FINGERPRINT_SEGMENTS};
+
+/// Size (bytes) of each sampled window.
+pub const WINDOW_BYTES: u64 = 1 << 20;
+
+/// Number of windows read inside a file, besides the first and last, unless configured otherwise.
+pub const DEFAULT_INTERIOR_WINDOWS: u32 = 64;
+
+/// Size (bytes) above which raw fingerprints are sampled, unless configured otherwise.
+pub const DEFAULT_THRESHOLD: u64 = 8 << 30;
+
+/// One window read from a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+	/// File position of the window.
+	pub offset: u64,
+
+	/// Size (bytes) of the window, less than [WINDOW_BYTES] only in files smaller than a window.
+	pub size: u64,
+
+	/// CRC-32 checksum of the window.
+	pub crc: u32,
+}
+
+/// Fingerprint of a very large file from a fixed set of windows: the first and last [WINDOW_BYTES], and windows at
+/// positions drawn from a seeded RNG, so files of the same size are sampled at the same positions.
+#[derive(Debug, Clone)]
+pub struct SampledFingerprint {
+	/// Windows read, in file order.
+	pub windows: Vec<Window>,
+
+	/// Summary of the windows as a 128-bit fingerprint, tagged with [Options::sampled].
+	///
+	/// Bit `i` is drawn from the checksum of window `i` modulo the number of windows, so files differing in a fraction
+	/// `d` of their windows disagree on about `d / 2` of the bits.
+	pub summary: Fingerprint,
+}
+
+/// Comparison of two sampled fingerprints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledComparison {
+	/// Similarity of the summaries, in [0, 1].
+	pub similarity: f64,
+
+	/// Number of windows at the same position with the same checksum.
+	pub matched: usize,
+
+	/// Number of windows of the fingerprint with the most windows.
+	pub windows: usize,
+}
+
+impl SampledFingerprint {
+	/// Compare the windows of this file with those of another.
+	pub fn compare(&self, other: &SampledFingerprint) -> SampledComparison {
+		SampledComparison {
+			similarity: self.summary.compare(&other.summary),
+			matched: self
+				.windows
+				.iter()
+				.filter(|window| other.windows.contains(window))
+				.count(),
+			windows: self.windows.len().max(other.windows.len()),
+		}
+	}
+}
+
+/// Return the offsets of the windows of a file of `size` bytes, in file order.
+pub fn window_offsets(size: u64, interior_windows: u32) -> Vec<u64> {
+	let last = size.saturating_sub(WINDOW_BYTES);
+	let mut rng = ChaCha8Rng::seed_from_u64(RNG_SEED);
+	let mut offsets: Vec<u64> = (0..interior_windows)
+		.map(|_| match last {
+			0 => 0,
+			last => rng.gen_range(0..=last),
+		})
+		.collect();
+
+	offsets.push(0);
+	offsets.push(last);
+	offsets.sort_unstable();
+
+	offsets
+}
+
+/// Fingerprint a file from its first and last windows and `interior_windows` windows inside it.
+pub fn fingerprint<P: AsRef<Path>>(
+	path: P,
+	interior_windows: u32,
+) -> Result<SampledFingerprint, FingerprintError> {
+	let _span = tracing::debug_span!("finger_sampled", path = %path.as_ref().display()).entered();
+	let mut file = File::open(&path)?;
+	let size = file.metadata()?.len();
+	let mut buffer = vec![0u8; WINDOW_BYTES.min(size) as usize];
+	let windows = window_offsets(size, interior_windows)
+		.into_iter()
+		.map(|offset| {
+			file.seek(SeekFrom::Start(offset))?;
+			file.read_exact(&mut buffer)?;
+
+			Ok(Window {
+				offset,
+				size: buffer.len() as u64,
+				crc: crc32fast::hash(&buffer),
+			})
+		})
+		.collect::<io::Result<Vec<_>>>()?;
+
+	tracing::trace!(windows = windows.len(), "sampled windows");
+
+	let fingerprint: BitBox<u8> = (0..NUM_FINGERPRINT_SEGMENTS)
+		.map(|index| {
+			let window = &windows[index % windows.len()];
+
+			super::mix(window.offset ^ super::mix((window.crc as u64) ^ ((index as u64) << 32))) & 1
+				== 1
+		})
+		.collect();
+
+	Ok(SampledFingerprint {
+		windows,
+		summary: Fingerprint {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Raw,
+			options_tag: Options::sampled(interior_windows).tag(),
+			original_version: None,
+		},
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs::{File, OpenOptions},
+		io::{Seek, SeekFrom, Write},
+		path::Path,
+	};
+
+	use super::{fingerprint, DEFAULT_INTERIOR_WINDOWS, DEFAULT_THRESHOLD};
+	use crate::{options::Options, Fingerprint};
+
+	/// Create a sparse file of `size` bytes with a header at its start.
+	fn sparse(path: &Path, size: u64) {
+		let mut file = File::create(path).unwrap();
+
+		file.write_all(b"disk image header").unwrap();
+		file.set_len(size).unwrap();
+	}
+
+	#[test]
+	fn test_sampled_windows() {
+		let dir = tempfile::tempdir().unwrap();
+		let (image, copy) = (dir.path().join("image"), dir.path().join("copy"));
+
+		sparse(&image, 4 << 30);
+		sparse(&copy, 4 << 30);
+
+		let original = fingerprint(&image, 8).unwrap();
+		let comparison = original.compare(&fingerprint(&copy, 8).unwrap());
+
+		assert_eq!(original.windows.len(), 10);
+		assert_eq!(original.windows[9].offset, (4 << 30) - super::WINDOW_BYTES);
+		assert_eq!((comparison.matched, comparison.windows), (10, 10));
+		assert_eq!(comparison.similarity, 1.0);
+
+		let mut file = OpenOptions::new().write(true).open(&copy).unwrap();
+
+		file.seek(SeekFrom::Start(original.windows[4].offset + 100))
+			.unwrap();
+		file.write_all(b"modified").unwrap();
+
+		let comparison = original.compare(&fingerprint(&copy, 8).unwrap());
+
+		assert_eq!(comparison.matched, 9);
+		assert!(comparison.similarity < 1.0);
+	}
+
+	#[test]
+	fn test_sampled_above_threshold() {
+		let dir = tempfile::tempdir().unwrap();
+		let image = dir.path().join("image");
+
+		sparse(&image, DEFAULT_THRESHOLD + 1);
+
+		assert_eq!(
+			Fingerprint::finger_raw(&image).unwrap().options_tag(),
+			Options::sampled(DEFAULT_INTERIOR_WINDOWS).tag()
+		);
+		assert_eq!(
+			Fingerprint::finger(&image).unwrap().options_tag(),
+			Options::sampled(DEFAULT_INTERIOR_WINDOWS).tag()
+		);
+		assert_eq!(
+			Fingerprint::finger_raw("samples/ascii.txt")
+				.unwrap()
+				.options_tag(),
+			Options::raw().tag()
+		);
+	}
+}
@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+use image::{
+	imageops::{self, FilterType},
+	GrayImage,
+};
+
+use super::phash::{phash, PHASH_SIZE};
+use crate::FingerprintError;
+
+/// Errors specific to sprite sheet fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SpriteError {
+	/// The sprite size is zero or larger than the sheet.
+	#[error("invalid sprite size {width}x{height} for a {sheet_width}x{sheet_height} sheet")]
+	InvalidSpriteSize {
+		/// Width (pixels) of a sprite.
+		width: u32,
+
+		/// Height (pixels) of a sprite.
+		height: u32,
+
+		/// Width (pixels) of the sheet.
+		sheet_width: u32,
+
+		/// Height (pixels) of the sheet.
+		sheet_height: u32,
+	},
+}
+
+/// Fingerprinter for the individual sprites packed into a sprite sheet.
+///
+/// The sheet is divided into a grid of sprite-sized tiles, dropping any partial tiles at the right and bottom edges.
+/// Tiles of a single colour, such as empty slots, are skipped, and every other tile is fingerprinted with the
+/// perceptual hash of its greyscale pixels.
+#[derive(Debug)]
+pub struct SpriteSheetFingerprinter {
+	path: PathBuf,
+	image: GrayImage,
+	sprite_size: (u32, u32),
+}
+
+impl SpriteSheetFingerprinter {
+	/// Create new fingerprinter for sprites of `sprite_size` (width, height) pixels.
+	pub fn new<P: AsRef<Path>>(path: P, sprite_size: (u32, u32)) -> Result<Self, FingerprintError> {
+		let image = image::open(&path)?.to_luma8();
+		let (width, height) = sprite_size;
+
+		if width == 0 || height == 0 || width > image.width() || height > image.height() {
+			return Err(FingerprintError::from(SpriteError::InvalidSpriteSize {
+				width,
+				height,
+				sheet_width: image.width(),
+				sheet_height: image.height(),
+			}));
+		}
+
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			image,
+			sprite_size,
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate a fingerprint for every non-blank tile, in row-major order.
+	pub fn finger(&self) -> Result<Vec<BitBox<u8>>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let (width, height) = self.sprite_size;
+		let mut fingerprints = Vec::new();
+
+		for top in (0..=self.image.height() - height).step_by(height as usize) {
+			for left in (0..=self.image.width() - width).step_by(width as usize) {
+				let tile = imageops::crop_imm(&self.image, left, top, width, height).to_image();
+
+				if tile.pixels().all(|pixel| pixel == tile.get_pixel(0, 0)) {
+					continue;
+				}
+
+				let tile = imageops::resize(
+					&tile,
+					PHASH_SIZE as u32,
+					PHASH_SIZE as u32,
+					FilterType::Triangle,
+				);
+				let pixels: Vec<f64> = tile.pixels().map(|pixel| pixel[0] as f64).collect();
+
+				fingerprints.push(phash(&pixels));
+			}
+		}
+
+		tracing::debug!(sprites = fingerprints.len(), "hashed sprites");
+
+		Ok(fingerprints)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use image::{GrayImage, Luma};
+	use rand::{Rng, SeedableRng};
+
+	use super::SpriteError;
+	use crate::Fingerprint;
+
+	/// Render a 16×16 sprite of random blocks, different for each seed.
+	fn sprite(seed: u64) -> GrayImage {
+		let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+		let blocks: Vec<u8> = (0..16).map(|_| rng.gen_range(32..224)).collect();
+
+		GrayImage::from_fn(16, 16, |x, y| Luma([blocks[(y / 4 * 4 + x / 4) as usize]]))
+	}
+
+	/// Pack sprites onto a sheet `columns` sprites wide, leaving `None` slots empty.
+	fn sheet(sprites: &[Option<u64>], columns: u32) -> GrayImage {
+		let rows = (sprites.len() as u32).div_ceil(columns);
+		let mut sheet = GrayImage::new(columns * 16, rows * 16);
+
+		for (index, seed) in sprites.iter().enumerate() {
+			if let Some(seed) = seed {
+				let (column, row) = (index as u32 % columns, index as u32 / columns);
+
+				image::imageops::replace(
+					&mut sheet,
+					&sprite(*seed),
+					(column * 16) as i64,
+					(row * 16) as i64,
+				);
+			}
+		}
+
+		sheet
+	}
+
+	#[test]
+	fn test_sprite_sheet_repacked() {
+		let dir = tempfile::tempdir().unwrap();
+		let packed = dir.path().join("packed.png");
+		let repacked = dir.path().join("repacked.png");
+		let other = dir.path().join("other.png");
+
+		sheet(&[Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)], 3)
+			.save(&packed)
+			.unwrap();
+		sheet(
+			&[
+				Some(6),
+				None,
+				Some(3),
+				Some(1),
+				Some(5),
+				None,
+				Some(2),
+				Some(4),
+			],
+			2,
+		)
+		.save(&repacked)
+		.unwrap();
+		sheet(&[Some(1), Some(2), Some(3), Some(4), Some(5), Some(7)], 3)
+			.save(&other)
+			.unwrap();
+
+		let packed = Fingerprint::finger_sprite_sheet(&packed, (16, 16)).unwrap();
+		let repacked = Fingerprint::finger_sprite_sheet(&repacked, (16, 16)).unwrap();
+		let other = Fingerprint::finger_sprite_sheet(&other, (16, 16)).unwrap();
+		let bytes = |fingerprints: &[Fingerprint]| {
+			fingerprints
+				.iter()
+				.map(|fingerprint| fingerprint.bytes().to_vec())
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(packed.len(), 6);
+		assert_eq!(bytes(&packed), bytes(&repacked));
+		assert_ne!(bytes(&packed), bytes(&other));
+	}
+
+	#[test]
+	fn test_sprite_size_invalid() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("sheet.png");
+
+		sheet(&[Some(1), Some(2)], 2).save(&path).unwrap();
+
+		for size in [(0, 16), (16, 0), (48, 16), (16, 32)] {
+			assert!(matches!(
+				Fingerprint::finger_sprite_sheet(&path, size)
+					.unwrap_err()
+					.downcast_ref(),
+				Some(SpriteError::InvalidSpriteSize { .. })
+			));
+		}
+	}
+}
@@ -0,0 +1,266 @@
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	time::Duration,
+};
+
+use bitvec::prelude::*;
+
+use super::text_simhash::SimHashTextFingerprinter;
+use crate::{Fingerprint, FingerprintError};
+
+/// Extensions of the subtitle formats parsed, SubRip and WebVTT.
+pub const EXTENSIONS: [&str; 2] = ["srt", "vtt"];
+
+/// Errors specific to subtitle fingerprints.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SubtitleError {
+	/// The file has no cues with a valid timing line.
+	#[error("no subtitle cues found")]
+	NoCues,
+}
+
+/// One cue of a subtitle file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+	/// Time (milliseconds) the cue is shown.
+	pub start: i64,
+
+	/// Time (milliseconds) the cue is hidden.
+	pub end: i64,
+
+	/// Dialogue of the cue, without markup and with whitespace collapsed.
+	pub text: String,
+}
+
+/// Comparison of two subtitle files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleComparison {
+	/// Similarity of the dialogue, in [0, 1].
+	pub similarity: f64,
+
+	/// Median time (milliseconds) to add to the cues of the left-hand file to align them with the right-hand file, or
+	/// `None` when no cues share their text.
+	pub offset: Option<i64>,
+}
+
+/// Fingerprinter for SubRip (`.srt`) and WebVTT (`.vtt`) subtitles by their dialogue.
+///
+/// Cue numbers, identifiers, timestamps, cue settings and markup are discarded and the dialogue is fingerprinted with
+/// the [SimHashTextFingerprinter], so resynced and reformatted copies of the same subtitles fingerprint alike. With a
+/// timing resolution, each cue is also tagged with its start time rounded down to that resolution, so only copies
+/// in sync within it match.
+#[derive(Debug)]
+pub struct SubtitleFingerprinter {
+	text: SimHashTextFingerprinter,
+}
+
+impl SubtitleFingerprinter {
+	/// Create new fingerprinter, parsing the cues of the file, with timestamps quantised to `timing` if given.
+	pub fn new<P: AsRef<Path>>(
+		path: P,
+		timing: Option<Duration>,
+	) -> Result<Self, FingerprintError> {
+		let cues = cues(&path)?;
+		let text = cues
+			.iter()
+			.map(|cue| match timing.map(|timing| timing.as_millis() as i64) {
+				Some(resolution) if resolution > 0 => {
+					format!("@{} {}", cue.start.div_euclid(resolution), cue.text)
+				}
+				_ => cue.text.clone(),
+			})
+			.collect::<Vec<_>>()
+			.join("\n");
+
+		Ok(Self {
+			text: SimHashTextFingerprinter::from_text(path, text),
+		})
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.text.path()
+	}
+
+	/// Generate the fingerprint of the dialogue.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		self.text.finger()
+	}
+}
+
+/// Return whether a path has a subtitle extension.
+pub fn is_subtitle(path: &Path) -> bool {
+	path.extension()
+		.and_then(|extension| extension.to_str())
+		.is_some_and(|extension| {
+			EXTENSIONS
+				.iter()
+				.any(|candidate| extension.eq_ignore_ascii_case(candidate))
+		})
+}
+
+/// Compare the dialogue of two subtitle files and estimate the constant time offset between them.
+pub fn compare<P: AsRef<Path>, Q: AsRef<Path>>(
+	left: P,
+	right: Q,
+) -> Result<SubtitleComparison, FingerprintError> {
+	let similarity = Fingerprint::finger_subtitle(&left, None)?
+		.compare(&Fingerprint::finger_subtitle(&right, None)?);
+
+	Ok(SubtitleComparison {
+		similarity,
+		offset: offset(&cues(left)?, &cues(right)?),
+	})
+}
+
+/// Estimate the time (milliseconds) to add to `left` to align it with `right`, as the median difference of the start
+/// times of cues with the same text, each occurrence of a text paired in order.
+pub fn offset(left: &[Cue], right: &[Cue]) -> Option<i64> {
+	let mut starts: HashMap<&str, Vec<i64>> = HashMap::new();
+
+	for cue in left.iter().rev() {
+		starts.entry(&cue.text).or_default().push(cue.start);
+	}
+
+	let mut differences: Vec<i64> = right
+		.iter()
+		.filter_map(|cue| {
+			let start = starts.get_mut(cue.text.as_str())?.pop()?;
+
+			Some(cue.start - start)
+		})
+		.collect();
+
+	differences.sort_unstable();
+
+	differences.get(differences.len() / 2).copied()
+}
+
+/// Parse the cues of a SubRip or WebVTT file.
+pub fn cues<P: AsRef<Path>>(path: P) -> Result<Vec<Cue>, FingerprintError> {
+	let contents = String::from_utf8_lossy(&std::fs::read(&path)?).replace("\r\n", "\n");
+	let cues: Vec<Cue> = contents
+		.trim_start_matches('\u{feff}')
+		.split("\n\n")
+		.filter_map(|block| {
+			let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+			let (start, end) = lines.next()?.split_once("-->")?;
+			let text = lines
+				.map(strip_markup)
+				.collect::<Vec<_>>()
+				.join(" ")
+				.split_whitespace()
+				.collect::<Vec<_>>()
+				.join(" ");
+
+			Some(Cue {
+				start: timestamp(start)?,
+				// WebVTT cue settings follow the end time.
+				end: timestamp(end.split_whitespace().next()?)?,
+				text,
+			})
+		})
+		.filter(|cue| !cue.text.is_empty())
+		.collect();
+
+	match cues.is_empty() {
+		true => Err(FingerprintError::from(SubtitleError::NoCues)),
+		false => Ok(cues),
+	}
+}
+
+/// Parse a `hh:mm:ss,mmm` (SubRip) or `[hh:]mm:ss.mmm` (WebVTT) timestamp to milliseconds.
+fn timestamp(text: &str) -> Option<i64> {
+	let (clock, millis) = text.trim().split_once([',', '.'])?;
+	let mut fields = clock.split(':').rev().map(|field| field.parse::<i64>());
+	let seconds = fields.next()?.ok()?;
+	let minutes = fields.next()?.ok()?;
+	let hours = fields.next().transpose().ok()?.unwrap_or(0);
+
+	Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis.parse::<i64>().ok()?)
+}
+
+/// Remove HTML-like tags, `{...}` override blocks and common entities from a line of dialogue.
+fn strip_markup(line: &str) -> String {
+	let mut text = String::with_capacity(line.len());
+	let mut closing = None;
+
+	for c in line.chars() {
+		match (closing, c) {
+			(None, '<') => closing = Some('>'),
+			(None, '{') => closing = Some('}'),
+			(Some(end), c) if c == end => closing = None,
+			(None, c) => text.push(c),
+			_ => {}
+		}
+	}
+
+	text.replace("&nbsp;", " ")
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{path::Path, time::Duration};
+
+	use super::{compare, cues, SubtitleFingerprinter};
+	use crate::{options::Options, Fingerprint};
+
+	#[test]
+	fn test_subtitle_resynced() {
+		let comparison = compare(
+			"samples/subtitle/episode.srt",
+			"samples/subtitle/episode_resynced.vtt",
+		)
+		.unwrap();
+
+		assert!(comparison.similarity >= 0.95, "{}", comparison.similarity);
+		assert_eq!(comparison.offset, Some(2500));
+
+		let original = Fingerprint::finger("samples/subtitle/episode.srt").unwrap();
+		let resynced = Fingerprint::finger("samples/subtitle/episode_resynced.vtt").unwrap();
+
+		assert_eq!(original.r#type(), crate::Type::Text);
+		assert!(original.compare(&resynced) >= 0.95);
+	}
+
+	#[test]
+	fn test_subtitle_cues() {
+		let parsed = cues("samples/subtitle/episode_resynced.vtt").unwrap();
+
+		assert_eq!(parsed[0].start, 3500);
+		assert_eq!(parsed[0].end, 6000);
+		assert_eq!(parsed[0].text, "Where were you last night?");
+		assert!(parsed.iter().all(|cue| !cue.text.contains('<')));
+
+		let quantised = |path: &str| {
+			SubtitleFingerprinter::new(path, Some(Duration::from_secs(10)))
+				.unwrap()
+				.finger()
+				.unwrap()
+		};
+
+		assert_ne!(
+			quantised("samples/subtitle/episode.srt"),
+			quantised("samples/subtitle/episode_resynced.vtt")
+		);
+		assert!(cues(Path::new("samples/ascii.txt")).is_err());
+	}
+
+	#[test]
+	fn test_subtitle_timing_tag() {
+		let path = "samples/subtitle/episode.srt";
+		let text = Fingerprint::finger_subtitle(path, None).unwrap();
+		let timed = Fingerprint::finger_subtitle(path, Some(Duration::from_secs(10))).unwrap();
+
+		assert_eq!(
+			text.options_tag(),
+			Fingerprint::finger(path).unwrap().options_tag()
+		);
+		assert_eq!(timed.options_tag(), Options::subtitle(10_000).tag());
+		assert!(timed.try_compare(&text).is_err());
+	}
+}
@@ -0,0 +1,39 @@
+#[cfg(any(feature = "archive", feature = "office"))]
+use std::{io::Write, path::Path};
+
+/// 5×7 bitmaps for the glyphs used by the tests, one byte per row with the leftmost pixel in bit 4.
+#[cfg(feature = "image")]
+pub(super) fn glyph(c: char) -> [u8; 7] {
+	match c {
+		'A' => [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+		'D' => [0x1e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1e],
+		'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+		'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+		'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+		'N' => [0x11, 0x19, 0x15, 0x13, 0x11, 0x11, 0x11],
+		'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+		'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+		'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+		'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+		'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+		_ => [0; 7],
+	}
+}
+
+/// Write a zip container of named files, compressed at `level`.
+#[cfg(any(feature = "archive", feature = "office"))]
+pub(super) fn write_zip<N: AsRef<str>, C: AsRef<[u8]>>(path: &Path, files: &[(N, C)], level: i64) {
+	use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+	let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+	let options = SimpleFileOptions::default()
+		.compression_method(CompressionMethod::Deflated)
+		.compression_level(Some(level));
+
+	for (name, contents) in files {
+		zip.start_file(name.as_ref(), options).unwrap();
+		zip.write_all(contents.as_ref()).unwrap();
+	}
+
+	zip.finish().unwrap();
+}
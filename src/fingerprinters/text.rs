@@ -0,0 +1,381 @@
+use std::path::{Path, PathBuf};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{options::FingerprintConfig, FingerprintError};
+
+use super::{FingerElement, FingerSegment, Fingerprinter};
+
+/// Fingerprinter for text files.
+///
+/// The file is decoded as UTF-8 (invalid sequences become U+FFFD), a leading byte order mark is dropped and CRLF line
+/// endings are normalised to LF, so the same text saved by different editors fingerprints alike. The characters are
+/// split into segments of about equal length like the raw fingerprinter, but each boundary is moved forward to the next
+/// word or line boundary within its segment. Segment values are the mean of the UTF-8 encoded bytes of their
+/// characters.
+#[derive(Debug)]
+pub struct TextFingerprinter {
+	path: PathBuf,
+	text: Vec<char>,
+	rng: ChaCha8Rng,
+	boundaries: Vec<usize>,
+}
+
+impl<'fp> Fingerprinter<'fp> for TextFingerprinter {
+	fn new<P: AsRef<Path>>(path: P) -> Result<TextFingerprinter, FingerprintError> {
+		Self::with_config(path, &FingerprintConfig::default())
+	}
+
+	fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+}
+
+impl TextFingerprinter {
+	/// Create new fingerprinter splitting the text into the segments of a configuration.
+	pub fn with_config<P: AsRef<Path>>(
+		path: P,
+		config: &FingerprintConfig,
+	) -> Result<Self, FingerprintError> {
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			..Self::from_text(normalise(&std::fs::read(&path)?), config)
+		})
+	}
+
+	/// Create new fingerprinter for text held in memory, with an empty path.
+	pub fn from_slice(data: &[u8]) -> Self {
+		Self::from_text(normalise(data), &FingerprintConfig::default())
+	}
+
+	/// Create new fingerprinter for text extracted from a file, such as the paragraphs of a document.
+	#[cfg(feature = "office")]
+	pub(super) fn from_extracted<P: AsRef<Path>>(path: P, text: &str) -> Self {
+		Self {
+			path: path.as_ref().to_path_buf(),
+			..Self::from_text(normalise(text.as_bytes()), &FingerprintConfig::default())
+		}
+	}
+
+	/// Create new fingerprinter for decoded text, with an empty path.
+	fn from_text(text: Vec<char>, config: &FingerprintConfig) -> Self {
+		Self {
+			path: PathBuf::new(),
+			boundaries: boundaries(&text, config.segments),
+			text,
+			rng: ChaCha8Rng::seed_from_u64(config.rng_seed),
+		}
+	}
+}
+
+/// Decode text, dropping a byte order mark and replacing CRLF line endings with LF.
+fn normalise(bytes: &[u8]) -> Vec<char> {
+	let text = String::from_utf8_lossy(bytes);
+	let mut chars = Vec::with_capacity(text.len());
+
+	for character in text.strip_prefix('\u{feff}').unwrap_or(&text).chars() {
+		if character == '\n' && chars.last() == Some(&'\r') {
+			chars.pop();
+		}
+
+		chars.push(character);
+	}
+
+	chars
+}
+
+/// Return the start of every one of `segments` segments and the end of the last, snapped forward to word or line
+/// boundaries.
+fn boundaries(text: &[char], segments: usize) -> Vec<usize> {
+	if segments == 0 {
+		return Vec::new();
+	}
+
+	let ideal = |index: usize| text.len() * index / segments;
+	let mut boundaries = vec![0];
+
+	for index in 1..segments {
+		let (start, limit) = (
+			ideal(index).max(*boundaries.last().unwrap_or(&0)),
+			ideal(index + 1),
+		);
+		let boundary = (start..limit)
+			.find(|pos| *pos > 0 && text[pos - 1].is_whitespace())
+			.unwrap_or(start);
+
+		boundaries.push(boundary);
+	}
+
+	boundaries.push(text.len());
+
+	boundaries
+}
+
+impl<'fp> IntoIterator for &'fp TextFingerprinter {
+	type Item = TextSegment<'fp>;
+	type IntoIter = TextSegmentIterator<'fp>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		Self::IntoIter {
+			fp: self,
+			index: 0,
+			rng: self.rng.clone(),
+		}
+	}
+}
+
+/// Structure for a text fingerprint segment.
+#[derive(Clone, Debug)]
+pub struct TextSegment<'fp> {
+	fp: &'fp TextFingerprinter,
+	index: usize,
+	pos: usize,
+	size: usize,
+	value: Option<u8>,
+}
+
+impl<'fp> FingerSegment<'fp> for TextSegment<'fp> {
+	type Fingerprinter = &'fp TextFingerprinter;
+	type Value = u8;
+
+	fn fingerprinter(&self) -> Self::Fingerprinter {
+		self.fp
+	}
+
+	fn index(&self) -> usize {
+		self.index
+	}
+
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	fn size(&self) -> usize {
+		self.size
+	}
+
+	fn value(&mut self) -> Result<Self::Value, FingerprintError> {
+		match self.value {
+			Some(value) => Ok(value),
+			None => {
+				let (total, bytes) =
+					self.into_iter()
+						.try_fold((0u128, 0u128), |(total, bytes), element| {
+							let mut buffer = [0u8; 4];
+							let encoded = element.data()?.encode_utf8(&mut buffer);
+
+							Ok::<_, FingerprintError>((
+								total + encoded.bytes().map(u128::from).sum::<u128>(),
+								bytes + encoded.len() as u128,
+							))
+						})?;
+
+				let value = (total / bytes) as u8;
+
+				self.value = Some(value);
+
+				Ok(value)
+			}
+		}
+	}
+}
+
+impl<'fp> IntoIterator for &'fp TextSegment<'fp> {
+	type Item = TextElement<'fp>;
+	type IntoIter = TextElementIterator<'fp>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		Self::IntoIter {
+			fp: self.fp,
+			segment: self,
+			index: 0,
+		}
+	}
+}
+
+/// Iterator for segments in a text fingerprint.
+#[derive(Clone, Debug)]
+pub struct TextSegmentIterator<'fp> {
+	fp: &'fp TextFingerprinter,
+	index: usize,
+	rng: ChaCha8Rng,
+}
+
+impl<'fp> Iterator for TextSegmentIterator<'fp> {
+	type Item = TextSegment<'fp>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index + 1 >= self.fp.boundaries.len() {
+			return None;
+		}
+
+		let index = self.index;
+		let pos = self.fp.boundaries[index];
+		let size = self.fp.boundaries[index + 1] - pos;
+
+		self.index += 1;
+
+		Some(TextSegment {
+			fp: self.fp,
+			index,
+			pos,
+			size,
+			value: match size {
+				0 => Some(self.rng.gen()),
+				_ => None,
+			},
+		})
+	}
+}
+
+/// Structure for a single character of text.
+#[derive(Clone, Debug)]
+pub struct TextElement<'fp> {
+	fp: &'fp TextFingerprinter,
+	segment: &'fp TextSegment<'fp>,
+	index: usize,
+	pos: usize,
+}
+
+impl<'fp> FingerElement for TextElement<'fp> {
+	type Fingerprinter = &'fp TextFingerprinter;
+	type Segment = &'fp TextSegment<'fp>;
+	type Data = char;
+
+	fn fingerprinter(&self) -> Self::Fingerprinter {
+		self.fp
+	}
+
+	fn segment(&self) -> Self::Segment {
+		self.segment
+	}
+
+	fn index(&self) -> usize {
+		self.index
+	}
+
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	fn size(&self) -> usize {
+		self.fp.text[self.pos].len_utf8()
+	}
+
+	fn data(&self) -> Result<Self::Data, FingerprintError> {
+		Ok(self.fp.text[self.pos])
+	}
+}
+
+/// Iterator for elements in a text fingerprint segment.
+#[derive(Clone, Debug)]
+pub struct TextElementIterator<'fp> {
+	fp: &'fp TextFingerprinter,
+	segment: &'fp TextSegment<'fp>,
+	index: usize,
+}
+
+impl<'fp> Iterator for TextElementIterator<'fp> {
+	type Item = TextElement<'fp>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index >= self.segment.size {
+			return None;
+		}
+
+		let index = self.index;
+
+		self.index += 1;
+
+		Some(TextElement {
+			fp: self.fp,
+			segment: self.segment,
+			index,
+			pos: self.segment.pos + index,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use bitvec::prelude::*;
+
+	use super::{boundaries, normalise, TextFingerprinter};
+	use crate::{fingerprinters::Fingerprinter, Fingerprint, Type};
+
+	/// Return the fraction of bits on which the text fingerprints of two files agree.
+	fn similarity(first: &str, second: &str) -> f64 {
+		let first = TextFingerprinter::new(first).unwrap().finger().unwrap();
+		let second: BitBox<u8> = TextFingerprinter::new(second).unwrap().finger().unwrap();
+
+		(first ^ second).count_zeros() as f64 / crate::NUM_FINGERPRINT_SEGMENTS as f64
+	}
+
+	#[test]
+	fn test_text_detected() {
+		let page = Fingerprint::finger("samples/text/page.html").unwrap();
+
+		assert_eq!(page.r#type(), Type::Text);
+		assert_eq!(page.to_string(), "db87cd9b8dd7309ec667e5765cd23459");
+		assert_eq!(
+			Fingerprint::finger("samples/text/script.sh")
+				.unwrap()
+				.to_string(),
+			"969b99bb6b52926492b959c4c8d29133"
+		);
+	}
+
+	#[test]
+	fn test_text_similar() {
+		let similar = similarity("samples/ascii.txt", "samples/ascii_similar.txt");
+		let different = similarity("samples/ascii.txt", "samples/ascii_different.txt");
+
+		assert_eq!(similarity("samples/ascii.txt", "samples/ascii.txt"), 1.0);
+		assert!(similar > different, "{} {}", similar, different);
+	}
+
+	#[test]
+	fn test_text_normalised() {
+		let dir = tempfile::tempdir().unwrap();
+		let (unix, windows) = (dir.path().join("unix.txt"), dir.path().join("windows.txt"));
+		let text = std::fs::read_to_string("samples/ascii.txt").unwrap();
+
+		std::fs::write(&unix, &text).unwrap();
+		std::fs::write(&windows, format!("\u{feff}{}", text.replace('\n', "\r\n"))).unwrap();
+
+		assert_eq!(
+			TextFingerprinter::new(&unix).unwrap().finger().unwrap(),
+			TextFingerprinter::new(&windows).unwrap().finger().unwrap()
+		);
+		assert_eq!(
+			TextFingerprinter::from_slice(text.as_bytes())
+				.finger()
+				.unwrap(),
+			TextFingerprinter::new(&unix).unwrap().finger().unwrap()
+		);
+		assert_eq!(
+			normalise("\u{feff}a\r\nb\rc".as_bytes()),
+			['a', '\n', 'b', '\r', 'c']
+		);
+	}
+
+	#[test]
+	fn test_text_boundaries() {
+		let unicode = normalise(&std::fs::read("samples/text/unicode.html").unwrap());
+		let segments = boundaries(&unicode, crate::NUM_FINGERPRINT_SEGMENTS);
+
+		assert_eq!(segments.len(), crate::NUM_FINGERPRINT_SEGMENTS + 1);
+		assert!(segments.windows(2).all(|pair| pair[0] <= pair[1]));
+
+		let empty = TextFingerprinter::new("samples/empty").unwrap();
+
+		assert!(boundaries(&[], crate::NUM_FINGERPRINT_SEGMENTS)
+			.iter()
+			.all(|pos| *pos == 0));
+		assert_eq!(
+			empty.finger().unwrap().len(),
+			crate::NUM_FINGERPRINT_SEGMENTS
+		);
+	}
+}
@@ -0,0 +1,135 @@
+use std::path::{Path, PathBuf};
+
+use bitvec::prelude::*;
+
+use crate::{FingerprintError, NUM_FINGERPRINT_SEGMENTS};
+
+/// Number of words in each shingle.
+const SHINGLE_WORDS: usize = 3;
+
+/// Fingerprinter for near-duplicate text detection with SimHash.
+///
+/// The text is lowercased and split into words, and every run of three consecutive words (shingle) is hashed with
+/// BLAKE3. Each fingerprint bit is the majority vote of that bit over all shingle hashes, so texts sharing most of their
+/// shingles share most of their bits.
+#[derive(Debug)]
+pub struct SimHashTextFingerprinter {
+	path: PathBuf,
+	text: String,
+}
+
+impl SimHashTextFingerprinter {
+	/// Create new fingerprinter, reading the whole file as UTF-8 (invalid sequences are replaced).
+	pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Ok(Self {
+			path: path.as_ref().to_path_buf(),
+			text: String::from_utf8_lossy(&std::fs::read(&path)?).into_owned(),
+		})
+	}
+
+	/// Create new fingerprinter for text already read, such as text extracted from a document.
+	pub fn from_text<P: AsRef<Path>>(path: P, text: String) -> Self {
+		Self {
+			path: path.as_ref().to_path_buf(),
+			text,
+		}
+	}
+
+	/// Return path of file being fingerprinted.
+	pub fn path(&self) -> PathBuf {
+		self.path.clone()
+	}
+
+	/// Generate the fingerprint from the votes of the shingle hashes.
+	pub fn finger(&self) -> Result<BitBox<u8>, FingerprintError> {
+		let _span = tracing::debug_span!("hash", path = %self.path.display()).entered();
+		let text = self.text.to_lowercase();
+		let words: Vec<&str> = text.split_whitespace().collect();
+		let mut votes = [0i64; NUM_FINGERPRINT_SEGMENTS];
+
+		// Texts shorter than a shingle are hashed as a single shingle.
+		for shingle in words.windows(SHINGLE_WORDS.min(words.len().max(1))) {
+			let hash = blake3::hash(shingle.join(" ").as_bytes());
+			let bits = hash.as_bytes().view_bits::<Lsb0>();
+
+			for (vote, bit) in votes.iter_mut().zip(bits) {
+				*vote += if *bit { 1 } else { -1 };
+			}
+		}
+
+		let mut fingerprint = bitbox![u8, Lsb0; 0; NUM_FINGERPRINT_SEGMENTS];
+
+		for (index, vote) in votes.iter().enumerate() {
+			fingerprint.set(index, *vote > 0);
+		}
+
+		tracing::trace!(words = words.len(), "hashed shingles");
+
+		Ok(fingerprint)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::{seq::SliceRandom, Rng, SeedableRng};
+
+	use crate::Fingerprint;
+
+	/// Vocabulary the test texts are drawn from.
+	const WORDS: [&str; 24] = [
+		"the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog", "river", "stone", "bright",
+		"cold", "window", "paper", "garden", "slowly", "under", "green", "market", "engine",
+		"silver", "quiet", "north", "letter",
+	];
+
+	/// Return a text of random words, with `changed` of every 100 words replaced.
+	fn text(seed: u64, changed: usize) -> String {
+		let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+		let mut words: Vec<&str> = (0..400).map(|_| *WORDS.choose(&mut rng).unwrap()).collect();
+		let mut edits = rand_chacha::ChaCha8Rng::seed_from_u64(seed + 1);
+
+		for chunk in words.chunks_mut(100) {
+			for _ in 0..changed {
+				chunk[edits.gen_range(0..100)] = "changed";
+			}
+		}
+
+		words.join(" ")
+	}
+
+	#[test]
+	fn test_simhash_near_duplicate() {
+		let dir = tempfile::tempdir().unwrap();
+		let paths: Vec<_> = ["original", "edited", "other"]
+			.iter()
+			.map(|name| dir.path().join(name))
+			.collect();
+
+		std::fs::write(&paths[0], text(0, 0)).unwrap();
+		// Replacing 5% of the words changes about 15% of the shingles, leaving the texts 85% similar.
+		std::fs::write(&paths[1], text(0, 5)).unwrap();
+		std::fs::write(&paths[2], text(9, 0)).unwrap();
+
+		let [original, edited, other] =
+			[0, 1, 2].map(|index| Fingerprint::finger_text_simhash(&paths[index]).unwrap());
+
+		assert!(original.compare(&edited) >= 0.8);
+		assert!(original.compare(&other) < 0.7);
+	}
+
+	#[test]
+	fn test_simhash_case_and_spacing() {
+		let dir = tempfile::tempdir().unwrap();
+		let a = dir.path().join("a");
+		let b = dir.path().join("b");
+
+		std::fs::write(&a, "The quick  brown fox\njumps").unwrap();
+		std::fs::write(&b, "the QUICK brown fox jumps").unwrap();
+
+		assert_eq!(
+			Fingerprint::finger_text_simhash(&a).unwrap().bytes(),
+			Fingerprint::finger_text_simhash(&b).unwrap().bytes()
+		);
+		assert!(Fingerprint::finger_text_simhash("samples/empty").is_ok());
+	}
+}
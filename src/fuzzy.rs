@@ -0,0 +1,426 @@
+use std::{
+	fs::File,
+	io::{self, Read},
+	path::Path,
+};
+
+use crate::FingerprintError;
+
+/// Fewest bytes a TLSH digest is computed from.
+pub const TLSH_MIN_LENGTH: usize = 50;
+
+/// Number of buckets counted, which each contribute two bits to the digest body.
+const BUCKETS: usize = 128;
+
+/// Size (bytes) of the digest body.
+const CODE_SIZE: usize = BUCKETS / 4;
+
+/// Version prefix of digests, following TLSH 4.
+const VERSION_PREFIX: &str = "T1";
+
+/// Pearson hash permutation published with TLSH.
+const V_TABLE: [u8; 256] = [
+	1, 87, 49, 12, 176, 178, 102, 166, 121, 193, 6, 84, 249, 230, 44, 163, 14, 197, 213, 181, 161,
+	85, 218, 80, 64, 239, 24, 226, 236, 142, 38, 200, 110, 177, 104, 103, 141, 253, 255, 50, 77,
+	101, 81, 18, 45, 96, 31, 222, 25, 107, 190, 70, 86, 237, 240, 34, 72, 242, 20, 214, 244, 227,
+	149, 235, 97, 234, 57, 22, 60, 250, 82, 175, 208, 5, 127, 199, 111, 62, 135, 248, 174, 169,
+	211, 58, 66, 154, 106, 195, 245, 171, 17, 187, 182, 179, 0, 243, 132, 56, 148, 75, 128, 133,
+	158, 100, 130, 126, 91, 13, 153, 246, 216, 219, 119, 68, 223, 78, 83, 88, 201, 99, 122, 11, 92,
+	32, 136, 114, 52, 10, 138, 30, 48, 183, 156, 35, 61, 26, 143, 74, 251, 94, 129, 162, 63, 152,
+	170, 7, 115, 167, 241, 206, 3, 150, 55, 59, 151, 220, 90, 53, 23, 131, 125, 173, 15, 238, 79,
+	95, 89, 16, 105, 137, 225, 224, 217, 160, 37, 123, 118, 73, 2, 157, 46, 116, 9, 145, 134, 228,
+	207, 212, 202, 215, 69, 229, 27, 188, 67, 124, 168, 252, 42, 4, 29, 108, 21, 247, 19, 205, 39,
+	203, 233, 40, 186, 147, 198, 192, 155, 33, 164, 191, 98, 204, 165, 180, 117, 76, 140, 36, 210,
+	172, 41, 54, 159, 8, 185, 232, 113, 196, 231, 47, 146, 120, 51, 65, 28, 144, 254, 221, 93, 189,
+	194, 139, 112, 43, 71, 109, 184, 209,
+];
+
+/// Largest input length (bytes) of each length byte of a digest, as tabulated by the reference implementation.
+const LENGTH_TOP_VALUES: [u32; 170] = [
+	1, 2, 3, 5, 7, 11, 17, 25, 38, 57, 86, 129, 194, 291, 437, 656, 854, 1110, 1443, 1876, 2439,
+	3171, 3475, 3823, 4205, 4626, 5088, 5597, 6157, 6772, 7450, 8195, 9014, 9916, 10907, 11998,
+	13198, 14518, 15970, 17567, 19323, 21256, 23382, 25720, 28292, 31121, 34233, 37656, 41422,
+	45564, 50121, 55133, 60646, 66711, 73382, 80721, 88793, 97672, 107439, 118183, 130002, 143002,
+	157302, 173032, 190335, 209369, 230306, 253337, 278670, 306538, 337191, 370911, 408002, 448802,
+	493682, 543050, 597356, 657091, 722800, 795081, 874589, 962048, 1058252, 1164078, 1280486,
+	1408534, 1549388, 1704327, 1874759, 2062236, 2268459, 2495305, 2744836, 3019320, 3321252,
+	3653374, 4018711, 4420582, 4862641, 5348905, 5883796, 6472176, 7119394, 7831333, 8614467,
+	9475909, 10423501, 11465851, 12612437, 13873681, 15261050, 16787154, 18465870, 20312458,
+	22343706, 24578077, 27035886, 29739474, 32713425, 35984770, 39583245, 43541573, 47895730,
+	52685306, 57953837, 63749221, 70124148, 77136564, 84850228, 93335252, 102668779, 112935659,
+	124229227, 136652151, 150317384, 165349128, 181884040, 200072456, 220079703, 242087671,
+	266296456, 292926096, 322218735, 354440623, 389884688, 428873168, 471760495, 518936559,
+	570830240, 627913311, 690704607, 759775136, 835752671, 919327967, 1011260767, 1112386880,
+	1223623232, 1345985727, 1480584256, 1628642751, 1791507135, 1970657856, 2167723648, 2384496256,
+	2622945920, 2885240448, 3173764736, 3491141248, 3840255616, 4224281216,
+];
+
+/// Errors produced while computing or parsing fuzzy hashes.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FuzzyError {
+	/// Input is shorter than [TLSH_MIN_LENGTH].
+	#[error("{0} bytes is too short for a TLSH digest, which needs at least {TLSH_MIN_LENGTH}")]
+	TooShort(u64),
+
+	/// Input is too uniform for its bucket counts to carry information.
+	#[error("input is too uniform for a TLSH digest")]
+	TooUniform,
+
+	/// A digest string is not a TLSH digest.
+	#[error("invalid TLSH digest: {0:?}")]
+	InvalidDigest(String),
+}
+
+/// Incremental TLSH digest of a byte stream.
+///
+/// Digests are computed as by the reference implementation in its default configuration (128 buckets, 1-byte
+/// checksum), so they can be compared with digests from other TLSH tools.
+#[derive(Debug, Clone)]
+pub struct Tlsh {
+	window: [u8; 5],
+	length: u64,
+	checksum: u8,
+	buckets: [u32; 256],
+}
+
+impl Default for Tlsh {
+	fn default() -> Self {
+		Self {
+			window: [0; 5],
+			length: 0,
+			checksum: 0,
+			buckets: [0; 256],
+		}
+	}
+}
+
+impl Tlsh {
+	/// Create an empty digest.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add bytes to the digest.
+	pub fn update(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.window.copy_within(0..4, 1);
+			self.window[0] = *byte;
+			self.length += 1;
+
+			if self.length < 5 {
+				continue;
+			}
+
+			let [c0, c1, c2, c3, c4] = self.window;
+
+			self.checksum = pearson(0, c0, c1, self.checksum);
+
+			for (salt, a, b) in [
+				(2, c1, c2),
+				(3, c1, c3),
+				(5, c2, c3),
+				(7, c2, c4),
+				(11, c1, c4),
+				(13, c3, c4),
+			] {
+				self.buckets[pearson(salt, c0, a, b) as usize] += 1;
+			}
+		}
+	}
+
+	/// Return the digest as its standard hexadecimal string, prefixed with `T1`.
+	pub fn finish(&self) -> Result<String, FuzzyError> {
+		if self.length < TLSH_MIN_LENGTH as u64 {
+			return Err(FuzzyError::TooShort(self.length));
+		}
+
+		let buckets = &self.buckets[..BUCKETS];
+		let mut sorted = buckets.to_vec();
+
+		sorted.sort_unstable();
+
+		let [q1, q2, q3] = [sorted[31], sorted[63], sorted[95]];
+
+		if q3 == 0 || buckets.iter().filter(|count| **count > 0).count() <= BUCKETS / 2 {
+			return Err(FuzzyError::TooUniform);
+		}
+
+		let mut code = [0u8; CODE_SIZE];
+
+		for (index, byte) in code.iter_mut().enumerate() {
+			for (dibit, count) in buckets[index * 4..index * 4 + 4].iter().enumerate() {
+				let quartile = match *count {
+					count if count > q3 => 3,
+					count if count > q2 => 2,
+					count if count > q1 => 1,
+					_ => 0,
+				};
+
+				*byte |= quartile << (dibit * 2);
+			}
+		}
+
+		let digest = Digest {
+			checksum: self.checksum,
+			length: length_capture(self.length),
+			q1_ratio: quartile_ratio(q1, q3),
+			q2_ratio: quartile_ratio(q2, q3),
+			code,
+		};
+
+		Ok(digest.to_string())
+	}
+}
+
+/// Parsed TLSH digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Digest {
+	checksum: u8,
+	length: u8,
+	q1_ratio: u8,
+	q2_ratio: u8,
+	code: [u8; CODE_SIZE],
+}
+
+impl Digest {
+	/// Parse a digest string, with or without its `T1` prefix.
+	fn parse(digest: &str) -> Result<Self, FuzzyError> {
+		let invalid = || FuzzyError::InvalidDigest(digest.to_string());
+		let hex = digest.strip_prefix(VERSION_PREFIX).unwrap_or(digest);
+
+		if hex.len() != 6 + CODE_SIZE * 2 || !hex.is_ascii() {
+			return Err(invalid());
+		}
+
+		let bytes = (0..hex.len() / 2)
+			.map(|index| u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16))
+			.collect::<Result<Vec<u8>, _>>()
+			.map_err(|_| invalid())?;
+		let mut code = [0u8; CODE_SIZE];
+
+		for (index, byte) in bytes[3..].iter().rev().enumerate() {
+			code[index] = *byte;
+		}
+
+		Ok(Self {
+			checksum: bytes[0].rotate_left(4),
+			length: bytes[1].rotate_left(4),
+			q1_ratio: bytes[2] >> 4,
+			q2_ratio: bytes[2] & 0xf,
+			code,
+		})
+	}
+
+	/// Return the TLSH distance to another digest, including the length difference.
+	fn distance(&self, other: &Digest) -> u32 {
+		let mut distance = match circular_difference(self.length, other.length, 256) {
+			difference @ (0 | 1) => difference,
+			difference => difference * 12,
+		};
+
+		for (a, b) in [
+			(self.q1_ratio, other.q1_ratio),
+			(self.q2_ratio, other.q2_ratio),
+		] {
+			distance += match circular_difference(a, b, 16) {
+				difference @ (0 | 1) => difference,
+				difference => (difference - 1) * 12,
+			};
+		}
+
+		distance += (self.checksum != other.checksum) as u32;
+
+		for (a, b) in self.code.iter().zip(&other.code) {
+			for dibit in 0..4 {
+				distance += match ((a >> (dibit * 2)) & 3).abs_diff((b >> (dibit * 2)) & 3) {
+					3 => 6,
+					difference => difference as u32,
+				};
+			}
+		}
+
+		distance
+	}
+}
+
+impl std::fmt::Display for Digest {
+	/// Formats the digest in the TLSH layout: nibble-swapped header bytes, then the body reversed.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}{:02X}{:02X}{:X}{:X}",
+			VERSION_PREFIX,
+			self.checksum.rotate_left(4),
+			self.length.rotate_left(4),
+			self.q1_ratio,
+			self.q2_ratio
+		)?;
+
+		self.code
+			.iter()
+			.rev()
+			.try_for_each(|byte| write!(f, "{:02X}", byte))
+	}
+}
+
+/// Pearson hash of a salt and three bytes.
+fn pearson(salt: u8, a: u8, b: u8, c: u8) -> u8 {
+	[a, b, c]
+		.into_iter()
+		.fold(V_TABLE[salt as usize], |hash, byte| {
+			V_TABLE[(hash ^ byte) as usize]
+		})
+}
+
+/// Quantise the input length logarithmically into a byte, following the TLSH length buckets.
+fn length_capture(length: u64) -> u8 {
+	LENGTH_TOP_VALUES.partition_point(|top| (*top as u64) < length) as u8
+}
+
+/// Return the ratio of a quartile to the third quartile as a percentage, modulo 16.
+///
+/// The reference implementation divides in single precision, which this matches for large bucket counts.
+fn quartile_ratio(quartile: u32, q3: u32) -> u8 {
+	((quartile.wrapping_mul(100) as f32 / q3 as f32) as u32 % 16) as u8
+}
+
+/// Return the distance between two values on a circle of `range` values.
+fn circular_difference(a: u8, b: u8, range: u32) -> u32 {
+	let difference = (a as u32).abs_diff(b as u32);
+
+	difference.min(range - difference)
+}
+
+/// Compute the TLSH digest of the bytes of a file, as a `T1`-prefixed hexadecimal string.
+pub fn tlsh<P: AsRef<Path>>(path: P) -> Result<String, FingerprintError> {
+	let mut file = File::open(path)?;
+	let mut buffer = vec![0u8; 64 * 1024];
+	let mut digest = Tlsh::new();
+
+	loop {
+		match file.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(read) => digest.update(&buffer[..read]),
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+			Err(e) => return Err(e.into()),
+		}
+	}
+
+	Ok(digest.finish()?)
+}
+
+/// Compute the TLSH digest of bytes, as a `T1`-prefixed hexadecimal string.
+pub fn tlsh_bytes(bytes: &[u8]) -> Result<String, FuzzyError> {
+	let mut digest = Tlsh::new();
+
+	digest.update(bytes);
+	digest.finish()
+}
+
+/// Return the TLSH distance between two digests, 0 for identical inputs and growing as they differ.
+///
+/// Scores are the reference implementation's distance with the length difference included, as printed by `tlsh -c`;
+/// digests may omit the `T1` prefix.
+pub fn distance(a: &str, b: &str) -> Result<u32, FingerprintError> {
+	Ok(Digest::parse(a)?.distance(&Digest::parse(b)?))
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::{Rng, SeedableRng};
+	use rand_chacha::ChaCha8Rng;
+
+	use super::{
+		distance, length_capture, tlsh, tlsh_bytes, Digest, FuzzyError, LENGTH_TOP_VALUES, V_TABLE,
+	};
+
+	#[test]
+	fn test_tlsh() {
+		let mut values = V_TABLE.to_vec();
+
+		values.sort_unstable();
+		assert!(values.iter().enumerate().all(|(i, v)| i == *v as usize));
+
+		let digest = tlsh("samples/ascii.txt").unwrap();
+
+		assert_eq!(digest.len(), 72);
+		assert!(digest.starts_with("T1"));
+		assert_eq!(
+			digest,
+			tlsh_bytes(&std::fs::read("samples/ascii.txt").unwrap()).unwrap()
+		);
+		assert_eq!(Digest::parse(&digest).unwrap().to_string(), digest);
+		assert_eq!(distance(&digest, &digest[2..]).unwrap(), 0);
+		assert_eq!(tlsh_bytes(&[1; 49]), Err(FuzzyError::TooShort(49)));
+		assert_eq!(tlsh_bytes(&[0; 4096]), Err(FuzzyError::TooUniform));
+		assert!(distance(&digest, "T1XYZ").is_err());
+	}
+
+	#[test]
+	fn test_length_capture() {
+		assert!(LENGTH_TOP_VALUES.windows(2).all(|pair| pair[0] < pair[1]));
+
+		for (length, capture) in [
+			(50, 9),
+			(656, 15),
+			(657, 16),
+			(3171, 21),
+			(3172, 22),
+			(190_335, 64),
+			(190_336, 65),
+			(4_224_281_216, 169),
+		] {
+			assert_eq!(length_capture(length), capture, "{}", length);
+		}
+	}
+
+	#[test]
+	#[ignore = "needs digests from the reference implementation, written by samples/tlsh/generate.py"]
+	fn test_reference() {
+		let digests = std::fs::read_to_string("samples/tlsh/digests.tsv").unwrap();
+		let distances = std::fs::read_to_string("samples/tlsh/distances.tsv").unwrap();
+
+		for line in digests.lines() {
+			let (path, expected) = line.split_once('\t').unwrap();
+
+			assert_eq!(tlsh(path).unwrap(), expected, "{}", path);
+		}
+
+		for line in distances.lines() {
+			let fields: Vec<&str> = line.split('\t').collect();
+			let [a, b, expected] = fields[..] else {
+				panic!("invalid line {:?}", line);
+			};
+
+			assert_eq!(
+				distance(&tlsh(a).unwrap(), &tlsh(b).unwrap()).unwrap(),
+				expected.parse::<u32>().unwrap(),
+				"{} {}",
+				a,
+				b
+			);
+		}
+	}
+
+	#[test]
+	fn test_distance() {
+		let mut rng = ChaCha8Rng::seed_from_u64(1);
+		let original: Vec<u8> = (0..8192).map(|_| rng.gen_range(b'a'..=b'z')).collect();
+		let mut edited = original.clone();
+
+		for _ in 0..40 {
+			let index = rng.gen_range(0..edited.len());
+
+			edited[index] = b' ';
+		}
+
+		let other: Vec<u8> = (0..8192).map(|_| rng.gen_range(b'a'..=b'z')).collect();
+		let [original, edited, other] =
+			[original, edited, other].map(|bytes| tlsh_bytes(&bytes).unwrap());
+		let near = distance(&original, &edited).unwrap();
+
+		assert_eq!(distance(&original, &original).unwrap(), 0);
+		assert_eq!(near, distance(&edited, &original).unwrap());
+		assert!(near < distance(&original, &other).unwrap());
+	}
+}
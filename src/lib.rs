@@ -1,26 +1,139 @@
 //! Rust file fingerprinting library, supporting many types of audio/video/image/text file formats.
+//!
+//! # Thread safety
+//!
+//! Fingerprints, their options and reports, every error type, and the fingerprinters are [Send] and [Sync], so
+//! fingerprints can be computed on worker threads and shared between them. A
+//! [SqliteStore](store::SqliteStore) is [Send] but deliberately not [Sync], as its connection is not safe to use from
+//! several threads at once; move it to the thread that uses it or share it behind a [Mutex](std::sync::Mutex). A
+//! [RedbStore](store::RedbStore) is both [Send] and [Sync].
 
 #![deny(missing_docs)]
 #![allow(clippy::tabs_in_doc_comments)]
 
 use std::{
-	error,
 	fmt::Display,
+	fs,
 	path::{Path, PathBuf},
+	str::FromStr,
+	time::UNIX_EPOCH,
 };
 
 use bitvec::prelude::*;
 
-use fingerprinters::{raw::RawFingerprinter, Fingerprinter};
+use error::FingerprintContext;
+pub use error::FingerprintError;
+use fingerprinters::{raw::RawFingerprinter, text::TextFingerprinter, Fingerprinter};
+use options::{FingerprintConfig, Options, OptionsTag};
 
 /// Dedicated fingerprinters for various file types.
 pub mod fingerprinters;
 
+/// Compact versioned binary wire format for fingerprints.
+pub mod wire;
+
+/// Canonical single-line text representation of fingerprints.
+pub mod uri;
+
+/// Parameters affecting fingerprint bits, and the tags identifying them.
+pub mod options;
+
+/// Protobuf messages for exchanging fingerprints with other languages.
+#[cfg(feature = "proto")]
+pub mod proto;
+
+/// Chromaprint fingerprints, as produced by `fpcalc` and stored by AcoustID.
+pub mod chromaprint;
+
+/// TLSH fuzzy hashes of file bytes.
+pub mod fuzzy;
+
+/// MinHash sketches for fast approximate comparison.
+pub mod minhash;
+
+/// Length-prefixed streams of fingerprint records.
+pub mod records;
+
+/// Persistent storage for fingerprints.
+pub mod store;
+
+/// Portable archives of a whole fingerprint store.
+pub mod archive;
+
+/// Sidecar fingerprint files written next to media files.
+pub mod sidecar;
+
+/// Walking directories for files to fingerprint, and the portable byte encoding of paths in storage formats.
+pub mod paths;
+
+/// Fingerprints stored in extended attributes of the fingerprinted files.
+#[cfg(all(unix, feature = "xattr"))]
+pub mod xattr_store;
+
+/// Checks for the external components fingerprinting depends on.
+pub mod diagnostics;
+
+/// Thread pool, temporary directory and child process limits used by fingerprinting.
+pub mod runtime;
+
+/// Errors produced while fingerprinting files.
+mod error;
+
+/// Markdown reports of fingerprints.
+mod report;
+
+/// Serde support for fingerprints.
+#[cfg(feature = "serde")]
+mod serialization;
+
 /// Number of bits (segments) in fingerprint.
 const NUM_FINGERPRINT_SEGMENTS: usize = 128;
 
+/// Entropy below which a fingerprint is considered too uninformative for reliable comparison.
+const LOW_ENTROPY: f64 = 0.5;
+
+/// Version of the fingerprinting algorithms, bumped whenever fingerprint bits change.
+const ALGORITHM_VERSION: u16 = 1;
+
+/// Expected [Fingerprint::compare] scores `(type_a, type_b, min, max)` for fingerprints of identical content, for
+/// every pair of types.
+///
+/// Fingerprints of the same type agree exactly. Fingerprints of different types come from unrelated algorithms, so
+/// their bits agree only by chance and the score says nothing about whether the content matches. The exception is raw
+/// and text fingerprints, which both compare the averages of neighbouring segments, differing only in where segment
+/// boundaries fall and how non-UTF-8 bytes decode, so they agree more often than chance.
+///
+/// No video fingerprinter exists, so no fingerprint of the [Type::Video] rows can be produced; they give the scores
+/// expected of unrelated algorithms, for fingerprints decoded with that type.
+pub const CROSS_TYPE_EXPECTED_SCORES: &[(Type, Type, f64, f64)] = &[
+	(Type::Raw, Type::Raw, 1.0, 1.0),
+	(Type::Raw, Type::Text, 0.5, 1.0),
+	(Type::Raw, Type::Image, 0.3, 0.7),
+	(Type::Raw, Type::Audio, 0.3, 0.7),
+	(Type::Raw, Type::Mesh, 0.3, 0.7),
+	(Type::Text, Type::Text, 1.0, 1.0),
+	(Type::Text, Type::Image, 0.3, 0.7),
+	(Type::Text, Type::Audio, 0.3, 0.7),
+	(Type::Text, Type::Mesh, 0.3, 0.7),
+	(Type::Image, Type::Image, 1.0, 1.0),
+	(Type::Image, Type::Audio, 0.3, 0.7),
+	(Type::Image, Type::Mesh, 0.3, 0.7),
+	(Type::Audio, Type::Audio, 1.0, 1.0),
+	(Type::Audio, Type::Mesh, 0.3, 0.7),
+	(Type::Mesh, Type::Mesh, 1.0, 1.0),
+	// No video fingerprinter exists: videos are fingerprinted raw, so these pairs are never produced.
+	(Type::Raw, Type::Video, 0.3, 0.7),
+	(Type::Text, Type::Video, 0.3, 0.7),
+	(Type::Image, Type::Video, 0.3, 0.7),
+	(Type::Audio, Type::Video, 0.3, 0.7),
+	(Type::Mesh, Type::Video, 0.3, 0.7),
+	(Type::Video, Type::Video, 1.0, 1.0),
+];
+
 /// File types with dedicated fingerprinters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Type {
 	/// Raw fingerprinter.
 	Raw,
@@ -34,12 +147,363 @@ pub enum Type {
 	/// Audio fingerprinter.
 	Audio,
 
-	/// Video fingerprinter.
+	/// Video. No video fingerprinter exists, so videos are fingerprinted raw, and this type only names videos in errors
+	/// and decoded fingerprints.
 	Video,
+
+	/// Mesh shape fingerprinter.
+	Mesh,
+}
+
+impl Display for Type {
+	/// Formats the type as its lowercase name.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Self::Raw => "raw",
+			Self::Text => "text",
+			Self::Image => "image",
+			Self::Audio => "audio",
+			Self::Video => "video",
+			Self::Mesh => "mesh",
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+/// Error produced when parsing an unknown [Type] name.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown fingerprint type {0:?}")]
+pub struct ParseTypeError(pub String);
+
+impl FromStr for Type {
+	type Err = ParseTypeError;
+
+	/// Parses a lowercase type name, as formatted by [Display].
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"raw" => Ok(Self::Raw),
+			"text" => Ok(Self::Text),
+			"image" => Ok(Self::Image),
+			"audio" => Ok(Self::Audio),
+			"video" => Ok(Self::Video),
+			"mesh" => Ok(Self::Mesh),
+			_ => Err(ParseTypeError(s.to_string())),
+		}
+	}
+}
+
+/// Generate the raw fingerprint bits of a file and their options, [sampling](fingerprinters::sampled) files larger than
+/// the [sampling threshold](runtime::Config::sampling_threshold) of the global configuration.
+fn finger_raw(path: &Path) -> Result<(BitBox<u8>, Options), FingerprintError> {
+	let config = runtime::Config::global();
+
+	if let Some(threshold) = config.sampling_threshold {
+		if fs::metadata(path).at_stage(Stage::Open, path)?.len() > threshold {
+			let sampled = fingerprinters::sampled::fingerprint(path, config.sampling_windows)
+				.at_stage(Stage::Hash, path)?;
+
+			return Ok((
+				sampled.summary.fingerprint,
+				Options::sampled(config.sampling_windows),
+			));
+		}
+	}
+
+	let fingerprint = RawFingerprinter::new(path)
+		.at_stage(Stage::Open, path)?
+		.finger()
+		.at_stage(Stage::Hash, path)?;
+
+	Ok((fingerprint, Options::raw()))
+}
+
+/// Fail with [FingerprintError::EmptyFile] at [Stage::Open] if a file to decode as its format has no bytes.
+#[cfg_attr(
+	not(any(
+		feature = "text",
+		feature = "mesh",
+		feature = "image",
+		feature = "audio"
+	)),
+	allow(dead_code)
+)]
+fn require_content(path: &Path) -> Result<(), FingerprintError> {
+	match fs::metadata(path).at_stage(Stage::Open, path)?.len() {
+		0 => Err(FingerprintError::EmptyFile).at_stage(Stage::Open, path),
+		_ => Ok(()),
+	}
+}
+
+/// Generate fingerprint bits with a format fingerprinter created by `new`, wrapping failures to create it in
+/// [Stage::Decode] and to hash in [Stage::Hash].
+#[cfg(any(feature = "text", feature = "mesh"))]
+fn decode_and_hash<F>(
+	path: &Path,
+	new: Result<F, FingerprintError>,
+	finger: impl FnOnce(&F) -> Result<BitBox<u8>, FingerprintError>,
+) -> Result<BitBox<u8>, FingerprintError> {
+	let fingerprinter = new.at_stage(Stage::Decode, path)?;
+
+	finger(&fingerprinter).at_stage(Stage::Hash, path)
+}
+
+/// Fingerprint of a file generated by the fingerprinter for its format, as returned by [finger_format].
+struct FormatFingerprint {
+	/// Fingerprint bits, or the failure to generate them.
+	bits: Result<BitBox<u8>, FingerprintError>,
+
+	/// Type of the fingerprint.
+	r#type: Type,
+
+	/// Options of the fingerprint.
+	options: Options,
+
+	/// Whether to fall back to a raw fingerprint when fingerprinting fails.
+	fallback: bool,
+}
+
+/// Generate the fingerprint of a file with the fingerprinter for its format, such as a PDF or mesh, if it has one.
+#[cfg_attr(not(any(feature = "text", feature = "mesh")), allow(unused_variables))]
+fn finger_format(path: &Path, kind: Option<&infer::Type>) -> Option<FormatFingerprint> {
+	#[cfg(feature = "text")]
+	if kind.is_none() && fingerprinters::subtitle::is_subtitle(path) {
+		use fingerprinters::subtitle::SubtitleFingerprinter;
+
+		return Some(FormatFingerprint {
+			bits: decode_and_hash(
+				path,
+				SubtitleFingerprinter::new(path, None),
+				SubtitleFingerprinter::finger,
+			),
+			r#type: Type::Text,
+			options: Options::text_simhash(),
+			fallback: true,
+		});
+	}
+
+	#[cfg(feature = "mesh")]
+	if kind.is_none() && fingerprinters::mesh::is_mesh(path) {
+		use fingerprinters::mesh::MeshFingerprinter;
+
+		return Some(FormatFingerprint {
+			bits: decode_and_hash(
+				path,
+				MeshFingerprinter::new(path),
+				MeshFingerprinter::finger,
+			),
+			r#type: Type::Mesh,
+			options: Options::mesh_d2(),
+			fallback: true,
+		});
+	}
+
+	#[cfg(feature = "pdf")]
+	if kind.is_some_and(|kind| kind.mime_type() == "application/pdf") {
+		use fingerprinters::pdf::PdfFingerprinter;
+
+		return Some(FormatFingerprint {
+			bits: decode_and_hash(path, PdfFingerprinter::new(path), PdfFingerprinter::finger),
+			r#type: Type::Text,
+			options: Options::text_simhash(),
+			fallback: true,
+		});
+	}
+
+	#[cfg(feature = "epub")]
+	if kind.is_some_and(|kind| kind.mime_type() == "application/epub+zip") {
+		use fingerprinters::epub::EpubFingerprinter;
+
+		return Some(FormatFingerprint {
+			bits: decode_and_hash(
+				path,
+				EpubFingerprinter::new(path, false),
+				EpubFingerprinter::finger,
+			),
+			r#type: Type::Text,
+			options: Options::text_simhash(),
+			fallback: false,
+		});
+	}
+
+	#[cfg(feature = "office")]
+	if kind.is_some_and(|kind| fingerprinters::office::is_office(kind.mime_type(), path)) {
+		use fingerprinters::office::OfficeFingerprinter;
+
+		return Some(FormatFingerprint {
+			bits: decode_and_hash(
+				path,
+				OfficeFingerprinter::new(path),
+				OfficeFingerprinter::finger,
+			),
+			r#type: Type::Text,
+			options: Options::text_simhash(),
+			fallback: true,
+		});
+	}
+
+	None
+}
+
+/// Return the ranks (starting at 1) of values, giving tied values the mean of their ranks.
+fn ranks(values: &[u8]) -> Vec<f64> {
+	let mut order: Vec<usize> = (0..values.len()).collect();
+	let mut ranks = vec![0f64; values.len()];
+	let mut start = 0;
+
+	order.sort_by_key(|index| values[*index]);
+
+	for tie in order.chunk_by(|a, b| values[*a] == values[*b]) {
+		let rank = start as f64 + (tie.len() + 1) as f64 / 2f64;
+
+		for index in tie {
+			ranks[*index] = rank;
+		}
+
+		start += tie.len();
+	}
+
+	ranks
+}
+
+/// Stage of processing a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+	/// Detecting the file type.
+	Detect,
+
+	/// Opening the file.
+	Open,
+
+	/// Decoding the file contents.
+	Decode,
+
+	/// Hashing the file contents into fingerprint bits.
+	Hash,
+
+	/// Comparing the file with another.
+	Compare,
+
+	/// Reading or writing a stored fingerprint of the file.
+	Persist,
+}
+
+impl Display for Stage {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Self::Detect => "detect",
+			Self::Open => "open",
+			Self::Decode => "decode",
+			Self::Hash => "hash",
+			Self::Compare => "compare",
+			Self::Persist => "persist",
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+/// Error returned by [Fingerprint::from_parts] and [Fingerprint::from_hex_with_metadata] for bytes that are not the
+/// length of a fingerprint: empty, or longer than one of [MAX_SEGMENTS](options::MAX_SEGMENTS) bits.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("expected 1 to {max} bytes of fingerprint, got {found}")]
+pub struct LengthError {
+	/// Largest number of bytes in a fingerprint.
+	pub max: usize,
+
+	/// Number of bytes given.
+	pub found: usize,
+}
+
+/// Errors produced when comparing fingerprints.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CompareError {
+	/// Fingerprints were produced with different [Options] and are not comparable.
+	#[error(
+		"fingerprints were produced with incompatible parameters ({left_tag} and {right_tag})"
+	)]
+	IncompatibleParameters {
+		/// Options tag of the left-hand fingerprint.
+		left_tag: OptionsTag,
+
+		/// Options tag of the right-hand fingerprint.
+		right_tag: OptionsTag,
+	},
+
+	/// Fingerprints were produced with different numbers of segments, such as with different
+	/// [FingerprintConfig::segments], and are not comparable.
+	#[error("fingerprints were produced with {left} and {right} segments")]
+	IncompatibleSegmentCount {
+		/// Segment count of the left-hand fingerprint.
+		left: usize,
+
+		/// Segment count of the right-hand fingerprint.
+		right: usize,
+	},
+
+	/// A fingerprint has no bits.
+	#[error("cannot compare an empty fingerprint")]
+	EmptyFingerprint,
+
+	/// Fingerprints have different numbers of bits.
+	#[error("cannot compare fingerprints of {left} and {right} bits")]
+	LengthMismatch {
+		/// Bit length of the left-hand fingerprint.
+		left: usize,
+
+		/// Bit length of the right-hand fingerprint.
+		right: usize,
+	},
+
+	/// Segment values of a file are all equal and cannot be ranked.
+	#[error("segment values are constant and cannot be ranked")]
+	ConstantValues,
+
+	/// Number of weights does not match the number of compared bits.
+	#[error("expected {expected} weights, got {found}")]
+	WeightCount {
+		/// Number of compared bits.
+		expected: usize,
+
+		/// Number of weights given.
+		found: usize,
+	},
+
+	/// A weight is negative or NaN.
+	#[error("weights must be non-negative, got {0}")]
+	InvalidWeight(f64),
+}
+
+/// How [Fingerprint::compare_with_strategy] compares fingerprints of different lengths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LengthStrategy {
+	/// Fingerprints of different lengths have a similarity of 0.0, as with [Fingerprint::compare].
+	#[default]
+	Exact,
+
+	/// The longer fingerprint is resampled to the length of the shorter, taking the bit nearest the centre of each
+	/// stretch of bits it covers.
+	Resample,
+
+	/// The shorter fingerprint is slid across the longer, and the best score of any window is returned.
+	SlidingWindow,
 }
 
-/// Generic [error::Error] type.
-type Error = Box<dyn error::Error>;
+/// Similarity of two fingerprints under each comparison metric, as returned by [Fingerprint::compare_report].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonReport {
+	/// Fraction of bits that agree, as returned by [Fingerprint::compare].
+	pub bit_agreement: f64,
+
+	/// Jaccard index of the set bits, as returned by [Fingerprint::compare_iou].
+	pub jaccard: f64,
+
+	/// Number of compared bits that differ.
+	pub hamming_distance: usize,
+
+	/// Whichever of [bit_agreement](Self::bit_agreement) and [jaccard](Self::jaccard) suits the fingerprint type.
+	pub recommended_score: f64,
+}
 
 /// High-level methods for producing deterministic fingerprints for files.
 #[derive(Debug, Clone)]
@@ -47,121 +511,2585 @@ pub struct Fingerprint {
 	path: PathBuf,
 	fingerprint: BitBox<u8>,
 	r#type: Type,
+	options_tag: OptionsTag,
+	original_version: Option<u8>,
 }
 
 impl Fingerprint {
 	/// Generate a deterministic fingerprint for a file at the given path.
-	pub fn finger<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
-		let (fingerprint, kind) = match infer::get_from_path(&path)? {
-			Some(kind) => match kind.matcher_type() {
-				infer::MatcherType::Text => {
-					todo!()
+	///
+	/// Images are fingerprinted with [Fingerprint::finger_image] when the `image` feature can decode their format, and
+	/// audio with [Fingerprint::finger_audio] when the `audio` feature can; use [Fingerprint::finger_strict] to fail
+	/// rather than fingerprint other images, audio and videos raw.
+	///
+	/// SubRip and WebVTT subtitles, recognised by extension, are fingerprinted by their dialogue with the `text` feature,
+	/// and STL and OBJ meshes by their shape as [Type::Mesh] with the `mesh` feature. PDFs are fingerprinted by their
+	/// text layer with the `pdf` feature, and DOCX, XLSX and OpenDocument files by their text with the `office`
+	/// feature. These fall back to raw when they cannot be fingerprinted by format. EPUBs are fingerprinted by their
+	/// chapter text with the `epub` feature, failing when corrupt or encrypted. Every other file is fingerprinted raw
+	/// with [Type::Raw], and [sampled](fingerprinters::sampled) as by [Fingerprint::finger_raw] when larger than the
+	/// sampling threshold.
+	///
+	/// Errors are [FingerprintError::Stage], carrying the path and the [Stage] that failed; match on
+	/// [FingerprintError::inner] for the underlying error.
+	pub fn finger<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Self::finger_typed(path, false)
+	}
+
+	/// Generate a deterministic fingerprint for a file like [Fingerprint::finger], but fail with
+	/// [FingerprintError::UnsupportedType] for images, audio and videos that cannot be fingerprinted by their type,
+	/// rather than fingerprinting them raw.
+	pub fn finger_strict<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Self::finger_typed(path, true)
+	}
+
+	/// Generate a fingerprint by detected type, falling back to raw for unsupported types unless `strict`.
+	fn finger_typed<P: AsRef<Path>>(path: P, strict: bool) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger", path = %path.as_ref().display()).entered();
+		let raw = || {
+			finger_raw(path.as_ref())
+				.map(|(fingerprint, options)| (fingerprint, Type::Raw, options))
+		};
+		let detected = infer::get_from_path(&path).at_stage(Stage::Detect, &path)?;
+
+		if let Some(format) = finger_format(path.as_ref(), detected.as_ref()) {
+			match format.bits {
+				Ok(fingerprint) => {
+					return Ok(Self {
+						path: path.as_ref().into(),
+						fingerprint,
+						r#type: format.r#type,
+						options_tag: format.options.tag(),
+						original_version: None,
+					})
 				}
-				infer::MatcherType::Image => {
-					todo!()
+				Err(e) if format.fallback => {
+					tracing::debug!(error = %e, "falling back to raw fingerprint");
+				}
+				Err(e) => return Err(e),
+			}
+		}
+
+		let (fingerprint, kind, options) = match detected {
+			Some(kind) => match kind.matcher_type() {
+				infer::MatcherType::Text => (
+					TextFingerprinter::new(&path)
+						.at_stage(Stage::Open, &path)?
+						.finger()
+						.at_stage(Stage::Hash, &path)?,
+					Type::Text,
+					Options::text(),
+				),
+				#[cfg(feature = "image")]
+				infer::MatcherType::Image
+					if fingerprinters::image::is_decodable(kind.mime_type()) =>
+				{
+					let fingerprinter = fingerprinters::image::ImageFingerprinter::new(&path)
+						.at_stage(Stage::Decode, &path)?;
+
+					(
+						fingerprinter.finger().at_stage(Stage::Hash, &path)?,
+						Type::Image,
+						fingerprinter.algorithm().options(),
+					)
 				}
-				infer::MatcherType::Audio => {
-					todo!()
+				#[cfg(feature = "audio")]
+				infer::MatcherType::Audio
+					if fingerprinters::audio::is_decodable(kind.mime_type()) =>
+				{
+					(
+						fingerprinters::audio::AudioFingerprinter::new(&path)
+							.at_stage(Stage::Decode, &path)?
+							.finger()
+							.at_stage(Stage::Hash, &path)?,
+						Type::Audio,
+						Options::audio_spectral(),
+					)
 				}
-				infer::MatcherType::Video => {
-					todo!()
+				matcher @ (infer::MatcherType::Image
+				| infer::MatcherType::Audio
+				| infer::MatcherType::Video) => {
+					let r#type = match matcher {
+						infer::MatcherType::Image => Type::Image,
+						infer::MatcherType::Audio => Type::Audio,
+						_ => Type::Video,
+					};
+
+					if strict {
+						return Err(FingerprintError::UnsupportedType {
+							r#type,
+							mime_type: kind.mime_type().to_string(),
+						})
+						.at_stage(Stage::Detect, &path);
+					}
+
+					tracing::debug!(
+						r#type = %r#type,
+						mime_type = kind.mime_type(),
+						"falling back to raw fingerprint"
+					);
+
+					raw()?
 				}
-				_ => (RawFingerprinter::new(&path)?.finger()?, Type::Raw),
+				_ => raw()?,
 			},
-			None => (RawFingerprinter::new(&path)?.finger()?, Type::Raw),
+			None => raw()?,
 		};
 
+		tracing::debug!(r#type = %kind, "fingerprinted file");
+
 		Ok(Self {
 			path: path.as_ref().into(),
 			fingerprint,
 			r#type: kind,
+			options_tag: options.tag(),
+			original_version: None,
 		})
 	}
 
-	/// Compare this fingerprint with another. Fingerprints may have different [Fingerprint::type]s.
-	pub fn compare(&self, other: &Fingerprint) -> f64 {
-		let mut similarity = 0f64;
+	/// Generate a deterministic fingerprint for a file with a configured number of segments and RNG seed.
+	///
+	/// With the [default configuration](FingerprintConfig::default) this is [Fingerprint::finger]. Otherwise text is
+	/// fingerprinted by its text segments and every other file raw, as the image and audio fingerprinters have a fixed
+	/// number of bits. The fingerprint has one bit per segment, and its [options tag](Fingerprint::options_tag)
+	/// records the configuration, so [Fingerprint::try_compare] rejects fingerprints of different configurations.
+	pub fn finger_with_config<P: AsRef<Path>>(
+		path: P,
+		config: &FingerprintConfig,
+	) -> Result<Self, FingerprintError> {
+		if *config == FingerprintConfig::default() {
+			return Self::finger(path);
+		}
+
+		let _span =
+			tracing::debug_span!("finger_with_config", path = %path.as_ref().display(), ?config)
+				.entered();
+		let (fingerprint, kind) = match infer::get_from_path(&path)
+			.at_stage(Stage::Detect, &path)?
+			.map(|kind| kind.matcher_type())
+		{
+			Some(infer::MatcherType::Text) => (
+				TextFingerprinter::with_config(&path, config)
+					.at_stage(Stage::Open, &path)?
+					.finger()
+					.at_stage(Stage::Hash, &path)?,
+				Type::Text,
+			),
+			_ => (
+				RawFingerprinter::with_config(&path, config)
+					.at_stage(Stage::Open, &path)?
+					.finger()
+					.at_stage(Stage::Hash, &path)?,
+				Type::Raw,
+			),
+		};
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			options_tag: config.options_for(&kind).tag(),
+			r#type: kind,
+			original_version: None,
+		})
+	}
+
+	/// Generate a deterministic fingerprint for bytes held in memory, such as a download, like [Fingerprint::finger].
+	///
+	/// Without a `hint`, text and bytes of unrecognised type are fingerprinted in memory, and every other detected type
+	/// is written to a temporary file of the [runtime configuration](runtime::Config), named with its extension, and
+	/// fingerprinted by [Fingerprint::finger], as are unrecognised bytes larger than the sampling threshold. With a
+	/// `hint`, the bytes are fingerprinted as that type, with images and audio decoded from a temporary file, and videos
+	/// and images and audio that cannot be decoded in this build fingerprinted raw. The fingerprint has an empty path.
+	pub fn finger_from_bytes(data: &[u8], hint: Option<Type>) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_from_bytes", len = data.len()).entered();
+		let detected = infer::get(data);
+		let temporary = || -> Result<tempfile::NamedTempFile, FingerprintError> {
+			use std::io::Write;
+
+			let suffix = detected
+				.map(|kind| format!(".{}", kind.extension()))
+				.unwrap_or_default();
+			let mut temporary = runtime::Config::global().temp_file_with_suffix(&suffix)?;
+
+			temporary.write_all(data)?;
+			temporary.flush()?;
+
+			Ok(temporary)
+		};
+
+		if hint.is_none() {
+			let large = runtime::Config::global()
+				.sampling_threshold
+				.is_some_and(|threshold| data.len() as u64 > threshold);
+			let by_file = match detected {
+				Some(kind) => kind.matcher_type() != infer::MatcherType::Text,
+				None => large,
+			};
+
+			if by_file {
+				let mut fingerprint = Self::finger(temporary()?.path())?;
 
-		for (lbit, rbit) in self.bits().iter().zip(other.bits().iter()) {
-			if lbit == rbit {
-				similarity += 1f64;
+				fingerprint.path = PathBuf::new();
+
+				return Ok(fingerprint);
 			}
 		}
 
-		similarity / NUM_FINGERPRINT_SEGMENTS as f64
-	}
+		let r#type = hint.unwrap_or_else(|| match detected.map(|kind| kind.matcher_type()) {
+			Some(infer::MatcherType::Text) => Type::Text,
+			Some(infer::MatcherType::Image) => Type::Image,
+			Some(infer::MatcherType::Audio) => Type::Audio,
+			Some(infer::MatcherType::Video) => Type::Video,
+			_ => Type::Raw,
+		});
+		let (fingerprint, r#type, options) = match r#type {
+			Type::Text => (
+				TextFingerprinter::from_slice(data).finger()?,
+				Type::Text,
+				Options::text(),
+			),
+			#[cfg(feature = "image")]
+			Type::Image
+				if detected
+					.is_none_or(|kind| fingerprinters::image::is_decodable(kind.mime_type())) =>
+			{
+				let temporary = temporary()?;
+				let fingerprinter =
+					fingerprinters::image::ImageFingerprinter::new(temporary.path())?;
 
-	/// Return vector of fingerprint bits.
-	pub fn bits(&self) -> BitBox<u8> {
-		self.fingerprint.clone()
-	}
+				(
+					fingerprinter.finger()?,
+					Type::Image,
+					fingerprinter.algorithm().options(),
+				)
+			}
+			#[cfg(feature = "audio")]
+			Type::Audio
+				if detected
+					.is_none_or(|kind| fingerprinters::audio::is_decodable(kind.mime_type())) =>
+			{
+				(
+					fingerprinters::audio::AudioFingerprinter::new(temporary()?.path())?
+						.finger()?,
+					Type::Audio,
+					Options::audio_spectral(),
+				)
+			}
+			_ => (
+				RawFingerprinter::from_slice(data).finger()?,
+				Type::Raw,
+				Options::raw(),
+			),
+		};
 
-	/// Return vector of fingerprint bytes.
-	pub fn bytes(&self) -> &[u8] {
-		self.fingerprint.as_raw_slice()
+		Ok(Self {
+			path: PathBuf::new(),
+			fingerprint,
+			r#type,
+			options_tag: options.tag(),
+			original_version: None,
+		})
 	}
 
-	/// Return path to fingerprinted file.
-	pub fn path(&self) -> PathBuf {
-		self.path.to_path_buf()
-	}
+	/// Generate a deterministic fingerprint for the next `len` bytes of a seekable reader, such as a request body
+	/// spooled to disk, like [Fingerprint::finger_from_bytes] with the type detected from the bytes.
+	///
+	/// Only a header for type detection is held in memory. Bytes of unrecognised type up to the sampling threshold are
+	/// fingerprinted raw through the reader, and the rest are copied to a temporary file of the
+	/// [runtime configuration](runtime::Config) and fingerprinted by [Fingerprint::finger]. The fingerprint has an
+	/// empty path.
+	///
+	/// Fails with [FingerprintError::Io] of kind [UnexpectedEof](std::io::ErrorKind::UnexpectedEof) if the reader ends
+	/// before `len` bytes.
+	pub fn finger_reader<R: std::io::Read + std::io::Seek + Send + 'static>(
+		mut reader: R,
+		len: u64,
+	) -> Result<Self, FingerprintError> {
+		use std::io::{Read, SeekFrom};
 
-	/// Return type of fingerprinter used.
-	pub fn r#type(&self) -> Type {
-		self.r#type.clone()
+		let _span = tracing::debug_span!("finger_reader", len).entered();
+		let start = reader.stream_position()?;
+
+		if reader.seek(SeekFrom::End(0))? - start < len {
+			return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+		}
+
+		let mut header = Vec::new();
+
+		reader.seek(SeekFrom::Start(start))?;
+		(&mut reader).take(len.min(8192)).read_to_end(&mut header)?;
+		reader.seek(SeekFrom::Start(start))?;
+
+		let detected = infer::get(&header);
+		let large = runtime::Config::global()
+			.sampling_threshold
+			.is_some_and(|threshold| len > threshold);
+
+		if detected.is_none() && !large {
+			return Ok(Self {
+				path: PathBuf::new(),
+				fingerprint: RawFingerprinter::from_reader(reader, len)?.finger()?,
+				r#type: Type::Raw,
+				options_tag: Options::raw().tag(),
+				original_version: None,
+			});
+		}
+
+		let suffix = detected
+			.map(|kind| format!(".{}", kind.extension()))
+			.unwrap_or_default();
+		let mut temporary = runtime::Config::global().temp_file_with_suffix(&suffix)?;
+
+		std::io::copy(&mut reader.take(len), &mut temporary)?;
+
+		let mut fingerprint = Self::finger(temporary.path())?;
+
+		fingerprint.path = PathBuf::new();
+
+		Ok(fingerprint)
 	}
-}
 
-impl Display for Fingerprint {
-	/// Formats the fingerprint in hexadecimal notation.
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "{}", hex::encode(self.bytes()))
+	/// Generate a raw fingerprint of the bytes of a file, whatever its type.
+	///
+	/// Files larger than the [sampling threshold](runtime::Config::sampling_threshold) of the global configuration are
+	/// [sampled](fingerprinters::sampled) rather than read whole, and only compare with other sampled fingerprints.
+	pub fn finger_raw<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_raw", path = %path.as_ref().display()).entered();
+		let (fingerprint, options) = finger_raw(path.as_ref())?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Raw,
+			options_tag: options.tag(),
+			original_version: None,
+		})
 	}
-}
 
-#[cfg(test)]
-mod tests {
-	use crate::Fingerprint;
+	/// Generate a SimHash fingerprint of a text file for near-duplicate detection, so that texts sharing most of their
+	/// wording compare as similar.
+	///
+	/// See [SimHashTextFingerprinter](fingerprinters::text_simhash::SimHashTextFingerprinter).
+	#[cfg(feature = "text")]
+	pub fn finger_text_simhash<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_text_simhash", path = %path.as_ref().display()).entered();
+		let fingerprint = fingerprinters::text_simhash::SimHashTextFingerprinter::new(&path)
+			.at_stage(Stage::Open, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
 
-	#[test]
-	fn test_empty() {
-		assert_eq!(
-			Fingerprint::finger("samples/empty").unwrap().to_string(),
-			"51ad9acc76659b1a4d4da56055b1b532"
-		);
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Text,
+			options_tag: Options::text_simhash().tag(),
+			original_version: None,
+		})
 	}
 
-	#[test]
-	fn test_ascii_text() {
-		assert_eq!(
-			Fingerprint::finger("samples/ascii.txt")
-				.unwrap()
-				.to_string(),
-			"6964d14b3a2bf3264db15649d5de4ad5"
-		);
+	/// Generate a fingerprint of the dialogue of SubRip or WebVTT subtitles, so that resynced and reformatted copies
+	/// fingerprint alike. With `timing`, cue start times quantised to that resolution are fingerprinted too.
+	///
+	/// See [SubtitleFingerprinter](fingerprinters::subtitle::SubtitleFingerprinter), and
+	/// [compare](fingerprinters::subtitle::compare) to estimate the offset between two files.
+	#[cfg(feature = "text")]
+	pub fn finger_subtitle<P: AsRef<Path>>(
+		path: P,
+		timing: Option<std::time::Duration>,
+	) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_subtitle", path = %path.as_ref().display()).entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::subtitle::SubtitleFingerprinter::new(&path, timing)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Text,
+			options_tag: Options::subtitle(
+				timing.map_or(0, |timing| timing.as_millis().min(u32::MAX as u128) as u32),
+			)
+			.tag(),
+			original_version: None,
+		})
 	}
 
-	#[test]
-	fn test_ascii_text_similar() {
-		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
-		let second = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+	/// Generate a fingerprint of the shape of an STL or OBJ mesh, so that the same model exported by different programs,
+	/// in other units or re-triangulated, fingerprints alike.
+	///
+	/// See [MeshFingerprinter](fingerprinters::mesh::MeshFingerprinter).
+	#[cfg(feature = "mesh")]
+	pub fn finger_mesh<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_mesh", path = %path.as_ref().display()).entered();
 
-		assert_eq!(first.compare(&second), 0.859375);
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::mesh::MeshFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Mesh,
+			options_tag: Options::mesh_d2().tag(),
+			original_version: None,
+		})
 	}
 
-	#[test]
-	fn test_ascii_text_somewhat_similar() {
-		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
-		let second = Fingerprint::finger("samples/ascii_somewhat_similar.txt").unwrap();
+	/// Generate a fingerprint of the text layer of a PDF, so that the same document exported by different producers
+	/// fingerprints alike.
+	///
+	/// The fingerprint is comparable with [finger_text_simhash](Self::finger_text_simhash) fingerprints of the same
+	/// text. See [PdfFingerprinter](fingerprinters::pdf::PdfFingerprinter).
+	#[cfg(feature = "pdf")]
+	pub fn finger_pdf<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_pdf", path = %path.as_ref().display()).entered();
 
-		assert_eq!(first.compare(&second), 0.65625);
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::pdf::PdfFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Text,
+			options_tag: Options::text_simhash().tag(),
+			original_version: None,
+		})
 	}
 
-	#[test]
-	fn test_ascii_text_different() {
-		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
-		let second = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+	/// Generate a fingerprint of the text of a DOCX, XLSX or OpenDocument file, so that copies saved by different
+	/// editors fingerprint alike.
+	///
+	/// The fingerprint is comparable with [finger_text_simhash](Self::finger_text_simhash) fingerprints of the same
+	/// text. See [OfficeFingerprinter](fingerprinters::office::OfficeFingerprinter).
+	#[cfg(feature = "office")]
+	pub fn finger_office<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_office", path = %path.as_ref().display()).entered();
 
-		assert_eq!(first.compare(&second), 0.4921875);
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::office::OfficeFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Text,
+			options_tag: Options::text_simhash().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint of the paragraph text of a DOCX, so that copies whose XML differs, such as by revision
+	/// IDs or tracked changes, fingerprint alike.
+	///
+	/// The fingerprint is comparable with [finger_from_bytes](Self::finger_from_bytes) fingerprints of the same text
+	/// as [Type::Text]. See [DocxFingerprinter](fingerprinters::docx::DocxFingerprinter).
+	#[cfg(feature = "office")]
+	pub fn finger_docx_content<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_docx_content", path = %path.as_ref().display()).entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::docx::DocxFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Text,
+			options_tag: Options::text().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint of the text of the chapters of an EPUB, in reading order, so that repackaged copies of the
+	/// same book fingerprint alike. The cover and title pages are skipped unless `include_cover_and_metadata` is set.
+	///
+	/// The fingerprint is comparable with [finger_text_simhash](Self::finger_text_simhash) fingerprints of the same
+	/// text. See [EpubFingerprinter](fingerprinters::epub::EpubFingerprinter).
+	#[cfg(feature = "epub")]
+	pub fn finger_epub<P: AsRef<Path>>(
+		path: P,
+		include_cover_and_metadata: bool,
+	) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_epub", path = %path.as_ref().display()).entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprint =
+			fingerprinters::epub::EpubFingerprinter::new(&path, include_cover_and_metadata)
+				.at_stage(Stage::Decode, &path)?
+				.finger()
+				.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Text,
+			options_tag: Options::text_simhash().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Fingerprint files in parallel on the thread pool of `config`, with the default fingerprinter for each type.
+	///
+	/// Results are in the order of `paths`. See [finger](Self::finger).
+	#[cfg(feature = "parallel")]
+	pub fn finger_many<P: AsRef<Path> + Sync>(
+		paths: &[P],
+		config: &runtime::Config,
+	) -> Vec<Result<Self, FingerprintError>> {
+		use rayon::prelude::*;
+
+		let _span = tracing::debug_span!("finger_many", files = paths.len()).entered();
+
+		config.install(|| paths.par_iter().map(Self::finger).collect())
+	}
+
+	/// Generate a fingerprint like [Fingerprint::finger] on a blocking thread of the Tokio runtime, without stalling
+	/// the async executor.
+	///
+	/// The fingerprint is bit for bit identical to that of [Fingerprint::finger]. A panic while fingerprinting is
+	/// returned as [FingerprintError::Io].
+	///
+	/// Raw and text files are not read with async `tokio::fs::File` reads: which fingerprinter applies depends on the
+	/// detected type, the extension-based formats and, for large raw files, the seeks of
+	/// [sampling](fingerprinters::sampled), all of which read the file synchronously. Running [Fingerprint::finger]
+	/// whole on a blocking thread keeps that dispatch in one place, so the bits cannot diverge from it. No video
+	/// fingerprinter runs a command, so there is none to run with `tokio::process::Command`.
+	#[cfg(feature = "tokio")]
+	pub async fn finger_async<P: AsRef<Path> + Send>(path: P) -> Result<Self, FingerprintError> {
+		let path = path.as_ref().to_path_buf();
+
+		tokio::task::spawn_blocking(move || Self::finger(path))
+			.await
+			.map_err(std::io::Error::from)?
+	}
+
+	/// Generate a fingerprint like [Fingerprint::finger_from_bytes] on a blocking thread of the Tokio runtime, without
+	/// stalling the async executor.
+	///
+	/// The fingerprint is bit for bit identical to that of [Fingerprint::finger_from_bytes]. A panic while
+	/// fingerprinting is returned as [FingerprintError::Io].
+	#[cfg(feature = "tokio")]
+	pub async fn finger_from_bytes_async(
+		data: Vec<u8>,
+		hint: Option<Type>,
+	) -> Result<Self, FingerprintError> {
+		tokio::task::spawn_blocking(move || Self::finger_from_bytes(&data, hint))
+			.await
+			.map_err(std::io::Error::from)?
+	}
+
+	/// Generate a perceptual fingerprint for an image with the difference hash, so that resized and re-encoded copies
+	/// compare as similar.
+	///
+	/// See [ImageFingerprinter](fingerprinters::image::ImageFingerprinter).
+	#[cfg(feature = "image")]
+	pub fn finger_image<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Self::finger_image_with_algorithm(path, fingerprinters::image::PHashAlgorithm::DHash)
+	}
+
+	/// Generate a perceptual fingerprint for an image with a chosen hash.
+	///
+	/// Fingerprints of different algorithms have different [options tags](Fingerprint::options_tag).
+	#[cfg(feature = "image")]
+	pub fn finger_image_with_algorithm<P: AsRef<Path>>(
+		path: P,
+		algorithm: fingerprinters::image::PHashAlgorithm,
+	) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_image", path = %path.as_ref().display(), ?algorithm)
+				.entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprinter =
+			fingerprinters::image::ImageFingerprinter::new_with_algorithm(&path, algorithm)
+				.at_stage(Stage::Decode, &path)?;
+		let fingerprint = fingerprinter.finger().at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Image,
+			options_tag: algorithm.options().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint for only the text regions of an image, such as a screenshot or UI mockup.
+	///
+	/// See [ImageTextRegionFingerprinter](fingerprinters::image_text::ImageTextRegionFingerprinter).
+	#[cfg(feature = "image")]
+	pub fn finger_image_text_region<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_image_text_region", path = %path.as_ref().display())
+				.entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::image_text::ImageTextRegionFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Image,
+			options_tag: Options::image_text_region().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint for an image with its outlined caption text removed, so that variants of a meme with
+	/// different captions on the same base image compare as similar.
+	///
+	/// Only light text with a dark outline is detected; other overlaid text is fingerprinted as part of the image.
+	///
+	/// See [ImageMemeFingerprinter](fingerprinters::image_meme::ImageMemeFingerprinter).
+	#[cfg(feature = "image")]
+	pub fn finger_image_meme_invariant<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_image_meme_invariant", path = %path.as_ref().display())
+				.entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::image_meme::ImageMemeFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Image,
+			options_tag: Options::image_meme_invariant().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint for a 16-bit depth map from a LiDAR or structured-light sensor, keeping the full depth
+	/// precision.
+	///
+	/// See [DepthMapFingerprinter](fingerprinters::image_depth::DepthMapFingerprinter).
+	#[cfg(feature = "image")]
+	pub fn finger_image_depth_map<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_image_depth_map", path = %path.as_ref().display())
+			.entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::image_depth::DepthMapFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Image,
+			options_tag: Options::image_depth_map().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint for every sprite of a sprite sheet, divided into tiles of `sprite_size` (width, height)
+	/// pixels.
+	///
+	/// The fingerprints are sorted by their bits, so sheets packing the same sprites in a different arrangement produce
+	/// the same list. See [SpriteSheetFingerprinter](fingerprinters::sprite::SpriteSheetFingerprinter).
+	#[cfg(feature = "image")]
+	pub fn finger_sprite_sheet<P: AsRef<Path>>(
+		path: P,
+		sprite_size: (u32, u32),
+	) -> Result<Vec<Self>, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_sprite_sheet", path = %path.as_ref().display()).entered();
+
+		require_content(path.as_ref())?;
+
+		let mut fingerprints =
+			fingerprinters::sprite::SpriteSheetFingerprinter::new(&path, sprite_size)
+				.at_stage(Stage::Decode, &path)?
+				.finger()
+				.at_stage(Stage::Hash, &path)?;
+
+		fingerprints.sort_by(|a, b| a.as_raw_slice().cmp(b.as_raw_slice()));
+
+		Ok(fingerprints
+			.into_iter()
+			.map(|fingerprint| Self {
+				path: path.as_ref().into(),
+				fingerprint,
+				r#type: Type::Image,
+				options_tag: Options::image_sprite().tag(),
+				original_version: None,
+			})
+			.collect())
+	}
+
+	/// Generate a fingerprint for the spectral energy of the decoded audio of a file, so that encodes of the same
+	/// recording at different bit rates compare as similar.
+	///
+	/// See [AudioFingerprinter](fingerprinters::audio::AudioFingerprinter).
+	#[cfg(feature = "audio")]
+	pub fn finger_audio<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_audio", path = %path.as_ref().display()).entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprinter =
+			fingerprinters::audio::AudioFingerprinter::new(&path).at_stage(Stage::Decode, &path)?;
+		let fingerprint = fingerprinter.finger().at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Audio,
+			options_tag: Options::audio_spectral().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint for the audio of a file that also reflects its tempo, so that a recording and a remix of
+	/// it at another speed differ.
+	///
+	/// The first byte is the tempo detected by [extract_bpm](fingerprinters::audio::extract_bpm) as a proportion of
+	/// 300 BPM, and the remaining 120 bits are the first bits of the fingerprint of [Fingerprint::finger_audio].
+	#[cfg(feature = "audio")]
+	pub fn finger_audio_with_bpm<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		let _span = tracing::debug_span!("finger_audio_with_bpm", path = %path.as_ref().display())
+			.entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprinter =
+			fingerprinters::audio::AudioFingerprinter::new(&path).at_stage(Stage::Decode, &path)?;
+		let bpm = fingerprinter.bpm().at_stage(Stage::Hash, &path)?;
+		let spectral = fingerprinter.finger().at_stage(Stage::Hash, &path)?;
+		let (_, max_bpm) = fingerprinters::audio::BPM_RANGE;
+		let mut fingerprint =
+			BitVec::<u8, Lsb0>::from_element((bpm / max_bpm * 255.0).round() as u8);
+
+		fingerprint.extend_from_bitslice(&spectral[..NUM_FINGERPRINT_SEGMENTS - 8]);
+		tracing::trace!(bpm, "detected tempo");
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint: fingerprint.into_boxed_bitslice(),
+			r#type: Type::Audio,
+			options_tag: Options::audio_bpm().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate a fingerprint for the decoded audio of a file, so that the same recording in MP3, FLAC, Ogg Vorbis or WAV
+	/// fingerprints alike.
+	///
+	/// See [AudioFormatInvariantFingerprinter](fingerprinters::audio::AudioFormatInvariantFingerprinter).
+	#[cfg(feature = "audio")]
+	pub fn finger_audio_format_invariant<P: AsRef<Path>>(
+		path: P,
+	) -> Result<Self, FingerprintError> {
+		let _span =
+			tracing::debug_span!("finger_audio_format_invariant", path = %path.as_ref().display())
+				.entered();
+
+		require_content(path.as_ref())?;
+
+		let fingerprint = fingerprinters::audio::AudioFormatInvariantFingerprinter::new(&path)
+			.at_stage(Stage::Decode, &path)?
+			.finger()
+			.at_stage(Stage::Hash, &path)?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint,
+			r#type: Type::Audio,
+			options_tag: Options::audio_format_invariant().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Generate fingerprints for every file listed in a manifest, one path per line.
+	///
+	/// Surrounding whitespace is trimmed, and blank lines and lines starting with `#` are skipped. Results are in
+	/// manifest order, and a file that cannot be fingerprinted yields an error in its place without stopping the rest.
+	/// Fails only when the manifest itself cannot be read.
+	pub fn finger_from_manifest<P: AsRef<Path>>(
+		manifest: P,
+	) -> Result<Vec<Result<Self, FingerprintError>>, FingerprintError> {
+		let _span =
+			tracing::info_span!("finger_from_manifest", manifest = %manifest.as_ref().display())
+				.entered();
+		let manifest = fs::read_to_string(manifest)?;
+		let results: Vec<_> = manifest
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.map(|path| {
+				Self::finger(path).inspect_err(|e| {
+					tracing::debug!(path, error = %e, "failed to fingerprint file");
+				})
+			})
+			.collect();
+
+		tracing::info!(
+			files = results.len(),
+			failed = results.iter().filter(|result| result.is_err()).count(),
+			"fingerprinted manifest"
+		);
+
+		Ok(results)
+	}
+
+	/// Generate a fingerprint of the files under a directory, such as a software release, reflecting which files it
+	/// holds and their properties.
+	///
+	/// Every regular file under the directory, without following symbolic links, is listed as a
+	/// `"{path}\t{size}\t{mtime}\n"` line: its path relative to the directory with `/` separators, its size (bytes)
+	/// and its modification time (whole seconds since the Unix epoch). The lines are sorted by path and fingerprinted as
+	/// [text](Type::Text), so directories holding files of the same paths, sizes and modification times compare 1.0
+	/// whatever their inode numbers and permissions. The fingerprint has the path of the directory.
+	pub fn from_dir_manifest<P: AsRef<Path>>(dir: P) -> Result<Self, FingerprintError> {
+		let dir = dir.as_ref();
+		let _span = tracing::debug_span!("from_dir_manifest", dir = %dir.display()).entered();
+		let mut lines = Vec::new();
+
+		for path in paths::files(dir).at_stage(Stage::Open, dir)? {
+			let metadata = path.metadata().at_stage(Stage::Open, &path)?;
+			let mtime = metadata
+				.modified()
+				.at_stage(Stage::Open, &path)?
+				.duration_since(UNIX_EPOCH)
+				.map(|time| time.as_secs())
+				.unwrap_or_default();
+			let relative: Vec<_> = path
+				.strip_prefix(dir)
+				.unwrap_or(&path)
+				.components()
+				.map(|component| component.as_os_str().to_string_lossy())
+				.collect();
+
+			lines.push(format!(
+				"{}\t{}\t{}\n",
+				relative.join("/"),
+				metadata.len(),
+				mtime
+			));
+		}
+
+		lines.sort();
+
+		Ok(Self {
+			path: dir.into(),
+			fingerprint: TextFingerprinter::from_slice(lines.concat().as_bytes())
+				.finger()
+				.at_stage(Stage::Hash, dir)?,
+			r#type: Type::Text,
+			options_tag: Options::text().tag(),
+			original_version: None,
+		})
+	}
+
+	/// Compare this fingerprint with another, returning the fraction of bits that agree. Fingerprints may have
+	/// different [Fingerprint::type]s, whose bits agree only by chance (see [CROSS_TYPE_EXPECTED_SCORES]).
+	///
+	/// Fingerprints of the same type produced with different parameters, whose
+	/// [options tags](Fingerprint::options_tag) differ, are not comparable: they score 0.0 and a warning is logged. Use
+	/// [Fingerprint::try_compare] to reject every pair of fingerprints with different options tags with an error. A
+	/// warning is also logged when either fingerprint has an [entropy](Fingerprint::entropy) below 0.5.
+	///
+	/// Never panics: returns 0.0 when either fingerprint is empty or their lengths differ, cases that
+	/// [Fingerprint::try_compare] reports as errors.
+	pub fn compare(&self, other: &Fingerprint) -> f64 {
+		if let Err(e) = self.check_compatible(other) {
+			tracing::warn!(
+				left = %self.path.display(),
+				right = %other.path.display(),
+				"{}, scoring 0.0",
+				e
+			);
+
+			return 0f64;
+		}
+
+		self.bit_agreement(other).unwrap_or(0f64)
+	}
+
+	/// Fail with [CompareError::IncompatibleParameters] when this fingerprint and another are of the same type but have
+	/// different [options tags](Fingerprint::options_tag), as checked by [Fingerprint::compare] and the
+	/// [stores](store::Store).
+	pub(crate) fn check_compatible(&self, other: &Fingerprint) -> Result<(), CompareError> {
+		if self.r#type == other.r#type && self.options_tag != other.options_tag {
+			return Err(CompareError::IncompatibleParameters {
+				left_tag: self.options_tag,
+				right_tag: other.options_tag,
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Compare this fingerprint with another like [Fingerprint::compare], comparing fingerprints of different lengths
+	/// with a [LengthStrategy] rather than scoring them 0.0.
+	///
+	/// Fingerprints of equal length score as with [Fingerprint::compare] under every strategy, and an empty fingerprint
+	/// always scores 0.0.
+	pub fn compare_with_strategy(&self, other: &Fingerprint, strategy: LengthStrategy) -> f64 {
+		let (shorter, longer) = match self.fingerprint.len() <= other.fingerprint.len() {
+			true => (&self.fingerprint, &other.fingerprint),
+			false => (&other.fingerprint, &self.fingerprint),
+		};
+
+		if shorter.len() == longer.len() || shorter.is_empty() {
+			return self.compare(other);
+		}
+
+		let agreement = |window: &BitSlice<u8>| {
+			let agreeing = shorter
+				.iter()
+				.zip(window.iter())
+				.filter(|(lbit, rbit)| lbit == rbit)
+				.count();
+
+			agreeing as f64 / shorter.len() as f64
+		};
+
+		match strategy {
+			LengthStrategy::Exact => 0f64,
+			LengthStrategy::Resample => {
+				let resampled: BitVec<u8> = (0..shorter.len())
+					.map(|index| longer[(2 * index + 1) * longer.len() / (2 * shorter.len())])
+					.collect();
+
+				agreement(&resampled)
+			}
+			LengthStrategy::SlidingWindow => longer
+				.windows(shorter.len())
+				.map(agreement)
+				.fold(0f64, f64::max),
+		}
+	}
+
+	/// Return the fraction of bits that agree, failing on empty fingerprints and fingerprints of different lengths.
+	fn bit_agreement(&self, other: &Fingerprint) -> Result<f64, CompareError> {
+		if self.fingerprint.is_empty() || other.fingerprint.is_empty() {
+			return Err(CompareError::EmptyFingerprint);
+		}
+
+		if self.fingerprint.len() != other.fingerprint.len() {
+			return Err(CompareError::LengthMismatch {
+				left: self.fingerprint.len(),
+				right: other.fingerprint.len(),
+			});
+		}
+
+		let _span = tracing::trace_span!(
+			"compare",
+			left = %self.path.display(),
+			right = %other.path.display()
+		)
+		.entered();
+
+		for fingerprint in [self, other] {
+			let entropy = fingerprint.entropy();
+
+			if entropy < LOW_ENTROPY {
+				tracing::warn!(
+					path = %fingerprint.path.display(),
+					entropy,
+					"comparing low-entropy fingerprint, similarity may be unreliable"
+				);
+			}
+		}
+
+		if tracing::enabled!(tracing::Level::TRACE) {
+			for index in self.diff_segments(other) {
+				tracing::trace!(
+					index,
+					left = self.fingerprint[index],
+					right = other.fingerprint[index],
+					"fingerprint bits differ"
+				);
+			}
+		}
+
+		let len = self.fingerprint.len();
+
+		Ok((len - self.hamming_distance(other)) as f64 / len as f64)
+	}
+
+	/// Return the number of bits that differ between this fingerprint and another. Only bits present in both
+	/// fingerprints are compared.
+	///
+	/// Whole bytes are compared with a single XOR and population count each. For fingerprints of equal length,
+	/// [Fingerprint::compare] is one less the distance as a fraction of the length:
+	///
+	/// ```
+	/// use fingerprint::Fingerprint;
+	///
+	/// let ascii = Fingerprint::finger("samples/ascii.txt")?;
+	/// let similar = Fingerprint::finger("samples/ascii_similar.txt")?;
+	/// let distance = ascii.hamming_distance(&similar);
+	///
+	/// assert_eq!(distance, 18);
+	/// assert_eq!(ascii.compare(&similar), 1.0 - distance as f64 / 128.0);
+	/// # Ok::<(), fingerprint::FingerprintError>(())
+	/// ```
+	pub fn hamming_distance(&self, other: &Fingerprint) -> usize {
+		let len = self.fingerprint.len().min(other.fingerprint.len());
+		let (left, right) = (&self.fingerprint[..len], &other.fingerprint[..len]);
+		let whole = len / 8 * 8;
+		let bytes: u32 = left[..whole]
+			.chunks_exact(8)
+			.zip(right[..whole].chunks_exact(8))
+			.map(|(lbyte, rbyte)| (lbyte.load_le::<u8>() ^ rbyte.load_le::<u8>()).count_ones())
+			.sum();
+		let tail = left[whole..]
+			.iter()
+			.zip(right[whole..].iter())
+			.filter(|(lbit, rbit)| lbit != rbit)
+			.count();
+
+		bytes as usize + tail
+	}
+
+	/// Return the binary Shannon entropy of the fingerprint bits, from 0.0 (all bits equal) to 1.0 (half the bits set).
+	///
+	/// This is `-p*log2(p) - (1-p)*log2(1-p)`, where `p` is the fraction of set bits.
+	pub fn entropy(&self) -> f64 {
+		if self.fingerprint.is_empty() {
+			return 0f64;
+		}
+
+		let p = self.fingerprint.count_ones() as f64 / self.fingerprint.len() as f64;
+
+		[p, 1f64 - p]
+			.iter()
+			.filter(|p| **p > 0f64)
+			.map(|p| -p * p.log2())
+			.sum()
+	}
+
+	/// Compare this fingerprint with another, failing with [CompareError::IncompatibleSegmentCount] when they were
+	/// produced with different numbers of segments, [CompareError::IncompatibleParameters] when they were produced with
+	/// otherwise different [Options], [CompareError::EmptyFingerprint] when either is empty, and
+	/// [CompareError::LengthMismatch] when their lengths differ.
+	pub fn try_compare(&self, other: &Fingerprint) -> Result<f64, CompareError> {
+		if self.options_tag != other.options_tag {
+			let (left, right) = (self.fingerprint.len(), other.fingerprint.len());
+
+			if left != right && left > 0 && right > 0 {
+				return Err(CompareError::IncompatibleSegmentCount { left, right });
+			}
+
+			return Err(CompareError::IncompatibleParameters {
+				left_tag: self.options_tag,
+				right_tag: other.options_tag,
+			});
+		}
+
+		self.bit_agreement(other)
+	}
+
+	/// Return the bit error rate (BER) between this fingerprint and another, the fraction of differing bits.
+	///
+	/// This is `1.0 - self.compare(other)`.
+	pub fn bit_error_rate(&self, other: &Fingerprint) -> f64 {
+		1f64 - self.compare(other)
+	}
+
+	/// Return whether the bit error rate between this fingerprint and another is at most `max_ber`.
+	pub fn is_within_ber(&self, other: &Fingerprint, max_ber: f64) -> bool {
+		self.bit_error_rate(other) <= max_ber
+	}
+
+	/// Return the indices, in ascending order, of the bits that differ between this fingerprint and another.
+	///
+	/// Each bit of a fingerprint summarises one segment of the file, so the indices locate the regions that changed
+	/// between two versions of it. Only bits present in both fingerprints are compared.
+	pub fn diff_segments(&self, other: &Fingerprint) -> Vec<usize> {
+		self.fingerprint
+			.iter()
+			.zip(other.fingerprint.iter())
+			.enumerate()
+			.filter(|(_, (lbit, rbit))| lbit != rbit)
+			.map(|(index, _)| index)
+			.collect()
+	}
+
+	/// Compare this fingerprint with another as binary masks, using the intersection over union (IoU) of their set bits.
+	///
+	/// The result is the number of positions where both fingerprints are set, divided by the number of positions where
+	/// at least one is set. Returns 0.0 when neither fingerprint has a set bit.
+	pub fn compare_iou(&self, other: &Fingerprint) -> f64 {
+		let (mut intersection, mut union) = (0usize, 0usize);
+
+		for (lbit, rbit) in self.fingerprint.iter().zip(other.fingerprint.iter()) {
+			intersection += (*lbit && *rbit) as usize;
+			union += (*lbit || *rbit) as usize;
+		}
+
+		match union {
+			0 => 0f64,
+			_ => intersection as f64 / union as f64,
+		}
+	}
+
+	/// Compare the files of this fingerprint and another by the Spearman rank correlation of their segment values.
+	///
+	/// Both files are re-read to collect the mean byte value of each [raw](fingerprinters::raw) segment, and
+	/// Spearman's ρ between the two sequences is mapped from `[-1, 1]` to `[0, 1]` as `(ρ + 1) / 2`. This rewards
+	/// files whose segments have a similar relative ordering even when their absolute values differ. Fails when
+	/// either file cannot be read, or when its segment values are all equal and so cannot be ranked.
+	pub fn compare_spearman(&self, other: &Fingerprint) -> Result<f64, FingerprintError> {
+		let segment_values = |path: &Path| {
+			RawFingerprinter::new(path)
+				.at_stage(Stage::Open, path)?
+				.segment_values()
+				.at_stage(Stage::Hash, path)
+		};
+		let left = ranks(&segment_values(&self.path)?);
+		let right = ranks(&segment_values(&other.path)?);
+		let mean = (left.len() + 1) as f64 / 2f64;
+		let (mut covariance, mut left_variance, mut right_variance) = (0f64, 0f64, 0f64);
+
+		for (l, r) in left.iter().zip(&right) {
+			covariance += (l - mean) * (r - mean);
+			left_variance += (l - mean).powi(2);
+			right_variance += (r - mean).powi(2);
+		}
+
+		for (variance, path) in [(left_variance, &self.path), (right_variance, &other.path)] {
+			if variance == 0f64 {
+				return Err(CompareError::ConstantValues).at_stage(Stage::Compare, path);
+			}
+		}
+
+		let rho = covariance / (left_variance * right_variance).sqrt();
+
+		Ok((rho + 1f64) / 2f64)
+	}
+
+	/// Compare this fingerprint with another under every metric, recommending the one that suits its type.
+	///
+	/// Image fingerprints mark the presence of features, so the [Jaccard index](Fingerprint::compare_iou) of their set
+	/// bits is recommended. Every other type is recommended the [bit agreement](Fingerprint::compare), since its clear
+	/// bits carry as much information as its set bits.
+	pub fn compare_report(&self, other: &Fingerprint) -> ComparisonReport {
+		let bit_agreement = self.compare(other);
+		let jaccard = self.compare_iou(other);
+		let hamming_distance = self.hamming_distance(other);
+		let recommended_score = match self.r#type {
+			Type::Image => jaccard,
+			_ => bit_agreement,
+		};
+
+		ComparisonReport {
+			bit_agreement,
+			jaccard,
+			hamming_distance,
+			recommended_score,
+		}
+	}
+
+	/// Compare this fingerprint with another using the weighted Jaccard index of their set bits.
+	///
+	/// The result is the total weight of positions where both fingerprints are set, divided by the total weight of
+	/// positions where at least one is set. `weights` must have one non-negative weight per bit. Returns 0.0 when
+	/// neither fingerprint has a set bit with non-zero weight.
+	pub fn compare_weighted_jaccard(
+		&self,
+		other: &Fingerprint,
+		weights: &[f64],
+	) -> Result<f64, FingerprintError> {
+		let len = self.fingerprint.len().min(other.fingerprint.len());
+
+		if weights.len() != len {
+			return Err(FingerprintError::from(CompareError::WeightCount {
+				expected: len,
+				found: weights.len(),
+			}));
+		}
+
+		if let Some(weight) = weights
+			.iter()
+			.find(|weight| weight.is_nan() || **weight < 0f64)
+		{
+			return Err(FingerprintError::from(CompareError::InvalidWeight(*weight)));
+		}
+
+		let mut intersection = 0f64;
+		let mut union = 0f64;
+
+		for ((lbit, rbit), weight) in self
+			.fingerprint
+			.iter()
+			.zip(other.fingerprint.iter())
+			.zip(weights)
+		{
+			if *lbit && *rbit {
+				intersection += weight;
+			}
+
+			if *lbit || *rbit {
+				union += weight;
+			}
+		}
+
+		match union {
+			0f64 => Ok(0f64),
+			_ => Ok(intersection / union),
+		}
+	}
+
+	/// Serialize the fingerprint in the compact [postcard](https://docs.rs/postcard) format.
+	///
+	/// A 128-bit fingerprint without a path serializes to at most 32 bytes, of which the varint-encoded options tag
+	/// takes up to 10. A path adds its length plus one or two bytes.
+	#[cfg(feature = "postcard")]
+	pub fn to_postcard(&self) -> Result<Vec<u8>, FingerprintError> {
+		Ok(postcard::to_allocvec(self)?)
+	}
+
+	/// Deserialize a fingerprint from the compact [postcard](https://docs.rs/postcard) format.
+	#[cfg(feature = "postcard")]
+	pub fn from_postcard(data: &[u8]) -> Result<Self, FingerprintError> {
+		Ok(postcard::from_bytes(data)?)
+	}
+
+	/// Find at most `k` corpus fingerprints that together cover the bits of `query`, using a greedy set cover.
+	///
+	/// A query bit is covered by a corpus fingerprint when both have the same value at that position. Each step
+	/// picks the corpus fingerprint covering the most bits not yet covered by earlier picks, stopping early once
+	/// every bit is covered or no fingerprint adds coverage. Each pick is returned with the fraction of query bits
+	/// it newly covered, so the sum of the fractions is the aggregate coverage of the returned set.
+	pub fn min_cover<'a>(
+		query: &Fingerprint,
+		corpus: &'a [Fingerprint],
+		k: usize,
+	) -> Vec<(&'a Fingerprint, f64)> {
+		let mut uncovered = bitbox![u8, Lsb0; 1; query.fingerprint.len()];
+		let mut picked = vec![false; corpus.len()];
+		let mut cover = Vec::with_capacity(k);
+
+		while cover.len() < k && uncovered.any() {
+			let best = corpus
+				.iter()
+				.enumerate()
+				.filter(|(index, _)| !picked[*index])
+				.map(|(index, candidate)| {
+					let gain = uncovered
+						.iter_ones()
+						.filter(|&bit| candidate.fingerprint.get(bit) == query.fingerprint.get(bit))
+						.count();
+
+					(index, gain)
+				})
+				.max_by_key(|&(index, gain)| (gain, std::cmp::Reverse(index)));
+
+			let (index, gain) = match best {
+				Some((index, gain)) if gain > 0 => (index, gain),
+				_ => break,
+			};
+
+			for bit in 0..uncovered.len() {
+				if corpus[index].fingerprint.get(bit) == query.fingerprint.get(bit) {
+					uncovered.set(bit, false);
+				}
+			}
+
+			picked[index] = true;
+			cover.push((&corpus[index], gain as f64 / query.fingerprint.len() as f64));
+		}
+
+		cover
+	}
+
+	/// Return vector of fingerprint bits.
+	pub fn bits(&self) -> BitBox<u8> {
+		self.fingerprint.clone()
+	}
+
+	/// Return vector of fingerprint bytes.
+	pub fn bytes(&self) -> &[u8] {
+		self.fingerprint.as_raw_slice()
+	}
+
+	/// Return path to fingerprinted file.
+	pub fn path(&self) -> PathBuf {
+		self.path.to_path_buf()
+	}
+
+	/// Return type of fingerprinter used.
+	pub fn r#type(&self) -> Type {
+		self.r#type.clone()
+	}
+
+	/// Return the tag of the [Options] the fingerprint was produced with.
+	pub fn options_tag(&self) -> OptionsTag {
+		self.options_tag
+	}
+
+	/// Return the [wire] format version this fingerprint was decoded from, or `None` if it was not decoded.
+	pub fn original_version(&self) -> Option<u8> {
+		self.original_version
+	}
+
+	/// Encode the fingerprint into the compact binary [wire] format. The path is not encoded.
+	pub fn encode(&self) -> Vec<u8> {
+		wire::encode(self)
+	}
+
+	/// Decode a fingerprint from the compact binary [wire] format. The decoded fingerprint has an empty path.
+	pub fn decode(data: &[u8]) -> Result<Self, FingerprintError> {
+		Ok(wire::decode(data)?)
+	}
+
+	/// Format the fingerprint as a single-line [uri], such as `fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q`. The path is not
+	/// included.
+	pub fn to_uri(&self) -> String {
+		uri::format(self)
+	}
+
+	/// Parse a fingerprint from a single-line [uri]. The parsed fingerprint has an empty path.
+	pub fn from_uri(s: &str) -> Result<Self, FingerprintError> {
+		Ok(uri::parse(s)?)
+	}
+
+	/// Return a copy of the fingerprint bytes, as accepted by [Fingerprint::from_parts].
+	pub fn to_bytes(&self) -> Vec<u8> {
+		self.bytes().to_vec()
+	}
+
+	/// Build a fingerprint from a path, its bytes as returned by [Fingerprint::to_bytes], its type and the tag of the
+	/// options it was produced with, as returned by [Fingerprint::options_tag].
+	///
+	/// The fingerprint has a bit for every bit of the bytes. Fails with a [LengthError] for no bytes or more than
+	/// those of a fingerprint of [MAX_SEGMENTS](options::MAX_SEGMENTS) bits.
+	pub fn from_parts<P: AsRef<Path>>(
+		path: P,
+		bytes: &[u8],
+		kind: Type,
+		options_tag: OptionsTag,
+	) -> Result<Self, FingerprintError> {
+		check_length(bytes.len())?;
+
+		Ok(Self {
+			path: path.as_ref().into(),
+			fingerprint: BitBox::from_boxed_slice(bytes.into()),
+			options_tag,
+			r#type: kind,
+			original_version: None,
+		})
+	}
+
+	/// Build a fingerprint from its hexadecimal notation, as formatted by [Display], with a path, type and options
+	/// tag, as for [Fingerprint::from_parts].
+	///
+	/// Fails with a [FromHexError](hex::FromHexError) for strings of odd length or with characters other than hex
+	/// digits, and with a [LengthError] for no bytes or too many.
+	pub fn from_hex_with_metadata<P: AsRef<Path>>(
+		hex: &str,
+		path: P,
+		kind: Type,
+		options_tag: OptionsTag,
+	) -> Result<Self, FingerprintError> {
+		Self::from_parts(path, &hex::decode(hex)?, kind, options_tag)
+	}
+}
+
+/// Fail with a [LengthError] unless a fingerprint of `len` bytes has between 1 and [options::MAX_SEGMENTS] bits.
+fn check_length(len: usize) -> Result<(), LengthError> {
+	let max = options::MAX_SEGMENTS / 8;
+
+	match len == 0 || len > max {
+		true => Err(LengthError { max, found: len }),
+		false => Ok(()),
+	}
+}
+
+impl Display for Fingerprint {
+	/// Formats the fingerprint in hexadecimal notation.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", hex::encode(self.bytes()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		io,
+		path::{Path, PathBuf},
+		sync::{Arc, Mutex},
+	};
+
+	use bitvec::prelude::*;
+
+	use super::ranks;
+	use crate::{
+		options::{FingerprintConfig, Options, MAX_SEGMENTS},
+		CompareError, ComparisonReport, Fingerprint, FingerprintError, LengthError, LengthStrategy,
+		Stage, Type,
+	};
+
+	/// Build a raw fingerprint from a bit pattern.
+	fn from_bits(bits: &BitSlice<u8>) -> Fingerprint {
+		Fingerprint {
+			path: PathBuf::new(),
+			fingerprint: bits.to_bitvec().into_boxed_bitslice(),
+			r#type: Type::Raw,
+			options_tag: Options::raw().tag(),
+			original_version: None,
+		}
+	}
+
+	#[test]
+	fn test_empty() {
+		assert_eq!(
+			Fingerprint::finger("samples/empty").unwrap().to_string(),
+			"51ad9acc76659b1a4d4da56055b1b532"
+		);
+	}
+
+	#[test]
+	fn test_from_hex_with_metadata() {
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let tag = original.options_tag();
+		let parsed = Fingerprint::from_hex_with_metadata(
+			&original.to_string(),
+			original.path(),
+			original.r#type(),
+			original.options_tag(),
+		)
+		.unwrap();
+
+		assert_eq!(parsed.compare(&original), 1.0);
+		assert_eq!(parsed.to_string(), original.to_string());
+		assert_eq!(parsed.path(), original.path());
+		assert_eq!(parsed.options_tag(), original.options_tag());
+
+		for (hex, expected) in [
+			("6964d", hex::FromHexError::OddLength),
+			(
+				"6964zz",
+				hex::FromHexError::InvalidHexCharacter { c: 'z', index: 4 },
+			),
+		] {
+			let error = Fingerprint::from_hex_with_metadata(hex, "", Type::Raw, tag).unwrap_err();
+
+			assert_eq!(error.downcast_ref(), Some(&expected), "{}", hex);
+		}
+
+		let error = Fingerprint::from_hex_with_metadata("", "", Type::Raw, tag).unwrap_err();
+
+		assert_eq!(
+			error.downcast_ref(),
+			Some(&LengthError {
+				max: MAX_SEGMENTS / 8,
+				found: 0
+			})
+		);
+	}
+
+	#[test]
+	fn test_from_parts() {
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let rebuilt = Fingerprint::from_parts(
+			original.path(),
+			&original.to_bytes(),
+			original.r#type(),
+			original.options_tag(),
+		)
+		.unwrap();
+
+		assert_eq!(rebuilt.compare(&original), 1.0);
+		assert_eq!(rebuilt.bits(), original.bits());
+		assert_eq!(rebuilt.options_tag(), original.options_tag());
+
+		let config = FingerprintConfig::default().segments(1024).unwrap();
+		let wide = Fingerprint::finger_with_config("samples/ascii.txt", &config).unwrap();
+		let rebuilt =
+			Fingerprint::from_parts("", &wide.to_bytes(), wide.r#type(), wide.options_tag())
+				.unwrap();
+
+		assert_eq!(rebuilt.bits().len(), 1024);
+		assert_eq!(rebuilt.try_compare(&wide).unwrap(), 1.0);
+		assert!(rebuilt.try_compare(&original).is_err());
+
+		for len in [0, MAX_SEGMENTS / 8 + 1] {
+			let error =
+				Fingerprint::from_parts("", &vec![0; len], Type::Raw, original.options_tag())
+					.unwrap_err();
+
+			assert_eq!(
+				error.downcast_ref(),
+				Some(&LengthError {
+					max: MAX_SEGMENTS / 8,
+					found: len
+				})
+			);
+		}
+	}
+
+	#[test]
+	fn test_finger_from_bytes() {
+		use crate::fingerprinters::{text::TextFingerprinter, Fingerprinter};
+
+		for path in [
+			"samples/empty",
+			"samples/ascii.txt",
+			"samples/text/page.html",
+			"samples/audio/tone.flac",
+			#[cfg(feature = "pdf")]
+			"samples/pdf/document.pdf",
+		] {
+			let expected = Fingerprint::finger(path).unwrap();
+			let fingerprint =
+				Fingerprint::finger_from_bytes(&std::fs::read(path).unwrap(), None).unwrap();
+
+			assert_eq!(fingerprint.to_string(), expected.to_string(), "{}", path);
+			assert_eq!(fingerprint.r#type(), expected.r#type(), "{}", path);
+			assert_eq!(
+				fingerprint.options_tag(),
+				expected.options_tag(),
+				"{}",
+				path
+			);
+			assert!(fingerprint.path().as_os_str().is_empty());
+		}
+
+		let text = Fingerprint::finger_from_bytes(
+			&std::fs::read("samples/ascii.txt").unwrap(),
+			Some(Type::Text),
+		)
+		.unwrap();
+
+		assert_eq!(text.r#type(), Type::Text);
+		assert_eq!(
+			text.bits(),
+			TextFingerprinter::new("samples/ascii.txt")
+				.unwrap()
+				.finger()
+				.unwrap()
+		);
+	}
+
+	#[test]
+	fn test_finger_reader() {
+		let data = std::fs::read("samples/ascii.txt").unwrap();
+		let expected = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let fingerprint =
+			Fingerprint::finger_reader(std::io::Cursor::new(data.clone()), data.len() as u64)
+				.unwrap();
+
+		assert_eq!(fingerprint.bits(), expected.bits());
+		assert_eq!(fingerprint.r#type(), expected.r#type());
+		assert_eq!(
+			Fingerprint::finger_reader(
+				std::io::Cursor::new([&data[..], b"trailing"].concat()),
+				data.len() as u64
+			)
+			.unwrap()
+			.bits(),
+			expected.bits()
+		);
+		assert!(matches!(
+			Fingerprint::finger_reader(std::io::Cursor::new(data.clone()), data.len() as u64 + 1),
+			Err(FingerprintError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+		));
+
+		let audio = std::fs::File::open("samples/audio/tone.flac").unwrap();
+		let len = audio.metadata().unwrap().len();
+		let expected = Fingerprint::finger("samples/audio/tone.flac").unwrap();
+		let fingerprint = Fingerprint::finger_reader(audio, len).unwrap();
+
+		assert_eq!(fingerprint.bits(), expected.bits());
+		assert_eq!(fingerprint.options_tag(), expected.options_tag());
+	}
+
+	#[test]
+	fn test_finger_with_config() {
+		let default = FingerprintConfig::default();
+		let segments = default.segments(256).unwrap();
+
+		for path in ["samples/ascii.txt", "samples/text/page.html"] {
+			let original = Fingerprint::finger(path).unwrap();
+			let wide = Fingerprint::finger_with_config(path, &segments).unwrap();
+
+			assert_eq!(
+				Fingerprint::finger_with_config(path, &default)
+					.unwrap()
+					.to_string(),
+				original.to_string()
+			);
+			assert_eq!(wide.bits().len(), 256);
+			assert_eq!(wide.r#type(), original.r#type());
+			assert_eq!(
+				wide.options_tag(),
+				segments.options_for(&wide.r#type()).tag()
+			);
+			assert_eq!(
+				wide.try_compare(&original),
+				Err(CompareError::IncompatibleSegmentCount {
+					left: 256,
+					right: 128,
+				})
+			);
+			assert_eq!(
+				wide.bits(),
+				Fingerprint::finger_with_config(path, &segments)
+					.unwrap()
+					.bits()
+			);
+		}
+
+		let seeded =
+			Fingerprint::finger_with_config("samples/empty", &default.rng_seed(42)).unwrap();
+
+		assert_eq!(seeded.bits().len(), 128);
+		assert_ne!(
+			seeded.bits(),
+			Fingerprint::finger("samples/empty").unwrap().bits()
+		);
+		assert_eq!(
+			seeded.try_compare(&Fingerprint::finger("samples/empty").unwrap()),
+			Err(CompareError::IncompatibleParameters {
+				left_tag: seeded.options_tag(),
+				right_tag: Options::raw().tag(),
+			})
+		);
+
+		for count in [0, MAX_SEGMENTS + 1] {
+			assert!(matches!(
+				default.segments(count),
+				Err(FingerprintError::InvalidData(_))
+			));
+		}
+
+		let longest = default.segments(MAX_SEGMENTS).unwrap();
+		let fingerprint = Fingerprint::finger_with_config("samples/ascii.txt", &longest).unwrap();
+
+		assert_eq!(fingerprint.bits().len(), MAX_SEGMENTS);
+		assert_eq!(
+			Fingerprint::from_uri(&fingerprint.to_uri())
+				.unwrap()
+				.try_compare(&fingerprint),
+			Ok(1.0)
+		);
+	}
+
+	#[test]
+	fn test_compare_with_strategy() {
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let similar = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+		let doubled = from_bits(
+			&ascii
+				.bits()
+				.iter()
+				.flat_map(|bit| [*bit, *bit])
+				.collect::<BitVec<u8>>(),
+		);
+		let prefix = from_bits(&ascii.bits()[..48]);
+
+		for strategy in [
+			LengthStrategy::Exact,
+			LengthStrategy::Resample,
+			LengthStrategy::SlidingWindow,
+		] {
+			assert_eq!(
+				ascii.compare_with_strategy(&similar, strategy),
+				ascii.compare(&similar)
+			);
+			assert_eq!(
+				ascii.compare_with_strategy(&from_bits(bits![u8, Lsb0;]), strategy),
+				0.0
+			);
+		}
+
+		assert_eq!(ascii.compare(&doubled), 0.0);
+		assert_eq!(
+			ascii.compare_with_strategy(&doubled, LengthStrategy::Exact),
+			0.0
+		);
+		assert_eq!(
+			ascii.compare_with_strategy(&doubled, LengthStrategy::Resample),
+			1.0
+		);
+		assert_eq!(
+			doubled.compare_with_strategy(&ascii, LengthStrategy::Resample),
+			1.0
+		);
+		assert_eq!(
+			prefix.compare_with_strategy(&ascii, LengthStrategy::SlidingWindow),
+			1.0
+		);
+
+		// A short pattern matches some window of a long fingerprint well, but not perfectly.
+		let alternating = from_bits(bits![u8, Lsb0; 1, 0, 1, 1, 0, 0, 1, 0]);
+		let sliding = alternating.compare_with_strategy(&similar, LengthStrategy::SlidingWindow);
+		let resampled = alternating.compare_with_strategy(&similar, LengthStrategy::Resample);
+
+		assert!(sliding >= resampled, "{} {}", sliding, resampled);
+		assert!(resampled < 1.0, "{}", resampled);
+	}
+
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_finger_async() {
+		for path in ["samples/empty", "samples/ascii.txt"] {
+			let fingerprint = Fingerprint::finger(path).unwrap();
+
+			let fingerprinted = Fingerprint::finger_async(path).await.unwrap();
+
+			assert_eq!(fingerprinted.bits(), fingerprint.bits());
+			assert_eq!(fingerprinted.options_tag(), fingerprint.options_tag());
+			assert_eq!(
+				Fingerprint::finger_from_bytes_async(std::fs::read(path).unwrap(), None)
+					.await
+					.unwrap()
+					.bits(),
+				Fingerprint::finger_from_bytes(&std::fs::read(path).unwrap(), None)
+					.unwrap()
+					.bits()
+			);
+		}
+
+		assert!(matches!(
+			Fingerprint::finger_async("samples/missing").await.unwrap_err().inner(),
+			FingerprintError::Io(e) if e.kind() == std::io::ErrorKind::NotFound
+		));
+	}
+
+	#[test]
+	fn test_missing() {
+		let error = Fingerprint::finger("samples/missing").unwrap_err();
+
+		assert_eq!(error.path(), Some(Path::new("samples/missing")));
+		assert_eq!(error.stage(), Some(Stage::Detect));
+		assert!(error
+			.to_string()
+			.starts_with("detect failed for samples/missing: "));
+		assert!(matches!(
+			error.inner(),
+			FingerprintError::Io(e) if e.kind() == io::ErrorKind::NotFound
+		));
+	}
+
+	#[cfg(feature = "image")]
+	#[test]
+	fn test_empty_file() {
+		let error = Fingerprint::finger_image("samples/empty").unwrap_err();
+
+		assert_eq!(error.stage(), Some(Stage::Open));
+		assert!(matches!(error.inner(), FingerprintError::EmptyFile));
+		assert_eq!(
+			Fingerprint::finger("samples/empty").unwrap().r#type(),
+			Type::Raw
+		);
+	}
+
+	#[test]
+	fn test_ascii_text() {
+		assert_eq!(
+			Fingerprint::finger("samples/ascii.txt")
+				.unwrap()
+				.to_string(),
+			"6964d14b3a2bf3264db15649d5de4ad5"
+		);
+	}
+
+	#[test]
+	fn test_ascii_text_similar() {
+		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let second = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+
+		assert_eq!(first.compare(&second), 0.859375);
+	}
+
+	#[test]
+	fn test_ascii_text_somewhat_similar() {
+		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let second = Fingerprint::finger("samples/ascii_somewhat_similar.txt").unwrap();
+
+		assert_eq!(first.compare(&second), 0.65625);
+	}
+
+	#[test]
+	fn test_ascii_text_different() {
+		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let second = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+
+		assert_eq!(first.compare(&second), 0.4921875);
+	}
+
+	#[test]
+	fn test_min_cover() {
+		let query = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let mut corpus = vec![
+			Fingerprint::finger("samples/ascii_different.txt").unwrap(),
+			Fingerprint::finger("samples/empty").unwrap(),
+		];
+
+		// Three partial copies of the query, each with a different third of the bits inverted.
+		for part in 0..3 {
+			let mut bits = query.bits();
+
+			for bit in (0..bits.len()).filter(|bit| bit % 3 == part) {
+				bits.set(bit, !query.fingerprint[bit]);
+			}
+
+			corpus.push(from_bits(&bits));
+		}
+
+		let cover = Fingerprint::min_cover(&query, &corpus, 3);
+		let coverage: f64 = cover.iter().map(|(_, gain)| gain).sum();
+
+		assert_eq!(cover.len(), 2);
+		assert!(coverage >= 0.95);
+		assert!(cover.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+		assert!(Fingerprint::min_cover(&query, &corpus, 0).is_empty());
+		assert_eq!(
+			Fingerprint::min_cover(&query, std::slice::from_ref(&query), 3).len(),
+			1
+		);
+	}
+
+	#[test]
+	fn test_compare_iou() {
+		let ones = from_bits(bits![u8, Lsb0; 1, 1, 1, 1]);
+		let zeros = from_bits(bits![u8, Lsb0; 0, 0, 0, 0]);
+		let left = from_bits(bits![u8, Lsb0; 1, 1, 0, 0]);
+		let right = from_bits(bits![u8, Lsb0; 0, 0, 1, 1]);
+
+		assert_eq!(ones.compare_iou(&ones), 1.0);
+		assert_eq!(left.compare_iou(&right), 0.0);
+		assert_eq!(zeros.compare_iou(&zeros), 0.0);
+		assert_eq!(left.compare_iou(&ones), 0.5);
+		assert_eq!(
+			left.compare_iou(&from_bits(bits![u8, Lsb0; 1, 0, 1, 0])),
+			1.0 / 3.0
+		);
+	}
+
+	#[test]
+	fn test_finger_from_manifest() {
+		let dir = tempfile::tempdir().unwrap();
+		let manifest = dir.path().join("manifest.txt");
+
+		std::fs::write(
+			&manifest,
+			"# samples\nsamples/ascii.txt\n\n  samples/missing  \nsamples/empty\n",
+		)
+		.unwrap();
+
+		let results = Fingerprint::finger_from_manifest(&manifest).unwrap();
+
+		assert_eq!(results.len(), 3);
+		assert_eq!(
+			results[0].as_ref().unwrap().path(),
+			Path::new("samples/ascii.txt")
+		);
+		assert!(results[1].is_err());
+		assert_eq!(
+			results[2].as_ref().unwrap().path(),
+			Path::new("samples/empty")
+		);
+		assert!(Fingerprint::finger_from_manifest(dir.path().join("missing")).is_err());
+	}
+
+	#[test]
+	fn test_from_dir_manifest() {
+		let copy = |from: &Path, to: &Path| {
+			for (source, target) in [("ascii.txt", "ascii.txt"), ("empty", "nested/empty")] {
+				let (source, target) = (from.join(source), to.join(target));
+
+				std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+				std::fs::copy(&source, &target).unwrap();
+				std::fs::File::options()
+					.write(true)
+					.open(&target)
+					.unwrap()
+					.set_modified(source.metadata().unwrap().modified().unwrap())
+					.unwrap();
+			}
+		};
+		let original = tempfile::tempdir().unwrap();
+		let copied = tempfile::tempdir().unwrap();
+
+		copy(Path::new("samples"), original.path());
+		copy(Path::new("samples"), copied.path());
+
+		let mut permissions = copied
+			.path()
+			.join("ascii.txt")
+			.metadata()
+			.unwrap()
+			.permissions();
+
+		permissions.set_readonly(true);
+		std::fs::set_permissions(copied.path().join("ascii.txt"), permissions).unwrap();
+
+		let fingerprint = Fingerprint::from_dir_manifest(original.path()).unwrap();
+
+		assert_eq!(fingerprint.r#type(), Type::Text);
+		assert_eq!(fingerprint.path(), original.path());
+		assert_eq!(
+			fingerprint.compare(&Fingerprint::from_dir_manifest(copied.path()).unwrap()),
+			1.0
+		);
+
+		std::fs::write(copied.path().join("nested/added.txt"), "added").unwrap();
+
+		assert!(fingerprint.compare(&Fingerprint::from_dir_manifest(copied.path()).unwrap()) < 1.0);
+		assert!(Fingerprint::from_dir_manifest("samples/missing").is_err());
+	}
+
+	#[test]
+	fn test_tracing_events() {
+		#[derive(Clone, Default)]
+		struct Capture(Arc<Mutex<Vec<u8>>>);
+
+		impl io::Write for Capture {
+			fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+				self.0.lock().unwrap().write(buf)
+			}
+
+			fn flush(&mut self) -> io::Result<()> {
+				Ok(())
+			}
+		}
+
+		let capture = Capture::default();
+		let subscriber = tracing_subscriber::fmt()
+			.with_max_level(tracing::Level::TRACE)
+			.with_writer({
+				let capture = capture.clone();
+				move || capture.clone()
+			})
+			.with_ansi(false)
+			.finish();
+
+		tracing::subscriber::with_default(subscriber, || {
+			let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+			let similar = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+
+			ascii.compare(&similar);
+		});
+
+		let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+
+		assert!(output.contains("finger{path=samples/ascii.txt}:hash{path=samples/ascii.txt}"));
+		assert!(output.contains("fingerprinted file type=raw"));
+		assert!(output.contains("fingerprint bits differ index=8 left=false right=true"));
+		assert!(output.contains("compare{left=samples/ascii.txt right=samples/ascii_similar.txt}"));
+	}
+
+	#[cfg(feature = "image")]
+	#[test]
+	fn test_stage_error_corrupt_sample() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("corrupt.png");
+		let mut data = Vec::new();
+
+		image::GrayImage::new(16, 16)
+			.write_to(&mut io::Cursor::new(&mut data), image::ImageFormat::Png)
+			.unwrap();
+		data.truncate(data.len() / 2);
+		std::fs::write(&path, data).unwrap();
+
+		let error = Fingerprint::finger_image_text_region(&path).unwrap_err();
+
+		assert_eq!(error.stage(), Some(Stage::Decode));
+		assert_eq!(error.path(), Some(path.as_path()));
+		assert!(error
+			.to_string()
+			.starts_with(&format!("decode failed for {}: ", path.display())));
+		assert!(error.downcast_ref::<image::ImageError>().is_some());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_stage_error_permission_denied() {
+		use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("unreadable.txt");
+
+		std::fs::copy("samples/ascii.txt", &path).unwrap();
+		std::fs::set_permissions(&path, Permissions::from_mode(0o000)).unwrap();
+
+		if std::fs::File::open(&path).is_ok() {
+			eprintln!("skipping, permissions are not enforced for this user");
+
+			return;
+		}
+
+		let error = Fingerprint::finger(&path).unwrap_err();
+
+		assert_eq!(error.stage(), Some(Stage::Detect));
+		assert_eq!(error.path(), Some(path.as_path()));
+		assert!(matches!(
+			error.inner(),
+			FingerprintError::Io(e) if e.kind() == io::ErrorKind::PermissionDenied
+		));
+		assert!(error.to_string().contains(&path.display().to_string()));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_non_utf8_path() {
+		use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join(OsStr::from_bytes(b"ascii-\xff.txt"));
+
+		std::fs::copy("samples/ascii.txt", &path).unwrap();
+
+		let fingerprint = Fingerprint::finger(&path).unwrap();
+
+		assert_eq!(fingerprint.path(), path);
+		assert_eq!(
+			fingerprint.compare(&Fingerprint::finger("samples/ascii.txt").unwrap()),
+			1.0
+		);
+	}
+
+	/// Write `header` padded to 1KiB to a file in `dir`, returning its path.
+	fn with_header(dir: &Path, name: &str, header: &[u8]) -> PathBuf {
+		let path = dir.join(name);
+		let mut data = header.to_vec();
+
+		data.resize(1024, 0x2a);
+		std::fs::write(&path, data).unwrap();
+
+		path
+	}
+
+	#[test]
+	fn test_finger_unsupported_types() {
+		let dir = tempfile::tempdir().unwrap();
+		let cases = [
+			(
+				"video.mp4",
+				&b"\0\0\0\x18ftypisom"[..],
+				infer::MatcherType::Video,
+			),
+			("image.psd", b"8BPS\0\x01", infer::MatcherType::Image),
+			(
+				"audio.m4a",
+				b"\0\0\0\x20ftypM4A ",
+				infer::MatcherType::Audio,
+			),
+			("archive.zip", b"PK\x03\x04", infer::MatcherType::Archive),
+			("app", b"\x7fELF\x02\x01\x01", infer::MatcherType::App),
+		];
+
+		for (name, header, matcher) in cases {
+			let path = with_header(dir.path(), name, header);
+
+			assert_eq!(
+				infer::get_from_path(&path).unwrap().unwrap().matcher_type(),
+				matcher,
+				"{}",
+				name
+			);
+			assert_eq!(
+				Fingerprint::finger(&path).unwrap().r#type(),
+				Type::Raw,
+				"{}",
+				name
+			);
+
+			let strict = Fingerprint::finger_strict(&path);
+
+			match matcher {
+				infer::MatcherType::Image
+				| infer::MatcherType::Audio
+				| infer::MatcherType::Video => {
+					assert!(
+						matches!(
+							strict.unwrap_err().inner(),
+							FingerprintError::UnsupportedType { .. }
+						),
+						"{}",
+						name
+					);
+				}
+				_ => assert_eq!(strict.unwrap().r#type(), Type::Raw, "{}", name),
+			}
+		}
+
+		assert!(matches!(
+			Fingerprint::finger_strict(dir.path().join("video.mp4")).unwrap_err().inner(),
+			FingerprintError::UnsupportedType { r#type: Type::Video, mime_type }
+				if mime_type == "video/mp4"
+		));
+	}
+
+	#[cfg(all(feature = "image", feature = "audio"))]
+	#[test]
+	fn test_finger_supported_types() {
+		let dir = tempfile::tempdir().unwrap();
+		let png = dir.path().join("image.png");
+
+		image::GrayImage::from_fn(32, 32, |x, y| image::Luma([((x * 8) ^ (y * 8)) as u8]))
+			.save(&png)
+			.unwrap();
+
+		for (path, r#type) in [
+			(Path::new("samples/text/page.html"), Type::Text),
+			(&png, Type::Image),
+			(Path::new("samples/audio/tone.flac"), Type::Audio),
+		] {
+			assert_eq!(Fingerprint::finger(path).unwrap().r#type(), r#type);
+			assert_eq!(Fingerprint::finger_strict(path).unwrap().r#type(), r#type);
+		}
+	}
+
+	#[test]
+	fn test_finger_defaults() {
+		assert_eq!(
+			Fingerprint::finger("samples/ascii.txt").unwrap().r#type(),
+			Type::Raw
+		);
+
+		let text = Fingerprint::finger("samples/text/page.html").unwrap();
+
+		assert_eq!(text.r#type(), Type::Text);
+		assert_eq!(text.options_tag(), Options::text().tag());
+		assert_ne!(text.options_tag(), Options::raw().tag());
+
+		#[cfg(feature = "audio")]
+		assert_eq!(
+			Fingerprint::finger("samples/audio/tone.flac")
+				.unwrap()
+				.options_tag(),
+			Options::audio_spectral().tag()
+		);
+	}
+
+	#[cfg(all(feature = "image", feature = "audio"))]
+	#[test]
+	fn test_cross_type_expected_scores() {
+		use std::collections::HashSet;
+
+		use image::{GrayImage, Luma};
+
+		use crate::{
+			fingerprinters::{raw::RawFingerprinter, text::TextFingerprinter, Fingerprinter},
+			CROSS_TYPE_EXPECTED_SCORES,
+		};
+
+		let types = [
+			Type::Raw,
+			Type::Text,
+			Type::Image,
+			Type::Audio,
+			Type::Video,
+			Type::Mesh,
+		];
+
+		for (i, a) in types.iter().enumerate() {
+			for b in &types[i..] {
+				assert!(CROSS_TYPE_EXPECTED_SCORES
+					.iter()
+					.any(|(x, y, _, _)| (x, y) == (a, b) || (x, y) == (b, a)));
+			}
+		}
+
+		let dir = tempfile::tempdir().unwrap();
+		let png = dir.path().join("blocks.png");
+
+		GrayImage::from_fn(200, 120, |x, y| match (x / 20 + y / 30) % 3 {
+			0 if x % 20 < 14 && y % 30 < 20 => Luma([20]),
+			_ => Luma([230]),
+		})
+		.save(&png)
+		.unwrap();
+
+		// Any file can be fingerprinted raw and as text, but each other fingerprinter only reads its own format, so no
+		// content yields, say, both image and audio fingerprints. There is no video fingerprinter at all.
+		type Finger = fn(&Path) -> Fingerprint;
+
+		let samples: Vec<(PathBuf, Option<Finger>)> = vec![
+			("samples/ascii.txt".into(), None),
+			("samples/text/page.html".into(), None),
+			(png, Some(|path| Fingerprint::finger_image(path).unwrap())),
+			(
+				"samples/audio/tone.flac".into(),
+				Some(|path| Fingerprint::finger_audio(path).unwrap()),
+			),
+			#[cfg(feature = "mesh")]
+			(
+				"samples/mesh/bracket.stl".into(),
+				Some(|path| Fingerprint::finger_mesh(path).unwrap()),
+			),
+		];
+		let mut covered = HashSet::new();
+
+		for (path, finger) in &samples {
+			let mut text = from_bits(&TextFingerprinter::new(path).unwrap().finger().unwrap());
+
+			text.r#type = Type::Text;
+
+			let mut fingerprints = vec![
+				from_bits(&RawFingerprinter::new(path).unwrap().finger().unwrap()),
+				text,
+			];
+
+			fingerprints.extend(finger.map(|finger| finger(path)));
+
+			for a in &fingerprints {
+				for b in &fingerprints {
+					let row = CROSS_TYPE_EXPECTED_SCORES
+						.iter()
+						.position(|(x, y, _, _)| {
+							(x, y) == (&a.r#type, &b.r#type) || (x, y) == (&b.r#type, &a.r#type)
+						})
+						.unwrap();
+					let (_, _, min, max) = CROSS_TYPE_EXPECTED_SCORES[row];
+					let score = a.compare(b);
+
+					assert!(
+						(min..=max).contains(&score),
+						"{} {:?} {:?} {}",
+						path.display(),
+						a.r#type,
+						b.r#type,
+						score
+					);
+					covered.insert(row);
+				}
+			}
+		}
+
+		let constructible = |a: &Type, b: &Type| match (a, b) {
+			(Type::Video, _) | (_, Type::Video) => false,
+			(Type::Mesh, _) | (_, Type::Mesh) if !cfg!(feature = "mesh") => false,
+			(Type::Raw | Type::Text, _) | (_, Type::Raw | Type::Text) => true,
+			_ => a == b,
+		};
+
+		for (row, (a, b, _, _)) in CROSS_TYPE_EXPECTED_SCORES.iter().enumerate() {
+			assert_eq!(
+				covered.contains(&row),
+				constructible(a, b),
+				"{:?} {:?}",
+				a,
+				b
+			);
+		}
+	}
+
+	#[test]
+	fn test_diff_segments() {
+		let left = from_bits(bits![u8, Lsb0; 1, 1, 0, 0, 1]);
+		let right = from_bits(bits![u8, Lsb0; 1, 0, 1, 0]);
+
+		assert_eq!(left.diff_segments(&right), [1, 2]);
+		assert!(left.diff_segments(&left).is_empty());
+
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let similar = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+
+		assert_eq!(
+			ascii.diff_segments(&similar).len(),
+			ascii.compare_report(&similar).hamming_distance
+		);
+	}
+
+	#[test]
+	fn test_hamming_distance() {
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let similar = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+
+		assert_eq!(ascii.hamming_distance(&similar), 18);
+		assert_eq!(ascii.hamming_distance(&ascii), 0);
+
+		for other in ["samples/ascii_similar.txt", "samples/ascii_different.txt"] {
+			let other = Fingerprint::finger(other).unwrap();
+
+			assert_eq!(
+				ascii.compare(&other),
+				1.0 - ascii.hamming_distance(&other) as f64 / 128.0
+			);
+		}
+
+		// Lengths that are not whole bytes compare the trailing bits one by one.
+		let left = from_bits(bits![u8, Lsb0; 1, 0, 1, 1, 0, 0, 1, 0, 1, 1, 0]);
+		let right = from_bits(bits![u8, Lsb0; 1, 1, 1, 1, 0, 0, 1, 1, 0, 1, 0]);
+
+		assert_eq!(left.hamming_distance(&right), 3);
+		assert_eq!(
+			left.hamming_distance(&right),
+			left.diff_segments(&right).len()
+		);
+		assert_eq!(left.compare(&right), 8.0 / 11.0);
+	}
+
+	#[test]
+	fn test_compare_report() {
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let similar = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+		let report = ascii.compare_report(&similar);
+
+		assert_eq!(report.bit_agreement, ascii.compare(&similar));
+		assert_eq!(report.jaccard, ascii.compare_iou(&similar));
+		assert_eq!(report.hamming_distance, 18);
+		assert_eq!(report.recommended_score, report.bit_agreement);
+
+		let mut image = ascii.clone();
+
+		image.r#type = Type::Image;
+
+		assert_eq!(
+			image.compare_report(&similar).recommended_score,
+			report.jaccard
+		);
+	}
+
+	#[test]
+	fn test_compare_weighted_jaccard() {
+		let left = from_bits(bits![u8, Lsb0; 1, 1, 0, 0]);
+		let right = from_bits(bits![u8, Lsb0; 1, 0, 1, 0]);
+
+		assert_eq!(
+			left.compare_weighted_jaccard(&right, &[1.0; 4]).unwrap(),
+			1.0 / 3.0
+		);
+		assert_eq!(
+			left.compare_weighted_jaccard(&right, &[2.0, 1.0, 1.0, 5.0])
+				.unwrap(),
+			0.5
+		);
+		assert_eq!(
+			left.compare_weighted_jaccard(&left, &[1.0; 4]).unwrap(),
+			1.0
+		);
+		assert_eq!(
+			left.compare_weighted_jaccard(&right, &[0.0, 0.0, 0.0, 1.0])
+				.unwrap(),
+			0.0
+		);
+		assert_eq!(
+			left.compare_weighted_jaccard(&right, &[1.0; 3])
+				.unwrap_err()
+				.downcast_ref::<CompareError>(),
+			Some(&CompareError::WeightCount {
+				expected: 4,
+				found: 3
+			})
+		);
+		assert_eq!(
+			left.compare_weighted_jaccard(&right, &[1.0, -1.0, 1.0, 1.0])
+				.unwrap_err()
+				.downcast_ref::<CompareError>(),
+			Some(&CompareError::InvalidWeight(-1.0))
+		);
+		assert!(left
+			.compare_weighted_jaccard(&right, &[1.0, f64::NAN, 1.0, 1.0])
+			.is_err());
+	}
+
+	#[test]
+	fn test_bit_error_rate() {
+		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let second = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+
+		assert_eq!(first.bit_error_rate(&first), 0.0);
+		assert_eq!(first.bit_error_rate(&second), 1.0 - first.compare(&second));
+		assert_eq!(first.bit_error_rate(&second), 0.140625);
+		assert!(first.is_within_ber(&second, 0.140625));
+		assert!(!first.is_within_ber(&second, 0.1));
+	}
+
+	#[test]
+	fn test_try_compare() {
+		let first = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let second = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+		let mut tagged = second.clone();
+
+		tagged.options_tag = Options::image_text_region().tag();
+
+		assert_eq!(first.try_compare(&second), Ok(first.compare(&second)));
+		assert_eq!(first.compare(&tagged), 0.0);
+		assert_eq!(
+			first.try_compare(&tagged),
+			Err(CompareError::IncompatibleParameters {
+				left_tag: Options::raw().tag(),
+				right_tag: Options::image_text_region().tag(),
+			})
+		);
+	}
+
+	#[test]
+	fn test_compare_degenerate() {
+		use rand::{Rng, SeedableRng};
+
+		let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+		let random = |rng: &mut rand_chacha::ChaCha8Rng| {
+			let len = match rng.gen_bool(0.2) {
+				true => 0,
+				false => rng.gen_range(1..300),
+			};
+
+			from_bits(&(0..len).map(|_| rng.gen::<bool>()).collect::<BitVec<u8>>())
+		};
+
+		for _ in 0..2_000 {
+			let (left, right) = (random(&mut rng), random(&mut rng));
+			let (left_len, right_len) = (left.bits().len(), right.bits().len());
+			let score = left.compare(&right);
+
+			assert!((0f64..=1f64).contains(&score));
+			assert_eq!(left.bit_error_rate(&right), 1f64 - score);
+			assert!(left.diff_segments(&right).len() <= left_len.min(right_len));
+			assert!((0f64..=1f64).contains(&left.compare_iou(&right)));
+			assert!(left.compare_report(&right).hamming_distance <= left_len.min(right_len));
+
+			match left.try_compare(&right) {
+				Ok(try_score) => {
+					assert_eq!(try_score, score);
+					assert_eq!(left_len, right_len);
+				}
+				Err(CompareError::EmptyFingerprint) => {
+					assert!(left_len == 0 || right_len == 0);
+					assert_eq!(score, 0f64);
+				}
+				Err(CompareError::LengthMismatch { left, right }) => {
+					assert_eq!((left, right), (left_len, right_len));
+					assert_eq!(score, 0f64);
+				}
+				Err(e) => panic!("unexpected error {}", e),
+			}
+		}
+
+		let empty = from_bits(bits![u8, Lsb0;]);
+
+		assert_eq!(empty.compare(&empty), 0f64);
+		assert_eq!(
+			empty.try_compare(&empty),
+			Err(CompareError::EmptyFingerprint)
+		);
+		assert_eq!(
+			from_bits(bits![u8, Lsb0; 1, 0]).try_compare(&from_bits(bits![u8, Lsb0; 1])),
+			Err(CompareError::LengthMismatch { left: 2, right: 1 })
+		);
+	}
+
+	#[test]
+	fn test_ranks() {
+		assert_eq!(ranks(&[30, 10, 20]), [3.0, 1.0, 2.0]);
+		assert_eq!(ranks(&[5, 5, 1, 5]), [3.0, 3.0, 1.0, 3.0]);
+	}
+
+	#[test]
+	fn test_compare_spearman() {
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let similar = Fingerprint::finger("samples/ascii_similar.txt").unwrap();
+		let different = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+
+		assert!((ascii.compare_spearman(&ascii).unwrap() - 1.0).abs() < 1e-12);
+		assert!(
+			ascii.compare_spearman(&similar).unwrap() > ascii.compare_spearman(&different).unwrap()
+		);
+		assert!(ascii
+			.compare_spearman(&Fingerprint::decode(&ascii.encode()).unwrap())
+			.is_err());
+
+		let dir = tempfile::tempdir().unwrap();
+		let zeros = dir.path().join("zeros");
+
+		std::fs::write(&zeros, [0u8; 256]).unwrap();
+
+		let error = ascii
+			.compare_spearman(&Fingerprint::finger(&zeros).unwrap())
+			.unwrap_err();
+
+		assert_eq!(
+			(error.stage(), error.path()),
+			(Some(Stage::Compare), Some(zeros.as_path()))
+		);
+		assert!(matches!(
+			error.inner(),
+			FingerprintError::Compare(CompareError::ConstantValues)
+		));
+	}
+
+	#[test]
+	fn test_entropy() {
+		let mut half = bitvec![u8, Lsb0; 0; 128];
+
+		half[..64].fill(true);
+
+		assert_eq!(from_bits(&bitvec![u8, Lsb0; 0; 128]).entropy(), 0.0);
+		assert_eq!(from_bits(&bitvec![u8, Lsb0; 1; 128]).entropy(), 0.0);
+		assert_eq!(from_bits(&half).entropy(), 1.0);
+		assert!((from_bits(&half[32..]).entropy() - 0.9183).abs() < 1e-4);
+		assert!(Fingerprint::finger("samples/ascii.txt").unwrap().entropy() > 0.9);
+	}
+
+	fn assert_send_sync<T: Send + Sync>() {}
+
+	#[test]
+	fn test_send_sync() {
+		assert_send_sync::<Fingerprint>();
+		assert_send_sync::<Type>();
+		assert_send_sync::<Options>();
+		assert_send_sync::<crate::options::OptionsTag>();
+		assert_send_sync::<ComparisonReport>();
+		assert_send_sync::<crate::minhash::MinHashSketch>();
+		assert_send_sync::<crate::store::DuplicateGroup>();
+		assert_send_sync::<FingerprintError>();
+		assert_send_sync::<CompareError>();
+		assert_send_sync::<crate::wire::CorruptFingerprint>();
+		assert_send_sync::<crate::fingerprinters::raw::RawFingerprinter>();
+		assert_send_sync::<crate::records::Reader<std::fs::File>>();
+		assert_send_sync::<crate::records::Writer<std::fs::File>>();
+		assert_send_sync::<crate::diagnostics::EnvironmentReport>();
+
+		#[cfg(feature = "image")]
+		assert_send_sync::<crate::fingerprinters::image_text::ImageTextRegionFingerprinter>();
+
+		#[cfg(feature = "image")]
+		assert_send_sync::<crate::fingerprinters::image_meme::ImageMemeFingerprinter>();
+
+		#[cfg(feature = "audio")]
+		assert_send_sync::<crate::fingerprinters::audio::AudioFormatInvariantFingerprinter>();
+
+		#[cfg(feature = "redb")]
+		assert_send_sync::<crate::store::RedbStore>();
+
+		// Deliberately not Sync, see the crate documentation.
+		#[cfg(feature = "sqlite")]
+		{
+			fn assert_send<T: Send>() {}
+
+			assert_send::<crate::store::SqliteStore>();
+		}
 	}
 }
@@ -0,0 +1,599 @@
+//! Command line interface to the fingerprint library.
+//!
+//! Results are written to standard output, and logs to standard error only.
+//!
+//! Exit codes:
+//!
+//! - 0: success, or `compare` found the files at least as similar as the threshold.
+//! - 1: `compare` found the files less similar than the threshold.
+//! - 2: an error occurred, including invalid arguments.
+
+use std::{
+	error,
+	fs::{self, File},
+	io::{self, BufRead, BufReader, BufWriter},
+	path::{Path, PathBuf},
+	process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use fingerprint::{
+	options::Options,
+	paths::files,
+	records, sidecar,
+	store::{group_duplicates, SqliteStore},
+	Fingerprint,
+};
+use serde::Serialize;
+use tracing::level_filters::LevelFilter;
+
+/// Generic [error::Error] type.
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Exit code when a comparison is below the threshold.
+const EXIT_BELOW_THRESHOLD: u8 = 1;
+
+/// Exit code when an error occurred.
+const EXIT_ERROR: u8 = 2;
+
+/// Fingerprint files and compare their fingerprints.
+#[derive(Debug, Parser)]
+#[clap(
+	name = "fingerprint",
+	version,
+	after_help = "EXIT CODES:\n    0    Success, or compared files are at least as similar as the threshold\n    1    Compared files are less similar than the threshold\n    2    An error occurred"
+)]
+struct Cli {
+	/// Print machine-readable JSON instead of text.
+	#[clap(long, global = true)]
+	json: bool,
+
+	/// Log more detail to standard error (repeat for more).
+	#[clap(short, long, global = true, action = clap::ArgAction::Count)]
+	verbose: u8,
+
+	/// Type of file to fingerprint, chosen from the file contents when `auto`.
+	#[clap(long = "type", global = true, value_enum, default_value = "auto")]
+	r#type: FileType,
+
+	/// Fingerprinter options, overriding the default options for the type.
+	#[clap(long, global = true, value_enum)]
+	options: Option<Preset>,
+
+	#[clap(subcommand)]
+	command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+	/// Print the canonical text representation of the fingerprint of each file.
+	Hash {
+		/// Files to fingerprint.
+		#[clap(required = true)]
+		paths: Vec<PathBuf>,
+	},
+
+	/// Print the similarity of two files, exiting with 1 when it is below the threshold.
+	Compare {
+		/// First file.
+		a: PathBuf,
+
+		/// Second file.
+		b: PathBuf,
+
+		/// Lowest similarity for the files to count as similar.
+		#[clap(long, default_value_t = 0.9)]
+		threshold: f64,
+	},
+
+	/// Fingerprint every file under a directory, writing the fingerprints to a record stream as they are computed.
+	Scan {
+		#[clap(flatten)]
+		walk: Walk,
+
+		/// Path of the record stream, readable with `fingerprint::records::Reader`.
+		#[clap(long)]
+		out: PathBuf,
+	},
+
+	/// Find groups of similar files under a directory.
+	Dedup {
+		#[clap(flatten)]
+		walk: Walk,
+
+		/// Lowest similarity for files to count as duplicates.
+		#[clap(long, default_value_t = 0.9)]
+		threshold: f64,
+
+		/// What to do with duplicates.
+		#[clap(long, value_enum, default_value = "report")]
+		action: Action,
+
+		/// Print the actions that would be taken without taking them.
+		#[clap(long)]
+		dry_run: bool,
+	},
+}
+
+/// Directory walked by `scan` and `dedup`, and how sidecars of its files are used.
+///
+/// Sidecars of files in the directory are not fingerprinted themselves.
+#[derive(Debug, Args)]
+struct Walk {
+	/// Directory to scan.
+	dir: PathBuf,
+
+	/// Use the fingerprint in a file's `.fpr` sidecar when it is up to date and has the chosen options, instead of
+	/// fingerprinting the file again.
+	#[clap(long)]
+	prefer_sidecars: bool,
+
+	/// Write a `.fpr` sidecar next to every file fingerprinted.
+	#[clap(long)]
+	write_sidecars: bool,
+
+	/// SQLite database whose content index records the path of every fingerprint, to track files across scans.
+	#[clap(long)]
+	index: Option<PathBuf>,
+}
+
+/// Type of file to fingerprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FileType {
+	/// Chosen from the file contents.
+	Auto,
+
+	/// Raw bytes.
+	Raw,
+
+	/// Image, fingerprinted by the difference hash of a downsampled copy.
+	Image,
+
+	/// Audio, fingerprinted by the spectral energy of its decoded samples.
+	Audio,
+}
+
+/// Fingerprinter options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Preset {
+	/// Raw bytes.
+	Raw,
+
+	/// Difference hash of a downsampled image, the default for images.
+	ImageDhash,
+
+	/// Text regions of an image.
+	ImageTextRegion,
+
+	/// Image with outlined caption text removed.
+	ImageMemeInvariant,
+
+	/// Spectral energy of decoded audio, the default for audio.
+	AudioSpectral,
+
+	/// Decoded audio samples.
+	AudioFormatInvariant,
+}
+
+/// What to do with duplicate files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Action {
+	/// Only report the groups of duplicates.
+	Report,
+
+	/// Replace byte-identical duplicates with hard links to the first file of their group.
+	Hardlink,
+}
+
+/// Fingerprint of a file, as printed by `hash`.
+#[derive(Serialize)]
+struct Hashed {
+	path: PathBuf,
+	uri: String,
+}
+
+/// Similarity of two files, as printed by `compare`.
+#[derive(Serialize)]
+struct Compared {
+	a: PathBuf,
+	b: PathBuf,
+	similarity: f64,
+	threshold: f64,
+	similar: bool,
+}
+
+/// File that could not be fingerprinted.
+#[derive(Serialize)]
+struct Failure {
+	path: PathBuf,
+	error: String,
+}
+
+/// Group of similar files found by `dedup`.
+#[derive(Serialize)]
+struct Group {
+	paths: Vec<PathBuf>,
+	similarity: f64,
+}
+
+/// Hard link made, or to be made, by `dedup`.
+#[derive(Serialize)]
+struct Link {
+	path: PathBuf,
+	target: PathBuf,
+	performed: bool,
+	skipped: Option<String>,
+}
+
+/// Output of `dedup`.
+#[derive(Serialize)]
+struct DedupReport {
+	groups: Vec<Group>,
+	links: Vec<Link>,
+	errors: Vec<Failure>,
+}
+
+fn main() -> ExitCode {
+	let cli = Cli::parse();
+	let level = match cli.verbose {
+		0 => LevelFilter::WARN,
+		1 => LevelFilter::INFO,
+		2 => LevelFilter::DEBUG,
+		_ => LevelFilter::TRACE,
+	};
+
+	tracing_subscriber::fmt()
+		.with_writer(io::stderr)
+		.with_max_level(level)
+		.init();
+
+	match run(&cli) {
+		Ok(code) => ExitCode::from(code),
+		Err(e) => {
+			eprintln!("error: {}", e);
+
+			ExitCode::from(EXIT_ERROR)
+		}
+	}
+}
+
+/// Run a command, returning the exit code.
+fn run(cli: &Cli) -> Result<u8, Error> {
+	match &cli.command {
+		Command::Hash { paths } => {
+			let mut hashed = Vec::new();
+			let mut failed = false;
+
+			for path in paths {
+				match finger(cli, path) {
+					Ok(fingerprint) => hashed.push(Hashed {
+						path: path.clone(),
+						uri: fingerprint.to_uri(),
+					}),
+					Err(e) => {
+						eprintln!("error: {}: {}", path.display(), e);
+						failed = true;
+					}
+				}
+			}
+
+			match cli.json {
+				true => println!("{}", serde_json::to_string(&hashed)?),
+				false => {
+					for hashed in &hashed {
+						println!("{}  {}", hashed.uri, hashed.path.display());
+					}
+				}
+			}
+
+			Ok(if failed { EXIT_ERROR } else { 0 })
+		}
+		Command::Compare { a, b, threshold } => {
+			let similarity = finger(cli, a)?.try_compare(&finger(cli, b)?)?;
+			let compared = Compared {
+				a: a.clone(),
+				b: b.clone(),
+				similarity,
+				threshold: *threshold,
+				similar: similarity >= *threshold,
+			};
+
+			match cli.json {
+				true => println!("{}", serde_json::to_string(&compared)?),
+				false => println!("{}", similarity),
+			}
+
+			Ok(if compared.similar {
+				0
+			} else {
+				EXIT_BELOW_THRESHOLD
+			})
+		}
+		Command::Scan { walk, out } => {
+			let mut writer = records::Writer::new(BufWriter::new(File::create(out)?));
+			let errors = walk_files(cli, walk, &mut |fingerprint| {
+				writer.write(&fingerprint, 0)?;
+
+				Ok(())
+			})?;
+			let files = writer.count();
+
+			writer.finish()?;
+
+			match cli.json {
+				true => println!(
+					"{}",
+					serde_json::json!({
+						"files": files,
+						"errors": errors.len(),
+						"failures": errors,
+						"out": out,
+					})
+				),
+				false => {
+					for failure in &errors {
+						println!("error {}: {}", failure.path.display(), failure.error);
+					}
+
+					println!(
+						"fingerprinted {} files ({} errors) to {}",
+						files,
+						errors.len(),
+						out.display()
+					)
+				}
+			}
+
+			Ok(0)
+		}
+		Command::Dedup {
+			walk,
+			threshold,
+			action,
+			dry_run,
+		} => {
+			let mut fingerprints = Vec::new();
+			let mut errors = walk_files(cli, walk, &mut |fingerprint| {
+				fingerprints.push(fingerprint);
+
+				Ok(())
+			})?;
+			let groups: Vec<Group> = group_duplicates(&fingerprints, *threshold)
+				.into_iter()
+				.map(|group| Group {
+					paths: group.paths,
+					similarity: group.similarity,
+				})
+				.collect();
+			let mut links = Vec::new();
+
+			if let Action::Hardlink = action {
+				for group in &groups {
+					for path in &group.paths[1..] {
+						match hardlink(path, &group.paths[0], *dry_run) {
+							Ok(link) => links.push(link),
+							Err(e) => {
+								tracing::warn!(path = %path.display(), "failed to link: {}", e);
+								errors.push(Failure {
+									path: path.clone(),
+									error: e.to_string(),
+								});
+							}
+						}
+					}
+				}
+			}
+
+			let report = DedupReport {
+				groups,
+				links,
+				errors,
+			};
+
+			match cli.json {
+				true => println!("{}", serde_json::to_string(&report)?),
+				false => {
+					for group in &report.groups {
+						println!("{:.4}", group.similarity);
+
+						for path in &group.paths {
+							println!("  {}", path.display());
+						}
+					}
+
+					for link in &report.links {
+						match (&link.skipped, link.performed) {
+							(Some(reason), _) => {
+								println!("skip {}: {}", link.path.display(), reason)
+							}
+							(None, true) => println!(
+								"linked {} -> {}",
+								link.path.display(),
+								link.target.display()
+							),
+							(None, false) => println!(
+								"would link {} -> {}",
+								link.path.display(),
+								link.target.display()
+							),
+						}
+					}
+				}
+			}
+
+			Ok(0)
+		}
+	}
+}
+
+/// Return the fingerprinter options chosen on the command line, or `None` to choose them from the file contents.
+fn preset(cli: &Cli) -> Option<Preset> {
+	match (cli.options, cli.r#type) {
+		(Some(preset), _) => Some(preset),
+		(None, FileType::Raw) => Some(Preset::Raw),
+		(None, FileType::Image) => Some(Preset::ImageDhash),
+		(None, FileType::Audio) => Some(Preset::AudioSpectral),
+		(None, FileType::Auto) => None,
+	}
+}
+
+/// Fingerprint a file with the options chosen on the command line.
+fn finger(cli: &Cli, path: &Path) -> Result<Fingerprint, Error> {
+	let Some(preset) = preset(cli) else {
+		return Ok(Fingerprint::finger(path)?);
+	};
+
+	tracing::debug!(path = %path.display(), ?preset, "fingerprinting");
+
+	Ok(match preset {
+		Preset::Raw => Fingerprint::finger_raw(path)?,
+		#[cfg(feature = "image")]
+		Preset::ImageDhash => Fingerprint::finger_image(path)?,
+		#[cfg(feature = "image")]
+		Preset::ImageTextRegion => Fingerprint::finger_image_text_region(path)?,
+		#[cfg(feature = "image")]
+		Preset::ImageMemeInvariant => Fingerprint::finger_image_meme_invariant(path)?,
+		#[cfg(feature = "audio")]
+		Preset::AudioSpectral => Fingerprint::finger_audio(path)?,
+		#[cfg(feature = "audio")]
+		Preset::AudioFormatInvariant => Fingerprint::finger_audio_format_invariant(path)?,
+		#[allow(unreachable_patterns)]
+		_ => return Err(format!("{:?} options are not supported by this build", preset).into()),
+	})
+}
+
+/// Return the fingerprint of a file, from its sidecar when preferred and usable, writing its sidecar when asked to.
+fn finger_with_sidecar(cli: &Cli, walk: &Walk, path: &Path) -> Result<Fingerprint, Error> {
+	if walk.prefer_sidecars {
+		let options = preset(cli).map(|preset| match preset {
+			Preset::Raw => Options::raw(),
+			Preset::ImageDhash => Options::image_dhash(),
+			Preset::ImageTextRegion => Options::image_text_region(),
+			Preset::ImageMemeInvariant => Options::image_meme_invariant(),
+			Preset::AudioSpectral => Options::audio_spectral(),
+			Preset::AudioFormatInvariant => Options::audio_format_invariant(),
+		});
+
+		match sidecar::read(path) {
+			Ok(Some(fingerprint))
+				if options.is_none_or(|options| options.tag() == fingerprint.options_tag()) =>
+			{
+				tracing::debug!(path = %path.display(), "using sidecar");
+
+				return Ok(fingerprint);
+			}
+			Ok(_) => {}
+			Err(e) => tracing::info!(path = %path.display(), "ignoring sidecar: {}", e),
+		}
+	}
+
+	let fingerprint = finger(cli, path)?;
+
+	if walk.write_sidecars {
+		if let Err(e) = sidecar::write(&fingerprint) {
+			tracing::warn!(path = %path.display(), "failed to write sidecar: {}", e);
+		}
+	}
+
+	Ok(fingerprint)
+}
+
+/// Fingerprint every file under a directory, in path order, passing each fingerprint to a callback as it is computed
+/// and returning the files that could not be fingerprinted.
+fn walk_files(
+	cli: &Cli,
+	walk: &Walk,
+	on_fingerprint: &mut dyn FnMut(Fingerprint) -> Result<(), Error>,
+) -> Result<Vec<Failure>, Error> {
+	let mut count = 0;
+	let mut failures = Vec::new();
+	let index = walk.index.as_ref().map(SqliteStore::open).transpose()?;
+
+	for path in files(&walk.dir)? {
+		// A sidecar is named after the media file next to it.
+		if path.extension() == Some(sidecar::EXTENSION.as_ref())
+			&& path.with_extension("").is_file()
+		{
+			continue;
+		}
+
+		match finger_with_sidecar(cli, walk, &path) {
+			Ok(fingerprint) => {
+				if let Some(index) = &index {
+					index.content_index().record(&fingerprint, &path)?;
+				}
+
+				count += 1;
+				on_fingerprint(fingerprint)?;
+			}
+			Err(e) => {
+				tracing::warn!(path = %path.display(), "failed to fingerprint: {}", e);
+				failures.push(Failure {
+					path,
+					error: e.to_string(),
+				});
+			}
+		}
+	}
+
+	tracing::info!(
+		files = count,
+		errors = failures.len(),
+		"fingerprinted directory"
+	);
+
+	Ok(failures)
+}
+
+/// Return whether two files have identical contents, comparing their sizes before reading them in chunks.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+	if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+		return Ok(false);
+	}
+
+	let (mut a, mut b) = (
+		BufReader::new(File::open(a)?),
+		BufReader::new(File::open(b)?),
+	);
+
+	loop {
+		let (chunk_a, chunk_b) = (a.fill_buf()?, b.fill_buf()?);
+		let length = chunk_a.len().min(chunk_b.len());
+
+		if length == 0 {
+			return Ok(chunk_a.len() == chunk_b.len());
+		}
+
+		if chunk_a[..length] != chunk_b[..length] {
+			return Ok(false);
+		}
+
+		a.consume(length);
+		b.consume(length);
+	}
+}
+
+/// Replace a file with a hard link to a target, when their contents are identical.
+fn hardlink(path: &Path, target: &Path, dry_run: bool) -> Result<Link, Error> {
+	let skipped = match same_contents(path, target)? {
+		true => None,
+		false => Some("contents differ from the target".to_string()),
+	};
+	let performed = skipped.is_none() && !dry_run;
+
+	if performed {
+		let mut temporary = path.as_os_str().to_owned();
+
+		temporary.push(".fingerprint-link");
+		fs::hard_link(target, &temporary)?;
+		fs::rename(&temporary, path)?;
+		tracing::info!(path = %path.display(), target = %target.display(), "linked duplicate");
+	}
+
+	Ok(Link {
+		path: path.to_path_buf(),
+		target: target.to_path_buf(),
+		performed,
+		skipped,
+	})
+}
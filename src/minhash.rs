@@ -0,0 +1,199 @@
+use crate::{
+	fingerprinters::{mix, RNG_SEED},
+	Fingerprint,
+};
+
+/// Precomputed MinHash sketch of a fingerprint.
+///
+/// A fingerprint is treated as the set of `(position, bit)` pairs, so two fingerprints agreeing on a fraction `a` of
+/// their bits have Jaccard similarity `a / (2 - a)`. Each sketch element is the minimum of one independently seeded
+/// hash function over that set, and two sketches match at an element with probability equal to the Jaccard
+/// similarity. Sketches of any size share their leading hash functions, and the hash functions are fixed, so sketches
+/// are comparable across runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinHashSketch {
+	minimums: Vec<u64>,
+}
+
+impl MinHashSketch {
+	/// Compute a sketch of `sketch_size` elements. Runs in O(bits × sketch_size).
+	pub fn new(fp: &Fingerprint, sketch_size: usize) -> Self {
+		let members: Vec<u64> = fp
+			.fingerprint
+			.iter()
+			.enumerate()
+			.map(|(position, bit)| mix((position as u64) << 1 | *bit as u64))
+			.collect();
+
+		let minimums = (0..sketch_size as u64)
+			.map(|index| {
+				let seed = mix(RNG_SEED ^ mix(index + 1));
+
+				members
+					.iter()
+					.map(|member| mix(member ^ seed))
+					.min()
+					.unwrap_or(u64::MAX)
+			})
+			.collect();
+
+		Self { minimums }
+	}
+
+	/// Return the number of elements in the sketch.
+	pub fn len(&self) -> usize {
+		self.minimums.len()
+	}
+
+	/// Return whether the sketch has no elements.
+	pub fn is_empty(&self) -> bool {
+		self.minimums.is_empty()
+	}
+
+	/// Estimate the Jaccard similarity of the `(position, bit)` sets of the sketched fingerprints.
+	///
+	/// The estimate is the fraction of elements at which the sketches hold the same minimum, with a standard error of
+	/// `sqrt(j × (1 - j) / sketch_size)` for Jaccard similarity `j`. Sketches of different sizes are compared over the
+	/// shorter sketch. Runs in O(sketch_size).
+	pub fn jaccard(&self, other: &MinHashSketch) -> f64 {
+		let len = self.len().min(other.len());
+
+		if len == 0 {
+			return 0f64;
+		}
+
+		let matches = self
+			.minimums
+			.iter()
+			.zip(&other.minimums)
+			.filter(|(left, right)| left == right)
+			.count();
+
+		matches as f64 / len as f64
+	}
+}
+
+/// Estimate [Fingerprint::compare] of two sketched fingerprints from their sketches in O(sketch_size).
+///
+/// The Jaccard estimate `j` is mapped back to the fraction of agreeing bits, `2j / (1 + j)`.
+pub fn compare_sketches(a: &MinHashSketch, b: &MinHashSketch) -> f64 {
+	let jaccard = a.jaccard(b);
+
+	2f64 * jaccard / (1f64 + jaccard)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use bitvec::prelude::*;
+	use rand::{Rng, SeedableRng};
+	use rand_chacha::ChaCha8Rng;
+
+	use super::{compare_sketches, MinHashSketch};
+	use crate::{options::Options, Fingerprint, Type};
+
+	/// Return the mean absolute, mean signed and largest absolute error of sketch estimates of [Fingerprint::compare]
+	/// over pairs of fingerprints.
+	fn errors(
+		fingerprints: &[Fingerprint],
+		sketches: &[MinHashSketch],
+		pairs: &[(usize, usize)],
+	) -> (f64, f64, f64) {
+		let errors: Vec<f64> = pairs
+			.iter()
+			.map(|(i, j)| {
+				compare_sketches(&sketches[*i], &sketches[*j])
+					- fingerprints[*i].compare(&fingerprints[*j])
+			})
+			.collect();
+		let count = errors.len() as f64;
+
+		(
+			errors.iter().map(|error| error.abs()).sum::<f64>() / count,
+			errors.iter().sum::<f64>() / count,
+			errors.iter().fold(0f64, |max, error| max.max(error.abs())),
+		)
+	}
+
+	#[test]
+	fn test_sketch_converges_to_compare() {
+		let mut rng = ChaCha8Rng::seed_from_u64(1);
+		let mut fingerprints = Vec::new();
+
+		// Families of fingerprints much longer than the sketches, derived from a common base with increasing numbers
+		// of flipped bits.
+		for _ in 0..20 {
+			let base: BitBox<u8> = (0..4096).map(|_| rng.gen::<bool>()).collect();
+
+			for variant in 0..10 {
+				let mut bits = base.clone();
+
+				for _ in 0..variant * 256 {
+					let index = rng.gen_range(0..bits.len());
+					let bit = bits[index];
+
+					bits.set(index, !bit);
+				}
+
+				fingerprints.push(Fingerprint {
+					path: PathBuf::new(),
+					fingerprint: bits,
+					r#type: Type::Raw,
+					options_tag: Options::raw().tag(),
+					original_version: None,
+				});
+			}
+		}
+
+		let sketches: Vec<MinHashSketch> = fingerprints
+			.iter()
+			.map(|fp| MinHashSketch::new(fp, 128))
+			.collect();
+		let pairs: Vec<(usize, usize)> = (0..fingerprints.len())
+			.flat_map(|i| (i + 1..fingerprints.len()).map(move |j| (i, j)))
+			.take(10_000)
+			.collect();
+
+		assert_eq!(pairs.len(), 10_000);
+
+		// Each estimate has a standard error of about 0.045 at 128 elements, so estimates converge on average, without
+		// bias, while single pairs stray further.
+		let (mean_absolute, mean_signed, max_absolute) = errors(&fingerprints, &sketches, &pairs);
+
+		assert!(
+			mean_absolute <= 0.05,
+			"mean absolute error {}",
+			mean_absolute
+		);
+		assert!(mean_signed.abs() <= 0.005, "mean error {}", mean_signed);
+		assert!(max_absolute <= 0.25, "largest error {}", max_absolute);
+
+		// Longer sketches converge further, with errors shrinking as the square root of their size.
+		let family = &fingerprints[..10];
+		let long: Vec<MinHashSketch> = family
+			.iter()
+			.map(|fp| MinHashSketch::new(fp, 1024))
+			.collect();
+		let pairs: Vec<(usize, usize)> = (0..family.len())
+			.flat_map(|i| (i + 1..family.len()).map(move |j| (i, j)))
+			.collect();
+		let (short_error, ..) = errors(family, &sketches[..10], &pairs);
+		let (long_error, ..) = errors(family, &long, &pairs);
+
+		assert!(long_error <= 0.02, "mean absolute error {}", long_error);
+		assert!(long_error < short_error);
+	}
+
+	#[test]
+	fn test_identical_and_prefix() {
+		let fp = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let long = MinHashSketch::new(&fp, 64);
+		let short = MinHashSketch::new(&fp, 16);
+
+		assert_eq!(compare_sketches(&long, &long), 1.0);
+		assert_eq!(compare_sketches(&long, &short), 1.0);
+		assert_eq!(short.len(), 16);
+		assert_eq!(compare_sketches(&long, &MinHashSketch::new(&fp, 0)), 0.0);
+	}
+}
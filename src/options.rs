@@ -0,0 +1,621 @@
+use std::fmt::Display;
+
+use crate::{
+	fingerprinters::{
+		sampled::{DEFAULT_INTERIOR_WINDOWS, WINDOW_BYTES},
+		RNG_SEED,
+	},
+	FingerprintError, Type, NUM_FINGERPRINT_SEGMENTS,
+};
+
+/// Largest number of segments, and so of bits, of a fingerprint.
+pub const MAX_SEGMENTS: usize = 1 << 16;
+
+/// Normalisation flag: input is converted to greyscale.
+pub const NORMALISE_GREYSCALE: u8 = 1 << 0;
+
+/// Normalisation flag: input is binarised.
+pub const NORMALISE_BINARISE: u8 = 1 << 1;
+
+/// Normalisation flag: input is rescaled to a fixed size.
+pub const NORMALISE_SCALE: u8 = 1 << 2;
+
+/// Normalisation flag: decoded audio is downmixed to mono and resampled to a fixed rate.
+pub const NORMALISE_RESAMPLE: u8 = 1 << 3;
+
+/// Hash reducing each fingerprint segment (or frame) to bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HashAlgorithm {
+	/// Bits compare the mean byte values of consecutive segments.
+	SegmentMean = 0,
+
+	/// DCT-based perceptual hash.
+	Phash = 1,
+
+	/// Bits compare the RMS energy of consecutive segments of decoded PCM.
+	SegmentRms = 2,
+
+	/// Bits are the majority votes of the hashes of word shingles.
+	SimHash = 3,
+
+	/// Each bit is the low bit of the minimum of one hash function over a set of member fingerprints.
+	MinHash = 4,
+
+	/// Bits are drawn from the CRC-32 checksums of sampled windows.
+	WindowCrc = 5,
+
+	/// Bits thermometer-code a histogram of distances between points sampled on a surface (D2 shape distribution).
+	ShapeDistribution = 6,
+
+	/// Bits compare the brightness of horizontally adjacent pixels of a downsampled image (difference hash).
+	DHash = 7,
+
+	/// Bits compare the brightness of each pixel of a downsampled image with the mean of its row (average hash).
+	AHash = 8,
+
+	/// Bits compare the spectral energy of consecutive overlapping windows of decoded PCM.
+	SpectralEnergy = 9,
+
+	/// The first byte is the tempo of decoded PCM, and the remaining bits are those of
+	/// [SpectralEnergy](Self::SpectralEnergy).
+	SpectralEnergyTempo = 10,
+}
+
+/// Strategy for choosing the parts of a file that are fingerprinted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Sampling {
+	/// The whole file is read.
+	Whole = 0,
+
+	/// Only detected text regions of an image are used.
+	TextRegions = 1,
+
+	/// Detected text regions of an image are removed before the rest is used.
+	TextStripped = 2,
+
+	/// Each tile of a grid over an image is used separately.
+	Tiles = 3,
+
+	/// Each member of an archive is fingerprinted separately.
+	Members = 4,
+
+	/// Each section of an executable is fingerprinted separately.
+	Sections = 5,
+
+	/// Only fixed windows at the ends of a file and at seeded positions inside it are read.
+	Windows = 6,
+
+	/// Each glyph outline of a font is hashed separately.
+	Glyphs = 7,
+
+	/// Points are sampled uniformly over the surface of a mesh.
+	Surface = 8,
+
+	/// Decoded text is split into segments at word boundaries.
+	Words = 9,
+}
+
+/// Every parameter of a fingerprinter that affects the fingerprint bits.
+///
+/// Fingerprints are only comparable when they were produced with the same options, which is checked through their
+/// [OptionsTag].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+	/// Number of segments (bits) in the fingerprint.
+	pub segments: u32,
+
+	/// Seed of the deterministic RNG used for segment sizing.
+	pub seed: u64,
+
+	/// Hash reducing segments or frames to bits.
+	pub hash: HashAlgorithm,
+
+	/// Size (pixels per side) of the grid hashed, or 0 when not applicable.
+	pub hash_size: u32,
+
+	/// Strategy for choosing the parts of a file that are fingerprinted.
+	pub sampling: Sampling,
+
+	/// Normalisation flags (`NORMALISE_*`) applied before hashing.
+	pub normalisation: u8,
+
+	/// Number of windows read with [Sampling::Windows], or 0 when not applicable.
+	pub windows: u32,
+
+	/// Size (bytes) of each window read with [Sampling::Windows], or 0 when not applicable.
+	pub window_size: u32,
+
+	/// Resolution (milliseconds) of the subtitle cue start times fingerprinted with their text, or 0 when not
+	/// applicable.
+	pub timing: u32,
+}
+
+impl Options {
+	/// Return the options of the raw fingerprinter.
+	pub fn raw() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::SegmentMean,
+			hash_size: 0,
+			sampling: Sampling::Whole,
+			normalisation: 0,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the [text fingerprinter](crate::fingerprinters::text::TextFingerprinter).
+	pub fn text() -> Self {
+		Self {
+			sampling: Sampling::Words,
+			..Self::raw()
+		}
+	}
+
+	/// Return the options of the
+	/// [image text region fingerprinter](crate::fingerprinters::image_text::ImageTextRegionFingerprinter).
+	pub fn image_text_region() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::Phash,
+			hash_size: 32,
+			sampling: Sampling::TextRegions,
+			normalisation: NORMALISE_GREYSCALE | NORMALISE_BINARISE | NORMALISE_SCALE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the
+	/// [meme invariant image fingerprinter](crate::fingerprinters::image_meme::ImageMemeFingerprinter).
+	pub fn image_meme_invariant() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::Phash,
+			hash_size: 32,
+			sampling: Sampling::TextStripped,
+			normalisation: NORMALISE_GREYSCALE | NORMALISE_SCALE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the [image fingerprinter](crate::fingerprinters::image::ImageFingerprinter) with
+	/// [PHashAlgorithm::DHash](crate::fingerprinters::image::PHashAlgorithm::DHash).
+	pub fn image_dhash() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::DHash,
+			hash_size: 16,
+			sampling: Sampling::Whole,
+			normalisation: NORMALISE_GREYSCALE | NORMALISE_SCALE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the [image fingerprinter](crate::fingerprinters::image::ImageFingerprinter) with
+	/// [PHashAlgorithm::AHash](crate::fingerprinters::image::PHashAlgorithm::AHash).
+	pub fn image_ahash() -> Self {
+		Self {
+			hash: HashAlgorithm::AHash,
+			..Self::image_dhash()
+		}
+	}
+
+	/// Return the options of the [depth map fingerprinter](crate::fingerprinters::image_depth::DepthMapFingerprinter).
+	pub fn image_depth_map() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::Phash,
+			hash_size: 32,
+			sampling: Sampling::Whole,
+			normalisation: NORMALISE_GREYSCALE | NORMALISE_SCALE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the [sprite sheet fingerprinter](crate::fingerprinters::sprite::SpriteSheetFingerprinter).
+	pub fn image_sprite() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::Phash,
+			hash_size: 32,
+			sampling: Sampling::Tiles,
+			normalisation: NORMALISE_GREYSCALE | NORMALISE_SCALE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the
+	/// [format invariant audio fingerprinter](crate::fingerprinters::audio::AudioFormatInvariantFingerprinter).
+	pub fn audio_format_invariant() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: 0,
+			hash: HashAlgorithm::SegmentRms,
+			hash_size: 0,
+			sampling: Sampling::Whole,
+			normalisation: NORMALISE_RESAMPLE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the [spectral audio fingerprinter](crate::fingerprinters::audio::AudioFingerprinter).
+	pub fn audio_spectral() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: 0,
+			hash: HashAlgorithm::SpectralEnergy,
+			hash_size: 32,
+			sampling: Sampling::Whole,
+			normalisation: NORMALISE_RESAMPLE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of [Fingerprint::finger_audio_with_bpm](crate::Fingerprint::finger_audio_with_bpm).
+	pub fn audio_bpm() -> Self {
+		Self {
+			hash: HashAlgorithm::SpectralEnergyTempo,
+			..Self::audio_spectral()
+		}
+	}
+
+	/// Return the options of the [SimHash text fingerprinter](crate::fingerprinters::text_simhash::SimHashTextFingerprinter).
+	pub fn text_simhash() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: 0,
+			hash: HashAlgorithm::SimHash,
+			hash_size: 0,
+			sampling: Sampling::Whole,
+			normalisation: 0,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the [subtitle fingerprinter](crate::fingerprinters::subtitle::SubtitleFingerprinter) with
+	/// cue start times quantised to `timing` milliseconds, which are those of
+	/// [text_simhash](Options::text_simhash) when 0.
+	pub fn subtitle(timing: u32) -> Self {
+		Self {
+			timing,
+			..Self::text_simhash()
+		}
+	}
+
+	/// Return the options of the summary of an [archive fingerprint](crate::fingerprinters::archive::ArchiveFingerprint).
+	pub fn archive_members() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::MinHash,
+			hash_size: 0,
+			sampling: Sampling::Members,
+			normalisation: 0,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the summary of an [executable fingerprint](crate::fingerprinters::binary::BinaryFingerprint).
+	pub fn binary_sections() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::SegmentMean,
+			hash_size: 0,
+			sampling: Sampling::Sections,
+			normalisation: 0,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the summary of a [font fingerprint](crate::fingerprinters::font::FontFingerprint).
+	pub fn font_glyphs() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::MinHash,
+			hash_size: 32,
+			sampling: Sampling::Glyphs,
+			normalisation: NORMALISE_SCALE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of the [mesh fingerprinter](crate::fingerprinters::mesh::MeshFingerprinter).
+	pub fn mesh_d2() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::ShapeDistribution,
+			hash_size: 0,
+			sampling: Sampling::Surface,
+			normalisation: NORMALISE_SCALE,
+			windows: 0,
+			window_size: 0,
+			timing: 0,
+		}
+	}
+
+	/// Return the options of a [sampled fingerprint](crate::fingerprinters::sampled::SampledFingerprint) reading
+	/// `interior_windows` windows besides the first and last.
+	pub fn sampled(interior_windows: u32) -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS as u32,
+			seed: RNG_SEED,
+			hash: HashAlgorithm::WindowCrc,
+			hash_size: 0,
+			sampling: Sampling::Windows,
+			normalisation: 0,
+			windows: interior_windows + 2,
+			window_size: WINDOW_BYTES as u32,
+			timing: 0,
+		}
+	}
+
+	/// Return the options assumed for a fingerprint of a type when none were recorded, as in fingerprints serialized
+	/// before options tags were introduced.
+	pub fn default_for(r#type: &Type) -> Self {
+		match r#type {
+			Type::Image => Self::image_dhash(),
+			Type::Audio => Self::audio_spectral(),
+			Type::Text => Self::text(),
+			Type::Mesh => Self::mesh_d2(),
+			_ => Self::raw(),
+		}
+	}
+
+	/// Return the tag identifying these options.
+	pub fn tag(&self) -> OptionsTag {
+		let mut canonical = Vec::with_capacity(27);
+
+		canonical.extend_from_slice(&self.segments.to_be_bytes());
+		canonical.extend_from_slice(&self.seed.to_be_bytes());
+		canonical.push(self.hash as u8);
+		canonical.extend_from_slice(&self.hash_size.to_be_bytes());
+		canonical.push(self.sampling as u8);
+		canonical.push(self.normalisation);
+
+		// Appended only for sampled options, so the tags of every other option set are unchanged.
+		if self.sampling == Sampling::Windows {
+			canonical.extend_from_slice(&self.windows.to_be_bytes());
+			canonical.extend_from_slice(&self.window_size.to_be_bytes());
+		}
+
+		// Appended only when set, so subtitles fingerprinted without timing keep the tag of their text.
+		if self.timing != 0 {
+			canonical.extend_from_slice(&self.timing.to_be_bytes());
+		}
+
+		// 64-bit FNV-1a, which is stable across platforms and releases.
+		let hash = canonical.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+			(hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+		});
+
+		OptionsTag(hash)
+	}
+}
+
+/// Short stable hash of the [Options] a fingerprint was produced with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OptionsTag(pub u64);
+
+impl OptionsTag {
+	/// Size (bytes) of a serialized tag.
+	pub const SIZE: usize = 8;
+
+	/// Return the serialized tag.
+	pub fn to_bytes(self) -> [u8; Self::SIZE] {
+		self.0.to_be_bytes()
+	}
+
+	/// Return the tag for serialized bytes, or `None` if they are not a serialized tag.
+	pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+		Some(Self(u64::from_be_bytes(bytes.try_into().ok()?)))
+	}
+
+	/// Return the options this tag identifies, if they are among the options of this crate's fingerprinters.
+	///
+	/// Useful for debugging incompatible fingerprints.
+	pub fn options(self) -> Option<Options> {
+		[
+			Options::raw(),
+			Options::text(),
+			Options::image_dhash(),
+			Options::image_ahash(),
+			Options::image_text_region(),
+			Options::image_meme_invariant(),
+			Options::image_depth_map(),
+			Options::image_sprite(),
+			Options::audio_format_invariant(),
+			Options::audio_spectral(),
+			Options::audio_bpm(),
+			Options::text_simhash(),
+			Options::archive_members(),
+			Options::binary_sections(),
+			Options::font_glyphs(),
+			Options::mesh_d2(),
+			Options::sampled(DEFAULT_INTERIOR_WINDOWS),
+		]
+		.into_iter()
+		.find(|options| options.tag() == self)
+	}
+}
+
+impl Display for OptionsTag {
+	/// Formats the tag as 16 hexadecimal digits.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:016x}", self.0)
+	}
+}
+
+/// Segment count and RNG seed of the raw and text fingerprinters, as used by
+/// [Fingerprint::finger_with_config](crate::Fingerprint::finger_with_config).
+///
+/// The [default](FingerprintConfig::default) is that of [Fingerprint::finger](crate::Fingerprint::finger), and is
+/// changed with builder methods, such as `FingerprintConfig::default().segments(256)?.rng_seed(42)`.
+///
+/// The configuration is recorded in the [options tag](crate::Fingerprint::options_tag) of every fingerprint produced
+/// with it. [Fingerprint::compare](crate::Fingerprint::compare) scores fingerprints of the same type but different
+/// configurations 0.0, and [Fingerprint::try_compare](crate::Fingerprint::try_compare) rejects them, failing with
+/// [CompareError::IncompatibleSegmentCount](crate::CompareError::IncompatibleSegmentCount) for different segment
+/// counts and [CompareError::IncompatibleParameters](crate::CompareError::IncompatibleParameters) for different seeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FingerprintConfig {
+	pub(crate) segments: usize,
+	pub(crate) rng_seed: u64,
+}
+
+impl Default for FingerprintConfig {
+	/// 128 segments and the seed of [Fingerprint::finger](crate::Fingerprint::finger).
+	fn default() -> Self {
+		Self {
+			segments: NUM_FINGERPRINT_SEGMENTS,
+			rng_seed: RNG_SEED,
+		}
+	}
+}
+
+impl FingerprintConfig {
+	/// Set the number of segments, which is the number of bits in the fingerprint.
+	///
+	/// Fails with [FingerprintError::InvalidData] unless the count is between 1 and [MAX_SEGMENTS].
+	pub fn segments(mut self, segments: usize) -> Result<Self, FingerprintError> {
+		if !(1..=MAX_SEGMENTS).contains(&segments) {
+			return Err(FingerprintError::InvalidData(format!(
+				"segment count {} is not between 1 and {}",
+				segments, MAX_SEGMENTS
+			)));
+		}
+
+		self.segments = segments;
+
+		Ok(self)
+	}
+
+	/// Set the seed of the deterministic RNG used for segment sizing.
+	pub fn rng_seed(mut self, rng_seed: u64) -> Self {
+		self.rng_seed = rng_seed;
+
+		self
+	}
+
+	/// Return the options of fingerprints of a type produced with this configuration: those of the
+	/// [text](Options::text) fingerprinter for text, and of the [raw](Options::raw) fingerprinter otherwise.
+	pub fn options_for(&self, r#type: &Type) -> Options {
+		let base = match r#type {
+			Type::Text => Options::text(),
+			_ => Options::raw(),
+		};
+
+		Options {
+			segments: self.segments as u32,
+			seed: self.rng_seed,
+			..base
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{HashAlgorithm, Options, OptionsTag, Sampling, NORMALISE_BINARISE};
+
+	#[test]
+	fn test_tag_changes_with_every_option() {
+		let base = Options::raw();
+		let variants = [
+			Options {
+				segments: 256,
+				..base.clone()
+			},
+			Options {
+				seed: base.seed + 1,
+				..base.clone()
+			},
+			Options {
+				hash: HashAlgorithm::Phash,
+				..base.clone()
+			},
+			Options {
+				hash_size: 8,
+				..base.clone()
+			},
+			Options {
+				sampling: Sampling::TextRegions,
+				..base.clone()
+			},
+			Options {
+				normalisation: NORMALISE_BINARISE,
+				..base.clone()
+			},
+		];
+		let sampled = Options::sampled(8);
+
+		assert_eq!(Options::subtitle(0), Options::text_simhash());
+		assert_ne!(Options::subtitle(1000).tag(), Options::text_simhash().tag());
+		assert_ne!(Options::subtitle(1000).tag(), Options::subtitle(2000).tag());
+
+		assert_ne!(sampled.tag(), Options::sampled(9).tag());
+		assert_ne!(
+			sampled.tag(),
+			Options {
+				window_size: 4096,
+				..sampled.clone()
+			}
+			.tag()
+		);
+
+		for variant in &variants {
+			assert_ne!(variant.tag(), base.tag(), "{:?}", variant);
+		}
+
+		assert_eq!(base.tag(), Options::raw().tag());
+		assert_ne!(base.tag(), Options::text().tag());
+		assert_ne!(base.tag(), Options::image_text_region().tag());
+		assert_ne!(
+			Options::image_text_region().tag(),
+			Options::image_meme_invariant().tag()
+		);
+	}
+
+	#[test]
+	fn test_inspect() {
+		let tag = Options::image_text_region().tag();
+
+		assert_eq!(OptionsTag::from_bytes(&tag.to_bytes()), Some(tag));
+		assert_eq!(OptionsTag::from_bytes(&[1, 2, 3]), None);
+		assert_eq!(tag.options(), Some(Options::image_text_region()));
+		assert_eq!(OptionsTag(42).options(), None);
+		assert_eq!(tag.to_string().len(), 16);
+	}
+}
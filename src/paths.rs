@@ -0,0 +1,99 @@
+use std::{
+	borrow::Cow,
+	fs, io,
+	path::{Path, PathBuf},
+};
+
+/// Return the bytes stored for a path.
+///
+/// On Unix these are the raw bytes of the path, so every path round-trips. Elsewhere they are the UTF-8 encoding of
+/// the path, with any unpaired surrogates of a Windows path replaced by U+FFFD.
+pub fn to_bytes(path: &Path) -> Cow<'_, [u8]> {
+	#[cfg(unix)]
+	{
+		use std::os::unix::ffi::OsStrExt;
+
+		Cow::Borrowed(path.as_os_str().as_bytes())
+	}
+
+	#[cfg(not(unix))]
+	match path.to_string_lossy() {
+		Cow::Borrowed(path) => Cow::Borrowed(path.as_bytes()),
+		Cow::Owned(path) => Cow::Owned(path.into_bytes()),
+	}
+}
+
+/// Return the path for bytes produced by [to_bytes], on this or any other platform.
+///
+/// Outside Unix, bytes that are not valid UTF-8 are replaced by U+FFFD.
+pub fn from_bytes(bytes: &[u8]) -> PathBuf {
+	#[cfg(unix)]
+	{
+		use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+		OsStr::from_bytes(bytes).into()
+	}
+
+	#[cfg(not(unix))]
+	String::from_utf8_lossy(bytes).into_owned().into()
+}
+
+/// Return every regular file under a directory, sorted, without following symbolic links.
+pub fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+	let mut files = Vec::new();
+	let mut dirs = vec![dir.to_path_buf()];
+
+	while let Some(dir) = dirs.pop() {
+		for entry in fs::read_dir(&dir)? {
+			let entry = entry?;
+			let file_type = entry.file_type()?;
+
+			if file_type.is_dir() {
+				dirs.push(entry.path());
+			} else if file_type.is_file() {
+				files.push(entry.path());
+			}
+		}
+	}
+
+	files.sort();
+
+	Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use super::{from_bytes, to_bytes};
+
+	#[test]
+	fn test_utf8_round_trip() {
+		let path = Path::new("library/ünïcödé/ascii.txt");
+
+		assert_eq!(to_bytes(path), path.to_str().unwrap().as_bytes());
+		assert_eq!(from_bytes(&to_bytes(path)), path);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_non_utf8_round_trip() {
+		let bytes = b"library/\xff\xfe.bin";
+
+		assert_eq!(to_bytes(&from_bytes(bytes)), &bytes[..]);
+	}
+
+	#[cfg(windows)]
+	#[test]
+	fn test_windows_paths() {
+		use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+		let verbatim = Path::new(r"\\?\C:\library\ascii.txt");
+
+		assert_eq!(from_bytes(&to_bytes(verbatim)), verbatim);
+
+		let unpaired = OsString::from_wide(&[0x61, 0xd800, 0x62]);
+
+		assert_eq!(to_bytes(Path::new(&unpaired)), "a\u{fffd}b".as_bytes());
+	}
+}
@@ -0,0 +1,363 @@
+use std::io::{self, Read, Write};
+
+use crate::{paths, wire, Fingerprint, FingerprintError};
+
+/// Marker preceding every record, used to resynchronise after damage.
+const SYNC: [u8; 4] = [0xf7, b'R', b'E', b'C'];
+
+/// Record length marking the end of the stream.
+const END: u32 = u32::MAX;
+
+/// Largest record body (bytes) accepted by the reader; longer lengths are treated as damage.
+const MAX_RECORD_SIZE: usize = 1 << 20;
+
+/// Size (bytes) of the chunks the reader fills its buffer with.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A fingerprint in a record stream, along with caller-defined flags.
+#[derive(Debug, Clone)]
+pub struct Record {
+	/// Fingerprint, including its path.
+	pub fingerprint: Fingerprint,
+
+	/// Caller-defined flags.
+	pub flags: u8,
+}
+
+/// Writer of a length-prefixed stream of fingerprint records.
+///
+/// Each record is the [SYNC] marker, the body length (u32, big-endian), a body of flags (1 byte), path size (u32,
+/// big-endian), path and [wire](crate::wire)-encoded fingerprint, and a CRC32 of the body. [Writer::finish] ends the
+/// stream with the marker, a length of [u32::MAX] and the number of records written (u64, big-endian).
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+	inner: W,
+	count: u64,
+}
+
+impl<W: Write> Writer<W> {
+	/// Create new writer.
+	pub fn new(inner: W) -> Self {
+		Self { inner, count: 0 }
+	}
+
+	/// Append a record to the stream.
+	///
+	/// Fails with [io::ErrorKind::InvalidInput], writing nothing, when the record body would be larger than the
+	/// [Reader] accepts.
+	pub fn write(&mut self, fingerprint: &Fingerprint, flags: u8) -> io::Result<()> {
+		let path = paths::to_bytes(&fingerprint.path);
+		let mut body = Vec::with_capacity(1 + 4 + path.len() + 64);
+
+		body.push(flags);
+		body.extend_from_slice(&(path.len() as u32).to_be_bytes());
+		body.extend_from_slice(&path);
+		body.extend_from_slice(&fingerprint.encode());
+
+		if body.len() > MAX_RECORD_SIZE {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!(
+					"record of {} bytes exceeds the maximum of {}",
+					body.len(),
+					MAX_RECORD_SIZE
+				),
+			));
+		}
+
+		self.inner.write_all(&SYNC)?;
+		self.inner.write_all(&(body.len() as u32).to_be_bytes())?;
+		self.inner.write_all(&body)?;
+		self.inner
+			.write_all(&crc32fast::hash(&body).to_be_bytes())?;
+		self.count += 1;
+
+		Ok(())
+	}
+
+	/// Return the number of records written.
+	pub fn count(&self) -> u64 {
+		self.count
+	}
+
+	/// Write the end marker and return the underlying writer.
+	pub fn finish(mut self) -> io::Result<W> {
+		self.inner.write_all(&SYNC)?;
+		self.inner.write_all(&END.to_be_bytes())?;
+		self.inner.write_all(&self.count.to_be_bytes())?;
+		self.inner.flush()?;
+
+		Ok(self.inner)
+	}
+}
+
+/// Reader of a stream produced by [Writer], iterating over its records.
+///
+/// Damaged or truncated records are skipped by scanning forward to the next [SYNC] marker, so a stream cut short by a
+/// crash yields every record written before the damage. Use [Reader::is_complete] after iterating to check whether the
+/// end marker was reached.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+	inner: R,
+	buffer: Vec<u8>,
+	start: usize,
+	eof: bool,
+	end: Option<u64>,
+	skipped: u64,
+}
+
+impl<R: Read> Reader<R> {
+	/// Create new reader.
+	pub fn new(inner: R) -> Self {
+		Self {
+			inner,
+			buffer: Vec::new(),
+			start: 0,
+			eof: false,
+			end: None,
+			skipped: 0,
+		}
+	}
+
+	/// Return whether the end marker has been read.
+	pub fn is_complete(&self) -> bool {
+		self.end.is_some()
+	}
+
+	/// Return the number of records the writer reported at the end marker, if it has been read.
+	pub fn expected_count(&self) -> Option<u64> {
+		self.end
+	}
+
+	/// Return the number of bytes skipped while resynchronising after damage.
+	pub fn skipped(&self) -> u64 {
+		self.skipped
+	}
+
+	/// Make at least `len` unread bytes available, returning `false` if the stream ends first.
+	fn fill(&mut self, len: usize) -> io::Result<bool> {
+		if self.start > 0 && self.start >= self.buffer.len() / 2 {
+			self.buffer.drain(..self.start);
+			self.start = 0;
+		}
+
+		while self.buffer.len() - self.start < len && !self.eof {
+			let filled = self.buffer.len();
+
+			self.buffer.resize(filled + CHUNK_SIZE, 0);
+
+			let read = loop {
+				match self.inner.read(&mut self.buffer[filled..]) {
+					Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+					result => break result,
+				}
+			};
+
+			self.buffer.truncate(filled + *read.as_ref().unwrap_or(&0));
+			self.eof = read? == 0;
+		}
+
+		Ok(self.buffer.len() - self.start >= len)
+	}
+
+	/// Skip the byte at the current position, then any bytes up to the next [SYNC] marker.
+	fn resync(&mut self) -> io::Result<()> {
+		self.start += 1;
+		self.skipped += 1;
+
+		while self.fill(SYNC.len())? {
+			if self.buffer[self.start..self.start + SYNC.len()] == SYNC {
+				return Ok(());
+			}
+
+			self.start += 1;
+			self.skipped += 1;
+		}
+
+		self.skipped += (self.buffer.len() - self.start) as u64;
+		self.start = self.buffer.len();
+
+		Ok(())
+	}
+
+	/// Read the next intact record, resynchronising past damage. Returns `None` at the end of the stream.
+	fn next_record(&mut self) -> Result<Option<Record>, FingerprintError> {
+		while self.end.is_none() {
+			if !self.fill(SYNC.len() + 4)? {
+				self.skipped += (self.buffer.len() - self.start) as u64;
+				self.start = self.buffer.len();
+
+				return Ok(None);
+			}
+
+			let header = &self.buffer[self.start..self.start + SYNC.len() + 4];
+
+			if header[..SYNC.len()] != SYNC {
+				self.resync()?;
+				continue;
+			}
+
+			let len = u32::from_be_bytes(header[SYNC.len()..].try_into().unwrap());
+
+			if len == END {
+				if self.fill(SYNC.len() + 4 + 8)? {
+					let count = &self.buffer[self.start + SYNC.len() + 4..][..8];
+
+					self.end = Some(u64::from_be_bytes(count.try_into().unwrap()));
+
+					return Ok(None);
+				}
+
+				self.resync()?;
+				continue;
+			}
+
+			let len = len as usize;
+
+			if len > MAX_RECORD_SIZE || !self.fill(SYNC.len() + 4 + len + 4)? {
+				self.resync()?;
+				continue;
+			}
+
+			let body_start = self.start + SYNC.len() + 4;
+			let body = &self.buffer[body_start..body_start + len];
+			let checksum = u32::from_be_bytes(
+				self.buffer[body_start + len..body_start + len + 4]
+					.try_into()
+					.unwrap(),
+			);
+
+			match (crc32fast::hash(body) == checksum)
+				.then(|| parse(body))
+				.flatten()
+			{
+				Some(record) => {
+					self.start = body_start + len + 4;
+
+					return Ok(Some(record));
+				}
+				None => self.resync()?,
+			}
+		}
+
+		Ok(None)
+	}
+}
+
+impl<R: Read> Iterator for Reader<R> {
+	type Item = Result<Record, FingerprintError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.next_record().transpose()
+	}
+}
+
+/// Parse a record body, returning `None` if it is malformed.
+fn parse(body: &[u8]) -> Option<Record> {
+	let (&flags, rest) = body.split_first()?;
+	let path_size = u32::from_be_bytes(rest.get(..4)?.try_into().unwrap()) as usize;
+	let path = rest.get(4..4 + path_size)?;
+	let mut fingerprint = wire::decode(rest.get(4 + path_size..)?).ok()?;
+
+	fingerprint.path = paths::from_bytes(path);
+
+	Some(Record { fingerprint, flags })
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{io, path::PathBuf};
+
+	use super::{Reader, Writer, MAX_RECORD_SIZE};
+	use crate::Fingerprint;
+
+	/// Write a stream of `count` copies of a sample, each with its own path and flags.
+	fn stream(count: usize) -> Vec<u8> {
+		let mut fingerprint = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let mut writer = Writer::new(Vec::new());
+
+		for index in 0..count {
+			fingerprint.path = PathBuf::from(format!("library/{:06}", index));
+			writer.write(&fingerprint, index as u8).unwrap();
+		}
+
+		writer.finish().unwrap()
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let data = stream(300_000);
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let mut reader = Reader::new(data.as_slice());
+		let mut count = 0;
+
+		for (index, record) in reader.by_ref().enumerate() {
+			let record = record.unwrap();
+
+			assert_eq!(
+				record.fingerprint.path(),
+				PathBuf::from(format!("library/{:06}", index))
+			);
+			assert_eq!(record.flags, index as u8);
+			assert_eq!(record.fingerprint.bytes(), original.bytes());
+			count += 1;
+		}
+
+		assert_eq!(count, 300_000);
+		assert!(reader.is_complete());
+		assert_eq!(reader.expected_count(), Some(300_000));
+		assert_eq!(reader.skipped(), 0);
+	}
+
+	#[test]
+	fn test_oversized() {
+		let mut fingerprint = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let mut writer = Writer::new(Vec::new());
+
+		fingerprint.path = PathBuf::from("a".repeat(MAX_RECORD_SIZE));
+
+		assert_eq!(
+			writer.write(&fingerprint, 0).unwrap_err().kind(),
+			io::ErrorKind::InvalidInput
+		);
+		assert_eq!(writer.count(), 0);
+
+		let data = writer.finish().unwrap();
+		let mut reader = Reader::new(data.as_slice());
+
+		assert_eq!(reader.by_ref().count(), 0);
+		assert_eq!(reader.expected_count(), Some(0));
+	}
+
+	#[test]
+	fn test_truncated() {
+		let data = stream(1000);
+		let record_size = (data.len() - 16) / 1000;
+		let truncated = &data[..record_size * 600 + record_size / 2];
+		let mut reader = Reader::new(truncated);
+
+		assert_eq!(reader.by_ref().map(Result::unwrap).count(), 600);
+		assert!(!reader.is_complete());
+		assert_eq!(reader.skipped(), (record_size / 2) as u64);
+	}
+
+	#[test]
+	fn test_damaged() {
+		let mut data = stream(1000);
+		let record_size = (data.len() - 16) / 1000;
+
+		data[record_size * 10 + 20] ^= 0xff;
+		data[record_size * 500 + 2] ^= 0xff;
+
+		let mut reader = Reader::new(data.as_slice());
+		let paths: Vec<PathBuf> = reader
+			.by_ref()
+			.map(|record| record.unwrap().fingerprint.path())
+			.collect();
+
+		assert_eq!(paths.len(), 998);
+		assert!(!paths.contains(&PathBuf::from("library/000010")));
+		assert!(!paths.contains(&PathBuf::from("library/000500")));
+		assert!(reader.is_complete());
+	}
+}
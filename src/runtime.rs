@@ -0,0 +1,141 @@
+use std::{env, io, path::PathBuf, sync::OnceLock};
+
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
+use crate::fingerprinters::sampled::{DEFAULT_INTERIOR_WINDOWS, DEFAULT_THRESHOLD};
+
+/// Configuration set with [Config::set_global].
+static GLOBAL: OnceLock<Config> = OnceLock::new();
+
+/// Errors configuring the runtime.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RuntimeError {
+	/// The global configuration was already set, or already read with its defaults.
+	#[error("global runtime configuration is already set")]
+	AlreadySet,
+}
+
+/// Resources fingerprinting may use on behalf of the host application.
+///
+/// Code paths that run work in parallel or create temporary files use the global configuration unless they take a
+/// configuration of their own.
+#[derive(Debug, Clone)]
+pub struct Config {
+	/// Thread pool parallel work runs on, or `None` for the global `rayon` pool.
+	#[cfg(feature = "parallel")]
+	pub thread_pool: Option<Arc<rayon::ThreadPool>>,
+
+	/// Directory temporary files are created in.
+	pub temp_dir: PathBuf,
+
+	/// Size (bytes) above which raw fingerprints are [sampled](crate::fingerprinters::sampled), or `None` to always
+	/// read the whole file.
+	pub sampling_threshold: Option<u64>,
+
+	/// Number of windows sampled inside a file, besides the first and last.
+	pub sampling_windows: u32,
+}
+
+impl Default for Config {
+	/// The global `rayon` pool, the system temporary directory, and sampling of files over [DEFAULT_THRESHOLD] bytes
+	/// with [DEFAULT_INTERIOR_WINDOWS] interior windows.
+	fn default() -> Self {
+		Self {
+			#[cfg(feature = "parallel")]
+			thread_pool: None,
+			temp_dir: env::temp_dir(),
+			sampling_threshold: Some(DEFAULT_THRESHOLD),
+			sampling_windows: DEFAULT_INTERIOR_WINDOWS,
+		}
+	}
+}
+
+impl Config {
+	/// Set the global configuration, failing if it was already set or read.
+	pub fn set_global(self) -> Result<(), RuntimeError> {
+		GLOBAL.set(self).map_err(|_| RuntimeError::AlreadySet)
+	}
+
+	/// Return the global configuration, fixing it to the defaults if it was not set.
+	pub fn global() -> &'static Self {
+		GLOBAL.get_or_init(Self::default)
+	}
+
+	/// Run `op` on the configured thread pool, so that parallel iterators inside it use that pool.
+	#[cfg(feature = "parallel")]
+	pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+		match &self.thread_pool {
+			Some(pool) => pool.install(op),
+			None => op(),
+		}
+	}
+
+	/// Create a temporary file in the configured directory, removed when dropped.
+	pub fn temp_file(&self) -> io::Result<tempfile::NamedTempFile> {
+		tempfile::NamedTempFile::new_in(&self.temp_dir)
+	}
+
+	/// Create a temporary file with a suffix, such as an extension for format detection.
+	pub fn temp_file_with_suffix(&self, suffix: &str) -> io::Result<tempfile::NamedTempFile> {
+		tempfile::Builder::new()
+			.suffix(suffix)
+			.tempfile_in(&self.temp_dir)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Config;
+
+	#[test]
+	fn test_temp_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let config = Config {
+			temp_dir: dir.path().to_path_buf(),
+			..Config::default()
+		};
+		let file = config.temp_file_with_suffix(".wav").unwrap();
+		let path = file.path().to_path_buf();
+
+		assert!(path.starts_with(dir.path()));
+		assert!(path.to_string_lossy().ends_with(".wav"));
+
+		drop(file);
+
+		assert!(!path.exists());
+	}
+
+	#[cfg(feature = "parallel")]
+	#[test]
+	fn test_thread_pool() {
+		let pool = rayon::ThreadPoolBuilder::new()
+			.num_threads(2)
+			.thread_name(|index| format!("fingerprint-test-{}", index))
+			.build()
+			.unwrap();
+		let config = Config {
+			thread_pool: Some(std::sync::Arc::new(pool)),
+			..Config::default()
+		};
+		let names: Vec<String> = config.install(|| {
+			use rayon::prelude::*;
+
+			(0..8)
+				.into_par_iter()
+				.map(|_| std::thread::current().name().unwrap_or("").to_string())
+				.collect()
+		});
+
+		assert!(names
+			.iter()
+			.all(|name| name.starts_with("fingerprint-test-")));
+
+		let fingerprints = crate::Fingerprint::finger_many(
+			&["samples/ascii.txt", "samples/missing", "samples/empty"],
+			&config,
+		);
+
+		assert!(fingerprints[0].is_ok() && fingerprints[1].is_err() && fingerprints[2].is_ok());
+	}
+}
@@ -0,0 +1,309 @@
+use std::{fmt, path::PathBuf};
+
+use bitvec::prelude::*;
+use serde::{
+	de::{self, SeqAccess, Visitor},
+	Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+	options::{Options, OptionsTag},
+	Fingerprint, Type, ALGORITHM_VERSION,
+};
+
+/// Fingerprint representation used by human-readable formats such as JSON.
+#[derive(Serialize, Deserialize)]
+struct Readable {
+	r#type: Type,
+	version: u16,
+	#[serde(default)]
+	options_tag: Option<String>,
+	bits_hex: String,
+	bit_len: u32,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	path: Option<PathBuf>,
+}
+
+/// Fingerprint representation used by binary formats such as bincode.
+#[derive(Serialize, Deserialize)]
+struct Compact {
+	r#type: Type,
+	version: u16,
+	options_tag: u64,
+	bits: Bytes,
+	bit_len: u32,
+	path: Option<PathBuf>,
+}
+
+/// Byte buffer serialized with [Serializer::serialize_bytes] rather than as a sequence.
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_bytes(&self.0)
+	}
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct BytesVisitor;
+
+		impl<'de> Visitor<'de> for BytesVisitor {
+			type Value = Bytes;
+
+			fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				write!(f, "fingerprint bytes")
+			}
+
+			fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+				Ok(Bytes(v.to_vec()))
+			}
+
+			fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+				Ok(Bytes(v))
+			}
+
+			fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+				let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+				while let Some(byte) = seq.next_element()? {
+					bytes.push(byte);
+				}
+
+				Ok(Bytes(bytes))
+			}
+		}
+
+		deserializer.deserialize_bytes(BytesVisitor)
+	}
+}
+
+/// Build fingerprint from decoded parts, checking the bit length against the payload.
+///
+/// A missing options tag is taken as the [default](Options::default_for) for the type.
+fn from_parts<E: de::Error>(
+	r#type: Type,
+	options_tag: Option<OptionsTag>,
+	bytes: Vec<u8>,
+	bit_len: u32,
+	path: Option<PathBuf>,
+) -> Result<Fingerprint, E> {
+	let bit_len = bit_len as usize;
+
+	if bit_len.div_ceil(8) != bytes.len() {
+		return Err(E::custom(format!(
+			"bit_len {} does not match {} bytes of fingerprint data",
+			bit_len,
+			bytes.len()
+		)));
+	}
+
+	let mut fingerprint = BitVec::<u8, Lsb0>::from_vec(bytes);
+
+	fingerprint.truncate(bit_len);
+
+	Ok(Fingerprint {
+		path: path.unwrap_or_default(),
+		fingerprint: fingerprint.into_boxed_bitslice(),
+		options_tag: options_tag.unwrap_or_else(|| Options::default_for(&r#type).tag()),
+		r#type,
+		original_version: None,
+	})
+}
+
+impl Serialize for Fingerprint {
+	/// Serializes the fingerprint bits as hex for human-readable formats, and as raw bytes otherwise.
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let path = match self.path.as_os_str().is_empty() {
+			true => None,
+			false => Some(self.path.clone()),
+		};
+
+		if serializer.is_human_readable() {
+			Readable {
+				r#type: self.r#type.clone(),
+				version: ALGORITHM_VERSION,
+				options_tag: Some(self.options_tag.to_string()),
+				bits_hex: self.to_string(),
+				bit_len: self.fingerprint.len() as u32,
+				path,
+			}
+			.serialize(serializer)
+		} else {
+			Compact {
+				r#type: self.r#type.clone(),
+				version: ALGORITHM_VERSION,
+				options_tag: self.options_tag.0,
+				bits: Bytes(self.bytes().to_vec()),
+				bit_len: self.fingerprint.len() as u32,
+				path,
+			}
+			.serialize(serializer)
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for Fingerprint {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		if deserializer.is_human_readable() {
+			let readable = Readable::deserialize(deserializer)?;
+			let bytes = hex::decode(&readable.bits_hex)
+				.map_err(|e| de::Error::custom(format!("invalid bits_hex: {}", e)))?;
+			let options_tag = readable
+				.options_tag
+				.map(|tag| {
+					u64::from_str_radix(&tag, 16)
+						.map(OptionsTag)
+						.map_err(|e| de::Error::custom(format!("invalid options_tag: {}", e)))
+				})
+				.transpose()?;
+
+			from_parts(
+				readable.r#type,
+				options_tag,
+				bytes,
+				readable.bit_len,
+				readable.path,
+			)
+		} else {
+			let compact = Compact::deserialize(deserializer)?;
+
+			from_parts(
+				compact.r#type,
+				Some(OptionsTag(compact.options_tag)),
+				compact.bits.0,
+				compact.bit_len,
+				compact.path,
+			)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{options::Options, Fingerprint, Type};
+
+	#[test]
+	fn test_json_round_trip() {
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let json = serde_json::to_string(&original).unwrap();
+
+		assert_eq!(
+			json,
+			r#"{"type":"raw","version":1,"options_tag":"b8dfc3d2b0e5b309","bits_hex":"6964d14b3a2bf3264db15649d5de4ad5","bit_len":128,"path":"samples/ascii.txt"}"#
+		);
+
+		let decoded: Fingerprint = serde_json::from_str(&json).unwrap();
+
+		assert_eq!(decoded.compare(&original), 1.0);
+		assert_eq!(decoded.path(), original.path());
+		assert_eq!(decoded.r#type(), Type::Raw);
+	}
+
+	#[test]
+	fn test_json_optional_path() {
+		let decoded: Fingerprint = serde_json::from_str(
+			r#"{"type":"video","version":1,"bits_hex":"6964d14b3a2bf3264db15649d5de4ad5","bit_len":128}"#,
+		)
+		.unwrap();
+
+		assert_eq!(decoded.r#type(), Type::Video);
+		assert_eq!(
+			decoded.options_tag(),
+			Options::default_for(&Type::Video).tag()
+		);
+		assert!(decoded.path().as_os_str().is_empty());
+		assert!(!serde_json::to_string(&decoded).unwrap().contains("path"));
+	}
+
+	#[test]
+	fn test_json_malformed() {
+		let error = serde_json::from_str::<Fingerprint>(
+			r#"{"type":"raw","version":1,"bits_hex":"6964zz","bit_len":24}"#,
+		)
+		.unwrap_err();
+
+		assert!(error.to_string().contains("invalid bits_hex"));
+
+		let error = serde_json::from_str::<Fingerprint>(
+			r#"{"type":"raw","version":1,"bits_hex":"6964d1","bit_len":128}"#,
+		)
+		.unwrap_err();
+
+		assert!(error.to_string().contains("does not match"));
+	}
+
+	#[test]
+	fn test_bincode_round_trip() {
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let data = bincode::serialize(&original).unwrap();
+		let decoded: Fingerprint = bincode::deserialize(&data).unwrap();
+
+		assert_eq!(decoded.compare(&original), 1.0);
+		assert_eq!(decoded.path(), original.path());
+	}
+
+	#[cfg(feature = "postcard")]
+	#[test]
+	fn test_postcard_round_trip() {
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let mut anonymous = original.clone();
+
+		anonymous.path = Default::default();
+
+		let data = anonymous.to_postcard().unwrap();
+
+		assert_eq!(data.len(), 32);
+		assert_eq!(
+			Fingerprint::from_postcard(&data)
+				.unwrap()
+				.compare(&original),
+			1.0
+		);
+
+		let data = original.to_postcard().unwrap();
+		let decoded = Fingerprint::from_postcard(&data).unwrap();
+
+		assert_eq!(data.len(), 32 + original.path().as_os_str().len() + 1);
+		assert_eq!(decoded.compare(&original), 1.0);
+		assert_eq!(decoded.path(), original.path());
+	}
+
+	#[cfg(feature = "postcard")]
+	#[test]
+	fn test_postcard_corrupted() {
+		use rand::{Rng, SeedableRng};
+
+		let data = Fingerprint::finger("samples/ascii.txt")
+			.unwrap()
+			.to_postcard()
+			.unwrap();
+		let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+		for len in 0..data.len() {
+			assert!(Fingerprint::from_postcard(&data[..len]).is_err());
+		}
+
+		for _ in 0..10_000 {
+			let mut corrupted = data.clone();
+
+			for _ in 0..rng.gen_range(1..4) {
+				let index = rng.gen_range(0..corrupted.len());
+
+				corrupted[index] = rng.gen();
+			}
+
+			if let Ok(decoded) = Fingerprint::from_postcard(&corrupted) {
+				assert_eq!(decoded.bytes().len(), decoded.bits().len().div_ceil(8));
+			}
+		}
+
+		for _ in 0..10_000 {
+			let garbage: Vec<u8> = (0..rng.gen_range(0..64)).map(|_| rng.gen()).collect();
+
+			if let Ok(decoded) = Fingerprint::from_postcard(&garbage) {
+				assert_eq!(decoded.bytes().len(), decoded.bits().len().div_ceil(8));
+			}
+		}
+	}
+}
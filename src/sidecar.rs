@@ -0,0 +1,267 @@
+use std::{
+	fmt::Display,
+	fs, io,
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	error::FingerprintContext, store::file_stats, wire, Fingerprint, FingerprintError, Stage,
+};
+
+/// Magic bytes identifying a sidecar file.
+const MAGIC: [u8; 4] = *b"FPSC";
+
+/// Current version of the sidecar format.
+const VERSION: u8 = 1;
+
+/// Size (bytes) of the sidecar header preceding the wire-encoded fingerprint.
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 8 + 8;
+
+/// Extension appended to a media file's name to form its sidecar's name.
+pub const EXTENSION: &str = "fpr";
+
+/// Errors specific to sidecar files.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SidecarError {
+	/// The sidecar header is malformed.
+	#[error("malformed sidecar file")]
+	Malformed,
+
+	/// The media file has changed since the sidecar was written.
+	#[error("sidecar is stale, media file has changed")]
+	Stale,
+
+	/// The existing sidecar was written for a newer version of the media file than the one on disk.
+	#[error("existing sidecar is newer than the media file")]
+	NewerSidecar,
+}
+
+/// Return the path of the sidecar for a media file, `<file>.fpr`.
+pub fn path<P: AsRef<Path>>(media_path: P) -> PathBuf {
+	let mut path = media_path.as_ref().as_os_str().to_owned();
+
+	path.push(".");
+	path.push(EXTENSION);
+
+	path.into()
+}
+
+/// Write a sidecar next to the fingerprinted file, returning the sidecar path.
+///
+/// The sidecar records the size and modification time of the media file along with the
+/// [wire](crate::wire)-encoded fingerprint. Writing fails with [SidecarError::NewerSidecar] rather than replacing a
+/// sidecar that was written for a more recently modified version of the media file.
+pub fn write(fingerprint: &Fingerprint) -> Result<PathBuf, FingerprintError> {
+	write_sidecar(fingerprint).at_stage(Stage::Persist, &fingerprint.path)
+}
+
+/// Write a sidecar, as [write] without the [Stage](FingerprintError::Stage) context.
+fn write_sidecar(fingerprint: &Fingerprint) -> Result<PathBuf, FingerprintError> {
+	let record = encode_record(&fingerprint.path, fingerprint)?;
+	let (_, mtime, _) = parse(&record)?;
+	let sidecar_path = path(&fingerprint.path);
+
+	match fs::read(&sidecar_path) {
+		Ok(data) => {
+			if let Ok((_, existing_mtime, _)) = parse(&data) {
+				if existing_mtime > mtime {
+					return Err(FingerprintError::from(SidecarError::NewerSidecar));
+				}
+			}
+		}
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+		Err(e) => return Err(FingerprintError::from(e)),
+	}
+
+	fs::write(&sidecar_path, record)?;
+
+	Ok(sidecar_path)
+}
+
+/// Read the sidecar of a media file.
+///
+/// Returns `None` when there is no sidecar, and fails with [SidecarError::Stale] when the media file's size or
+/// modification time no longer match those recorded in the sidecar, or with
+/// [CorruptFingerprint](crate::wire::CorruptFingerprint) when the stored fingerprint is damaged.
+pub fn read<P: AsRef<Path>>(media_path: P) -> Result<Option<Fingerprint>, FingerprintError> {
+	read_sidecar(media_path.as_ref()).at_stage(Stage::Persist, media_path)
+}
+
+/// Read a sidecar, as [read] without the [Stage](FingerprintError::Stage) context.
+fn read_sidecar(media_path: &Path) -> Result<Option<Fingerprint>, FingerprintError> {
+	let sidecar_path = path(media_path);
+
+	match fs::read(&sidecar_path) {
+		Ok(data) => Ok(Some(decode_record(
+			media_path,
+			&data,
+			sidecar_path.display(),
+		)?)),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+		Err(e) => Err(FingerprintError::from(e)),
+	}
+}
+
+/// Encode a record of a fingerprint for a media file: the media file's size and modification time, followed by the
+/// [wire](crate::wire)-encoded fingerprint.
+pub(crate) fn encode_record(
+	media_path: &Path,
+	fingerprint: &Fingerprint,
+) -> Result<Vec<u8>, FingerprintError> {
+	let (size, mtime) = file_stats(media_path)?;
+	let mut data = Vec::with_capacity(HEADER_SIZE);
+
+	data.extend_from_slice(&MAGIC);
+	data.push(VERSION);
+	data.extend_from_slice(&size.to_be_bytes());
+	data.extend_from_slice(&mtime.to_be_bytes());
+	data.extend_from_slice(&fingerprint.encode());
+
+	Ok(data)
+}
+
+/// Decode a record produced by [encode_record], failing with [SidecarError::Stale] when the media file has changed.
+///
+/// The location of the record is reported when the fingerprint is corrupt.
+pub(crate) fn decode_record<L: Display>(
+	media_path: &Path,
+	data: &[u8],
+	location: L,
+) -> Result<Fingerprint, FingerprintError> {
+	let (size, mtime, encoded) = parse(data)?;
+
+	if file_stats(media_path)? != (size, mtime) {
+		return Err(FingerprintError::from(SidecarError::Stale));
+	}
+
+	let mut fingerprint = wire::decode_stored(encoded, location)?;
+
+	fingerprint.path = media_path.into();
+
+	Ok(fingerprint)
+}
+
+/// Split sidecar data into the recorded size, modification time and encoded fingerprint.
+fn parse(data: &[u8]) -> Result<(u64, i64, &[u8]), SidecarError> {
+	if data.len() < HEADER_SIZE || data[..MAGIC.len()] != MAGIC || data[MAGIC.len()] != VERSION {
+		return Err(SidecarError::Malformed);
+	}
+
+	let size = u64::from_be_bytes(data[5..13].try_into().unwrap());
+	let mtime = i64::from_be_bytes(data[13..21].try_into().unwrap());
+
+	Ok((size, mtime, &data[HEADER_SIZE..]))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs::{self, File},
+		time::{Duration, SystemTime},
+	};
+
+	use super::SidecarError;
+	use crate::{sidecar, Fingerprint, FingerprintError, Stage};
+
+	fn set_modified(path: &std::path::Path, time: SystemTime) {
+		File::options()
+			.write(true)
+			.open(path)
+			.unwrap()
+			.set_modified(time)
+			.unwrap();
+	}
+
+	#[test]
+	fn test_write_read() {
+		let dir = tempfile::tempdir().unwrap();
+		let media = dir.path().join("ascii.txt");
+
+		fs::copy("samples/ascii.txt", &media).unwrap();
+
+		let fingerprint = Fingerprint::finger(&media).unwrap();
+
+		assert!(sidecar::read(&media).unwrap().is_none());
+		assert_eq!(
+			sidecar::write(&fingerprint).unwrap(),
+			dir.path().join("ascii.txt.fpr")
+		);
+
+		let read = sidecar::read(&media).unwrap().unwrap();
+
+		assert_eq!(read.compare(&fingerprint), 1.0);
+		assert_eq!(read.path(), media);
+	}
+
+	#[test]
+	fn test_stale() {
+		let dir = tempfile::tempdir().unwrap();
+		let media = dir.path().join("ascii.txt");
+
+		fs::copy("samples/ascii.txt", &media).unwrap();
+		set_modified(
+			&media,
+			SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000),
+		);
+		sidecar::write(&Fingerprint::finger(&media).unwrap()).unwrap();
+		set_modified(
+			&media,
+			SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000),
+		);
+
+		let error = sidecar::read(&media).unwrap_err();
+
+		assert_eq!(
+			error.downcast_ref::<SidecarError>(),
+			Some(&SidecarError::Stale)
+		);
+	}
+
+	#[test]
+	fn test_refuse_newer_sidecar() {
+		let dir = tempfile::tempdir().unwrap();
+		let media = dir.path().join("ascii.txt");
+
+		fs::copy("samples/ascii.txt", &media).unwrap();
+		set_modified(
+			&media,
+			SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000),
+		);
+		sidecar::write(&Fingerprint::finger(&media).unwrap()).unwrap();
+		set_modified(
+			&media,
+			SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000),
+		);
+
+		let error = sidecar::write(&Fingerprint::finger(&media).unwrap()).unwrap_err();
+
+		assert_eq!(
+			error.downcast_ref::<SidecarError>(),
+			Some(&SidecarError::NewerSidecar)
+		);
+	}
+
+	#[test]
+	fn test_corrupt() {
+		let dir = tempfile::tempdir().unwrap();
+		let media = dir.path().join("ascii.txt");
+
+		fs::copy("samples/ascii.txt", &media).unwrap();
+
+		let sidecar_path = sidecar::write(&Fingerprint::finger(&media).unwrap()).unwrap();
+		let mut data = fs::read(&sidecar_path).unwrap();
+		let last = data.len() - 6;
+
+		data[last] ^= 0x01;
+		fs::write(&sidecar_path, data).unwrap();
+
+		let error = sidecar::read(&media).unwrap_err();
+
+		assert_eq!(error.stage(), Some(Stage::Persist));
+		assert_eq!(error.path(), Some(media.as_path()));
+		assert!(matches!(
+			error.inner(),
+			FingerprintError::Corrupt(corrupt) if corrupt.location == sidecar_path.display().to_string()
+		));
+	}
+}
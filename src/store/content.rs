@@ -0,0 +1,183 @@
+use std::path::PathBuf;
+
+use rusqlite::params;
+
+use crate::{paths, Fingerprint, FingerprintError};
+
+use super::{unix_time, SqliteStore};
+
+/// A path seen bearing a fingerprint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathRecord {
+	/// Path of the file.
+	pub path: PathBuf,
+
+	/// Time the path was first recorded with the fingerprint (seconds since the Unix epoch).
+	pub first_seen: i64,
+
+	/// Time the path was last recorded with the fingerprint (seconds since the Unix epoch).
+	pub last_seen: i64,
+}
+
+/// Inverse index from fingerprints to every path ever seen bearing them, persisted in a [SqliteStore].
+///
+/// Fingerprints are keyed by their [wire](crate::wire) encoding, so only fingerprints with identical bits, type and
+/// options share paths.
+#[derive(Debug)]
+pub struct ContentIndex<'a> {
+	pub(super) store: &'a SqliteStore,
+}
+
+impl ContentIndex<'_> {
+	/// Record that a path was seen bearing a fingerprint now.
+	pub fn record<P: Into<PathBuf>>(
+		&self,
+		fingerprint: &Fingerprint,
+		path: P,
+	) -> Result<(), FingerprintError> {
+		self.record_at(fingerprint, path, unix_time())
+	}
+
+	/// Record that a path was seen bearing a fingerprint at a time (seconds since the Unix epoch).
+	fn record_at<P: Into<PathBuf>>(
+		&self,
+		fingerprint: &Fingerprint,
+		path: P,
+		seen_at: i64,
+	) -> Result<(), FingerprintError> {
+		self.store.connection.execute(
+			"INSERT INTO content_paths (bits, path, first_seen, last_seen) VALUES (?1, ?2, ?3, ?3)
+			ON CONFLICT (bits, path) DO UPDATE SET
+				first_seen = MIN(first_seen, excluded.first_seen),
+				last_seen = MAX(last_seen, excluded.last_seen)",
+			params![fingerprint.encode(), paths::to_bytes(&path.into()), seen_at],
+		)?;
+
+		Ok(())
+	}
+
+	/// Return every path recorded with a fingerprint, ordered by when they were first seen.
+	pub fn paths_for(
+		&self,
+		fingerprint: &Fingerprint,
+	) -> Result<Vec<PathRecord>, FingerprintError> {
+		self.paths_for_encoded(&fingerprint.encode())
+	}
+
+	/// Return every path recorded with a [wire](crate::wire)-encoded fingerprint, ordered by when they were first
+	/// seen.
+	pub fn paths_for_encoded(&self, encoded: &[u8]) -> Result<Vec<PathRecord>, FingerprintError> {
+		let mut statement = self.store.connection.prepare(
+			"SELECT path, first_seen, last_seen FROM content_paths WHERE bits = ?1 ORDER BY first_seen, path",
+		)?;
+		let records = statement
+			.query_map([encoded], |row| {
+				Ok(PathRecord {
+					path: paths::from_bytes(&row.get::<_, Vec<u8>>(0)?),
+					first_seen: row.get(1)?,
+					last_seen: row.get(2)?,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+
+		Ok(records)
+	}
+
+	/// Return the fingerprints none of whose recorded paths exist on disk any more.
+	pub fn orphans(&self) -> Result<Vec<Fingerprint>, FingerprintError> {
+		let mut statement = self
+			.store
+			.connection
+			.prepare("SELECT bits, path FROM content_paths ORDER BY bits")?;
+		let mut rows = statement.query([])?;
+		let mut orphans = Vec::new();
+		let mut current: Option<(Vec<u8>, bool)> = None;
+
+		while let Some(row) = rows.next()? {
+			let bits: Vec<u8> = row.get(0)?;
+			let exists = paths::from_bytes(&row.get::<_, Vec<u8>>(1)?).exists();
+
+			match &mut current {
+				Some((current_bits, any_exists)) if *current_bits == bits => *any_exists |= exists,
+				_ => {
+					if let Some((bits, false)) = current.replace((bits, exists)) {
+						orphans.push(Fingerprint::decode(&bits)?);
+					}
+				}
+			}
+		}
+
+		if let Some((bits, false)) = current {
+			orphans.push(Fingerprint::decode(&bits)?);
+		}
+
+		Ok(orphans)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, path::PathBuf};
+
+	use super::PathRecord;
+	use crate::{store::SqliteStore, Fingerprint};
+
+	#[test]
+	fn test_path_history() {
+		let dir = tempfile::tempdir().unwrap();
+		let store = SqliteStore::open_in_memory().unwrap();
+		let index = store.content_index();
+		let first = dir.path().join("first.txt");
+		let second = dir.path().join("second.txt");
+
+		fs::copy("samples/ascii.txt", &first).unwrap();
+
+		let fingerprint = Fingerprint::finger(&first).unwrap();
+
+		// Scan 1 sees the file at its original path, scan 2 sees it again, and before scan 3 it moves.
+		index.record_at(&fingerprint, &first, 100).unwrap();
+		index.record_at(&fingerprint, &first, 200).unwrap();
+		fs::rename(&first, &second).unwrap();
+		index.record_at(&fingerprint, &second, 300).unwrap();
+
+		assert_eq!(
+			index.paths_for(&fingerprint).unwrap(),
+			[
+				PathRecord {
+					path: first.clone(),
+					first_seen: 100,
+					last_seen: 200,
+				},
+				PathRecord {
+					path: second.clone(),
+					first_seen: 300,
+					last_seen: 300,
+				},
+			]
+		);
+		assert_eq!(
+			index
+				.paths_for_encoded(&fingerprint.encode())
+				.unwrap()
+				.len(),
+			2
+		);
+		assert!(index.orphans().unwrap().is_empty());
+
+		let other = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+
+		index
+			.record_at(&other, PathBuf::from("samples/missing.txt"), 100)
+			.unwrap();
+		fs::remove_file(&second).unwrap();
+
+		let orphans = index.orphans().unwrap();
+
+		assert_eq!(orphans.len(), 2);
+		assert!(orphans.iter().any(|orphan| orphan.compare(&other) == 1.0));
+		assert!(index
+			.paths_for(&Fingerprint::finger("samples/empty").unwrap())
+			.unwrap()
+			.is_empty());
+	}
+}
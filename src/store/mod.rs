@@ -0,0 +1,369 @@
+use std::{
+	io,
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH},
+	vec,
+};
+
+use crate::{Fingerprint, FingerprintError, Type};
+
+/// SQLite storage backend.
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+/// redb storage backend.
+#[cfg(feature = "redb")]
+mod redb;
+
+#[cfg(feature = "redb")]
+pub use self::redb::RedbStore;
+
+/// Content-addressed index of the paths bearing each fingerprint.
+#[cfg(feature = "sqlite")]
+mod content;
+
+#[cfg(feature = "sqlite")]
+pub use content::{ContentIndex, PathRecord};
+
+/// PostgreSQL storage backend.
+#[cfg(feature = "sqlx-postgres")]
+mod postgres;
+
+#[cfg(feature = "sqlx-postgres")]
+pub use postgres::{bulk_upsert_postgres, PostgresError, POSTGRES_MIGRATOR};
+
+/// Fingerprint storage keyed by path, implemented by every backend so that they are interchangeable.
+///
+/// Fingerprints are stored in the canonical [wire](crate::wire) encoding, and reading a corrupt one fails with
+/// [CorruptFingerprint](crate::wire::CorruptFingerprint).
+///
+/// A fingerprint is only written or looked up against the fingerprint stored for its path when the two can be
+/// compared: when the stored one is of the same type but has a different [options tag](Fingerprint::options_tag),
+/// [Store::upsert] and [Store::find_exact] fail with
+/// [CompareError::IncompatibleParameters](crate::CompareError::IncompatibleParameters), naming the given tag first.
+/// [Delete](Store::delete) the stored fingerprint to replace it with one produced with other options.
+pub trait Store {
+	/// Return the fingerprint stored for a path.
+	fn get(&self, path: &Path) -> Result<Option<Fingerprint>, FingerprintError>;
+
+	/// Insert a fingerprint, replacing any fingerprint already stored for the same path unless it was produced with
+	/// incompatible options.
+	///
+	/// The size and modification time of the fingerprinted file are recorded when it exists.
+	fn upsert(&self, fingerprint: &Fingerprint) -> Result<(), FingerprintError>;
+
+	/// Remove the fingerprint stored for a path, returning whether there was one.
+	fn delete(&self, path: &Path) -> Result<bool, FingerprintError>;
+
+	/// Iterate over all stored fingerprints, ordered by path.
+	fn iter(&self) -> Result<vec::IntoIter<Fingerprint>, FingerprintError>;
+
+	/// Call a function with every stored fingerprint, ordered by path, without loading them all into memory.
+	fn for_each(
+		&self,
+		f: &mut dyn FnMut(Fingerprint) -> Result<(), FingerprintError>,
+	) -> Result<(), FingerprintError>;
+
+	/// Iterate over all stored fingerprints of a type, ordered by path.
+	fn iter_by_type(&self, r#type: Type) -> Result<vec::IntoIter<Fingerprint>, FingerprintError>;
+
+	/// Return all stored fingerprints with exactly the same bits, type and parameters as the given fingerprint, ordered
+	/// by path, failing when the fingerprint stored for its path was produced with incompatible options.
+	fn find_exact(&self, fingerprint: &Fingerprint) -> Result<Vec<Fingerprint>, FingerprintError>;
+
+	/// Return the number of stored fingerprints, in total and of each type.
+	fn stats(&self) -> Result<StoreStats, FingerprintError>;
+
+	/// Run a function against the store, committing its writes together only if it succeeds.
+	///
+	/// Backends that commit every write on its own run the function directly.
+	fn in_transaction<T, F: FnOnce(&Self) -> Result<T, FingerprintError>>(
+		&self,
+		f: F,
+	) -> Result<T, FingerprintError>
+	where
+		Self: Sized,
+	{
+		f(self)
+	}
+}
+
+/// Numbers of fingerprints in a [Store].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StoreStats {
+	/// Number of stored fingerprints.
+	pub fingerprints: u64,
+
+	/// Number of stored fingerprints of each type present, ordered by type name.
+	pub by_type: Vec<(Type, u64)>,
+}
+
+/// Group of files considered duplicates of each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+	/// Paths of the duplicate files.
+	pub paths: Vec<PathBuf>,
+
+	/// Similarity score of the files in the group.
+	pub similarity: f64,
+}
+
+/// Return size (bytes) and modification time (nanoseconds since the Unix epoch) of a file.
+pub(crate) fn file_stats(path: &Path) -> io::Result<(u64, i64)> {
+	let metadata = path.metadata()?;
+	let mtime = metadata
+		.modified()?
+		.duration_since(UNIX_EPOCH)
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+	Ok((metadata.len(), mtime.as_nanos() as i64))
+}
+
+/// Fail with [CompareError::IncompatibleParameters](crate::CompareError::IncompatibleParameters) when a fingerprint
+/// cannot be compared with the fingerprint stored for its path.
+#[cfg_attr(not(any(feature = "sqlite", feature = "redb")), allow(dead_code))]
+pub(crate) fn check_stored<S: Store>(
+	store: &S,
+	fingerprint: &Fingerprint,
+) -> Result<(), FingerprintError> {
+	if let Some(stored) = store.get(&fingerprint.path)? {
+		fingerprint.check_compatible(&stored)?;
+	}
+
+	Ok(())
+}
+
+/// Return the current time (seconds since the Unix epoch).
+#[cfg_attr(not(any(feature = "sqlite", feature = "redb")), allow(dead_code))]
+pub(crate) fn unix_time() -> i64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_secs() as i64)
+		.unwrap_or_default()
+}
+
+/// Group fingerprints linked by a similarity of at least `threshold`, in the order of their first fingerprints.
+///
+/// Pairs that cannot be compared, such as fingerprints made with different options, are never linked.
+///
+/// Each group's similarity is the lowest similarity of the links joining it.
+pub fn group_duplicates(fingerprints: &[Fingerprint], threshold: f64) -> Vec<DuplicateGroup> {
+	let mut parents: Vec<usize> = (0..fingerprints.len()).collect();
+	let mut lowest = vec![1f64; fingerprints.len()];
+
+	fn root(parents: &mut [usize], mut index: usize) -> usize {
+		while parents[index] != index {
+			parents[index] = parents[parents[index]];
+			index = parents[index];
+		}
+
+		index
+	}
+
+	for (i, a) in fingerprints.iter().enumerate() {
+		for (j, b) in fingerprints.iter().enumerate().skip(i + 1) {
+			let Ok(similarity) = a.try_compare(b) else {
+				continue;
+			};
+
+			if similarity >= threshold {
+				let (i, j) = (root(&mut parents, i), root(&mut parents, j));
+
+				parents[j] = i;
+				lowest[i] = lowest[i].min(lowest[j]).min(similarity);
+			}
+		}
+	}
+
+	let mut groups: Vec<DuplicateGroup> = Vec::new();
+	let mut roots = Vec::new();
+
+	for (index, fingerprint) in fingerprints.iter().enumerate() {
+		let root = root(&mut parents, index);
+
+		match roots.iter().position(|r| *r == root) {
+			Some(position) => groups[position].paths.push(fingerprint.path()),
+			None => {
+				roots.push(root);
+				groups.push(DuplicateGroup {
+					paths: vec![fingerprint.path()],
+					similarity: lowest[root],
+				});
+			}
+		}
+	}
+
+	groups.retain(|group| group.paths.len() > 1);
+
+	groups
+}
+
+#[cfg(test)]
+mod tests {
+	#[cfg(any(feature = "sqlite", feature = "redb"))]
+	use std::path::PathBuf;
+
+	use super::group_duplicates;
+	#[cfg(any(feature = "sqlite", feature = "redb"))]
+	use super::{Store, StoreStats};
+	use crate::Fingerprint;
+	#[cfg(any(feature = "sqlite", feature = "redb"))]
+	use crate::{options::Options, CompareError, FingerprintError, Type};
+
+	/// Check the behaviour every [Store] backend shares, opening the same store again with `open` to check that
+	/// fingerprints persist.
+	#[cfg(any(feature = "sqlite", feature = "redb"))]
+	fn conformance<S: Store>(open: impl Fn() -> S) {
+		let paths = |fingerprints: Vec<Fingerprint>| -> Vec<PathBuf> {
+			fingerprints.iter().map(Fingerprint::path).collect()
+		};
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let different = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+		let empty = Fingerprint::finger("samples/empty").unwrap();
+		let page = Fingerprint::finger("samples/text/page.html").unwrap();
+		let mut copy = ascii.clone();
+
+		copy.path = PathBuf::from("copy.txt");
+
+		{
+			let store = open();
+
+			assert_eq!(store.stats().unwrap(), StoreStats::default());
+			assert_eq!(store.iter().unwrap().count(), 0);
+			assert!(store.get(&ascii.path).unwrap().is_none());
+			assert!(!store.delete(&ascii.path).unwrap());
+
+			for fingerprint in [&ascii, &copy, &empty, &page] {
+				store.upsert(fingerprint).unwrap();
+			}
+
+			let stored = store.get(&ascii.path).unwrap().unwrap();
+
+			assert_eq!(stored.compare(&ascii), 1.0);
+			assert_eq!(stored.path(), ascii.path());
+			assert_eq!(
+				paths(store.find_exact(&ascii).unwrap()),
+				[copy.path(), ascii.path()]
+			);
+
+			let mut replacement = different.clone();
+
+			replacement.path = ascii.path();
+			store.upsert(&replacement).unwrap();
+
+			assert_eq!(
+				store.get(&ascii.path).unwrap().unwrap().compare(&different),
+				1.0
+			);
+			assert_eq!(paths(store.find_exact(&ascii).unwrap()), [copy.path()]);
+			assert_eq!(paths(store.find_exact(&different).unwrap()), [ascii.path()]);
+
+			let mut retagged = page.clone();
+
+			retagged.options_tag = Options::text_simhash().tag();
+
+			for result in [
+				store.upsert(&retagged),
+				store.find_exact(&retagged).map(|_| ()),
+			] {
+				assert!(matches!(
+					result,
+					Err(FingerprintError::Compare(CompareError::IncompatibleParameters {
+						left_tag,
+						right_tag,
+					})) if left_tag == retagged.options_tag() && right_tag == page.options_tag()
+				));
+			}
+
+			assert_eq!(store.get(&page.path).unwrap().unwrap().compare(&page), 1.0);
+		}
+
+		let store = open();
+		let mut visited = Vec::new();
+
+		store
+			.for_each(&mut |fingerprint| {
+				visited.push(fingerprint.path());
+
+				Ok(())
+			})
+			.unwrap();
+
+		assert_eq!(
+			paths(store.iter().unwrap().collect()),
+			[copy.path(), ascii.path(), empty.path(), page.path()]
+		);
+		assert_eq!(visited, paths(store.iter().unwrap().collect()));
+		assert_eq!(
+			paths(store.iter_by_type(Type::Raw).unwrap().collect()),
+			[copy.path(), ascii.path(), empty.path()]
+		);
+		assert_eq!(
+			paths(store.iter_by_type(Type::Text).unwrap().collect()),
+			[page.path()]
+		);
+		assert_eq!(store.iter_by_type(Type::Video).unwrap().count(), 0);
+		assert_eq!(
+			store.stats().unwrap(),
+			StoreStats {
+				fingerprints: 4,
+				by_type: vec![(Type::Raw, 3), (Type::Text, 1)],
+			}
+		);
+
+		assert!(store.delete(&copy.path).unwrap());
+		assert!(!store.delete(&copy.path).unwrap());
+		assert!(store.get(&copy.path).unwrap().is_none());
+		assert!(store.find_exact(&ascii).unwrap().is_empty());
+		assert_eq!(store.stats().unwrap().fingerprints, 3);
+	}
+
+	#[cfg(feature = "sqlite")]
+	#[test]
+	fn test_sqlite_conformance() {
+		let dir = tempfile::tempdir().unwrap();
+
+		conformance(|| super::SqliteStore::open(dir.path().join("store.db")).unwrap());
+	}
+
+	#[cfg(feature = "redb")]
+	#[test]
+	fn test_redb_conformance() {
+		let dir = tempfile::tempdir().unwrap();
+
+		conformance(|| super::RedbStore::open(dir.path().join("store.redb")).unwrap());
+	}
+
+	#[test]
+	fn test_group_duplicates() {
+		let fingerprints: Vec<Fingerprint> = [
+			"samples/ascii.txt",
+			"samples/ascii_different.txt",
+			"samples/ascii_similar.txt",
+			"samples/ascii.txt",
+		]
+		.iter()
+		.map(|path| Fingerprint::finger_raw(path).unwrap())
+		.collect();
+		let groups = group_duplicates(&fingerprints, 0.85);
+
+		assert_eq!(groups.len(), 1);
+		assert_eq!(
+			groups[0].paths,
+			[
+				"samples/ascii.txt",
+				"samples/ascii_similar.txt",
+				"samples/ascii.txt"
+			]
+			.map(std::path::PathBuf::from)
+		);
+		assert_eq!(
+			groups[0].similarity,
+			fingerprints[0].compare(&fingerprints[2])
+		);
+		assert!(group_duplicates(&fingerprints, 1.1).is_empty());
+	}
+}
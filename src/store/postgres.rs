@@ -0,0 +1,169 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use sqlx::{migrate::Migrator, PgPool};
+
+use crate::{wire, Fingerprint, FingerprintError};
+
+/// Migrations creating the PostgreSQL `fingerprints` table, embedded from `migrations/postgres`.
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("migrations/postgres");
+
+/// Errors specific to PostgreSQL storage.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PostgresError {
+	/// A fingerprint path is not valid UTF-8 and cannot be stored as `TEXT`.
+	#[error("path {0:?} is not valid UTF-8")]
+	NonUtf8Path(PathBuf),
+}
+
+/// Insert or update a batch of fingerprints in the PostgreSQL `fingerprints` table, returning the number of rows
+/// written.
+///
+/// The batch is written with a single statement. Fingerprints are keyed by path and stored in the canonical
+/// [wire](crate::wire) encoding; `inserted_at` keeps the time a path was first inserted. When the batch holds a path
+/// more than once, the last fingerprint for it is written. Paths must be valid UTF-8. Run [POSTGRES_MIGRATOR] to
+/// create the table.
+///
+/// As with [Store::upsert](super::Store::upsert), nothing is written when a fingerprint cannot replace the one stored
+/// for its path, being of the same type but with a different [options tag](Fingerprint::options_tag): the batch fails
+/// with [CompareError::IncompatibleParameters](crate::CompareError::IncompatibleParameters).
+pub async fn bulk_upsert_postgres(
+	pool: &PgPool,
+	prints: &[Fingerprint],
+) -> Result<u64, FingerprintError> {
+	let mut paths = Vec::with_capacity(prints.len());
+	let mut kinds = Vec::with_capacity(prints.len());
+	let mut bits = Vec::with_capacity(prints.len());
+	let mut latest = Vec::with_capacity(prints.len());
+	// ON CONFLICT cannot update a row twice in one statement, so keep only the last fingerprint of each path.
+	let mut rows = HashMap::with_capacity(prints.len());
+
+	for fingerprint in prints {
+		let path = fingerprint
+			.path
+			.to_str()
+			.ok_or_else(|| PostgresError::NonUtf8Path(fingerprint.path.clone()))?;
+
+		match rows.get(path) {
+			Some(&row) => {
+				kinds[row] = fingerprint.r#type.to_string();
+				bits[row] = fingerprint.encode();
+				latest[row] = fingerprint;
+			}
+			None => {
+				rows.insert(path, paths.len());
+				paths.push(path.to_string());
+				kinds.push(fingerprint.r#type.to_string());
+				bits.push(fingerprint.encode());
+				latest.push(fingerprint);
+			}
+		}
+	}
+
+	let mut transaction = pool.begin().await?;
+	let stored: Vec<(String, Vec<u8>)> =
+		sqlx::query_as("SELECT path, bits FROM fingerprints WHERE path = ANY($1) FOR UPDATE")
+			.bind(&paths)
+			.fetch_all(&mut *transaction)
+			.await?;
+
+	for (path, encoded) in stored {
+		let stored = wire::decode_stored(&encoded, format_args!("fingerprints row {}", path))?;
+
+		latest[rows[path.as_str()]].check_compatible(&stored)?;
+	}
+
+	let result = sqlx::query(
+		"INSERT INTO fingerprints (path, kind, bits, inserted_at)
+		SELECT path, kind, bits, now() FROM UNNEST($1::TEXT[], $2::TEXT[], $3::BYTEA[]) AS batch (path, kind, bits)
+		ON CONFLICT (path) DO UPDATE SET kind = excluded.kind, bits = excluded.bits",
+	)
+	.bind(paths)
+	.bind(kinds)
+	.bind(bits)
+	.execute(&mut *transaction)
+	.await?;
+
+	transaction.commit().await?;
+
+	Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+	#[cfg(unix)]
+	use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+	use sqlx::PgPool;
+
+	use super::{bulk_upsert_postgres, PostgresError};
+	use crate::Fingerprint;
+
+	#[sqlx::test(migrations = "migrations/postgres")]
+	#[ignore = "needs a PostgreSQL server at DATABASE_URL"]
+	async fn test_bulk_upsert(pool: PgPool) {
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let empty = Fingerprint::finger("samples/empty").unwrap();
+
+		assert_eq!(
+			bulk_upsert_postgres(&pool, &[ascii.clone(), empty])
+				.await
+				.unwrap(),
+			2
+		);
+
+		let mut updated = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+
+		updated.path = ascii.path();
+
+		assert_eq!(
+			bulk_upsert_postgres(&pool, &[updated.clone()])
+				.await
+				.unwrap(),
+			1
+		);
+
+		let (count, bits): (i64, Vec<u8>) = sqlx::query_as(
+			"SELECT (SELECT COUNT(*) FROM fingerprints), bits FROM fingerprints WHERE path = $1",
+		)
+		.bind("samples/ascii.txt")
+		.fetch_one(&pool)
+		.await
+		.unwrap();
+
+		assert_eq!(count, 2);
+		assert_eq!(Fingerprint::decode(&bits).unwrap().compare(&updated), 1.0);
+		assert_eq!(
+			bulk_upsert_postgres(&pool, &[updated, ascii.clone()])
+				.await
+				.unwrap(),
+			1
+		);
+
+		let bits: Vec<u8> = sqlx::query_scalar("SELECT bits FROM fingerprints WHERE path = $1")
+			.bind("samples/ascii.txt")
+			.fetch_one(&pool)
+			.await
+			.unwrap();
+
+		assert_eq!(Fingerprint::decode(&bits).unwrap().compare(&ascii), 1.0);
+	}
+
+	#[cfg(unix)]
+	#[sqlx::test]
+	async fn test_non_utf8_path() {
+		// The path is checked before connecting, so no server is needed.
+		let pool = PgPool::connect_lazy("postgres://localhost/fingerprint").unwrap();
+		let mut fingerprint = Fingerprint::finger("samples/ascii.txt").unwrap();
+
+		fingerprint.path = OsStr::from_bytes(b"bad\xff").into();
+
+		let error = bulk_upsert_postgres(&pool, &[fingerprint.clone()])
+			.await
+			.unwrap_err();
+
+		assert_eq!(
+			error.downcast_ref(),
+			Some(&PostgresError::NonUtf8Path(fingerprint.path()))
+		);
+	}
+}
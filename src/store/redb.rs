@@ -0,0 +1,258 @@
+use std::{collections::BTreeMap, path::Path, vec};
+
+use ::redb::{
+	backends::InMemoryBackend, Database, MultimapTableDefinition, ReadableTable,
+	ReadableTableMetadata, TableDefinition, WriteTransaction,
+};
+
+use crate::{
+	paths,
+	wire::{self, CorruptFingerprint},
+	Fingerprint, FingerprintError, Type,
+};
+
+use super::{check_stored, file_stats, unix_time, Store, StoreStats};
+
+/// Stored fingerprint record: type name, bits prefix, wire-encoded fingerprint, size, modification time and creation
+/// time.
+type Record<'a> = (&'a str, u64, &'a [u8], Option<u64>, Option<i64>, i64);
+
+/// Fingerprint records keyed by path.
+const FINGERPRINTS: TableDefinition<&[u8], Record> = TableDefinition::new("fingerprints");
+
+/// Paths of the fingerprints of each type.
+const BY_TYPE: MultimapTableDefinition<&str, &[u8]> =
+	MultimapTableDefinition::new("fingerprints_by_type");
+
+/// Paths of the fingerprints with each [prefix] of their bits.
+const BY_PREFIX: MultimapTableDefinition<u64, &[u8]> =
+	MultimapTableDefinition::new("fingerprints_by_prefix");
+
+/// Fingerprint store backed by a [redb](https://www.redb.org) database, for deployments that cannot ship SQLite.
+///
+/// Fingerprints are keyed by path and stored in the canonical [wire](crate::wire) encoding, with indexes by type and
+/// by the leading bits of the fingerprint for [Store::find_exact]. Every write is a single transaction, committed
+/// only if it succeeds.
+///
+/// A store is [Send] and [Sync], and may be shared between threads.
+#[derive(Debug)]
+pub struct RedbStore {
+	database: Database,
+}
+
+impl RedbStore {
+	/// Open (creating if needed) a store at the given path.
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Self::from_database(Database::create(path)?)
+	}
+
+	/// Open a temporary store held in memory.
+	pub fn open_in_memory() -> Result<Self, FingerprintError> {
+		Self::from_database(Database::builder().create_with_backend(InMemoryBackend::new())?)
+	}
+
+	fn from_database(database: Database) -> Result<Self, FingerprintError> {
+		let transaction = database.begin_write()?;
+
+		transaction.open_table(FINGERPRINTS)?;
+		transaction.open_multimap_table(BY_TYPE)?;
+		transaction.open_multimap_table(BY_PREFIX)?;
+		transaction.commit()?;
+
+		Ok(Self { database })
+	}
+
+	/// Return the fingerprints stored at paths, in their order, that are exactly the given encoding when given one.
+	fn load<'p, I: IntoIterator<Item = &'p [u8]>>(
+		&self,
+		paths: I,
+		encoded: Option<&[u8]>,
+	) -> Result<Vec<Fingerprint>, FingerprintError> {
+		let transaction = self.database.begin_read()?;
+		let fingerprints = transaction.open_table(FINGERPRINTS)?;
+		let mut loaded = Vec::new();
+
+		for path in paths {
+			if let Some(record) = fingerprints.get(path)? {
+				let (_, _, bits, ..) = record.value();
+
+				if encoded.is_none_or(|encoded| encoded == bits) {
+					loaded.push(decode(path, bits)?);
+				}
+			}
+		}
+
+		Ok(loaded)
+	}
+
+	/// Remove the record of a path and its index entries, returning its creation time.
+	fn remove(
+		transaction: &WriteTransaction,
+		path: &[u8],
+	) -> Result<Option<i64>, FingerprintError> {
+		let mut fingerprints = transaction.open_table(FINGERPRINTS)?;
+		let Some(record) = fingerprints.remove(path)? else {
+			return Ok(None);
+		};
+		let (r#type, prefix, _, _, _, created_at) = record.value();
+
+		transaction
+			.open_multimap_table(BY_TYPE)?
+			.remove(r#type, path)?;
+		transaction
+			.open_multimap_table(BY_PREFIX)?
+			.remove(prefix, path)?;
+
+		Ok(Some(created_at))
+	}
+}
+
+impl Store for RedbStore {
+	fn get(&self, path: &Path) -> Result<Option<Fingerprint>, FingerprintError> {
+		let path = paths::to_bytes(path);
+
+		Ok(self.load([path.as_ref()], None)?.pop())
+	}
+
+	fn upsert(&self, fingerprint: &Fingerprint) -> Result<(), FingerprintError> {
+		let stats = file_stats(&fingerprint.path).ok();
+		let path = paths::to_bytes(&fingerprint.path);
+		let r#type = fingerprint.r#type.to_string();
+		let prefix = prefix(fingerprint);
+		let transaction = self.database.begin_write()?;
+
+		if let Some(record) = transaction.open_table(FINGERPRINTS)?.get(path.as_ref())? {
+			fingerprint.check_compatible(&decode(&path, record.value().2)?)?;
+		}
+
+		let created_at = Self::remove(&transaction, &path)?.unwrap_or_else(unix_time);
+
+		transaction.open_table(FINGERPRINTS)?.insert(
+			path.as_ref(),
+			(
+				r#type.as_str(),
+				prefix,
+				fingerprint.encode().as_slice(),
+				stats.map(|(size, _)| size),
+				stats.map(|(_, mtime)| mtime),
+				created_at,
+			),
+		)?;
+		transaction
+			.open_multimap_table(BY_TYPE)?
+			.insert(r#type.as_str(), path.as_ref())?;
+		transaction
+			.open_multimap_table(BY_PREFIX)?
+			.insert(prefix, path.as_ref())?;
+		transaction.commit()?;
+
+		Ok(())
+	}
+
+	fn delete(&self, path: &Path) -> Result<bool, FingerprintError> {
+		let transaction = self.database.begin_write()?;
+		let deleted = Self::remove(&transaction, &paths::to_bytes(path))?.is_some();
+
+		transaction.commit()?;
+
+		Ok(deleted)
+	}
+
+	fn iter(&self) -> Result<vec::IntoIter<Fingerprint>, FingerprintError> {
+		let transaction = self.database.begin_read()?;
+		let mut fingerprints = Vec::new();
+
+		for entry in transaction.open_table(FINGERPRINTS)?.iter()? {
+			let (path, record) = entry?;
+			let (_, _, bits, ..) = record.value();
+
+			fingerprints.push(decode(path.value(), bits)?);
+		}
+
+		Ok(fingerprints.into_iter())
+	}
+
+	fn for_each(
+		&self,
+		f: &mut dyn FnMut(Fingerprint) -> Result<(), FingerprintError>,
+	) -> Result<(), FingerprintError> {
+		let transaction = self.database.begin_read()?;
+
+		for entry in transaction.open_table(FINGERPRINTS)?.iter()? {
+			let (path, record) = entry?;
+			let (_, _, bits, ..) = record.value();
+
+			f(decode(path.value(), bits)?)?;
+		}
+
+		Ok(())
+	}
+
+	fn iter_by_type(&self, r#type: Type) -> Result<vec::IntoIter<Fingerprint>, FingerprintError> {
+		let transaction = self.database.begin_read()?;
+		let paths = transaction
+			.open_multimap_table(BY_TYPE)?
+			.get(r#type.to_string().as_str())?
+			.map(|path| Ok(path?.value().to_vec()))
+			.collect::<Result<Vec<_>, ::redb::StorageError>>()?;
+
+		Ok(self
+			.load(paths.iter().map(Vec::as_slice), None)?
+			.into_iter())
+	}
+
+	fn find_exact(&self, fingerprint: &Fingerprint) -> Result<Vec<Fingerprint>, FingerprintError> {
+		check_stored(self, fingerprint)?;
+
+		let transaction = self.database.begin_read()?;
+		let paths = transaction
+			.open_multimap_table(BY_PREFIX)?
+			.get(prefix(fingerprint))?
+			.map(|path| Ok(path?.value().to_vec()))
+			.collect::<Result<Vec<_>, ::redb::StorageError>>()?;
+
+		self.load(paths.iter().map(Vec::as_slice), Some(&fingerprint.encode()))
+	}
+
+	fn stats(&self) -> Result<StoreStats, FingerprintError> {
+		let transaction = self.database.begin_read()?;
+		let fingerprints = transaction.open_table(FINGERPRINTS)?;
+		let mut counts = BTreeMap::<String, u64>::new();
+
+		for entry in fingerprints.iter()? {
+			let (_, record) = entry?;
+
+			*counts.entry(record.value().0.to_string()).or_default() += 1;
+		}
+
+		Ok(StoreStats {
+			fingerprints: fingerprints.len()?,
+			by_type: counts
+				.into_iter()
+				.map(|(r#type, count)| Ok((r#type.parse()?, count)))
+				.collect::<Result<_, FingerprintError>>()?,
+		})
+	}
+}
+
+/// Return the leading 64 bits of a fingerprint, zero-padded, under which it is indexed for exact lookup.
+fn prefix(fingerprint: &Fingerprint) -> u64 {
+	let mut bytes = [0u8; 8];
+	let raw = fingerprint.fingerprint.as_raw_slice();
+	let len = raw.len().min(bytes.len());
+
+	bytes[..len].copy_from_slice(&raw[..len]);
+
+	u64::from_be_bytes(bytes)
+}
+
+/// Decode a stored fingerprint, reporting its path when it is corrupt.
+fn decode(path: &[u8], bits: &[u8]) -> Result<Fingerprint, CorruptFingerprint> {
+	let path = paths::from_bytes(path);
+	let mut fingerprint =
+		wire::decode_stored(bits, format_args!("fingerprints record {}", path.display()))?;
+
+	fingerprint.path = path;
+
+	Ok(fingerprint)
+}
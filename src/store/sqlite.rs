@@ -0,0 +1,508 @@
+use std::{path::Path, vec};
+
+use rusqlite::{params, Connection, OptionalExtension, Params};
+
+use crate::{
+	paths,
+	wire::{self, CorruptFingerprint},
+	Fingerprint, FingerprintError, Type, ALGORITHM_VERSION,
+};
+
+use super::{
+	check_stored, content::ContentIndex, file_stats, unix_time, DuplicateGroup, Store, StoreStats,
+};
+
+/// Embedded schema migrations, applied in order. The schema version is tracked in `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &[
+	// Version 1: fingerprints keyed by path.
+	"CREATE TABLE fingerprints (
+		id INTEGER PRIMARY KEY,
+		path BLOB NOT NULL UNIQUE,
+		type TEXT NOT NULL,
+		bits BLOB NOT NULL,
+		size INTEGER,
+		mtime INTEGER,
+		created_at INTEGER NOT NULL
+	);
+	CREATE INDEX fingerprints_type ON fingerprints (type);",
+	// Version 2: algorithm version and options tag, exact lookup, and recorded duplicate groups.
+	"ALTER TABLE fingerprints ADD COLUMN algo_version INTEGER NOT NULL DEFAULT 1;
+	ALTER TABLE fingerprints ADD COLUMN options_tag BLOB NOT NULL DEFAULT x'';
+	CREATE INDEX fingerprints_bits ON fingerprints (bits);
+	CREATE TABLE matches (
+		group_id INTEGER NOT NULL,
+		path BLOB NOT NULL,
+		similarity REAL NOT NULL,
+		created_at INTEGER NOT NULL,
+		PRIMARY KEY (group_id, path)
+	);",
+	// Version 3: paths seen bearing each fingerprint, for the content index.
+	"CREATE TABLE content_paths (
+		bits BLOB NOT NULL,
+		path BLOB NOT NULL,
+		first_seen INTEGER NOT NULL,
+		last_seen INTEGER NOT NULL,
+		PRIMARY KEY (bits, path)
+	);",
+];
+
+/// Stored fingerprint row: record id, path bytes and wire-encoded bits.
+type Row = (i64, Vec<u8>, Vec<u8>);
+
+/// Fingerprint store backed by an SQLite database.
+///
+/// Fingerprints are keyed by path and stored in the canonical [wire](crate::wire) encoding.
+///
+/// A store is [Send] but not [Sync]: open one per thread, or share one behind a [Mutex](std::sync::Mutex).
+#[derive(Debug)]
+pub struct SqliteStore {
+	pub(super) connection: Connection,
+}
+
+impl SqliteStore {
+	/// Open (creating if needed) a store at the given path, migrating its schema to the latest version.
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FingerprintError> {
+		Self::from_connection(Connection::open(path)?)
+	}
+
+	/// Open a temporary store held in memory.
+	pub fn open_in_memory() -> Result<Self, FingerprintError> {
+		Self::from_connection(Connection::open_in_memory()?)
+	}
+
+	fn from_connection(connection: Connection) -> Result<Self, FingerprintError> {
+		migrate(&connection, MIGRATIONS.len())?;
+
+		Ok(Self { connection })
+	}
+
+	/// Return the content index persisted in this store.
+	pub fn content_index(&self) -> ContentIndex<'_> {
+		ContentIndex { store: self }
+	}
+
+	/// Return the schema version of the store.
+	pub fn schema_version(&self) -> Result<usize, FingerprintError> {
+		schema_version(&self.connection)
+	}
+
+	/// Return the fingerprint stored for a path.
+	pub fn get_by_path<P: AsRef<Path>>(
+		&self,
+		path: P,
+	) -> Result<Option<Fingerprint>, FingerprintError> {
+		self.connection
+			.query_row(
+				"SELECT id, path, bits FROM fingerprints WHERE path = ?1",
+				[paths::to_bytes(path.as_ref())],
+				|row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+			)
+			.optional()?
+			.map(|(id, path, bits): (i64, Vec<u8>, Vec<u8>)| decode(id, &path, &bits))
+			.transpose()
+			.map_err(|e| e.into())
+	}
+
+	/// Load all stored fingerprints of a type, ordered by path, skipping corrupt records.
+	///
+	/// Returns the intact fingerprints along with a [CorruptFingerprint] for every skipped record.
+	pub fn load_by_type(
+		&self,
+		r#type: Type,
+	) -> Result<(Vec<Fingerprint>, Vec<CorruptFingerprint>), FingerprintError> {
+		let mut fingerprints = Vec::new();
+		let mut corrupt = Vec::new();
+
+		for (id, path, bits) in self.rows(
+			"SELECT id, path, bits FROM fingerprints WHERE type = ?1 ORDER BY path",
+			[r#type.to_string()],
+		)? {
+			match decode(id, &path, &bits) {
+				Ok(fingerprint) => fingerprints.push(fingerprint),
+				Err(e) => corrupt.push(e),
+			}
+		}
+
+		Ok((fingerprints, corrupt))
+	}
+
+	/// Record a group of duplicate files, returning the identifier of the recorded group.
+	pub fn record_group(&self, group: &DuplicateGroup) -> Result<i64, FingerprintError> {
+		let transaction = self.connection.unchecked_transaction()?;
+		let group_id: i64 = transaction.query_row(
+			"SELECT COALESCE(MAX(group_id), 0) + 1 FROM matches",
+			[],
+			|row| row.get(0),
+		)?;
+		let created_at = unix_time();
+
+		for path in &group.paths {
+			transaction.execute(
+				"INSERT OR IGNORE INTO matches (group_id, path, similarity, created_at) VALUES (?1, ?2, ?3, ?4)",
+				params![group_id, paths::to_bytes(path), group.similarity, created_at],
+			)?;
+		}
+
+		transaction.commit()?;
+
+		Ok(group_id)
+	}
+
+	/// Return a recorded group of duplicate files.
+	pub fn get_group(&self, group_id: i64) -> Result<Option<DuplicateGroup>, FingerprintError> {
+		let mut statement = self
+			.connection
+			.prepare("SELECT path, similarity FROM matches WHERE group_id = ?1 ORDER BY path")?;
+		let rows = statement
+			.query_map([group_id], |row| {
+				Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, f64>(1)?))
+			})?
+			.collect::<Result<Vec<_>, _>>()?;
+
+		Ok(rows.first().map(|(_, similarity)| DuplicateGroup {
+			paths: rows
+				.iter()
+				.map(|(path, _)| paths::from_bytes(path))
+				.collect(),
+			similarity: *similarity,
+		}))
+	}
+
+	fn query<P: Params>(
+		&self,
+		sql: &str,
+		params: P,
+	) -> Result<vec::IntoIter<Fingerprint>, FingerprintError> {
+		Ok(self
+			.rows(sql, params)?
+			.iter()
+			.map(|(id, path, bits)| decode(*id, path, bits))
+			.collect::<Result<Vec<_>, _>>()?
+			.into_iter())
+	}
+
+	/// Return the rows selected by a query.
+	fn rows<P: Params>(&self, sql: &str, params: P) -> Result<Vec<Row>, FingerprintError> {
+		let mut statement = self.connection.prepare(sql)?;
+		let rows = statement
+			.query_map(params, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+			.collect::<Result<_, _>>()?;
+
+		Ok(rows)
+	}
+}
+
+impl Store for SqliteStore {
+	fn get(&self, path: &Path) -> Result<Option<Fingerprint>, FingerprintError> {
+		self.get_by_path(path)
+	}
+
+	fn upsert(&self, fingerprint: &Fingerprint) -> Result<(), FingerprintError> {
+		let stats = file_stats(&fingerprint.path).ok();
+
+		check_stored(self, fingerprint)?;
+		self.connection.execute(
+			"INSERT INTO fingerprints (path, type, algo_version, options_tag, bits, size, mtime, created_at)
+			VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+			ON CONFLICT (path) DO UPDATE SET
+				type = excluded.type,
+				algo_version = excluded.algo_version,
+				options_tag = excluded.options_tag,
+				bits = excluded.bits,
+				size = excluded.size,
+				mtime = excluded.mtime",
+			params![
+				paths::to_bytes(&fingerprint.path),
+				fingerprint.r#type.to_string(),
+				ALGORITHM_VERSION,
+				fingerprint.options_tag.to_bytes(),
+				fingerprint.encode(),
+				stats.map(|(size, _)| size as i64),
+				stats.map(|(_, mtime)| mtime),
+				unix_time(),
+			],
+		)?;
+
+		Ok(())
+	}
+
+	fn delete(&self, path: &Path) -> Result<bool, FingerprintError> {
+		let deleted = self.connection.execute(
+			"DELETE FROM fingerprints WHERE path = ?1",
+			[paths::to_bytes(path)],
+		)?;
+
+		Ok(deleted > 0)
+	}
+
+	fn iter(&self) -> Result<vec::IntoIter<Fingerprint>, FingerprintError> {
+		self.query("SELECT id, path, bits FROM fingerprints ORDER BY path", [])
+	}
+
+	fn for_each(
+		&self,
+		f: &mut dyn FnMut(Fingerprint) -> Result<(), FingerprintError>,
+	) -> Result<(), FingerprintError> {
+		let mut statement = self
+			.connection
+			.prepare("SELECT id, path, bits FROM fingerprints ORDER BY path")?;
+		let mut rows = statement.query([])?;
+
+		while let Some(row) = rows.next()? {
+			f(decode(
+				row.get(0)?,
+				&row.get::<_, Vec<u8>>(1)?,
+				&row.get::<_, Vec<u8>>(2)?,
+			)?)?;
+		}
+
+		Ok(())
+	}
+
+	fn iter_by_type(&self, r#type: Type) -> Result<vec::IntoIter<Fingerprint>, FingerprintError> {
+		self.query(
+			"SELECT id, path, bits FROM fingerprints WHERE type = ?1 ORDER BY path",
+			[r#type.to_string()],
+		)
+	}
+
+	fn find_exact(&self, fingerprint: &Fingerprint) -> Result<Vec<Fingerprint>, FingerprintError> {
+		check_stored(self, fingerprint)?;
+
+		Ok(self
+			.query(
+				"SELECT id, path, bits FROM fingerprints WHERE bits = ?1 ORDER BY path",
+				[fingerprint.encode()],
+			)?
+			.collect())
+	}
+
+	fn stats(&self) -> Result<StoreStats, FingerprintError> {
+		let mut statement = self
+			.connection
+			.prepare("SELECT type, COUNT(*) FROM fingerprints GROUP BY type ORDER BY type")?;
+		let counts = statement
+			.query_map([], |row| {
+				Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+			})?
+			.collect::<Result<Vec<_>, _>>()?;
+		let by_type = counts
+			.into_iter()
+			.map(|(r#type, count)| Ok((r#type.parse()?, count as u64)))
+			.collect::<Result<Vec<(Type, u64)>, FingerprintError>>()?;
+
+		Ok(StoreStats {
+			fingerprints: by_type.iter().map(|(_, count)| count).sum(),
+			by_type,
+		})
+	}
+
+	fn in_transaction<T, F: FnOnce(&Self) -> Result<T, FingerprintError>>(
+		&self,
+		f: F,
+	) -> Result<T, FingerprintError> {
+		let transaction = self.connection.unchecked_transaction()?;
+		let result = f(self)?;
+
+		transaction.commit()?;
+
+		Ok(result)
+	}
+}
+
+/// Return the schema version of a database.
+fn schema_version(connection: &Connection) -> Result<usize, FingerprintError> {
+	Ok(connection.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize)
+}
+
+/// Apply pending migrations up to (and including) the target schema version.
+fn migrate(connection: &Connection, target: usize) -> Result<(), FingerprintError> {
+	for (version, migration) in MIGRATIONS
+		.iter()
+		.enumerate()
+		.take(target)
+		.skip(schema_version(connection)?)
+	{
+		let transaction = connection.unchecked_transaction()?;
+
+		transaction.execute_batch(migration)?;
+		transaction.pragma_update(None, "user_version", version as i64 + 1)?;
+		transaction.commit()?;
+	}
+
+	Ok(())
+}
+
+/// Decode a stored fingerprint row, reporting the record id and path when it is corrupt.
+fn decode(id: i64, path: &[u8], bits: &[u8]) -> Result<Fingerprint, CorruptFingerprint> {
+	let path = paths::from_bytes(path);
+	let mut fingerprint = wire::decode_stored(
+		bits,
+		format_args!("fingerprints record {} ({})", id, path.display()),
+	)?;
+
+	fingerprint.path = path;
+
+	Ok(fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use rusqlite::Connection;
+
+	use super::{migrate, SqliteStore, Store, MIGRATIONS};
+	use crate::wire::CorruptFingerprint;
+	use crate::{store::DuplicateGroup, Fingerprint, Type};
+
+	#[test]
+	fn test_upsert() {
+		let dir = tempfile::tempdir().unwrap();
+		let store = SqliteStore::open(dir.path().join("store.db")).unwrap();
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+
+		store.upsert(&original).unwrap();
+
+		let stored = store.get_by_path("samples/ascii.txt").unwrap().unwrap();
+
+		assert_eq!(stored.compare(&original), 1.0);
+		assert_eq!(stored.path(), original.path());
+
+		let (size, created_at): (i64, i64) = store
+			.connection
+			.query_row("SELECT size, created_at FROM fingerprints", [], |row| {
+				Ok((row.get(0)?, row.get(1)?))
+			})
+			.unwrap();
+
+		assert_eq!(size, 1068);
+
+		let mut updated = Fingerprint::finger("samples/ascii_different.txt").unwrap();
+
+		updated.path = original.path();
+		store.upsert(&updated).unwrap();
+
+		let stored = store.get_by_path("samples/ascii.txt").unwrap().unwrap();
+		let (count, created): (i64, i64) = store
+			.connection
+			.query_row(
+				"SELECT COUNT(*), MIN(created_at) FROM fingerprints",
+				[],
+				|row| Ok((row.get(0)?, row.get(1)?)),
+			)
+			.unwrap();
+
+		assert_eq!(count, 1);
+		assert_eq!(created, created_at);
+		assert_eq!(stored.compare(&updated), 1.0);
+		assert!(store.get_by_path("samples/missing").unwrap().is_none());
+	}
+
+	#[test]
+	fn test_find_exact_and_iter_by_type() {
+		let store = SqliteStore::open_in_memory().unwrap();
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let mut copy = ascii.clone();
+
+		copy.path = PathBuf::from("copy.txt");
+
+		for fingerprint in [
+			&ascii,
+			&copy,
+			&Fingerprint::finger("samples/empty").unwrap(),
+		] {
+			store.upsert(fingerprint).unwrap();
+		}
+
+		let exact: Vec<PathBuf> = store
+			.find_exact(&ascii)
+			.unwrap()
+			.iter()
+			.map(|fingerprint| fingerprint.path())
+			.collect();
+
+		assert_eq!(exact, [PathBuf::from("copy.txt"), ascii.path()]);
+		assert_eq!(store.iter_by_type(Type::Raw).unwrap().count(), 3);
+		assert_eq!(store.iter_by_type(Type::Video).unwrap().count(), 0);
+	}
+
+	#[test]
+	fn test_record_group() {
+		let store = SqliteStore::open_in_memory().unwrap();
+		let group = DuplicateGroup {
+			paths: vec![PathBuf::from("a"), PathBuf::from("b")],
+			similarity: 0.9,
+		};
+
+		assert_eq!(store.record_group(&group).unwrap(), 1);
+		assert_eq!(store.record_group(&group).unwrap(), 2);
+		assert_eq!(store.get_group(2).unwrap().unwrap(), group);
+		assert!(store.get_group(3).unwrap().is_none());
+	}
+
+	#[test]
+	fn test_migrate_from_version_1() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("store.db");
+		let fingerprint = Fingerprint::finger("samples/ascii.txt").unwrap();
+
+		{
+			let connection = Connection::open(&path).unwrap();
+
+			migrate(&connection, 1).unwrap();
+			connection
+				.execute(
+					"INSERT INTO fingerprints (path, type, bits, created_at) VALUES (?1, 'raw', ?2, 0)",
+					(b"samples/ascii.txt", fingerprint.encode()),
+				)
+				.unwrap();
+		}
+
+		let store = SqliteStore::open(&path).unwrap();
+
+		assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+		assert_eq!(
+			store
+				.get_by_path("samples/ascii.txt")
+				.unwrap()
+				.unwrap()
+				.compare(&fingerprint),
+			1.0
+		);
+		assert_eq!(store.find_exact(&fingerprint).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn test_corrupt() {
+		let store = SqliteStore::open_in_memory().unwrap();
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let empty = Fingerprint::finger("samples/empty").unwrap();
+
+		store.upsert(&ascii).unwrap();
+		store.upsert(&empty).unwrap();
+
+		let mut bits = ascii.encode();
+		let last = bits.len() - 6;
+
+		bits[last] ^= 0x01;
+		store
+			.connection
+			.execute("UPDATE fingerprints SET bits = ?1 WHERE id = 1", [bits])
+			.unwrap();
+
+		let error = store.get_by_path("samples/ascii.txt").unwrap_err();
+
+		assert_eq!(
+			error.downcast_ref::<CorruptFingerprint>().unwrap().location,
+			"fingerprints record 1 (samples/ascii.txt)"
+		);
+		assert!(store.iter_by_type(Type::Raw).is_err());
+
+		let (fingerprints, corrupt) = store.load_by_type(Type::Raw).unwrap();
+
+		assert_eq!(fingerprints.len(), 1);
+		assert_eq!(fingerprints[0].path(), empty.path());
+		assert_eq!(corrupt.len(), 1);
+	}
+}
@@ -0,0 +1,348 @@
+use std::{fmt::Display, path::PathBuf};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bitvec::prelude::*;
+
+use crate::{
+	options::{Options, OptionsTag, MAX_SEGMENTS},
+	Fingerprint, ParseTypeError, Type, ALGORITHM_VERSION,
+};
+
+/// Scheme prefixing every fingerprint URI.
+pub const SCHEME: &str = "fp1";
+
+/// Longest input (bytes) accepted by [parse], that of a URI of a fingerprint of [MAX_SEGMENTS] bits with every field
+/// at its longest.
+pub const MAX_LENGTH: usize = SCHEME.len()
+	+ ":video:v65535:".len()
+	+ (MAX_SEGMENTS / 8 * 4).div_ceil(3)
+	+ ":65535".len()
+	+ ":o".len()
+	+ 16;
+
+/// Field of a fingerprint URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UriField {
+	/// Scheme, [SCHEME].
+	Scheme,
+
+	/// Fingerprint type.
+	Type,
+
+	/// Algorithm version, `v` followed by the version number.
+	Version,
+
+	/// Base64url-encoded fingerprint bits.
+	Payload,
+
+	/// Bit length, present only when it is not a whole number of bytes.
+	BitLength,
+
+	/// Options tag, `o` followed by 16 hexadecimal digits, present only when not the default for the type.
+	OptionsTag,
+}
+
+impl Display for UriField {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Self::Scheme => "scheme",
+			Self::Type => "type",
+			Self::Version => "version",
+			Self::Payload => "payload",
+			Self::BitLength => "bit length",
+			Self::OptionsTag => "options tag",
+		};
+
+		write!(f, "{}", name)
+	}
+}
+
+/// Errors produced while parsing a fingerprint URI.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UriError {
+	/// Input is longer than [MAX_LENGTH].
+	#[error("fingerprint URI is {0} bytes, longer than the maximum of {MAX_LENGTH}")]
+	TooLong(usize),
+
+	/// Input ends before a required field.
+	#[error("fingerprint URI is missing the {0}")]
+	MissingField(UriField),
+
+	/// A field is present but invalid.
+	#[error("invalid {field} in fingerprint URI: {reason}")]
+	InvalidField {
+		/// Field that failed to parse.
+		field: UriField,
+
+		/// Why the field is invalid.
+		reason: String,
+	},
+
+	/// Input contains more fields than expected.
+	#[error("unexpected fields at end of fingerprint URI")]
+	TrailingFields,
+}
+
+/// Format a fingerprint as a URI.
+///
+/// The URI is `fp1:<type>:v<algorithm version>:<payload>`, where the payload is the fingerprint bits in unpadded
+/// base64url. When the bit length is not a whole number of bytes it is appended as a `:<bit length>` field, and when
+/// the [OptionsTag] is not the [default](Options::default_for) for the type it is appended as a final `:o<tag>` field.
+/// This format is stable across releases.
+pub fn format(fingerprint: &Fingerprint) -> String {
+	let mut uri = format!(
+		"{}:{}:v{}:{}",
+		SCHEME,
+		fingerprint.r#type,
+		ALGORITHM_VERSION,
+		URL_SAFE_NO_PAD.encode(fingerprint.bytes())
+	);
+
+	if !fingerprint.fingerprint.len().is_multiple_of(8) {
+		uri.push_str(&format!(":{}", fingerprint.fingerprint.len()));
+	}
+
+	if fingerprint.options_tag != Options::default_for(&fingerprint.r#type).tag() {
+		uri.push_str(&format!(":o{}", fingerprint.options_tag));
+	}
+
+	uri
+}
+
+/// Parse a fingerprint URI produced by [format]. The parsed fingerprint has an empty path.
+pub fn parse(s: &str) -> Result<Fingerprint, UriError> {
+	if s.len() > MAX_LENGTH {
+		return Err(UriError::TooLong(s.len()));
+	}
+
+	let invalid = |field, reason: String| UriError::InvalidField { field, reason };
+	let mut fields = s.split(':');
+	let mut next = |field| fields.next().ok_or(UriError::MissingField(field));
+
+	let scheme = next(UriField::Scheme)?;
+
+	if scheme != SCHEME {
+		return Err(invalid(
+			UriField::Scheme,
+			format!("expected {:?} but found {:?}", SCHEME, scheme),
+		));
+	}
+
+	let r#type: Type = next(UriField::Type)?
+		.parse()
+		.map_err(|e: ParseTypeError| invalid(UriField::Type, e.to_string()))?;
+	let version = next(UriField::Version)?;
+	let version: u16 = version
+		.strip_prefix('v')
+		.and_then(|version| version.parse().ok())
+		.ok_or_else(|| {
+			invalid(
+				UriField::Version,
+				format!("expected v<number> but found {:?}", version),
+			)
+		})?;
+
+	if version != ALGORITHM_VERSION {
+		return Err(invalid(
+			UriField::Version,
+			format!(
+				"algorithm version {} is not supported, expected {}",
+				version, ALGORITHM_VERSION
+			),
+		));
+	}
+
+	let payload = URL_SAFE_NO_PAD
+		.decode(next(UriField::Payload)?)
+		.map_err(|e| invalid(UriField::Payload, e.to_string()))?;
+	let mut field = fields.next();
+	let bit_len = match field.filter(|field| !field.starts_with('o')) {
+		Some(bit_len) => {
+			field = fields.next();
+
+			let bit_len: usize = bit_len.parse().map_err(|_| {
+				invalid(
+					UriField::BitLength,
+					format!("{:?} is not a number", bit_len),
+				)
+			})?;
+
+			if bit_len.is_multiple_of(8) || bit_len.div_ceil(8) != payload.len() {
+				return Err(invalid(
+					UriField::BitLength,
+					format!(
+						"{} bits does not match a {} byte payload",
+						bit_len,
+						payload.len()
+					),
+				));
+			}
+
+			bit_len
+		}
+		None => payload.len() * 8,
+	};
+	let options_tag = match field {
+		Some(tag) => {
+			field = fields.next();
+
+			tag.strip_prefix('o')
+				.filter(|tag| tag.len() == 16)
+				.and_then(|tag| u64::from_str_radix(tag, 16).ok())
+				.map(OptionsTag)
+				.ok_or_else(|| {
+					invalid(
+						UriField::OptionsTag,
+						format!("expected o<16 hex digits> but found {:?}", tag),
+					)
+				})?
+		}
+		None => Options::default_for(&r#type).tag(),
+	};
+
+	if field.is_some() {
+		return Err(UriError::TrailingFields);
+	}
+
+	let mut fingerprint = BitVec::<u8, Lsb0>::from_vec(payload);
+
+	fingerprint.truncate(bit_len);
+
+	Ok(Fingerprint {
+		path: PathBuf::new(),
+		fingerprint: fingerprint.into_boxed_bitslice(),
+		r#type,
+		options_tag,
+		original_version: None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use bitvec::prelude::*;
+
+	use super::{UriError, UriField, MAX_LENGTH};
+	use crate::{
+		options::{OptionsTag, MAX_SEGMENTS},
+		Fingerprint, Type,
+	};
+
+	#[test]
+	fn test_golden() {
+		let ascii = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let empty = Fingerprint::finger("samples/empty").unwrap();
+
+		assert_eq!(ascii.to_uri(), "fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q");
+		assert_eq!(empty.to_uri(), "fp1:raw:v1:Ua2azHZlmxpNTaVgVbG1Mg");
+
+		let parsed = Fingerprint::from_uri("fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q").unwrap();
+
+		assert_eq!(parsed.compare(&ascii), 1.0);
+		assert_eq!(parsed.r#type(), Type::Raw);
+
+		let mut short = Fingerprint::decode(&ascii.encode()).unwrap();
+
+		short.fingerprint = short.fingerprint[..12].into();
+		short.r#type = Type::Video;
+
+		assert_eq!(short.to_uri(), "fp1:video:v1:aQQ:12");
+		assert_eq!(
+			Fingerprint::from_uri(&short.to_uri()).unwrap().bits(),
+			short.bits()
+		);
+
+		let mut tagged = ascii.clone();
+
+		tagged.options_tag = OptionsTag(0x0123456789abcdef);
+
+		assert_eq!(
+			tagged.to_uri(),
+			"fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q:o0123456789abcdef"
+		);
+		assert_eq!(
+			Fingerprint::from_uri(&tagged.to_uri())
+				.unwrap()
+				.options_tag(),
+			tagged.options_tag()
+		);
+
+		short.options_tag = tagged.options_tag;
+
+		assert_eq!(
+			Fingerprint::from_uri(&short.to_uri())
+				.unwrap()
+				.options_tag(),
+			tagged.options_tag()
+		);
+	}
+
+	#[test]
+	fn test_longest() {
+		let mut longest = Fingerprint::finger("samples/ascii.txt").unwrap();
+
+		longest.fingerprint = bitbox![u8, Lsb0; 1; MAX_SEGMENTS - 1];
+		longest.r#type = Type::Video;
+		longest.options_tag = OptionsTag(u64::MAX);
+
+		let uri = longest.to_uri();
+
+		assert!(uri.len() <= MAX_LENGTH, "{} > {}", uri.len(), MAX_LENGTH);
+		assert_eq!(Fingerprint::from_uri(&uri).unwrap().bits(), longest.bits());
+	}
+
+	#[test]
+	fn test_errors() {
+		let parse = |s: &str| {
+			Fingerprint::from_uri(s)
+				.unwrap_err()
+				.downcast_ref::<UriError>()
+				.unwrap()
+				.clone()
+		};
+		let field = |s: &str| match parse(s) {
+			UriError::InvalidField { field, .. } => field,
+			e => panic!("unexpected error {:?}", e),
+		};
+
+		assert_eq!(
+			parse(&"a".repeat(MAX_LENGTH + 1)),
+			UriError::TooLong(MAX_LENGTH + 1)
+		);
+		assert_eq!(parse("fp1:raw"), UriError::MissingField(UriField::Version));
+		assert_eq!(
+			parse("fp1:raw:v1"),
+			UriError::MissingField(UriField::Payload)
+		);
+		assert_eq!(field("fp2:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q"), UriField::Scheme);
+		assert_eq!(field("fp1:rar:v1:aWTRSzor8yZNsVZJ1d5K1Q"), UriField::Type);
+		assert_eq!(field("fp1:raw:1:aWTRSzor8yZNsVZJ1d5K1Q"), UriField::Version);
+		assert_eq!(
+			field("fp1:raw:v9:aWTRSzor8yZNsVZJ1d5K1Q"),
+			UriField::Version
+		);
+		assert_eq!(
+			field("fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q=="),
+			UriField::Payload
+		);
+		assert_eq!(
+			field("fp1:raw:v1:aWTR+zor8yZNsVZJ1d5K1Q"),
+			UriField::Payload
+		);
+		assert_eq!(
+			field("fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q:x"),
+			UriField::BitLength
+		);
+		assert_eq!(
+			field("fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q:12"),
+			UriField::BitLength
+		);
+		assert_eq!(
+			field("fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q:o12"),
+			UriField::OptionsTag
+		);
+		assert_eq!(
+			parse("fp1:video:v1:aQQ:12:o0123456789abcdef:0"),
+			UriError::TrailingFields
+		);
+	}
+}
@@ -0,0 +1,403 @@
+use std::{fmt::Display, path::PathBuf};
+
+use bitvec::prelude::*;
+
+use crate::{
+	options::{Options, OptionsTag, MAX_SEGMENTS},
+	Fingerprint, Type, ALGORITHM_VERSION,
+};
+
+/// Magic bytes identifying an encoded fingerprint.
+pub const MAGIC: [u8; 4] = *b"FPRT";
+
+/// Current version of the wire format.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Size (bytes) of the fixed header preceding the options tag.
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 1 + 2 + 1;
+
+/// Size (bytes) of the trailing CRC32 checksum.
+const CHECKSUM_SIZE: usize = 4;
+
+/// Largest size (bytes) of an encoded fingerprint, that of one with [MAX_SEGMENTS] bits and the longest options tag.
+pub const MAX_ENCODED_SIZE: usize =
+	HEADER_SIZE + u8::MAX as usize + 4 + MAX_SEGMENTS / 8 + CHECKSUM_SIZE;
+
+/// Errors produced while decoding the wire format.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WireError {
+	/// Input does not start with [MAGIC].
+	#[error("bad magic, not an encoded fingerprint")]
+	BadMagic,
+
+	/// Input was encoded with a format version this crate cannot read.
+	#[error("unsupported wire format version {found}, newest supported is {max_supported}")]
+	UnsupportedVersion {
+		/// Format version of the input.
+		found: u8,
+
+		/// Newest format version this crate can read.
+		max_supported: u8,
+	},
+
+	/// Input was produced by a fingerprinting algorithm whose fingerprints cannot be compared with this crate's.
+	#[error("fingerprint of algorithm version {found}, expected {expected}")]
+	IncompatibleAlgorithm {
+		/// Algorithm version of the input.
+		found: u16,
+
+		/// Algorithm version of this crate.
+		expected: u16,
+	},
+
+	/// Input contains an unknown fingerprint type tag.
+	#[error("unknown fingerprint type tag {0}")]
+	UnknownType(u8),
+
+	/// Input ended before the complete fingerprint was read.
+	#[error("encoded fingerprint is truncated")]
+	Truncated,
+
+	/// Input contains an options tag of an unexpected size.
+	#[error("invalid {0} byte options tag")]
+	InvalidOptionsTag(usize),
+
+	/// Input contains a fingerprint with no bits, or more than [MAX_SEGMENTS].
+	#[error("invalid fingerprint of {0} bits, expected 1 to {max}", max = MAX_SEGMENTS)]
+	InvalidLength(usize),
+
+	/// Input contains bytes after the checksum.
+	#[error("{0} unexpected bytes after encoded fingerprint")]
+	TrailingBytes(usize),
+
+	/// Stored checksum does not match the checksum of the decoded bytes.
+	#[error("checksum mismatch, expected {expected:08x} but found {found:08x}")]
+	ChecksumMismatch {
+		/// Checksum stored in the input.
+		expected: u32,
+
+		/// Checksum computed over the input.
+		found: u32,
+	},
+}
+
+/// A stored fingerprint that failed to decode, such as one damaged by bit rot or truncation.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("corrupt fingerprint in {location}: {error}")]
+pub struct CorruptFingerprint {
+	/// Where the fingerprint is stored, such as a sidecar path or a database record.
+	pub location: String,
+
+	/// Error produced while decoding the stored fingerprint.
+	#[source]
+	pub error: WireError,
+}
+
+/// Return the wire tag of a fingerprint type.
+fn type_tag(r#type: &Type) -> u8 {
+	match r#type {
+		Type::Raw => 0,
+		Type::Text => 1,
+		Type::Image => 2,
+		Type::Audio => 3,
+		Type::Video => 4,
+		Type::Mesh => 5,
+	}
+}
+
+/// Return the fingerprint type of a wire tag.
+fn tag_type(tag: u8) -> Result<Type, WireError> {
+	match tag {
+		0 => Ok(Type::Raw),
+		1 => Ok(Type::Text),
+		2 => Ok(Type::Image),
+		3 => Ok(Type::Audio),
+		4 => Ok(Type::Video),
+		5 => Ok(Type::Mesh),
+		_ => Err(WireError::UnknownType(tag)),
+	}
+}
+
+/// Cursor over encoded bytes, failing with [WireError::Truncated] on short reads.
+struct Reader<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, len: usize) -> Result<&'a [u8], WireError> {
+		let bytes = self
+			.data
+			.get(self.pos..self.pos + len)
+			.ok_or(WireError::Truncated)?;
+
+		self.pos += len;
+
+		Ok(bytes)
+	}
+
+	fn u8(&mut self) -> Result<u8, WireError> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn u16(&mut self) -> Result<u16, WireError> {
+		Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+	}
+
+	fn u32(&mut self) -> Result<u32, WireError> {
+		Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+	}
+}
+
+/// Encode a fingerprint into the wire format.
+///
+/// Layout (multi-byte integers are big-endian):
+///
+/// | Field            | Size                |
+/// |------------------|---------------------|
+/// | magic `FPRT`     | 4                   |
+/// | format version   | 1                   |
+/// | type tag         | 1                   |
+/// | algorithm version| 2                   |
+/// | options tag size | 1                   |
+/// | options tag      | options tag size    |
+/// | bit length       | 4                   |
+/// | payload          | ceil(bit length / 8)|
+/// | CRC32            | 4                   |
+///
+/// The options tag is the 8-byte [OptionsTag] of the fingerprint. The CRC32 covers every preceding byte. The file
+/// path is not encoded.
+pub fn encode(fingerprint: &Fingerprint) -> Vec<u8> {
+	let payload = fingerprint.bytes();
+	let options_tag = fingerprint.options_tag.to_bytes();
+	let mut data =
+		Vec::with_capacity(HEADER_SIZE + options_tag.len() + 4 + payload.len() + CHECKSUM_SIZE);
+
+	data.extend_from_slice(&MAGIC);
+	data.push(FORMAT_VERSION);
+	data.push(type_tag(&fingerprint.r#type));
+	data.extend_from_slice(&ALGORITHM_VERSION.to_be_bytes());
+	data.push(options_tag.len() as u8);
+	data.extend_from_slice(&options_tag);
+	data.extend_from_slice(&(fingerprint.fingerprint.len() as u32).to_be_bytes());
+	data.extend_from_slice(payload);
+	data.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+
+	data
+}
+
+/// Decode a fingerprint from the wire format. The decoded fingerprint has an empty path.
+///
+/// Every format version up to [FORMAT_VERSION] is decoded by its own decoder and upgraded to the current in-memory
+/// representation. The version read is available from [Fingerprint::original_version].
+pub fn decode(data: &[u8]) -> Result<Fingerprint, WireError> {
+	let mut reader = Reader { data, pos: 0 };
+
+	if reader.take(MAGIC.len()).map_err(|_| WireError::BadMagic)? != MAGIC {
+		return Err(WireError::BadMagic);
+	}
+
+	let version = reader.u8()?;
+	let mut fingerprint = match version {
+		1 => decode_v1(&mut reader)?,
+		_ => {
+			return Err(WireError::UnsupportedVersion {
+				found: version,
+				max_supported: FORMAT_VERSION,
+			})
+		}
+	};
+
+	fingerprint.original_version = Some(version);
+
+	Ok(fingerprint)
+}
+
+/// Decode a stored fingerprint, failing with [CorruptFingerprint] at the given location.
+pub(crate) fn decode_stored<L: Display>(
+	data: &[u8],
+	location: L,
+) -> Result<Fingerprint, CorruptFingerprint> {
+	decode(data).map_err(|error| CorruptFingerprint {
+		location: location.to_string(),
+		error,
+	})
+}
+
+/// Decode the remainder of a version 1 encoding, following the format version.
+fn decode_v1(reader: &mut Reader) -> Result<Fingerprint, WireError> {
+	let r#type = tag_type(reader.u8()?)?;
+	let algorithm_version = reader.u16()?;
+	let options_tag_size = reader.u8()? as usize;
+	let options_tag = reader.take(options_tag_size)?;
+	let bit_len = reader.u32()? as usize;
+
+	if !(1..=MAX_SEGMENTS).contains(&bit_len) {
+		return Err(WireError::InvalidLength(bit_len));
+	}
+
+	let payload = reader.take(bit_len.div_ceil(8))?;
+	let found = crc32fast::hash(&reader.data[..reader.pos]);
+	let expected = reader.u32()?;
+
+	if reader.pos < reader.data.len() {
+		return Err(WireError::TrailingBytes(reader.data.len() - reader.pos));
+	}
+
+	if expected != found {
+		return Err(WireError::ChecksumMismatch { expected, found });
+	}
+
+	if algorithm_version != ALGORITHM_VERSION {
+		return Err(WireError::IncompatibleAlgorithm {
+			found: algorithm_version,
+			expected: ALGORITHM_VERSION,
+		});
+	}
+
+	// Fingerprints encoded before options tags were introduced carry an empty tag.
+	let options_tag = match options_tag.is_empty() {
+		true => Options::default_for(&r#type).tag(),
+		false => OptionsTag::from_bytes(options_tag)
+			.ok_or(WireError::InvalidOptionsTag(options_tag.len()))?,
+	};
+	let mut fingerprint = BitVec::<u8, Lsb0>::from_slice(payload);
+
+	fingerprint.truncate(bit_len);
+
+	Ok(Fingerprint {
+		path: PathBuf::new(),
+		fingerprint: fingerprint.into_boxed_bitslice(),
+		r#type,
+		options_tag,
+		original_version: None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		options::{Options, MAX_SEGMENTS},
+		wire::{decode_stored, CorruptFingerprint, WireError},
+		Fingerprint, Type, ALGORITHM_VERSION,
+	};
+
+	const GOLDEN_ASCII: &str =
+		"465052540100000108b8dfc3d2b0e5b309000000806964d14b3a2bf3264db15649d5de4ad58aa7f5f1";
+
+	#[test]
+	fn test_encode_golden() {
+		let fingerprint = Fingerprint::finger("samples/ascii.txt").unwrap();
+
+		assert_eq!(hex::encode(fingerprint.encode()), GOLDEN_ASCII);
+	}
+
+	#[test]
+	fn test_decode_golden() {
+		let original = Fingerprint::finger("samples/ascii.txt").unwrap();
+		let decoded = Fingerprint::decode(&hex::decode(GOLDEN_ASCII).unwrap()).unwrap();
+
+		assert_eq!(decoded.r#type(), Type::Raw);
+		assert_eq!(decoded.to_string(), original.to_string());
+		assert_eq!(decoded.compare(&original), 1.0);
+	}
+
+	#[test]
+	fn test_decode_errors() {
+		let golden = hex::decode(GOLDEN_ASCII).unwrap();
+		let decode = |data: &[u8]| {
+			Fingerprint::decode(data)
+				.unwrap_err()
+				.downcast_ref::<WireError>()
+				.unwrap()
+				.clone()
+		};
+
+		assert_eq!(decode(b"FPR"), WireError::BadMagic);
+		assert_eq!(decode(b"JUNKJUNK"), WireError::BadMagic);
+
+		let mut data = golden.clone();
+		data[4] = 9;
+		assert_eq!(
+			decode(&data),
+			WireError::UnsupportedVersion {
+				found: 9,
+				max_supported: 1
+			}
+		);
+
+		data[4] = 0;
+		assert_eq!(
+			decode(&data),
+			WireError::UnsupportedVersion {
+				found: 0,
+				max_supported: 1
+			}
+		);
+
+		let mut data = golden.clone();
+		data[5] = 42;
+		assert_eq!(decode(&data), WireError::UnknownType(42));
+
+		let mut data = golden.clone();
+		data[6..8].copy_from_slice(&(ALGORITHM_VERSION + 1).to_be_bytes());
+		data.truncate(data.len() - 4);
+		data.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+		assert_eq!(
+			decode(&data),
+			WireError::IncompatibleAlgorithm {
+				found: ALGORITHM_VERSION + 1,
+				expected: ALGORITHM_VERSION
+			}
+		);
+
+		let mut data = golden.clone();
+		data[8] = 3;
+		data.drain(9..14);
+		data.truncate(data.len() - 4);
+		data.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+		assert_eq!(decode(&data), WireError::InvalidOptionsTag(3));
+
+		for bit_len in [0, MAX_SEGMENTS + 1] {
+			let mut data = golden[..17].to_vec();
+			data.extend_from_slice(&(bit_len as u32).to_be_bytes());
+			data.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+			assert_eq!(decode(&data), WireError::InvalidLength(bit_len));
+			assert_eq!(
+				decode_stored(&data, "record 1").unwrap_err(),
+				CorruptFingerprint {
+					location: "record 1".to_string(),
+					error: WireError::InvalidLength(bit_len),
+				}
+			);
+		}
+
+		for len in 5..golden.len() {
+			assert_eq!(decode(&golden[..len]), WireError::Truncated);
+		}
+
+		let mut data = golden.clone();
+		data.push(0);
+		assert_eq!(decode(&data), WireError::TrailingBytes(1));
+
+		let mut data = golden.clone();
+		data[25] ^= 0xff;
+		assert!(matches!(decode(&data), WireError::ChecksumMismatch { .. }));
+	}
+
+	#[test]
+	fn test_decode_v1_fixtures() {
+		for (fixture, sample) in [
+			("samples/wire/v1_ascii.fpr", "samples/ascii.txt"),
+			("samples/wire/v1_empty.fpr", "samples/empty"),
+		] {
+			let decoded = Fingerprint::decode(&std::fs::read(fixture).unwrap()).unwrap();
+			let computed = Fingerprint::finger(sample).unwrap();
+
+			assert_eq!(decoded.original_version(), Some(1));
+			assert_eq!(decoded.options_tag(), Options::raw().tag());
+			assert_eq!(decoded.compare(&computed), 1.0);
+			assert_eq!(computed.original_version(), None);
+		}
+	}
+}
@@ -0,0 +1,121 @@
+use std::{io, path::Path};
+
+use crate::{
+	error::FingerprintContext,
+	sidecar::{decode_record, encode_record},
+	Fingerprint, FingerprintError, Stage,
+};
+
+/// Name of the extended attribute holding the fingerprint.
+pub const ATTRIBUTE: &str = "user.fingerprint";
+
+/// Errors specific to extended attribute storage.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum XattrError {
+	/// The filesystem does not support extended attributes.
+	#[error("extended attributes are not supported")]
+	XattrUnsupported,
+}
+
+/// Map filesystem errors rejecting extended attributes to [XattrError::XattrUnsupported].
+fn map_unsupported(e: io::Error) -> FingerprintError {
+	match e.kind() {
+		io::ErrorKind::Unsupported => XattrError::XattrUnsupported.into(),
+		_ => e.into(),
+	}
+}
+
+/// Attach a fingerprint to a file in the [ATTRIBUTE] extended attribute.
+///
+/// The attribute holds the same record as a [sidecar](crate::sidecar): the file's size and modification time
+/// followed by the [wire](crate::wire)-encoded fingerprint.
+pub fn set<P: AsRef<Path>>(path: P, fingerprint: &Fingerprint) -> Result<(), FingerprintError> {
+	encode_record(path.as_ref(), fingerprint)
+		.and_then(|record| xattr::set(&path, ATTRIBUTE, &record).map_err(map_unsupported))
+		.at_stage(Stage::Persist, path)
+}
+
+/// Return the fingerprint attached to a file.
+///
+/// Returns `None` when the file has no fingerprint attribute, and fails with
+/// [SidecarError::Stale](crate::sidecar::SidecarError::Stale) when the file has changed since the attribute was set.
+pub fn get<P: AsRef<Path>>(path: P) -> Result<Option<Fingerprint>, FingerprintError> {
+	let path = path.as_ref();
+	let record = xattr::get(path, ATTRIBUTE)
+		.map_err(map_unsupported)
+		.at_stage(Stage::Persist, path)?;
+
+	record
+		.map(|record| {
+			decode_record(
+				path,
+				&record,
+				format_args!("{} attribute of {}", ATTRIBUTE, path.display()),
+			)
+			.at_stage(Stage::Persist, path)
+		})
+		.transpose()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs::{self, File},
+		time::{Duration, SystemTime},
+	};
+
+	use super::{XattrError, ATTRIBUTE};
+	use crate::{sidecar::SidecarError, xattr_store, Fingerprint, FingerprintError};
+
+	#[test]
+	fn test_set_get() {
+		let dir = tempfile::tempdir().unwrap();
+		let media = dir.path().join("ascii.txt");
+
+		fs::copy("samples/ascii.txt", &media).unwrap();
+
+		let fingerprint = Fingerprint::finger(&media).unwrap();
+
+		match xattr_store::set(&media, &fingerprint) {
+			Err(e) if e.downcast_ref() == Some(&XattrError::XattrUnsupported) => {
+				eprintln!(
+					"skipping, extended attributes are not supported on {:?}",
+					dir.path()
+				);
+
+				return;
+			}
+			result => result.unwrap(),
+		}
+
+		let read = xattr_store::get(&media).unwrap().unwrap();
+
+		assert_eq!(read.compare(&fingerprint), 1.0);
+		assert_eq!(read.path(), media);
+
+		let mut record = xattr::get(&media, ATTRIBUTE).unwrap().unwrap();
+		let last = record.len() - 6;
+
+		record[last] ^= 0x01;
+		xattr::set(&media, ATTRIBUTE, &record).unwrap();
+
+		let error = xattr_store::get(&media).unwrap_err();
+
+		assert!(matches!(
+			error.inner(),
+			FingerprintError::Corrupt(corrupt) if corrupt.location.starts_with(ATTRIBUTE)
+		));
+
+		File::options()
+			.write(true)
+			.open(&media)
+			.unwrap()
+			.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000))
+			.unwrap();
+
+		let error = xattr_store::get(&media).unwrap_err();
+
+		assert_eq!(error.downcast_ref(), Some(&SidecarError::Stale));
+		assert!(xattr_store::get("samples/empty").unwrap().is_none());
+	}
+}
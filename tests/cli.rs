@@ -0,0 +1,306 @@
+#![cfg(feature = "cli")]
+
+use assert_cmd::Command;
+use fingerprint::records::Reader;
+use predicates::prelude::*;
+use std::path::Path;
+
+fn fingerprint() -> Command {
+	Command::cargo_bin("fingerprint").unwrap()
+}
+
+/// Read the record stream written by `scan`, returning the encoded fingerprints.
+fn read_records(path: &Path) -> Vec<Vec<u8>> {
+	let mut reader = Reader::new(std::fs::File::open(path).unwrap());
+	let encoded = reader
+		.by_ref()
+		.map(|record| record.unwrap().fingerprint.encode())
+		.collect();
+
+	assert!(reader.is_complete());
+
+	encoded
+}
+
+#[test]
+fn test_hash() {
+	fingerprint()
+		.args(["hash", "samples/ascii.txt", "samples/empty"])
+		.assert()
+		.success()
+		.stdout(predicate::str::starts_with("fp1:raw:v1:"))
+		.stdout(predicate::str::contains("samples/empty"));
+}
+
+#[test]
+fn test_hash_json() {
+	let output = fingerprint()
+		.args(["hash", "--json", "samples/ascii.txt"])
+		.output()
+		.unwrap();
+	let hashed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+	assert!(output.status.success());
+	assert_eq!(hashed[0]["path"], "samples/ascii.txt");
+	assert!(hashed[0]["uri"]
+		.as_str()
+		.unwrap()
+		.starts_with("fp1:raw:v1:"));
+}
+
+#[test]
+fn test_hash_missing() {
+	fingerprint()
+		.args(["hash", "samples/missing"])
+		.assert()
+		.code(2)
+		.stdout("")
+		.stderr(predicate::str::contains("samples/missing"));
+}
+
+#[test]
+fn test_compare_threshold() {
+	fingerprint()
+		.args(["compare", "samples/ascii.txt", "samples/ascii.txt"])
+		.assert()
+		.code(0)
+		.stdout("1\n");
+	fingerprint()
+		.args([
+			"compare",
+			"samples/ascii.txt",
+			"samples/ascii_different.txt",
+		])
+		.assert()
+		.code(1);
+	fingerprint()
+		.args([
+			"compare",
+			"samples/ascii.txt",
+			"samples/ascii_different.txt",
+			"--threshold",
+			"0",
+		])
+		.assert()
+		.code(0);
+}
+
+#[test]
+fn test_usage_error() {
+	fingerprint().arg("unknown").assert().code(2);
+}
+
+#[test]
+fn test_scan() {
+	let dir = tempfile::tempdir().unwrap();
+	let out = dir.path().join("fingerprints.rec");
+
+	fingerprint()
+		.args(["--json", "scan", "samples/wire", "--out"])
+		.arg(&out)
+		.assert()
+		.success()
+		.stdout(predicate::str::contains(r#""errors":0"#));
+
+	assert_eq!(read_records(&out).len(), 2);
+}
+
+#[test]
+fn test_scan_sidecars() {
+	let dir = tempfile::tempdir().unwrap();
+	let media = dir.path().join("media");
+	let path = media.join("a.txt");
+	let scan = |args: &[&str]| {
+		let out = dir.path().join("fingerprints.rec");
+
+		fingerprint()
+			.arg("scan")
+			.arg(&media)
+			.arg("--out")
+			.arg(&out)
+			.args(args)
+			.assert()
+			.success();
+
+		read_records(&out)
+	};
+
+	std::fs::create_dir(&media).unwrap();
+	std::fs::copy("samples/ascii.txt", &path).unwrap();
+
+	let written = scan(&["--write-sidecars"]);
+
+	assert!(media.join("a.txt.fpr").exists());
+	assert_eq!(scan(&[]), written);
+
+	// Same size and modification time, so the sidecar still looks up to date.
+	let modified = path.metadata().unwrap().modified().unwrap();
+	let mut contents = std::fs::read(&path).unwrap();
+
+	contents.reverse();
+	std::fs::write(&path, contents).unwrap();
+	std::fs::File::options()
+		.write(true)
+		.open(&path)
+		.unwrap()
+		.set_modified(modified)
+		.unwrap();
+
+	assert_eq!(scan(&["--prefer-sidecars"]), written);
+	assert_eq!(scan(&["--prefer-sidecars", "--type", "raw"]), written);
+	assert_ne!(scan(&[]), written);
+}
+
+#[test]
+fn test_scan_index() {
+	let dir = tempfile::tempdir().unwrap();
+	let media = dir.path().join("media");
+	let index = dir.path().join("index.db");
+	let (before, after) = (media.join("a.txt"), media.join("moved/a.txt"));
+	let scan = || {
+		fingerprint()
+			.arg("scan")
+			.arg(&media)
+			.arg("--out")
+			.arg(dir.path().join("fingerprints.rec"))
+			.arg("--index")
+			.arg(&index)
+			.assert()
+			.success();
+	};
+
+	std::fs::create_dir_all(after.parent().unwrap()).unwrap();
+	std::fs::copy("samples/ascii.txt", &before).unwrap();
+	scan();
+	std::fs::rename(&before, &after).unwrap();
+	scan();
+
+	let store = fingerprint::store::SqliteStore::open(&index).unwrap();
+	let ascii = fingerprint::Fingerprint::finger("samples/ascii.txt").unwrap();
+	let paths: Vec<_> = store
+		.content_index()
+		.paths_for(&ascii)
+		.unwrap()
+		.into_iter()
+		.map(|record| record.path)
+		.collect();
+
+	assert_eq!(paths.len(), 2);
+	assert!(paths.contains(&before) && paths.contains(&after));
+	assert!(store.content_index().orphans().unwrap().is_empty());
+}
+
+#[test]
+fn test_dedup_hardlink() {
+	let dir = tempfile::tempdir().unwrap();
+	let (a, b, c) = (
+		dir.path().join("a.txt"),
+		dir.path().join("b.txt"),
+		dir.path().join("c.txt"),
+	);
+
+	std::fs::copy("samples/ascii.txt", &a).unwrap();
+	std::fs::copy("samples/ascii.txt", &b).unwrap();
+	std::fs::copy("samples/ascii_different.txt", &c).unwrap();
+
+	fingerprint()
+		.args(["dedup", "--action", "hardlink", "--dry-run"])
+		.arg(dir.path())
+		.assert()
+		.success()
+		.stdout(predicate::str::contains("would link"));
+	std::fs::write(&a, "changed").unwrap();
+	assert_eq!(
+		std::fs::read(&b).unwrap(),
+		std::fs::read("samples/ascii.txt").unwrap()
+	);
+	std::fs::copy("samples/ascii.txt", &a).unwrap();
+
+	let output = fingerprint()
+		.args(["dedup", "--json", "--action", "hardlink"])
+		.arg(dir.path())
+		.output()
+		.unwrap();
+	let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+	assert!(output.status.success());
+	assert_eq!(report["groups"].as_array().unwrap().len(), 1);
+	assert_eq!(report["links"][0]["performed"], true);
+
+	// Writing through one link shows through the other.
+	std::fs::write(&a, "changed").unwrap();
+	assert_eq!(std::fs::read(&b).unwrap(), b"changed");
+}
+
+#[test]
+fn test_dedup_link_failure() {
+	let dir = tempfile::tempdir().unwrap();
+	let (a, b, c) = (
+		dir.path().join("a.txt"),
+		dir.path().join("b.txt"),
+		dir.path().join("c.txt"),
+	);
+	let mut edited = std::fs::read("samples/ascii.txt").unwrap();
+
+	edited[0] ^= 1;
+	std::fs::copy("samples/ascii.txt", &a).unwrap();
+	std::fs::copy("samples/ascii.txt", &b).unwrap();
+	std::fs::write(&c, edited).unwrap();
+	// Occupies the temporary name the link to b.txt is made under.
+	std::fs::create_dir(dir.path().join("b.txt.fingerprint-link")).unwrap();
+
+	let output = fingerprint()
+		.args(["dedup", "--json", "--action", "hardlink"])
+		.arg(dir.path())
+		.output()
+		.unwrap();
+	let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+	assert!(output.status.success());
+	assert_eq!(report["links"].as_array().unwrap().len(), 1);
+	assert_eq!(report["links"][0]["path"], c.to_str().unwrap());
+	assert_eq!(
+		report["links"][0]["skipped"],
+		"contents differ from the target"
+	);
+	assert_eq!(report["errors"].as_array().unwrap().len(), 1);
+	assert_eq!(report["errors"][0]["path"], b.to_str().unwrap());
+}
+
+#[test]
+fn test_fp_compare_csv() {
+	let dir = tempfile::tempdir().unwrap();
+	let first = dir.path().join("first");
+
+	std::fs::write(&first, "samples/ascii.txt\n\nsamples/empty\n").unwrap();
+
+	Command::cargo_bin("fp_compare")
+		.unwrap()
+		.arg(&first)
+		.arg("-")
+		.write_stdin("samples/ascii.txt\n")
+		.assert()
+		.success()
+		.stdout(
+			"path1,path2,similarity\n\
+			samples/ascii.txt,samples/ascii.txt,1\n\
+			samples/empty,samples/ascii.txt,0.4921875\n",
+		);
+}
+
+#[test]
+fn test_fp_compare_failure() {
+	Command::cargo_bin("fp_compare")
+		.unwrap()
+		.args(["-", "-"])
+		.assert()
+		.code(2);
+	Command::cargo_bin("fp_compare")
+		.unwrap()
+		.args(["-", "samples/ascii.txt"])
+		.write_stdin("samples/missing\nsamples/ascii.txt\n")
+		.assert()
+		.code(1)
+		.stdout(predicate::str::contains("samples/missing").not())
+		.stderr(predicate::str::contains("samples/missing"));
+}
@@ -0,0 +1,24 @@
+use fingerprint::runtime::{Config, RuntimeError};
+
+// The only test of this binary, since setting the global configuration fixes it for the whole process.
+#[test]
+fn test_set_global_twice() {
+	let dir = tempfile::tempdir().unwrap();
+
+	Config {
+		temp_dir: dir.path().to_path_buf(),
+		..Config::default()
+	}
+	.set_global()
+	.unwrap();
+
+	assert_eq!(Config::global().temp_dir, dir.path());
+	assert_eq!(
+		Config::default().set_global(),
+		Err(RuntimeError::AlreadySet)
+	);
+	assert_eq!(
+		Config::global().temp_file().unwrap().path().parent(),
+		Some(dir.path())
+	);
+}
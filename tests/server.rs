@@ -0,0 +1,168 @@
+#![cfg(feature = "server")]
+
+use std::{
+	io::{BufRead, BufReader},
+	process::{Child, Command, Stdio},
+};
+
+use reqwest::blocking::{multipart::Form, Client};
+use serde_json::{json, Value};
+
+/// Running service, killed when dropped.
+struct Server {
+	child: Child,
+	url: String,
+}
+
+impl Server {
+	/// Start the service on a free port with extra arguments.
+	fn start(args: &[&str]) -> Self {
+		Self::start_with_env(args, &[])
+	}
+
+	/// Start the service on a free port with extra arguments and environment variables.
+	fn start_with_env(args: &[&str], vars: &[(&str, &str)]) -> Self {
+		let mut child = Command::new(env!("CARGO_BIN_EXE_fingerprint-server"))
+			.args(["--listen", "127.0.0.1:0"])
+			.args(args)
+			.envs(vars.iter().copied())
+			.stdout(Stdio::piped())
+			.spawn()
+			.unwrap();
+		let mut line = String::new();
+
+		BufReader::new(child.stdout.take().unwrap())
+			.read_line(&mut line)
+			.unwrap();
+
+		let address = line.trim().strip_prefix("listening on ").unwrap();
+
+		Self {
+			child,
+			url: format!("http://{}", address),
+		}
+	}
+}
+
+impl Drop for Server {
+	fn drop(&mut self) {
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+	}
+}
+
+#[test]
+fn test_fingerprint_upload_and_compare() {
+	let server = Server::start(&[]);
+	let client = Client::new();
+	let upload = |path: &str| -> Value {
+		client
+			.post(format!("{}/fingerprint", server.url))
+			.multipart(Form::new().file("file", path).unwrap())
+			.send()
+			.unwrap()
+			.json()
+			.unwrap()
+	};
+
+	let a = upload("samples/ascii.txt");
+	let b = upload("samples/ascii_similar.txt");
+
+	assert_eq!(a["uri"], "fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q");
+	assert_eq!(a["name"], "ascii.txt");
+	assert_eq!(upload("samples/audio/tone.flac")["type"], "audio");
+
+	let response: Value = client
+		.post(format!("{}/compare", server.url))
+		.json(&json!({"a": a["uri"], "b": b["uri"]}))
+		.send()
+		.unwrap()
+		.json()
+		.unwrap();
+
+	assert_eq!(response["similarity"], 0.859375);
+
+	let response: Value = client
+		.post(format!("{}/compare", server.url))
+		.multipart(
+			Form::new()
+				.file("a", "samples/ascii.txt")
+				.unwrap()
+				.file("b", "samples/ascii.txt")
+				.unwrap(),
+		)
+		.send()
+		.unwrap()
+		.json()
+		.unwrap();
+
+	assert_eq!(response["similarity"], 1.0);
+}
+
+#[test]
+fn test_fingerprint_path() {
+	let server = Server::start(&["--root", "samples"]);
+	let client = Client::new();
+	let request = |path: &str| {
+		client
+			.post(format!("{}/fingerprint", server.url))
+			.json(&json!({ "path": path }))
+			.send()
+			.unwrap()
+	};
+
+	let response = request("ascii.txt");
+
+	assert!(response.status().is_success());
+	assert_eq!(
+		response.json::<Value>().unwrap()["uri"],
+		"fp1:raw:v1:aWTRSzor8yZNsVZJ1d5K1Q"
+	);
+	assert_eq!(request("../Cargo.toml").status(), 403);
+	assert_eq!(request("missing").status(), 404);
+}
+
+#[test]
+fn test_limits_and_health() {
+	let server = Server::start(&["--max-body-bytes", "16"]);
+	let client = Client::new();
+
+	let response = client
+		.post(format!("{}/fingerprint", server.url))
+		.multipart(Form::new().file("file", "samples/ascii.txt").unwrap())
+		.send()
+		.unwrap();
+
+	assert_eq!(response.status(), 413);
+
+	let response = client
+		.post(format!("{}/fingerprint", server.url))
+		.json(&json!({ "path": "a" }))
+		.send()
+		.unwrap();
+
+	assert_eq!(response.status(), 403);
+
+	let health: Value = client
+		.get(format!("{}/healthz", server.url))
+		.send()
+		.unwrap()
+		.json()
+		.unwrap();
+
+	assert_eq!(health["findings"].as_array().unwrap().len(), 1);
+	assert_eq!(health["status"], "ok");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_healthz_unavailable() {
+	let server = Server::start_with_env(&[], &[("TMPDIR", "/nonexistent")]);
+	let response = Client::new()
+		.get(format!("{}/healthz", server.url))
+		.send()
+		.unwrap();
+
+	assert_eq!(response.status(), 503);
+	assert_eq!(response.json::<Value>().unwrap()["status"], "unavailable");
+}